@@ -0,0 +1,57 @@
+-- Types d'utilisateurs et comptes
+CREATE TABLE types_user (
+    id INT NOT NULL AUTO_INCREMENT,
+    nom_type_user VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uq_types_user_nom (nom_type_user),
+    KEY idx_types_user_updated_at (updated_at)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE users (
+    id INT NOT NULL AUTO_INCREMENT,
+    type_user_id INT NOT NULL,
+    nom VARCHAR(100) NOT NULL,
+    prenom VARCHAR(100) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    numero_telephone VARCHAR(30) NOT NULL,
+    mot_de_passe VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uq_users_email (email),
+    KEY idx_users_updated_at (updated_at),
+    CONSTRAINT fk_users_type_user FOREIGN KEY (type_user_id) REFERENCES types_user (id),
+    CONSTRAINT fk_users_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+ALTER TABLE types_user
+    ADD CONSTRAINT fk_types_user_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL;
+
+-- Types de référence pour les exploitations et les éléments
+CREATE TABLE types_exploitation (
+    id INT NOT NULL AUTO_INCREMENT,
+    nom_type_exploitation VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uq_types_exploitation_nom (nom_type_exploitation),
+    KEY idx_types_exploitation_updated_at (updated_at),
+    CONSTRAINT fk_types_exploitation_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE types_element (
+    id INT NOT NULL AUTO_INCREMENT,
+    nom_type_element VARCHAR(100) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    UNIQUE KEY uq_types_element_nom (nom_type_element),
+    KEY idx_types_element_updated_at (updated_at),
+    CONSTRAINT fk_types_element_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
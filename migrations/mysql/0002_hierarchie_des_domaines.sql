@@ -0,0 +1,69 @@
+-- Domaines, exploitations, éléments et productions
+-- Les suppressions ne sont pas propagées : un parent ne peut être supprimé
+-- tant qu'il a des enfants, pour que chaque suppression laisse sa trace.
+CREATE TABLE domaines (
+    id INT NOT NULL AUTO_INCREMENT,
+    user_id INT NOT NULL,
+    nom_domaine VARCHAR(255) NOT NULL,
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_domaines_user_id (user_id, updated_at),
+    KEY idx_domaines_updated_at (updated_at),
+    CONSTRAINT fk_domaines_user FOREIGN KEY (user_id) REFERENCES users (id),
+    CONSTRAINT fk_domaines_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE exploitations (
+    id INT NOT NULL AUTO_INCREMENT,
+    type_exploitation_id INT NOT NULL,
+    domaine_id INT NOT NULL,
+    nom_exploitation VARCHAR(255) NOT NULL,
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_exploitations_domaine_id (domaine_id, updated_at),
+    KEY idx_exploitations_updated_at (updated_at),
+    CONSTRAINT fk_exploitations_type FOREIGN KEY (type_exploitation_id) REFERENCES types_exploitation (id),
+    CONSTRAINT fk_exploitations_domaine FOREIGN KEY (domaine_id) REFERENCES domaines (id),
+    CONSTRAINT fk_exploitations_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE elements (
+    id INT NOT NULL AUTO_INCREMENT,
+    exploitation_id INT NOT NULL,
+    nom_element VARCHAR(255) NOT NULL,
+    quantite INT NOT NULL DEFAULT 0,
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_elements_exploitation_id (exploitation_id, updated_at),
+    KEY idx_elements_updated_at (updated_at),
+    CONSTRAINT chk_elements_quantite CHECK (quantite >= 0),
+    CONSTRAINT fk_elements_exploitation FOREIGN KEY (exploitation_id) REFERENCES exploitations (id),
+    CONSTRAINT fk_elements_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE production (
+    id INT NOT NULL AUTO_INCREMENT,
+    element_id INT NOT NULL,
+    quantite_produite INT NOT NULL,
+    unite_production VARCHAR(50) NOT NULL,
+    date_de_production DATE NOT NULL,
+    version INT NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_production_element_date (element_id, date_de_production),
+    KEY idx_production_updated_at (updated_at),
+    CONSTRAINT chk_production_quantite CHECK (quantite_produite >= 0),
+    CONSTRAINT fk_production_element FOREIGN KEY (element_id) REFERENCES elements (id),
+    CONSTRAINT fk_production_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,23 @@
+-- Traces des suppressions pour la synchronisation des clients hors ligne
+-- Pas de clé étrangère vers la ligne supprimée, qui n'existe plus
+CREATE TABLE tombstones (
+    id BIGINT NOT NULL AUTO_INCREMENT,
+    entity VARCHAR(32) NOT NULL,
+    entity_id INT NOT NULL,
+    domaine_id INT NOT NULL,
+    user_id INT NOT NULL,
+    deleted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    KEY idx_tombstones_user_deleted_at (user_id, deleted_at),
+    CONSTRAINT fk_tombstones_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- Correspondance entre les identifiants générés par les clients et les ID serveur
+CREATE TABLE sync_client_ids (
+    user_id INT NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    entity VARCHAR(32) NOT NULL,
+    entity_id INT NOT NULL,
+    PRIMARY KEY (user_id, entity, client_id),
+    CONSTRAINT fk_sync_client_ids_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
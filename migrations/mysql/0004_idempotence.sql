@@ -0,0 +1,13 @@
+-- Réponses mémorisées pour les requêtes POST portant un en-tête Idempotency-Key
+-- status_code reste NULL tant que la première requête est en cours
+CREATE TABLE idempotency_keys (
+    principal VARCHAR(255) NOT NULL,
+    idempotency_key VARCHAR(255) NOT NULL,
+    request_hash CHAR(64) NOT NULL,
+    status_code SMALLINT UNSIGNED NULL,
+    content_type VARCHAR(255) NULL,
+    response_body MEDIUMBLOB NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (principal, idempotency_key),
+    KEY idx_idempotency_keys_created_at (created_at)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
@@ -0,0 +1,34 @@
+-- Abonnements aux événements d'un domaine, notifiés par POST signé (HMAC-SHA256)
+-- Un abonnement n'est pas une donnée de ferme : il disparaît avec son domaine
+CREATE TABLE webhooks (
+    id INT NOT NULL AUTO_INCREMENT,
+    domaine_id INT NOT NULL,
+    url VARCHAR(2048) NOT NULL,
+    events VARCHAR(1024) NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_webhooks_domaine_id (domaine_id),
+    CONSTRAINT fk_webhooks_domaine FOREIGN KEY (domaine_id) REFERENCES domaines (id) ON DELETE CASCADE,
+    CONSTRAINT fk_webhooks_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+-- File des livraisons et journal de leurs tentatives
+CREATE TABLE webhook_deliveries (
+    id INT NOT NULL AUTO_INCREMENT,
+    webhook_id INT NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    payload MEDIUMTEXT NOT NULL,
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_status_code INT NULL,
+    last_error TEXT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP NULL,
+    PRIMARY KEY (id),
+    KEY idx_webhook_deliveries_due (status, next_attempt_at),
+    KEY idx_webhook_deliveries_webhook_id (webhook_id, created_at),
+    CONSTRAINT fk_webhook_deliveries_webhook FOREIGN KEY (webhook_id) REFERENCES webhooks (id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
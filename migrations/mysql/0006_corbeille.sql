@@ -0,0 +1,11 @@
+-- Corbeille : une ligne supprimée garde sa place jusqu'à sa purge, deleted_at marque sa suppression
+-- Les lectures ordinaires ignorent les lignes dont deleted_at est renseigné
+ALTER TABLE domaines ADD COLUMN deleted_at TIMESTAMP NULL;
+ALTER TABLE exploitations ADD COLUMN deleted_at TIMESTAMP NULL;
+ALTER TABLE elements ADD COLUMN deleted_at TIMESTAMP NULL;
+ALTER TABLE production ADD COLUMN deleted_at TIMESTAMP NULL;
+
+CREATE INDEX idx_domaines_deleted_at ON domaines (deleted_at);
+CREATE INDEX idx_exploitations_deleted_at ON exploitations (deleted_at);
+CREATE INDEX idx_elements_deleted_at ON elements (deleted_at);
+CREATE INDEX idx_production_deleted_at ON production (deleted_at);
@@ -0,0 +1,9 @@
+-- Noms des types de référence dans d'autres langues ; le nom stocké dans la table du type reste le nom français
+CREATE TABLE types_traductions (
+    kind VARCHAR(32) NOT NULL,
+    type_id INT NOT NULL,
+    lang VARCHAR(8) NOT NULL,
+    nom VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, type_id, lang)
+);
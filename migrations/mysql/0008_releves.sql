@@ -0,0 +1,30 @@
+-- Relevés de qualité de l'eau par exploitation et distributions d'aliment par élément
+-- Ce sont des mesures, pas des données synchronisées : elles partent avec leur parent à la purge
+CREATE TABLE qualite_eau (
+    id INT NOT NULL AUTO_INCREMENT,
+    exploitation_id INT NOT NULL,
+    date_releve DATE NOT NULL,
+    temperature DOUBLE NOT NULL,
+    oxygene_dissous DOUBLE NOT NULL,
+    ph DOUBLE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_qualite_eau_exploitation_date (exploitation_id, date_releve),
+    CONSTRAINT fk_qualite_eau_exploitation FOREIGN KEY (exploitation_id) REFERENCES exploitations (id) ON DELETE CASCADE,
+    CONSTRAINT fk_qualite_eau_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
+
+CREATE TABLE alimentations (
+    id INT NOT NULL AUTO_INCREMENT,
+    element_id INT NOT NULL,
+    date_alimentation DATE NOT NULL,
+    quantite_kg DOUBLE NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INT NULL,
+    PRIMARY KEY (id),
+    KEY idx_alimentations_element_date (element_id, date_alimentation),
+    CONSTRAINT chk_alimentations_quantite CHECK (quantite_kg >= 0),
+    CONSTRAINT fk_alimentations_element FOREIGN KEY (element_id) REFERENCES elements (id) ON DELETE CASCADE,
+    CONSTRAINT fk_alimentations_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4;
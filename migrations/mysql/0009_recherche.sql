@@ -0,0 +1,21 @@
+-- Nom replié comparé par la recherche : minuscules sans accents, ponctuation remplacée par des espaces
+-- L'application le remplit avec search::normalize à chaque écriture du nom ; les lignes existantes
+-- sont repliées ici pour les accents et la ponctuation courante
+
+ALTER TABLE domaines ADD COLUMN nom_recherche VARCHAR(512) NOT NULL DEFAULT '';
+UPDATE domaines SET nom_recherche = TRIM(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(LOWER(nom_domaine), 'à', 'a'), 'â', 'a'), 'ä', 'a'), 'á', 'a'), 'ã', 'a'), 'å', 'a'), 'ç', 'c'), 'é', 'e'), 'è', 'e'), 'ê', 'e'), 'ë', 'e'), 'î', 'i'), 'ï', 'i'), 'í', 'i'), 'ì', 'i'), 'ô', 'o'), 'ö', 'o'), 'ó', 'o'), 'ò', 'o'), 'õ', 'o'), 'ù', 'u'), 'û', 'u'), 'ü', 'u'), 'ú', 'u'), 'ÿ', 'y'), 'ý', 'y'), 'ñ', 'n'), 'œ', 'oe'), 'æ', 'ae'), '''', ' '), '’', ' '), '-', ' '), '_', ' '), '.', ' '), ',', ' '), '/', ' '), '(', ' '), ')', ' '), '  ', ' '), '  ', ' '));
+
+ALTER TABLE exploitations ADD COLUMN nom_recherche VARCHAR(512) NOT NULL DEFAULT '';
+UPDATE exploitations SET nom_recherche = TRIM(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(LOWER(nom_exploitation), 'à', 'a'), 'â', 'a'), 'ä', 'a'), 'á', 'a'), 'ã', 'a'), 'å', 'a'), 'ç', 'c'), 'é', 'e'), 'è', 'e'), 'ê', 'e'), 'ë', 'e'), 'î', 'i'), 'ï', 'i'), 'í', 'i'), 'ì', 'i'), 'ô', 'o'), 'ö', 'o'), 'ó', 'o'), 'ò', 'o'), 'õ', 'o'), 'ù', 'u'), 'û', 'u'), 'ü', 'u'), 'ú', 'u'), 'ÿ', 'y'), 'ý', 'y'), 'ñ', 'n'), 'œ', 'oe'), 'æ', 'ae'), '''', ' '), '’', ' '), '-', ' '), '_', ' '), '.', ' '), ',', ' '), '/', ' '), '(', ' '), ')', ' '), '  ', ' '), '  ', ' '));
+
+ALTER TABLE elements ADD COLUMN nom_recherche VARCHAR(512) NOT NULL DEFAULT '';
+UPDATE elements SET nom_recherche = TRIM(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(LOWER(nom_element), 'à', 'a'), 'â', 'a'), 'ä', 'a'), 'á', 'a'), 'ã', 'a'), 'å', 'a'), 'ç', 'c'), 'é', 'e'), 'è', 'e'), 'ê', 'e'), 'ë', 'e'), 'î', 'i'), 'ï', 'i'), 'í', 'i'), 'ì', 'i'), 'ô', 'o'), 'ö', 'o'), 'ó', 'o'), 'ò', 'o'), 'õ', 'o'), 'ù', 'u'), 'û', 'u'), 'ü', 'u'), 'ú', 'u'), 'ÿ', 'y'), 'ý', 'y'), 'ñ', 'n'), 'œ', 'oe'), 'æ', 'ae'), '''', ' '), '’', ' '), '-', ' '), '_', ' '), '.', ' '), ',', ' '), '/', ' '), '(', ' '), ')', ' '), '  ', ' '), '  ', ' '));
+
+ALTER TABLE types_user ADD COLUMN nom_recherche VARCHAR(512) NOT NULL DEFAULT '';
+UPDATE types_user SET nom_recherche = TRIM(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(LOWER(nom_type_user), 'à', 'a'), 'â', 'a'), 'ä', 'a'), 'á', 'a'), 'ã', 'a'), 'å', 'a'), 'ç', 'c'), 'é', 'e'), 'è', 'e'), 'ê', 'e'), 'ë', 'e'), 'î', 'i'), 'ï', 'i'), 'í', 'i'), 'ì', 'i'), 'ô', 'o'), 'ö', 'o'), 'ó', 'o'), 'ò', 'o'), 'õ', 'o'), 'ù', 'u'), 'û', 'u'), 'ü', 'u'), 'ú', 'u'), 'ÿ', 'y'), 'ý', 'y'), 'ñ', 'n'), 'œ', 'oe'), 'æ', 'ae'), '''', ' '), '’', ' '), '-', ' '), '_', ' '), '.', ' '), ',', ' '), '/', ' '), '(', ' '), ')', ' '), '  ', ' '), '  ', ' '));
+
+ALTER TABLE types_exploitation ADD COLUMN nom_recherche VARCHAR(512) NOT NULL DEFAULT '';
+UPDATE types_exploitation SET nom_recherche = TRIM(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(LOWER(nom_type_exploitation), 'à', 'a'), 'â', 'a'), 'ä', 'a'), 'á', 'a'), 'ã', 'a'), 'å', 'a'), 'ç', 'c'), 'é', 'e'), 'è', 'e'), 'ê', 'e'), 'ë', 'e'), 'î', 'i'), 'ï', 'i'), 'í', 'i'), 'ì', 'i'), 'ô', 'o'), 'ö', 'o'), 'ó', 'o'), 'ò', 'o'), 'õ', 'o'), 'ù', 'u'), 'û', 'u'), 'ü', 'u'), 'ú', 'u'), 'ÿ', 'y'), 'ý', 'y'), 'ñ', 'n'), 'œ', 'oe'), 'æ', 'ae'), '''', ' '), '’', ' '), '-', ' '), '_', ' '), '.', ' '), ',', ' '), '/', ' '), '(', ' '), ')', ' '), '  ', ' '), '  ', ' '));
+
+ALTER TABLE types_element ADD COLUMN nom_recherche VARCHAR(512) NOT NULL DEFAULT '';
+UPDATE types_element SET nom_recherche = TRIM(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(LOWER(nom_type_element), 'à', 'a'), 'â', 'a'), 'ä', 'a'), 'á', 'a'), 'ã', 'a'), 'å', 'a'), 'ç', 'c'), 'é', 'e'), 'è', 'e'), 'ê', 'e'), 'ë', 'e'), 'î', 'i'), 'ï', 'i'), 'í', 'i'), 'ì', 'i'), 'ô', 'o'), 'ö', 'o'), 'ó', 'o'), 'ò', 'o'), 'õ', 'o'), 'ù', 'u'), 'û', 'u'), 'ü', 'u'), 'ú', 'u'), 'ÿ', 'y'), 'ý', 'y'), 'ñ', 'n'), 'œ', 'oe'), 'æ', 'ae'), '''', ' '), '’', ' '), '-', ' '), '_', ' '), '.', ' '), ',', ' '), '/', ' '), '(', ' '), ')', ' '), '  ', ' '), '  ', ' '));
@@ -0,0 +1,6 @@
+-- Réservation d'un identifiant client avant la création de la ligne : entity_id reste NULL
+-- tant que la création est en cours, et une réservation plus ancienne que reserved_at + délai
+-- (création interrompue) peut être reprise
+ALTER TABLE sync_client_ids
+    MODIFY entity_id INT NULL,
+    ADD COLUMN reserved_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP;
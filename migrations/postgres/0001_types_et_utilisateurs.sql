@@ -0,0 +1,57 @@
+-- Types d'utilisateurs et comptes
+CREATE TABLE types_user (
+    id SERIAL PRIMARY KEY,
+    nom_type_user VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT uq_types_user_nom UNIQUE (nom_type_user)
+);
+
+CREATE INDEX idx_types_user_updated_at ON types_user (updated_at);
+
+CREATE TABLE users (
+    id SERIAL PRIMARY KEY,
+    type_user_id INTEGER NOT NULL,
+    nom VARCHAR(100) NOT NULL,
+    prenom VARCHAR(100) NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    numero_telephone VARCHAR(30) NOT NULL,
+    mot_de_passe VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT uq_users_email UNIQUE (email),
+    CONSTRAINT fk_users_type_user FOREIGN KEY (type_user_id) REFERENCES types_user (id),
+    CONSTRAINT fk_users_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_users_updated_at ON users (updated_at);
+
+ALTER TABLE types_user
+    ADD CONSTRAINT fk_types_user_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL;
+
+-- Types de référence pour les exploitations et les éléments
+CREATE TABLE types_exploitation (
+    id SERIAL PRIMARY KEY,
+    nom_type_exploitation VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT uq_types_exploitation_nom UNIQUE (nom_type_exploitation),
+    CONSTRAINT fk_types_exploitation_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_types_exploitation_updated_at ON types_exploitation (updated_at);
+
+CREATE TABLE types_element (
+    id SERIAL PRIMARY KEY,
+    nom_type_element VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT uq_types_element_nom UNIQUE (nom_type_element),
+    CONSTRAINT fk_types_element_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_types_element_updated_at ON types_element (updated_at);
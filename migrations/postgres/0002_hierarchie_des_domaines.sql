@@ -0,0 +1,69 @@
+-- Domaines, exploitations, éléments et productions
+-- Les suppressions ne sont pas propagées : un parent ne peut être supprimé
+-- tant qu'il a des enfants, pour que chaque suppression laisse sa trace.
+CREATE TABLE domaines (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    nom_domaine VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT fk_domaines_user FOREIGN KEY (user_id) REFERENCES users (id),
+    CONSTRAINT fk_domaines_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_domaines_user_id ON domaines (user_id, updated_at);
+CREATE INDEX idx_domaines_updated_at ON domaines (updated_at);
+
+CREATE TABLE exploitations (
+    id SERIAL PRIMARY KEY,
+    type_exploitation_id INTEGER NOT NULL,
+    domaine_id INTEGER NOT NULL,
+    nom_exploitation VARCHAR(255) NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT fk_exploitations_type FOREIGN KEY (type_exploitation_id) REFERENCES types_exploitation (id),
+    CONSTRAINT fk_exploitations_domaine FOREIGN KEY (domaine_id) REFERENCES domaines (id),
+    CONSTRAINT fk_exploitations_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_exploitations_domaine_id ON exploitations (domaine_id, updated_at);
+CREATE INDEX idx_exploitations_updated_at ON exploitations (updated_at);
+
+CREATE TABLE elements (
+    id SERIAL PRIMARY KEY,
+    exploitation_id INTEGER NOT NULL,
+    nom_element VARCHAR(255) NOT NULL,
+    quantite INTEGER NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT chk_elements_quantite CHECK (quantite >= 0),
+    CONSTRAINT fk_elements_exploitation FOREIGN KEY (exploitation_id) REFERENCES exploitations (id),
+    CONSTRAINT fk_elements_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_elements_exploitation_id ON elements (exploitation_id, updated_at);
+CREATE INDEX idx_elements_updated_at ON elements (updated_at);
+
+CREATE TABLE production (
+    id SERIAL PRIMARY KEY,
+    element_id INTEGER NOT NULL,
+    quantite_produite INTEGER NOT NULL,
+    unite_production VARCHAR(50) NOT NULL,
+    date_de_production DATE NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT chk_production_quantite CHECK (quantite_produite >= 0),
+    CONSTRAINT fk_production_element FOREIGN KEY (element_id) REFERENCES elements (id),
+    CONSTRAINT fk_production_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_production_element_date ON production (element_id, date_de_production);
+CREATE INDEX idx_production_updated_at ON production (updated_at);
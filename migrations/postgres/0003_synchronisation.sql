@@ -0,0 +1,26 @@
+-- Type d'entité synchronisable, lu et écrit par l'énumération Entity
+CREATE TYPE entity AS ENUM ('domaine', 'exploitation', 'element', 'production');
+
+-- Traces des suppressions pour la synchronisation des clients hors ligne
+-- Pas de clé étrangère vers la ligne supprimée, qui n'existe plus
+CREATE TABLE tombstones (
+    id BIGSERIAL PRIMARY KEY,
+    entity entity NOT NULL,
+    entity_id INTEGER NOT NULL,
+    domaine_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT fk_tombstones_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_tombstones_user_deleted_at ON tombstones (user_id, deleted_at);
+
+-- Correspondance entre les identifiants générés par les clients et les ID serveur
+CREATE TABLE sync_client_ids (
+    user_id INTEGER NOT NULL,
+    client_id VARCHAR(255) NOT NULL,
+    entity entity NOT NULL,
+    entity_id INTEGER NOT NULL,
+    PRIMARY KEY (user_id, entity, client_id),
+    CONSTRAINT fk_sync_client_ids_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
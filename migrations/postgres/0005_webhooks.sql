@@ -0,0 +1,37 @@
+-- Abonnements aux événements d'un domaine, notifiés par POST signé (HMAC-SHA256)
+-- Un abonnement n'est pas une donnée de ferme : il disparaît avec son domaine
+CREATE TABLE webhooks (
+    id SERIAL PRIMARY KEY,
+    domaine_id INTEGER NOT NULL,
+    url VARCHAR(2048) NOT NULL,
+    events VARCHAR(1024) NOT NULL,
+    secret VARCHAR(255) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT fk_webhooks_domaine FOREIGN KEY (domaine_id) REFERENCES domaines (id) ON DELETE CASCADE,
+    CONSTRAINT fk_webhooks_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_webhooks_domaine_id ON webhooks (domaine_id);
+
+-- État d'une livraison, lu et écrit par l'énumération DeliveryStatus
+CREATE TYPE delivery_status AS ENUM ('pending', 'delivered', 'failed');
+
+-- File des livraisons et journal de leurs tentatives
+CREATE TABLE webhook_deliveries (
+    id SERIAL PRIMARY KEY,
+    webhook_id INTEGER NOT NULL,
+    event_type VARCHAR(64) NOT NULL,
+    payload TEXT NOT NULL,
+    status delivery_status NOT NULL DEFAULT 'pending',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_status_code INTEGER NULL,
+    last_error TEXT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMPTZ NULL,
+    CONSTRAINT fk_webhook_deliveries_webhook FOREIGN KEY (webhook_id) REFERENCES webhooks (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries (status, next_attempt_at);
+CREATE INDEX idx_webhook_deliveries_webhook_id ON webhook_deliveries (webhook_id, created_at);
@@ -0,0 +1,12 @@
+-- Famille de types de référence traduisibles, lue et écrite par l'énumération TypeKind
+CREATE TYPE type_kind AS ENUM ('type_user', 'type_exploitation', 'type_element');
+
+-- Noms des types de référence dans d'autres langues ; le nom stocké dans la table du type reste le nom français
+CREATE TABLE types_traductions (
+    kind type_kind NOT NULL,
+    type_id INTEGER NOT NULL,
+    lang VARCHAR(8) NOT NULL,
+    nom VARCHAR(255) NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, type_id, lang)
+);
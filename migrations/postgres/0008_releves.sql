@@ -0,0 +1,30 @@
+-- Relevés de qualité de l'eau par exploitation et distributions d'aliment par élément
+-- Ce sont des mesures, pas des données synchronisées : elles partent avec leur parent à la purge
+CREATE TABLE qualite_eau (
+    id SERIAL PRIMARY KEY,
+    exploitation_id INTEGER NOT NULL,
+    date_releve DATE NOT NULL,
+    temperature DOUBLE PRECISION NOT NULL,
+    oxygene_dissous DOUBLE PRECISION NOT NULL,
+    ph DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT fk_qualite_eau_exploitation FOREIGN KEY (exploitation_id) REFERENCES exploitations (id) ON DELETE CASCADE,
+    CONSTRAINT fk_qualite_eau_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_qualite_eau_exploitation_date ON qualite_eau (exploitation_id, date_releve);
+
+CREATE TABLE alimentations (
+    id SERIAL PRIMARY KEY,
+    element_id INTEGER NOT NULL,
+    date_alimentation DATE NOT NULL,
+    quantite_kg DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL,
+    CONSTRAINT chk_alimentations_quantite CHECK (quantite_kg >= 0),
+    CONSTRAINT fk_alimentations_element FOREIGN KEY (element_id) REFERENCES elements (id) ON DELETE CASCADE,
+    CONSTRAINT fk_alimentations_created_by FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_alimentations_element_date ON alimentations (element_id, date_alimentation);
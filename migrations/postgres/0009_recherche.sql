@@ -0,0 +1,21 @@
+-- Nom replié comparé par la recherche : minuscules sans accents, ponctuation remplacée par des espaces
+-- L'application le remplit avec search::normalize à chaque écriture du nom ; les lignes existantes
+-- sont repliées ici pour les accents et la ponctuation courante
+
+ALTER TABLE domaines ADD COLUMN nom_recherche TEXT NOT NULL DEFAULT '';
+UPDATE domaines SET nom_recherche = trim(replace(replace(replace(replace(translate(lower(nom_domaine), 'àâäáãåçéèêëîïíìôöóòõùûüúÿýñ''’-_.,/()', 'aaaaaaceeeeiiiiooooouuuuyyn         '), 'œ', 'oe'), 'æ', 'ae'), '  ', ' '), '  ', ' '));
+
+ALTER TABLE exploitations ADD COLUMN nom_recherche TEXT NOT NULL DEFAULT '';
+UPDATE exploitations SET nom_recherche = trim(replace(replace(replace(replace(translate(lower(nom_exploitation), 'àâäáãåçéèêëîïíìôöóòõùûüúÿýñ''’-_.,/()', 'aaaaaaceeeeiiiiooooouuuuyyn         '), 'œ', 'oe'), 'æ', 'ae'), '  ', ' '), '  ', ' '));
+
+ALTER TABLE elements ADD COLUMN nom_recherche TEXT NOT NULL DEFAULT '';
+UPDATE elements SET nom_recherche = trim(replace(replace(replace(replace(translate(lower(nom_element), 'àâäáãåçéèêëîïíìôöóòõùûüúÿýñ''’-_.,/()', 'aaaaaaceeeeiiiiooooouuuuyyn         '), 'œ', 'oe'), 'æ', 'ae'), '  ', ' '), '  ', ' '));
+
+ALTER TABLE types_user ADD COLUMN nom_recherche TEXT NOT NULL DEFAULT '';
+UPDATE types_user SET nom_recherche = trim(replace(replace(replace(replace(translate(lower(nom_type_user), 'àâäáãåçéèêëîïíìôöóòõùûüúÿýñ''’-_.,/()', 'aaaaaaceeeeiiiiooooouuuuyyn         '), 'œ', 'oe'), 'æ', 'ae'), '  ', ' '), '  ', ' '));
+
+ALTER TABLE types_exploitation ADD COLUMN nom_recherche TEXT NOT NULL DEFAULT '';
+UPDATE types_exploitation SET nom_recherche = trim(replace(replace(replace(replace(translate(lower(nom_type_exploitation), 'àâäáãåçéèêëîïíìôöóòõùûüúÿýñ''’-_.,/()', 'aaaaaaceeeeiiiiooooouuuuyyn         '), 'œ', 'oe'), 'æ', 'ae'), '  ', ' '), '  ', ' '));
+
+ALTER TABLE types_element ADD COLUMN nom_recherche TEXT NOT NULL DEFAULT '';
+UPDATE types_element SET nom_recherche = trim(replace(replace(replace(replace(translate(lower(nom_type_element), 'àâäáãåçéèêëîïíìôöóòõùûüúÿýñ''’-_.,/()', 'aaaaaaceeeeiiiiooooouuuuyyn         '), 'œ', 'oe'), 'æ', 'ae'), '  ', ' '), '  ', ' '));
@@ -0,0 +1,5 @@
+-- Réservation d'un identifiant client avant la création de la ligne : entity_id reste NULL
+-- tant que la création est en cours, et une réservation plus ancienne que reserved_at + délai
+-- (création interrompue) peut être reprise
+ALTER TABLE sync_client_ids ALTER COLUMN entity_id DROP NOT NULL;
+ALTER TABLE sync_client_ids ADD COLUMN reserved_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP;
@@ -0,0 +1,5 @@
+-- Jeton du passage du worker qui a réservé la livraison : la réservation se fait en une seule
+-- mise à jour, puis les livraisons portant ce jeton sont relues
+ALTER TABLE webhook_deliveries ADD COLUMN lease_token TEXT NULL;
+
+CREATE INDEX idx_webhook_deliveries_lease_token ON webhook_deliveries (lease_token);
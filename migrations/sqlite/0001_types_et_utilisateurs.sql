@@ -0,0 +1,51 @@
+-- Types d'utilisateurs et comptes
+-- Les dates sont stockées en texte RFC 3339, toujours fournies par l'application
+CREATE TABLE types_user (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    nom_type_user TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT uq_types_user_nom UNIQUE (nom_type_user)
+);
+
+CREATE INDEX idx_types_user_updated_at ON types_user (updated_at);
+
+CREATE TABLE users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    type_user_id INTEGER NOT NULL REFERENCES types_user (id),
+    nom TEXT NOT NULL,
+    prenom TEXT NOT NULL,
+    email TEXT NOT NULL,
+    numero_telephone TEXT NOT NULL,
+    mot_de_passe TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT uq_users_email UNIQUE (email)
+);
+
+CREATE INDEX idx_users_updated_at ON users (updated_at);
+
+-- Types de référence pour les exploitations et les éléments
+CREATE TABLE types_exploitation (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    nom_type_exploitation TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT uq_types_exploitation_nom UNIQUE (nom_type_exploitation)
+);
+
+CREATE INDEX idx_types_exploitation_updated_at ON types_exploitation (updated_at);
+
+CREATE TABLE types_element (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    nom_type_element TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT uq_types_element_nom UNIQUE (nom_type_element)
+);
+
+CREATE INDEX idx_types_element_updated_at ON types_element (updated_at);
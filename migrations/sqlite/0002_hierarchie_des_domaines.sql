@@ -0,0 +1,60 @@
+-- Domaines, exploitations, éléments et productions
+-- Les suppressions ne sont pas propagées : un parent ne peut être supprimé
+-- tant qu'il a des enfants, pour que chaque suppression laisse sa trace.
+CREATE TABLE domaines (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL REFERENCES users (id),
+    nom_domaine TEXT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_domaines_user_id ON domaines (user_id, updated_at);
+CREATE INDEX idx_domaines_updated_at ON domaines (updated_at);
+
+CREATE TABLE exploitations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    type_exploitation_id INTEGER NOT NULL REFERENCES types_exploitation (id),
+    domaine_id INTEGER NOT NULL REFERENCES domaines (id),
+    nom_exploitation TEXT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_exploitations_domaine_id ON exploitations (domaine_id, updated_at);
+CREATE INDEX idx_exploitations_updated_at ON exploitations (updated_at);
+
+CREATE TABLE elements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    exploitation_id INTEGER NOT NULL REFERENCES exploitations (id),
+    nom_element TEXT NOT NULL,
+    quantite INTEGER NOT NULL DEFAULT 0,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT chk_elements_quantite CHECK (quantite >= 0)
+);
+
+CREATE INDEX idx_elements_exploitation_id ON elements (exploitation_id, updated_at);
+CREATE INDEX idx_elements_updated_at ON elements (updated_at);
+
+CREATE TABLE production (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    element_id INTEGER NOT NULL REFERENCES elements (id),
+    quantite_produite INTEGER NOT NULL,
+    unite_production TEXT NOT NULL,
+    date_de_production TEXT NOT NULL,
+    version INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT chk_production_quantite CHECK (quantite_produite >= 0)
+);
+
+CREATE INDEX idx_production_element_date ON production (element_id, date_de_production);
+CREATE INDEX idx_production_updated_at ON production (updated_at);
@@ -0,0 +1,21 @@
+-- Traces des suppressions pour la synchronisation des clients hors ligne
+-- Pas de clé étrangère vers la ligne supprimée, qui n'existe plus
+CREATE TABLE tombstones (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    domaine_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    deleted_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_tombstones_user_deleted_at ON tombstones (user_id, deleted_at);
+
+-- Correspondance entre les identifiants générés par les clients et les ID serveur
+CREATE TABLE sync_client_ids (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    client_id TEXT NOT NULL,
+    entity TEXT NOT NULL,
+    entity_id INTEGER NOT NULL,
+    PRIMARY KEY (user_id, entity, client_id)
+);
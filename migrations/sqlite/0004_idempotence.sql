@@ -0,0 +1,14 @@
+-- Réponses mémorisées pour les requêtes POST portant un en-tête Idempotency-Key
+-- status_code reste NULL tant que la première requête est en cours
+CREATE TABLE idempotency_keys (
+    principal TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    status_code INTEGER NULL,
+    content_type TEXT NULL,
+    response_body BLOB NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (principal, idempotency_key)
+);
+
+CREATE INDEX idx_idempotency_keys_created_at ON idempotency_keys (created_at);
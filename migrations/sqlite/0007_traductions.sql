@@ -0,0 +1,9 @@
+-- Noms des types de référence dans d'autres langues ; le nom stocké dans la table du type reste le nom français
+CREATE TABLE types_traductions (
+    kind TEXT NOT NULL,
+    type_id INTEGER NOT NULL,
+    lang TEXT NOT NULL,
+    nom TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kind, type_id, lang)
+);
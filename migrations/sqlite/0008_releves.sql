@@ -0,0 +1,25 @@
+-- Relevés de qualité de l'eau par exploitation et distributions d'aliment par élément
+-- Ce sont des mesures, pas des données synchronisées : elles partent avec leur parent à la purge
+CREATE TABLE qualite_eau (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    exploitation_id INTEGER NOT NULL REFERENCES exploitations (id) ON DELETE CASCADE,
+    date_releve TEXT NOT NULL,
+    temperature REAL NOT NULL,
+    oxygene_dissous REAL NOT NULL,
+    ph REAL NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_qualite_eau_exploitation_date ON qualite_eau (exploitation_id, date_releve);
+
+CREATE TABLE alimentations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    element_id INTEGER NOT NULL REFERENCES elements (id) ON DELETE CASCADE,
+    date_alimentation TEXT NOT NULL,
+    quantite_kg REAL NOT NULL CHECK (quantite_kg >= 0),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_by INTEGER NULL REFERENCES users (id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_alimentations_element_date ON alimentations (element_id, date_alimentation);
@@ -0,0 +1,7 @@
+-- Lot de suppression : une suppression en cascade marque la ligne et tous ses descendants du même identifiant
+-- La restauration ramène les lignes de ce lot, et elles seules ; une suppression simple n'en a pas
+-- Les lignes déjà à la corbeille n'ont pas de lot : restaurées, elles reviennent seules
+ALTER TABLE domaines ADD COLUMN deletion_batch TEXT NULL;
+ALTER TABLE exploitations ADD COLUMN deletion_batch TEXT NULL;
+ALTER TABLE elements ADD COLUMN deletion_batch TEXT NULL;
+ALTER TABLE production ADD COLUMN deletion_batch TEXT NULL;
@@ -0,0 +1,19 @@
+-- Réservation d'un identifiant client avant la création de la ligne : entity_id reste NULL
+-- tant que la création est en cours, et une réservation plus ancienne que reserved_at + délai
+-- (création interrompue) peut être reprise
+-- SQLite ne sait pas retirer un NOT NULL : la table est reconstruite
+CREATE TABLE sync_client_ids_reservees (
+    user_id INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    client_id TEXT NOT NULL,
+    entity TEXT NOT NULL,
+    entity_id INTEGER NULL,
+    reserved_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, entity, client_id)
+);
+
+INSERT INTO sync_client_ids_reservees (user_id, client_id, entity, entity_id)
+SELECT user_id, client_id, entity, entity_id FROM sync_client_ids;
+
+DROP TABLE sync_client_ids;
+
+ALTER TABLE sync_client_ids_reservees RENAME TO sync_client_ids;
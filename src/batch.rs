@@ -0,0 +1,383 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::production::Production;
+use crate::repository::RepoError;
+use crate::tombstone::Entity;
+
+// Nombre maximal d'opérations acceptées dans un lot
+pub const MAX_OPERATIONS: usize = 100;
+
+// Référence vers une ligne : ID existant, ou "$N" pour l'ID produit par l'opération N du lot
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum Ref {
+    Id(i32),
+    Result(String),
+}
+
+// Opération exécutée dans la transaction du lot
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    CreateDomaine {
+        nom_domaine: String,
+    },
+    CreateExploitation {
+        domaine_id: Ref,
+        type_exploitation_id: i32,
+        nom_exploitation: String,
+    },
+    CreateElement {
+        exploitation_id: Ref,
+        nom_element: String,
+        quantite: i32,
+    },
+    CreateProduction {
+        element_id: Ref,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+    },
+    UpdateDomaine {
+        id: Ref,
+        version: i32,
+        nom_domaine: Option<String>,
+    },
+    UpdateExploitation {
+        id: Ref,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    },
+    UpdateElement {
+        id: Ref,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    },
+    UpdateProduction {
+        id: Ref,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    },
+    DeleteDomaine {
+        id: Ref,
+        version: i32,
+    },
+    DeleteExploitation {
+        id: Ref,
+        version: i32,
+    },
+    DeleteElement {
+        id: Ref,
+        version: i32,
+    },
+    DeleteProduction {
+        id: Ref,
+        version: i32,
+    },
+}
+
+// Résultat d'une opération réussie
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OperationResult {
+    pub index: usize,
+    pub entity: Entity,
+    pub id: i32,
+    pub version: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchErrorKind {
+    Invalid,
+    Forbidden,
+    NotFound,
+    Conflict,
+    // Suppression refusée : des lignes dépendent encore de la ligne
+    Dependents,
+    Database,
+}
+
+// Erreur ayant provoqué l'annulation du lot
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchError {
+    pub index: Option<usize>,
+    #[serde(skip)]
+    pub kind: BatchErrorKind,
+    pub message: String,
+    pub current_version: Option<i32>,
+}
+
+impl BatchError {
+    fn new(kind: BatchErrorKind, message: &str) -> Self {
+        BatchError {
+            index: None,
+            kind,
+            message: message.to_string(),
+            current_version: None,
+        }
+    }
+
+    fn at(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+}
+
+impl From<sqlx::Error> for BatchError {
+    fn from(e: sqlx::Error) -> Self {
+        tracing::error!(error = ?e, "Erreur lors de l'exécution du lot");
+        BatchError::new(BatchErrorKind::Database, "Erreur lors de l'exécution du lot")
+    }
+}
+
+impl From<RepoError> for BatchError {
+    fn from(e: RepoError) -> Self {
+        match e {
+            RepoError::Conflict(_) => {
+                BatchError::new(BatchErrorKind::Dependents, "Suppression impossible : des lignes en dépendent")
+            }
+            RepoError::Database(e) => e.into(),
+        }
+    }
+}
+
+// Écritures nécessaires à un lot, réalisées dans la transaction de chaque dépôt
+#[async_trait]
+pub trait BatchStore: Send {
+    async fn create_domaine(
+        &mut self,
+        user_id: i32,
+        nom_domaine: String,
+        created_by: Option<i32>,
+    ) -> Result<Domaine, sqlx::Error>;
+    async fn create_exploitation(
+        &mut self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, sqlx::Error>;
+    async fn create_element(
+        &mut self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, sqlx::Error>;
+    async fn create_production(
+        &mut self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, sqlx::Error>;
+    async fn update_domaine(&mut self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, sqlx::Error>;
+    async fn update_exploitation(
+        &mut self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, sqlx::Error>;
+    async fn update_element(
+        &mut self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, sqlx::Error>;
+    async fn update_production(
+        &mut self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, sqlx::Error>;
+    // Refuse par RepoError::Conflict une ligne dont d'autres dépendent encore
+    async fn delete(&mut self, entity: Entity, id: i32, version: i32) -> Result<bool, RepoError>;
+    // Propriétaire du domaine auquel appartient une ligne, None si elle n'existe pas
+    async fn owner_of(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, sqlx::Error>;
+    // Version actuelle d'une ligne, None si elle n'existe pas
+    async fn current_version(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, sqlx::Error>;
+}
+
+// Refuser les lots dépassant MAX_OPERATIONS
+fn check_size(operations: &[BatchOperation]) -> Result<(), BatchError> {
+    if operations.len() > MAX_OPERATIONS {
+        return Err(BatchError::new(BatchErrorKind::Invalid, "Trop d'opérations dans le lot"));
+    }
+    Ok(())
+}
+
+// Exécuter toutes les opérations dans l'ordre sur le même support
+// Le dépôt appelant valide la transaction si tout réussit, et l'abandonne sinon
+pub async fn run_all<S: BatchStore>(
+    store: &mut S,
+    user_id: i32,
+    operations: Vec<BatchOperation>,
+) -> Result<Vec<OperationResult>, BatchError> {
+    check_size(&operations)?;
+
+    let mut results: Vec<OperationResult> = Vec::with_capacity(operations.len());
+
+    for (index, operation) in operations.into_iter().enumerate() {
+        let result = run(store, user_id, &results, index, operation)
+            .await
+            .map_err(|e| e.at(index))?;
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+async fn run<S: BatchStore>(
+    store: &mut S,
+    user_id: i32,
+    results: &[OperationResult],
+    index: usize,
+    operation: BatchOperation,
+) -> Result<OperationResult, BatchError> {
+    let (entity, id, version) = match operation {
+        BatchOperation::CreateDomaine { nom_domaine } => {
+            let domaine = store.create_domaine(user_id, nom_domaine, Some(user_id)).await?;
+            (Entity::Domaine, domaine.id, Some(domaine.version))
+        }
+        BatchOperation::CreateExploitation { domaine_id, type_exploitation_id, nom_exploitation } => {
+            let domaine_id = resolve(results, &domaine_id, Entity::Domaine)?;
+            check_owner(store, user_id, Entity::Domaine, domaine_id).await?;
+            let exploitation = store
+                .create_exploitation(type_exploitation_id, domaine_id, nom_exploitation, Some(user_id))
+                .await?;
+            (Entity::Exploitation, exploitation.id, Some(exploitation.version))
+        }
+        BatchOperation::CreateElement { exploitation_id, nom_element, quantite } => {
+            let exploitation_id = resolve(results, &exploitation_id, Entity::Exploitation)?;
+            check_owner(store, user_id, Entity::Exploitation, exploitation_id).await?;
+            let element = store
+                .create_element(exploitation_id, nom_element, quantite, Some(user_id))
+                .await?;
+            (Entity::Element, element.id, Some(element.version))
+        }
+        BatchOperation::CreateProduction { element_id, quantite_produite, unite_production, date_de_production } => {
+            let element_id = resolve(results, &element_id, Entity::Element)?;
+            check_owner(store, user_id, Entity::Element, element_id).await?;
+            let production = store
+                .create_production(
+                    element_id,
+                    quantite_produite,
+                    unite_production,
+                    date_de_production,
+                    Some(user_id),
+                )
+                .await?;
+            (Entity::Production, production.id, Some(production.version))
+        }
+        BatchOperation::UpdateDomaine { id, version, nom_domaine } => {
+            let id = resolve(results, &id, Entity::Domaine)?;
+            check_owner(store, user_id, Entity::Domaine, id).await?;
+            let applied = store.update_domaine(id, version, nom_domaine).await?;
+            written(store, Entity::Domaine, id, applied).await?;
+            (Entity::Domaine, id, Some(version + 1))
+        }
+        BatchOperation::UpdateExploitation { id, version, type_exploitation_id, nom_exploitation } => {
+            let id = resolve(results, &id, Entity::Exploitation)?;
+            check_owner(store, user_id, Entity::Exploitation, id).await?;
+            let applied = store
+                .update_exploitation(id, version, type_exploitation_id, nom_exploitation)
+                .await?;
+            written(store, Entity::Exploitation, id, applied).await?;
+            (Entity::Exploitation, id, Some(version + 1))
+        }
+        BatchOperation::UpdateElement { id, version, nom_element, quantite } => {
+            let id = resolve(results, &id, Entity::Element)?;
+            check_owner(store, user_id, Entity::Element, id).await?;
+            let applied = store.update_element(id, version, nom_element, quantite).await?;
+            written(store, Entity::Element, id, applied).await?;
+            (Entity::Element, id, Some(version + 1))
+        }
+        BatchOperation::UpdateProduction { id, version, quantite_produite, unite_production, date_de_production } => {
+            let id = resolve(results, &id, Entity::Production)?;
+            check_owner(store, user_id, Entity::Production, id).await?;
+            let applied = store
+                .update_production(id, version, quantite_produite, unite_production, date_de_production)
+                .await?;
+            written(store, Entity::Production, id, applied).await?;
+            (Entity::Production, id, Some(version + 1))
+        }
+        BatchOperation::DeleteDomaine { id, version } => delete(store, user_id, results, Entity::Domaine, id, version).await?,
+        BatchOperation::DeleteExploitation { id, version } => {
+            delete(store, user_id, results, Entity::Exploitation, id, version).await?
+        }
+        BatchOperation::DeleteElement { id, version } => delete(store, user_id, results, Entity::Element, id, version).await?,
+        BatchOperation::DeleteProduction { id, version } => {
+            delete(store, user_id, results, Entity::Production, id, version).await?
+        }
+    };
+
+    Ok(OperationResult { index, entity, id, version })
+}
+
+async fn delete<S: BatchStore>(
+    store: &mut S,
+    user_id: i32,
+    results: &[OperationResult],
+    entity: Entity,
+    id: Ref,
+    version: i32,
+) -> Result<(Entity, i32, Option<i32>), BatchError> {
+    let id = resolve(results, &id, entity)?;
+    check_owner(store, user_id, entity, id).await?;
+    let applied = store.delete(entity, id, version).await?;
+    written(store, entity, id, applied).await?;
+    Ok((entity, id, None))
+}
+
+// Traduire une référence "$N" en ID produit par une opération précédente du lot
+fn resolve(results: &[OperationResult], reference: &Ref, entity: Entity) -> Result<i32, BatchError> {
+    match reference {
+        Ref::Id(id) => Ok(*id),
+        Ref::Result(reference) => reference
+            .strip_prefix('$')
+            .and_then(|n| n.parse::<usize>().ok())
+            .and_then(|n| results.get(n))
+            .filter(|result| result.entity == entity)
+            .map(|result| result.id)
+            .ok_or_else(|| BatchError::new(BatchErrorKind::Invalid, "Référence invalide")),
+    }
+}
+
+async fn check_owner<S: BatchStore>(store: &mut S, user_id: i32, entity: Entity, id: i32) -> Result<(), BatchError> {
+    match store.owner_of(entity, id).await? {
+        Some(owner) if owner == user_id => Ok(()),
+        Some(_) => Err(BatchError::new(BatchErrorKind::Forbidden, "Non autorisé")),
+        None => Err(BatchError::new(BatchErrorKind::NotFound, "Ressource introuvable")),
+    }
+}
+
+// Une mise à jour ou suppression conditionnelle qui n'a touché aucune ligne est un conflit de version
+async fn written<S: BatchStore>(store: &mut S, entity: Entity, id: i32, applied: bool) -> Result<(), BatchError> {
+    if applied {
+        return Ok(());
+    }
+
+    match store.current_version(entity, id).await? {
+        Some(version) => Err(BatchError {
+            index: None,
+            kind: BatchErrorKind::Conflict,
+            message: "La ressource a été modifiée entre-temps".to_string(),
+            current_version: Some(version),
+        }),
+        None => Err(BatchError::new(BatchErrorKind::NotFound, "Ressource introuvable")),
+    }
+}
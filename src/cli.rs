@@ -0,0 +1,157 @@
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand};
+
+use crate::repository::Repositories;
+use crate::seed::{self, SeedOptions};
+use crate::user::{NewUser, User, UserChanges, TYPE_ADMINISTRATEUR};
+
+pub type CliError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Parser)]
+#[command(name = "aquafarm-backend", about = "Serveur AquaFarm et commandes d'administration")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Démarrer le serveur HTTP (commande par défaut)
+    Serve,
+    /// Appliquer les migrations puis quitter
+    Migrate,
+    /// Créer un compte administrateur
+    CreateAdmin {
+        #[arg(long)]
+        email: String,
+        #[arg(long)]
+        nom: String,
+        #[arg(long)]
+        prenom: String,
+        #[arg(long, default_value = "")]
+        numero_telephone: String,
+        /// Mot de passe en clair, haché avant l'enregistrement
+        #[arg(long, env = "AQUAFARM_ADMIN_PASSWORD", hide_env_values = true)]
+        mot_de_passe: String,
+    },
+    /// Remplacer le mot de passe d'un utilisateur
+    ResetPassword {
+        #[arg(long)]
+        email: String,
+        #[arg(long, env = "AQUAFARM_NEW_PASSWORD", hide_env_values = true)]
+        mot_de_passe: String,
+    },
+    /// Lister les domaines, éventuellement ceux d'un seul utilisateur
+    ListDomaines {
+        #[arg(long)]
+        email: Option<String>,
+    },
+    /// Insérer les types d'utilisateur, d'exploitation et d'élément par défaut
+    Seed {
+        /// Générer aussi des fermes de démonstration appartenant à cet utilisateur (email)
+        #[arg(long)]
+        demo: Option<String>,
+        /// Graine du générateur : mêmes paramètres, mêmes données
+        #[arg(long, default_value_t = 1)]
+        graine: u64,
+        #[arg(long, default_value_t = 2)]
+        domaines: u32,
+        /// Nombre d'années de productions quotidiennes
+        #[arg(long, default_value_t = 1)]
+        annees: u32,
+        /// Premier jour de production (AAAA-MM-JJ)
+        #[arg(long, default_value = "2023-01-01")]
+        debut: NaiveDate,
+    },
+}
+
+// Exécuter une commande d'administration ; serve et migrate sont traités par le binaire
+pub async fn run(repos: &Repositories, command: Command) -> Result<(), CliError> {
+    match command {
+        Command::Serve | Command::Migrate => Ok(()),
+        Command::CreateAdmin { email, nom, prenom, numero_telephone, mot_de_passe } => {
+            let type_user_id = type_user_id(repos, TYPE_ADMINISTRATEUR).await?;
+            let user = repos
+                .users
+                .create(
+                    NewUser { type_user_id, nom, prenom, email, numero_telephone, mot_de_passe },
+                    None,
+                )
+                .await?;
+
+            println!("Administrateur {} créé (id {}).", user.email, user.id);
+            Ok(())
+        }
+        Command::ResetPassword { email, mot_de_passe } => {
+            let user = find_user(repos, &email).await?;
+            let changes = UserChanges {
+                mot_de_passe: Some(hash(mot_de_passe, DEFAULT_COST)?),
+                ..UserChanges::default()
+            };
+            repos.users.update(user.id, changes).await?;
+
+            println!("Mot de passe de {} modifié.", user.email);
+            Ok(())
+        }
+        Command::ListDomaines { email } => {
+            let domaines = match email {
+                Some(email) => {
+                    let user = find_user(repos, &email).await?;
+                    repos.domaines.get_all_by_user_id(user.id, None).await?
+                }
+                None => repos.domaines.get_all(None).await?,
+            };
+
+            for domaine in domaines {
+                println!(
+                    "{}\t{}\tutilisateur {}\tversion {}",
+                    domaine.id, domaine.nom_domaine, domaine.user_id, domaine.version
+                );
+            }
+            Ok(())
+        }
+        Command::Seed { demo: None, .. } => {
+            seed::references(repos).await?;
+
+            println!("Types de référence en place.");
+            Ok(())
+        }
+        Command::Seed { demo: Some(email), graine, domaines, annees, debut } => {
+            let user = find_user(repos, &email).await?;
+            let options = SeedOptions { graine, domaines, annees, debut };
+            let report = seed::demo(repos, user.id, &options).await?;
+
+            println!(
+                "Données de démonstration créées pour {} : {} domaines, {} exploitations, {} éléments, {} productions, \
+                 {} relevés de qualité de l'eau, {} alimentations.",
+                user.email,
+                report.domaines,
+                report.exploitations,
+                report.elements,
+                report.productions,
+                report.qualite_eau,
+                report.alimentations
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn find_user(repos: &Repositories, email: &str) -> Result<User, CliError> {
+    match repos.users.get_by_email(email).await {
+        Ok(user) => Ok(user),
+        Err(sqlx::Error::RowNotFound) => Err(format!("Aucun utilisateur avec l'email {}", email).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// ID d'un type d'utilisateur, créé s'il n'existe pas encore
+async fn type_user_id(repos: &Repositories, nom: &str) -> Result<i32, CliError> {
+    let existing = repos.references.get_types_user(None).await?;
+    if let Some(type_user) = existing.into_iter().find(|t| t.nom_type_user == nom) {
+        return Ok(type_user.id);
+    }
+
+    Ok(repos.references.create_type_user(nom.to_string(), None).await?.id)
+}
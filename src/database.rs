@@ -0,0 +1,126 @@
+use sqlx::migrate::MigrateError;
+use sqlx::mysql::{MySqlPool, MySqlPoolOptions};
+use std::time::Duration;
+
+use crate::repository::Repositories;
+use crate::schema;
+
+// Attente maximale entre deux tentatives de connexion au démarrage
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+// Taille maximale des pools, réglée par DB_MAX_CONNECTIONS (10 par défaut, comme sqlx)
+// sqlx 0.6 ne permet pas de la relire sur le pool : les métriques la reprennent d'ici
+pub fn max_connections() -> u32 {
+    std::env::var("DB_MAX_CONNECTIONS")
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .unwrap_or(10)
+        .max(1)
+}
+
+// Base de données choisie d'après le schéma de DATABASE_URL (mysql:, postgres: ou sqlite:)
+pub enum Database {
+    MySql(MySqlPool),
+    #[cfg(feature = "postgres")]
+    Postgres(sqlx::PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::SqlitePool),
+}
+
+impl Database {
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        if url.starts_with("sqlite:") {
+            return Self::connect_sqlite(url).await;
+        }
+        if url.starts_with("postgres:") || url.starts_with("postgresql:") {
+            return Self::connect_postgres(url).await;
+        }
+
+        let pool = MySqlPoolOptions::new().max_connections(max_connections()).connect(url).await?;
+        Ok(Database::MySql(pool))
+    }
+
+    // Réessayer avec une attente doublée à chaque échec : la base peut démarrer après le serveur
+    // Nombre de tentatives réglé par DB_CONNECT_ATTEMPTS (10 par défaut)
+    pub async fn connect_with_retry(url: &str) -> Result<Self, sqlx::Error> {
+        let attempts = std::env::var("DB_CONNECT_ATTEMPTS")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(10)
+            .max(1);
+        let mut delay = Duration::from_millis(500);
+        let mut attempt = 1;
+
+        loop {
+            match Self::connect(url).await {
+                Ok(database) => return Ok(database),
+                // Une URL invalide ou un backend absent ne se corrige pas en attendant
+                Err(e) if attempt < attempts && !matches!(e, sqlx::Error::Configuration(_)) => {
+                    tracing::warn!(
+                        error = %e,
+                        attempt,
+                        attempts,
+                        "Base de données injoignable, nouvelle tentative dans {:?}",
+                        delay
+                    );
+                    actix_web::rt::time::sleep(delay).await;
+                    delay = (delay * 2).min(MAX_RETRY_DELAY);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Le fichier est créé au premier démarrage
+    #[cfg(feature = "sqlite")]
+    async fn connect_sqlite(url: &str) -> Result<Self, sqlx::Error> {
+        use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+        use std::str::FromStr;
+
+        let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        let pool = SqlitePoolOptions::new().max_connections(max_connections()).connect_with(options).await?;
+        Ok(Database::Sqlite(pool))
+    }
+
+    #[cfg(not(feature = "sqlite"))]
+    async fn connect_sqlite(_url: &str) -> Result<Self, sqlx::Error> {
+        Err(sqlx::Error::Configuration(
+            "SQLite n'est pas disponible : recompiler avec --features sqlite".into(),
+        ))
+    }
+
+    #[cfg(feature = "postgres")]
+    async fn connect_postgres(url: &str) -> Result<Self, sqlx::Error> {
+        let pool = sqlx::postgres::PgPoolOptions::new().max_connections(max_connections()).connect(url).await?;
+        Ok(Database::Postgres(pool))
+    }
+
+    #[cfg(not(feature = "postgres"))]
+    async fn connect_postgres(_url: &str) -> Result<Self, sqlx::Error> {
+        Err(sqlx::Error::Configuration(
+            "PostgreSQL n'est pas disponible : recompiler avec --features postgres".into(),
+        ))
+    }
+
+    // Appliquer les migrations du dialecte correspondant
+    pub async fn migrate(&self) -> Result<(), MigrateError> {
+        match self {
+            Database::MySql(pool) => schema::migrate(pool).await,
+            #[cfg(feature = "postgres")]
+            Database::Postgres(pool) => schema::migrate_postgres(pool).await,
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(pool) => schema::migrate_sqlite(pool).await,
+        }
+    }
+
+    pub fn repositories(self) -> Repositories {
+        match self {
+            Database::MySql(pool) => Repositories::mysql(pool),
+            #[cfg(feature = "postgres")]
+            Database::Postgres(pool) => Repositories::postgres(pool),
+            #[cfg(feature = "sqlite")]
+            Database::Sqlite(pool) => Repositories::sqlite(pool),
+        }
+    }
+}
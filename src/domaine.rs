@@ -1,30 +1,51 @@
-use sqlx::{mysql::MySqlPool, FromRow, Error as SqlxError};
+use sqlx::{Connection, MySqlConnection, FromRow, Error as SqlxError};
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::repository::RepoError;
+use crate::search;
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+use crate::trash;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+#[graphql(complex)]
 pub struct Domaine {
     pub id: i32,
     pub user_id: i32,
     pub nom_domaine: String,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
 }
 
 impl Domaine {
     // Ajouter un domaine
     pub async fn create(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         user_id: i32,
         nom_domaine: String,
-    ) -> Result<Self, SqlxError> {
+        created_by: Option<i32>,
+    ) -> Result<Self, SqlxError>
+    {
+        let now = timestamps::now();
         // Insérer le domaine dans la base de données
-        let insert_result = sqlx::query!(
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO domaines (user_id, nom_domaine)
-            VALUES (?, ?)
+            INSERT INTO domaines (user_id, nom_domaine, nom_recherche, created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
-            user_id,
-            nom_domaine
         )
-        .execute(pool)
+        .bind(user_id)
+        .bind(&nom_domaine)
+        .bind(search::normalize(&nom_domaine))
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
+        .execute(&mut *conn)
         .await?;
 
         // Obtenir l'ID de la dernière insertion
@@ -35,75 +56,148 @@ impl Domaine {
             id: last_id,
             user_id,
             nom_domaine,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
-    // Récupérer tous les domaines
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, sqlx::Error> {
-        let domaines = sqlx::query_as!(
-            Domaine,
+    // Récupérer tous les domaines, éventuellement modifiés depuis une date
+    pub async fn get_all(
+        conn: &mut MySqlConnection,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let domaines = sqlx::query_as::<_, Domaine>(
             r#"
-            SELECT id, user_id, nom_domaine
+            SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
             FROM domaines
-            "#
+            WHERE deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
+            "#,
         )
-        .fetch_all(pool)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
-    
+
         Ok(domaines)
     }
 
-    // Mettre à jour un domaine
+    // Récupérer un domaine par son ID
+    pub async fn get_by_id(conn: &mut MySqlConnection, id: i32) -> Result<Self, sqlx::Error>
+    {
+        let domaine = sqlx::query_as::<_, Domaine>(
+            r#"
+            SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
+            FROM domaines
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(domaine)
+    }
+
+    // Mettre à jour un domaine si sa version n'a pas changé
+    // Renvoie false si aucune ligne ne correspond à (id, version)
     pub async fn update_domaine(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         id: i32,
+        version: i32,
         nom_domaine: Option<String>,
-    ) -> Result<(), sqlx::Error> {
-        if let Some(nom) = nom_domaine {
-            sqlx::query!(
-                r#"
-                UPDATE domaines
-                SET nom_domaine = ?
-                WHERE id = ?
-                "#,
-                nom,
-                id
-            )
-            .execute(pool)
-            .await?;
-        }
+    ) -> Result<bool, sqlx::Error>
+    {
+        let result = sqlx::query(
+            r#"
+            UPDATE domaines
+            SET nom_domaine = COALESCE(?, nom_domaine), nom_recherche = COALESCE(?, nom_recherche),
+                version = version + 1, updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(&nom_domaine)
+        .bind(nom_domaine.as_deref().map(search::normalize))
+        .bind(timestamps::now())
+        .bind(id)
+        .bind(version)
+        .execute(&mut *conn)
+        .await?;
 
-        Ok(())
+        Ok(result.rows_affected() > 0)
     }
 
-    // Supprimer un domaine
-    pub async fn delete_domaine(pool: &MySqlPool, id: i32) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+    // Mettre un domaine à la corbeille si sa version n'a pas changé, en laissant une trace pour la synchronisation
+    pub async fn delete_domaine(conn: &mut MySqlConnection, id: i32, version: i32) -> Result<bool, RepoError>
+    {
+        let mut tx = conn.begin().await?;
+
+        let owner: Option<i32> = sqlx::query_scalar("SELECT user_id FROM domaines WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?;
+
+        // La ligne reste verrouillée jusqu'à la fin de la transaction : aucun enfant ne peut s'y rattacher
+        // entre le décompte de ses descendants et sa mise à la corbeille
+        let current: Option<i32> = sqlx::query_scalar("SELECT version FROM domaines WHERE id = ? AND deleted_at IS NULL FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?;
+        if current != Some(version) {
+            return Ok(false);
+        }
+        let dependents = trash::subtree(&mut tx, Entity::Domaine, id).await?;
+        if !dependents.is_empty() {
+            return Err(RepoError::Conflict(dependents.dependents()));
+        }
+
+        let now = timestamps::now();
+        let result = sqlx::query(
             r#"
-            DELETE FROM domaines WHERE id = ?
+            UPDATE domaines
+            SET deleted_at = ?, version = version + 1, updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
             "#,
-            id
         )
-        .execute(pool)
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .bind(version)
+        .execute(&mut tx)
         .await?;
-    
-        Ok(())
+
+        match owner {
+            Some(user_id) if result.rows_affected() > 0 => {
+                Tombstone::record(&mut tx, Entity::Domaine, id, id, user_id).await?;
+                tx.commit().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 
     // Récupérer tous les domaines pour un utilisateur donné
-    pub async fn get_all_by_user_id(pool: &MySqlPool, user_id: i32) -> Result<Vec<Self>, sqlx::Error> {
-        let domaines = sqlx::query_as!(
-            Domaine,
+    pub async fn get_all_by_user_id(
+        conn: &mut MySqlConnection,
+        user_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let domaines = sqlx::query_as::<_, Domaine>(
             r#"
-            SELECT id, user_id, nom_domaine
+            SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
             FROM domaines
-            WHERE user_id = ?
+            WHERE user_id = ? AND deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
             "#,
-            user_id
         )
-        .fetch_all(pool)
+        .bind(user_id)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
-    
+
         Ok(domaines)
     }
 }
@@ -1,32 +1,53 @@
 use serde::{Deserialize, Serialize};
-use sqlx::{mysql::MySqlPool, FromRow, Error};
+use sqlx::{Connection, MySql, MySqlConnection, FromRow, QueryBuilder, Error};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::repository::RepoError;
+use crate::search;
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+use crate::trash;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+#[graphql(complex)]
 pub struct Element {
     pub id: i32,                   // ID unique de l'élément
     pub exploitation_id: i32,      // Référence à l'exploitation
     pub nom_element: String,       // Nom de l'élément
     pub quantite: i32,             // Quantité de l'élément
+    pub version: i32,              // Version pour le contrôle de concurrence
+    pub created_at: DateTime<Utc>, // Date de création
+    pub updated_at: DateTime<Utc>, // Date de dernière modification
+    pub created_by: Option<i32>,   // Utilisateur ayant créé l'élément
 }
 
 impl Element {
     // Ajouter un nouvel élément
     pub async fn create(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         exploitation_id: i32,
         nom_element: String,
         quantite: i32,
-    ) -> Result<Self, Error> {
-        let insert_result = sqlx::query!(
+        created_by: Option<i32>,
+    ) -> Result<Self, Error>
+    {
+        let now = timestamps::now();
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO elements (exploitation_id, nom_element, quantite)
-            VALUES (?, ?, ?)
+            INSERT INTO elements (exploitation_id, nom_element, nom_recherche, quantite, created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
-            exploitation_id,
-            nom_element,
-            quantite
         )
-        .execute(pool)
+        .bind(exploitation_id)
+        .bind(&nom_element)
+        .bind(search::normalize(&nom_element))
+        .bind(quantite)
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
+        .execute(&mut *conn)
         .await?;
 
         let last_id = insert_result.last_insert_id() as i32;
@@ -36,19 +57,29 @@ impl Element {
             exploitation_id,
             nom_element,
             quantite,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
-    // Récupérer tous les éléments
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, Error> {
-        let elements = sqlx::query_as!(
-            Element,
+    // Récupérer tous les éléments, éventuellement modifiés depuis une date
+    pub async fn get_all(
+        conn: &mut MySqlConnection,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, Error>
+    {
+        let elements = sqlx::query_as::<_, Element>(
             r#"
-            SELECT id, exploitation_id, nom_element, quantite
+            SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
             FROM elements
-            "#
+            WHERE deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
+            "#,
         )
-        .fetch_all(pool)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
 
         Ok(elements)
@@ -56,35 +87,154 @@ impl Element {
 
     // Récupérer les éléments d'une exploitation spécifique
     pub async fn get_by_exploitation_id(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         exploitation_id: i32,
-    ) -> Result<Vec<Self>, Error> {
-        let elements = sqlx::query_as!(
-            Element,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, Error>
+    {
+        let elements = sqlx::query_as::<_, Element>(
             r#"
-            SELECT id, exploitation_id, nom_element, quantite
+            SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
             FROM elements
-            WHERE exploitation_id = ?
+            WHERE exploitation_id = ? AND deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
             "#,
-            exploitation_id
         )
-        .fetch_all(pool)
+        .bind(exploitation_id)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
 
         Ok(elements)
     }
 
-    // Supprimer un élément
-    pub async fn delete(pool: &MySqlPool, id: i32) -> Result<(), Error> {
-        sqlx::query!(
+    // Récupérer les éléments de plusieurs exploitations
+    pub async fn get_by_exploitation_ids(conn: &mut MySqlConnection, exploitation_ids: &[i32]) -> Result<Vec<Self>, sqlx::Error>
+    {
+        // Une liste IN vide n'est pas du SQL valide
+        if exploitation_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new(
+            r#"
+            SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+            FROM elements
+            WHERE deleted_at IS NULL AND exploitation_id IN ("#,
+        );
+        let mut ids = query.separated(", ");
+        for id in exploitation_ids {
+            ids.push_bind(*id);
+        }
+        ids.push_unseparated(") ORDER BY id");
+
+        let elements = query.build_query_as::<Self>().fetch_all(&mut *conn).await?;
+
+        Ok(elements)
+    }
+
+    // Récupérer un élément par ID
+    pub async fn get_by_id(conn: &mut MySqlConnection, id: i32) -> Result<Self, Error>
+    {
+        let element = sqlx::query_as::<_, Element>(
+            r#"
+            SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+            FROM elements
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(element)
+    }
+
+    // Mettre à jour un élément si sa version n'a pas changé
+    pub async fn update(
+        conn: &mut MySqlConnection,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error>
+    {
+        let result = sqlx::query(
+            r#"
+            UPDATE elements
+            SET nom_element = COALESCE(?, nom_element),
+                nom_recherche = COALESCE(?, nom_recherche),
+                quantite = COALESCE(?, quantite),
+                version = version + 1,
+                updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(&nom_element)
+        .bind(nom_element.as_deref().map(search::normalize))
+        .bind(quantite)
+        .bind(timestamps::now())
+        .bind(id)
+        .bind(version)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Mettre un élément à la corbeille si sa version n'a pas changé, en laissant une trace
+    pub async fn delete(conn: &mut MySqlConnection, id: i32, version: i32) -> Result<bool, RepoError>
+    {
+        let mut tx = conn.begin().await?;
+
+        let owner: Option<(i32, i32)> = sqlx::query_as(
+            r#"
+            SELECT d.id AS domaine_id, d.user_id
+            FROM elements el
+            JOIN exploitations e ON e.id = el.exploitation_id
+            JOIN domaines d ON d.id = e.domaine_id
+            WHERE el.id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?;
+
+        // La ligne reste verrouillée jusqu'à la fin de la transaction : aucun enfant ne peut s'y rattacher
+        // entre le décompte de ses descendants et sa mise à la corbeille
+        let current: Option<i32> = sqlx::query_scalar("SELECT version FROM elements WHERE id = ? AND deleted_at IS NULL FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?;
+        if current != Some(version) {
+            return Ok(false);
+        }
+        let dependents = trash::subtree(&mut tx, Entity::Element, id).await?;
+        if !dependents.is_empty() {
+            return Err(RepoError::Conflict(dependents.dependents()));
+        }
+
+        let now = timestamps::now();
+        let result = sqlx::query(
             r#"
-            DELETE FROM elements WHERE id = ?
+            UPDATE elements
+            SET deleted_at = ?, version = version + 1, updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
             "#,
-            id
         )
-        .execute(pool)
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .bind(version)
+        .execute(&mut tx)
         .await?;
 
-        Ok(())
+        match owner {
+            Some((domaine_id, user_id)) if result.rows_affected() > 0 => {
+                Tombstone::record(&mut tx, Entity::Element, id, domaine_id, user_id).await?;
+                tx.commit().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 }
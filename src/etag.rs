@@ -0,0 +1,81 @@
+use actix_web::{http::header, HttpRequest, HttpResponse};
+use std::future::Future;
+
+// Construire la valeur de l'en-tête ETag à partir de la version d'une ligne
+pub fn etag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+// Versions acceptées par l'en-tête If-Match : « * » ou une liste d'ETags séparés par des virgules
+#[derive(Debug, Clone, PartialEq)]
+pub enum IfMatch {
+    Any,
+    Versions(Vec<i32>),
+}
+
+impl IfMatch {
+    // La version courante satisfait-elle l'en-tête ?
+    pub fn matches(&self, current: i32) -> bool {
+        match self {
+            IfMatch::Any => true,
+            IfMatch::Versions(versions) => versions.contains(&current),
+        }
+    }
+}
+
+// Lire les versions attendues depuis l'en-tête If-Match
+//
+// L'en-tête est obligatoire pour les mises à jour et les suppressions :
+// son absence renvoie 428, une valeur illisible renvoie 412.
+pub fn if_match(req: &HttpRequest) -> Result<IfMatch, HttpResponse> {
+    let value = match req.headers().get(header::IF_MATCH) {
+        Some(value) => value,
+        None => {
+            return Err(HttpResponse::PreconditionRequired()
+                .body("En-tête If-Match requis pour modifier la ressource"))
+        }
+    };
+    let invalid = || HttpResponse::PreconditionFailed().body("En-tête If-Match invalide");
+
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(IfMatch::Any);
+    }
+
+    value
+        .split(',')
+        .map(|tag| tag.trim().trim_start_matches("W/").trim_matches('"').parse::<i32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map(IfMatch::Versions)
+        .map_err(|_| invalid())
+}
+
+// Version à passer à la mise à jour conditionnelle
+//
+// Un seul ETag est transmis tel quel : la requête conditionnelle détecte elle-même un conflit.
+// Pour « * » ou une liste, la version courante est relue puis confrontée à l'en-tête.
+pub async fn expected_version(
+    req: &HttpRequest,
+    current: impl Future<Output = Result<i32, sqlx::Error>>,
+    not_found: &'static str,
+) -> Result<i32, HttpResponse> {
+    match if_match(req)? {
+        IfMatch::Versions(versions) if versions.len() == 1 => Ok(versions[0]),
+        expected => match current.await {
+            Ok(current) if expected.matches(current) => Ok(current),
+            Ok(current) => Err(precondition_failed(current)),
+            Err(sqlx::Error::RowNotFound) => Err(HttpResponse::NotFound().body(not_found)),
+            Err(e) => {
+                tracing::error!(error = ?e, "Erreur lors de la lecture de la version courante");
+                Err(HttpResponse::InternalServerError().body("Erreur lors de la récupération"))
+            },
+        },
+    }
+}
+
+// Réponse renvoyée quand la version fournie ne correspond plus à celle en base
+pub fn precondition_failed(current_version: i32) -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+        .insert_header((header::ETAG, etag(current_version)))
+        .body("La ressource a été modifiée entre-temps")
+}
@@ -0,0 +1,73 @@
+use actix_web_lab::sse;
+use futures::stream::{self, Stream};
+use serde::Serialize;
+use std::convert::Infallible;
+use tokio::sync::broadcast::{self, error::RecvError};
+use utoipa::ToSchema;
+
+use crate::sync::Operation;
+use crate::tombstone::Entity;
+
+// Événements conservés pour un abonné lent avant qu'il ne soit déclaré en retard
+const CAPACITY: usize = 1024;
+
+// Écriture réussie sur une ligne, diffusée aux abonnés de son domaine
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FarmEvent {
+    pub domaine_id: i32,
+    pub entity: Entity,
+    pub operation: Operation,
+    pub id: i32,
+    // Version après l'écriture, absente pour une suppression
+    pub version: Option<i32>,
+}
+
+// Canal partagé par tous les workers : les dépôts publient, les flux SSE s'abonnent
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<FarmEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        EventBus { sender }
+    }
+}
+
+impl EventBus {
+    // Sans abonné, l'événement est simplement perdu
+    pub fn publish(&self, event: FarmEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<FarmEvent> {
+        self.sender.subscribe()
+    }
+}
+
+// Flux SSE des événements d'un domaine
+// Un abonné trop lent reçoit un événement `resync` (nombre d'événements perdus) et doit relire le domaine
+pub fn domaine_stream(
+    receiver: broadcast::Receiver<FarmEvent>,
+    domaine_id: i32,
+) -> impl Stream<Item = Result<sse::Event, Infallible>> {
+    stream::unfold(receiver, move |mut receiver| async move {
+        loop {
+            let data = match receiver.recv().await {
+                Ok(event) if event.domaine_id == domaine_id => match sse::Data::new_json(&event) {
+                    Ok(data) => data.event("change"),
+                    Err(e) => {
+                        tracing::error!(error = ?e, "Événement impossible à sérialiser");
+                        continue;
+                    },
+                },
+                Ok(_) => continue,
+                Err(RecvError::Lagged(skipped)) => sse::Data::new(skipped.to_string()).event("resync"),
+                Err(RecvError::Closed) => return None,
+            };
+
+            return Some((Ok(sse::Event::from(data)), receiver));
+        }
+    })
+}
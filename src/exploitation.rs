@@ -1,12 +1,26 @@
-use sqlx::{mysql::MySqlPool, FromRow};
+use sqlx::{Connection, MySql, MySqlConnection, FromRow, QueryBuilder};
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::repository::RepoError;
+use crate::search;
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+use crate::trash;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
+#[graphql(complex)]
 pub struct Exploitation {
     pub id: i32,
     pub type_exploitation_id: i32,
     pub domaine_id: i32,
     pub nom_exploitation: String,
+    pub version: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,21 +33,28 @@ pub struct CreateExploitation {
 impl Exploitation {
     // Ajouter une exploitation
     pub async fn create(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         type_exploitation_id: i32,
         domaine_id: i32,
         nom_exploitation: String,
-    ) -> Result<Self, sqlx::Error> {
-        let insert_result = sqlx::query!(
+        created_by: Option<i32>,
+    ) -> Result<Self, sqlx::Error>
+    {
+        let now = timestamps::now();
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO exploitations (type_exploitation_id, domaine_id, nom_exploitation)
-            VALUES (?, ?, ?)
+            INSERT INTO exploitations (type_exploitation_id, domaine_id, nom_exploitation, nom_recherche, created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
-            type_exploitation_id,
-            domaine_id,
-            nom_exploitation
         )
-        .execute(pool)
+        .bind(type_exploitation_id)
+        .bind(domaine_id)
+        .bind(&nom_exploitation)
+        .bind(search::normalize(&nom_exploitation))
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
+        .execute(&mut *conn)
         .await?;
 
         let last_id = insert_result.last_insert_id() as i32;
@@ -43,51 +64,183 @@ impl Exploitation {
             type_exploitation_id,
             domaine_id,
             nom_exploitation,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
-    // Récupérer toutes les exploitations
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, sqlx::Error> {
-        let exploitations = sqlx::query_as!(
-            Exploitation,
+    // Récupérer toutes les exploitations, éventuellement modifiées depuis une date
+    pub async fn get_all(
+        conn: &mut MySqlConnection,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let exploitations = sqlx::query_as::<_, Exploitation>(
             r#"
-            SELECT id, type_exploitation_id, domaine_id, nom_exploitation
+            SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version, created_at, updated_at, created_by
             FROM exploitations
-            "#
+            WHERE deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
+            "#,
         )
-        .fetch_all(pool)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
 
         Ok(exploitations)
     }
 
-    // Supprimer une exploitation par ID
-    pub async fn delete(pool: &MySqlPool, id: i32) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+    // Récupérer une exploitation par ID
+    pub async fn get_by_id(conn: &mut MySqlConnection, id: i32) -> Result<Self, sqlx::Error>
+    {
+        let exploitation = sqlx::query_as::<_, Exploitation>(
+            r#"
+            SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version, created_at, updated_at, created_by
+            FROM exploitations
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(exploitation)
+    }
+
+    // Mettre à jour une exploitation si sa version n'a pas changé
+    pub async fn update(
+        conn: &mut MySqlConnection,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, sqlx::Error>
+    {
+        let result = sqlx::query(
             r#"
-            DELETE FROM exploitations WHERE id = ?
+            UPDATE exploitations
+            SET type_exploitation_id = COALESCE(?, type_exploitation_id),
+                nom_exploitation = COALESCE(?, nom_exploitation),
+                nom_recherche = COALESCE(?, nom_recherche),
+                version = version + 1,
+                updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
             "#,
-            id
         )
-        .execute(pool)
+        .bind(type_exploitation_id)
+        .bind(&nom_exploitation)
+        .bind(nom_exploitation.as_deref().map(search::normalize))
+        .bind(timestamps::now())
+        .bind(id)
+        .bind(version)
+        .execute(&mut *conn)
         .await?;
-        Ok(())
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Mettre une exploitation à la corbeille si sa version n'a pas changé, en laissant une trace
+    pub async fn delete(conn: &mut MySqlConnection, id: i32, version: i32) -> Result<bool, RepoError>
+    {
+        let mut tx = conn.begin().await?;
+
+        let owner: Option<(i32, i32)> = sqlx::query_as(
+            r#"
+            SELECT d.id AS domaine_id, d.user_id
+            FROM exploitations e
+            JOIN domaines d ON d.id = e.domaine_id
+            WHERE e.id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?;
+
+        // La ligne reste verrouillée jusqu'à la fin de la transaction : aucun enfant ne peut s'y rattacher
+        // entre le décompte de ses descendants et sa mise à la corbeille
+        let current: Option<i32> = sqlx::query_scalar("SELECT version FROM exploitations WHERE id = ? AND deleted_at IS NULL FOR UPDATE")
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?;
+        if current != Some(version) {
+            return Ok(false);
+        }
+        let dependents = trash::subtree(&mut tx, Entity::Exploitation, id).await?;
+        if !dependents.is_empty() {
+            return Err(RepoError::Conflict(dependents.dependents()));
+        }
+
+        let now = timestamps::now();
+        let result = sqlx::query(
+            r#"
+            UPDATE exploitations
+            SET deleted_at = ?, version = version + 1, updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .bind(version)
+        .execute(&mut tx)
+        .await?;
+
+        match owner {
+            Some((domaine_id, user_id)) if result.rows_affected() > 0 => {
+                Tombstone::record(&mut tx, Entity::Exploitation, id, domaine_id, user_id).await?;
+                tx.commit().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 
     // Récupérer toutes les exploitations pour un domaine donné
-    pub async fn get_by_domaine_id(pool: &MySqlPool, domaine_id: i32) -> Result<Vec<Self>, sqlx::Error> {
-        let exploitations = sqlx::query_as!(
-            Exploitation,
+    pub async fn get_by_domaine_id(
+        conn: &mut MySqlConnection,
+        domaine_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let exploitations = sqlx::query_as::<_, Exploitation>(
             r#"
-            SELECT id, type_exploitation_id, domaine_id, nom_exploitation
+            SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version, created_at, updated_at, created_by
             FROM exploitations
-            WHERE domaine_id = ?
+            WHERE domaine_id = ? AND deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
             "#,
-            domaine_id
         )
-        .fetch_all(pool)
+        .bind(domaine_id)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
 
         Ok(exploitations)
     }
+
+    // Récupérer les exploitations de plusieurs domaines
+    pub async fn get_by_domaine_ids(conn: &mut MySqlConnection, domaine_ids: &[i32]) -> Result<Vec<Self>, sqlx::Error>
+    {
+        // Une liste IN vide n'est pas du SQL valide
+        if domaine_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new(
+            r#"
+            SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version, created_at, updated_at, created_by
+            FROM exploitations
+            WHERE deleted_at IS NULL AND domaine_id IN ("#,
+        );
+        let mut ids = query.separated(", ");
+        for id in domaine_ids {
+            ids.push_bind(*id);
+        }
+        ids.push_unseparated(") ORDER BY id");
+
+        let exploitations = query.build_query_as::<Self>().fetch_all(&mut *conn).await?;
+
+        Ok(exploitations)
+    }
 }
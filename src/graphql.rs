@@ -0,0 +1,428 @@
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{ComplexObject, Context, EmptySubscription, Error, ErrorExtensions, Object, Result, Schema};
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::batch::{BatchError, BatchErrorKind, BatchOperation, OperationResult, Ref};
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::production::Production;
+use crate::repository::Repositories;
+use crate::tombstone::Entity;
+use crate::type_element::TypeElement;
+use crate::type_exploitation::TypeExploitation;
+use crate::type_user::TypeUser;
+
+// Profondeur maximale d'une requête (domaine > exploitation > élément > production, avec une marge)
+const MAX_DEPTH: usize = 10;
+
+pub type FarmSchema = Schema<Query, Mutation, EmptySubscription>;
+
+// Utilisateur connecté, résolu depuis le token avant l'exécution de la requête
+#[derive(Debug, Clone, Copy)]
+pub struct Viewer(pub Option<i32>);
+
+pub fn schema() -> FarmSchema {
+    Schema::build(Query, Mutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+// Exécuter une requête avec ses propres chargeurs : le cache ne survit pas à la requête
+pub async fn execute(
+    schema: &FarmSchema,
+    repos: Repositories,
+    user_id: Option<i32>,
+    request: async_graphql::Request,
+) -> async_graphql::Response {
+    let loader = DataLoader::new(FarmLoader { repos: repos.clone() }, tokio::spawn);
+    let request = request.data(repos).data(Viewer(user_id)).data(loader);
+
+    schema.execute(request).await
+}
+
+// Page GraphiQL pointant vers le point d'accès
+pub fn graphiql(endpoint: &str) -> String {
+    GraphiQLSource::build().endpoint(endpoint).finish()
+}
+
+fn coded(message: &str, code: &'static str) -> Error {
+    Error::new(message).extend_with(|_, e| e.set("code", code))
+}
+
+// Le détail des erreurs de base va dans les journaux, pas dans la réponse
+fn internal(e: impl std::fmt::Debug) -> Error {
+    tracing::error!(error = ?e, "Erreur lors de l'exécution d'une requête GraphQL");
+    coded("Erreur interne", "INTERNAL")
+}
+
+fn batch_error(error: BatchError) -> Error {
+    let code = match error.kind {
+        BatchErrorKind::Invalid => "BAD_REQUEST",
+        BatchErrorKind::Forbidden => "FORBIDDEN",
+        BatchErrorKind::NotFound => "NOT_FOUND",
+        BatchErrorKind::Conflict => "CONFLICT",
+        BatchErrorKind::Dependents => "HAS_DEPENDENTS",
+        BatchErrorKind::Database => "INTERNAL",
+    };
+    let current_version = error.current_version;
+
+    Error::new(error.message).extend_with(|_, e| {
+        e.set("code", code);
+        if let Some(version) = current_version {
+            e.set("currentVersion", version);
+        }
+    })
+}
+
+fn repos<'a>(ctx: &Context<'a>) -> Result<&'a Repositories> {
+    ctx.data::<Repositories>()
+}
+
+fn loader<'a>(ctx: &Context<'a>) -> Result<&'a DataLoader<FarmLoader>> {
+    ctx.data::<DataLoader<FarmLoader>>()
+}
+
+// Toute donnée de ferme exige un token valide et n'est servie qu'à son propriétaire, comme sur /sync,
+// /batch et les routes /users/me. Plus strict que le CRUD REST de /api/v1, qui ne contrôle ni l'un ni l'autre
+fn viewer(ctx: &Context<'_>) -> Result<i32> {
+    ctx.data::<Viewer>()?
+        .0
+        .ok_or_else(|| coded("Non autorisé", "UNAUTHORIZED"))
+}
+
+// false si la ligne n'existe pas, erreur si elle appartient à un autre utilisateur
+async fn owned(ctx: &Context<'_>, entity: Entity, id: i32) -> Result<bool> {
+    let user_id = viewer(ctx)?;
+
+    match repos(ctx)?.domaines.owner_of(entity, id).await.map_err(internal)? {
+        None => Ok(false),
+        Some(owner) if owner == user_id => Ok(true),
+        Some(_) => Err(coded("Accès refusé", "FORBIDDEN")),
+    }
+}
+
+// Les écritures passent par le lot transactionnel, qui contrôle déjà propriété et versions
+async fn apply(ctx: &Context<'_>, operation: BatchOperation) -> Result<OperationResult> {
+    let user_id = viewer(ctx)?;
+    let mut results = repos(ctx)?
+        .batch
+        .execute(user_id, vec![operation])
+        .await
+        .map_err(batch_error)?;
+
+    results.pop().ok_or_else(|| internal("Lot exécuté sans résultat"))
+}
+
+// Clés des chargements groupés : l'ID du parent dont on veut les enfants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DomaineId(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExploitationId(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ElementId(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeExploitationId(pub i32);
+
+// Regroupe les demandes d'un même niveau de la requête en une seule lecture
+pub struct FarmLoader {
+    repos: Repositories,
+}
+
+fn group<K: Hash + Eq, V>(rows: Vec<V>, key: impl Fn(&V) -> K) -> HashMap<K, Vec<V>> {
+    let mut groups: HashMap<K, Vec<V>> = HashMap::new();
+    for row in rows {
+        groups.entry(key(&row)).or_default().push(row);
+    }
+    groups
+}
+
+impl Loader<DomaineId> for FarmLoader {
+    type Value = Vec<Exploitation>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[DomaineId]) -> Result<HashMap<DomaineId, Self::Value>, Self::Error> {
+        let ids: Vec<i32> = keys.iter().map(|k| k.0).collect();
+        let exploitations = self.repos.exploitations.get_by_domaine_ids(&ids).await?;
+
+        Ok(group(exploitations, |e| DomaineId(e.domaine_id)))
+    }
+}
+
+impl Loader<ExploitationId> for FarmLoader {
+    type Value = Vec<Element>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[ExploitationId]) -> Result<HashMap<ExploitationId, Self::Value>, Self::Error> {
+        let ids: Vec<i32> = keys.iter().map(|k| k.0).collect();
+        let elements = self.repos.elements.get_by_exploitation_ids(&ids).await?;
+
+        Ok(group(elements, |e| ExploitationId(e.exploitation_id)))
+    }
+}
+
+impl Loader<ElementId> for FarmLoader {
+    type Value = Vec<Production>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[ElementId]) -> Result<HashMap<ElementId, Self::Value>, Self::Error> {
+        let ids: Vec<i32> = keys.iter().map(|k| k.0).collect();
+        let productions = self.repos.productions.get_by_element_ids(&ids).await?;
+
+        Ok(group(productions, |p| ElementId(p.element_id)))
+    }
+}
+
+impl Loader<TypeExploitationId> for FarmLoader {
+    type Value = TypeExploitation;
+    type Error = Arc<sqlx::Error>;
+
+    // Table de référence courte : lue en entier, une fois par requête
+    async fn load(
+        &self,
+        keys: &[TypeExploitationId],
+    ) -> Result<HashMap<TypeExploitationId, Self::Value>, Self::Error> {
+        let types = self.repos.references.get_types_exploitation(None).await?;
+
+        Ok(types
+            .into_iter()
+            .map(|t| (TypeExploitationId(t.id), t))
+            .filter(|(id, _)| keys.contains(id))
+            .collect())
+    }
+}
+
+#[ComplexObject]
+impl Domaine {
+    async fn exploitations(&self, ctx: &Context<'_>) -> Result<Vec<Exploitation>> {
+        let exploitations = loader(ctx)?.load_one(DomaineId(self.id)).await.map_err(internal)?;
+        Ok(exploitations.unwrap_or_default())
+    }
+}
+
+#[ComplexObject]
+impl Exploitation {
+    async fn type_exploitation(&self, ctx: &Context<'_>) -> Result<Option<TypeExploitation>> {
+        loader(ctx)?
+            .load_one(TypeExploitationId(self.type_exploitation_id))
+            .await
+            .map_err(internal)
+    }
+
+    async fn elements(&self, ctx: &Context<'_>) -> Result<Vec<Element>> {
+        let elements = loader(ctx)?.load_one(ExploitationId(self.id)).await.map_err(internal)?;
+        Ok(elements.unwrap_or_default())
+    }
+}
+
+#[ComplexObject]
+impl Element {
+    async fn productions(&self, ctx: &Context<'_>) -> Result<Vec<Production>> {
+        let productions = loader(ctx)?.load_one(ElementId(self.id)).await.map_err(internal)?;
+        Ok(productions.unwrap_or_default())
+    }
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Domaines de l'utilisateur connecté
+    async fn domaines(&self, ctx: &Context<'_>) -> Result<Vec<Domaine>> {
+        let user_id = viewer(ctx)?;
+        repos(ctx)?.domaines.get_all_by_user_id(user_id, None).await.map_err(internal)
+    }
+
+    /// Un domaine de l'utilisateur connecté, null s'il n'existe pas
+    async fn domaine(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Domaine>> {
+        if !owned(ctx, Entity::Domaine, id).await? {
+            return Ok(None);
+        }
+        repos(ctx)?.domaines.get_by_id(id).await.map(Some).map_err(internal)
+    }
+
+    async fn exploitation(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Exploitation>> {
+        if !owned(ctx, Entity::Exploitation, id).await? {
+            return Ok(None);
+        }
+        repos(ctx)?.exploitations.get_by_id(id).await.map(Some).map_err(internal)
+    }
+
+    async fn element(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Element>> {
+        if !owned(ctx, Entity::Element, id).await? {
+            return Ok(None);
+        }
+        repos(ctx)?.elements.get_by_id(id).await.map(Some).map_err(internal)
+    }
+
+    async fn production(&self, ctx: &Context<'_>, id: i32) -> Result<Option<Production>> {
+        if !owned(ctx, Entity::Production, id).await? {
+            return Ok(None);
+        }
+        repos(ctx)?.productions.get_by_id(id).await.map(Some).map_err(internal)
+    }
+
+    /// Les tables de référence sont publiques, comme sur les routes REST
+    async fn types_user(&self, ctx: &Context<'_>) -> Result<Vec<TypeUser>> {
+        repos(ctx)?.references.get_types_user(None).await.map_err(internal)
+    }
+
+    async fn types_exploitation(&self, ctx: &Context<'_>) -> Result<Vec<TypeExploitation>> {
+        repos(ctx)?.references.get_types_exploitation(None).await.map_err(internal)
+    }
+
+    async fn types_element(&self, ctx: &Context<'_>) -> Result<Vec<TypeElement>> {
+        repos(ctx)?.references.get_types_element(None).await.map_err(internal)
+    }
+}
+
+pub struct Mutation;
+
+#[Object]
+impl Mutation {
+    async fn create_domaine(&self, ctx: &Context<'_>, nom_domaine: String) -> Result<Domaine> {
+        let result = apply(ctx, BatchOperation::CreateDomaine { nom_domaine }).await?;
+        repos(ctx)?.domaines.get_by_id(result.id).await.map_err(internal)
+    }
+
+    /// Modifier un domaine ; `version` doit être la version actuelle
+    async fn update_domaine(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        version: i32,
+        nom_domaine: Option<String>,
+    ) -> Result<Domaine> {
+        let operation = BatchOperation::UpdateDomaine { id: Ref::Id(id), version, nom_domaine };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.domaines.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn delete_domaine(&self, ctx: &Context<'_>, id: i32, version: i32) -> Result<bool> {
+        apply(ctx, BatchOperation::DeleteDomaine { id: Ref::Id(id), version }).await?;
+        Ok(true)
+    }
+
+    async fn create_exploitation(
+        &self,
+        ctx: &Context<'_>,
+        domaine_id: i32,
+        type_exploitation_id: i32,
+        nom_exploitation: String,
+    ) -> Result<Exploitation> {
+        let operation = BatchOperation::CreateExploitation {
+            domaine_id: Ref::Id(domaine_id),
+            type_exploitation_id,
+            nom_exploitation,
+        };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.exploitations.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn update_exploitation(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<Exploitation> {
+        let operation = BatchOperation::UpdateExploitation {
+            id: Ref::Id(id),
+            version,
+            type_exploitation_id,
+            nom_exploitation,
+        };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.exploitations.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn delete_exploitation(&self, ctx: &Context<'_>, id: i32, version: i32) -> Result<bool> {
+        apply(ctx, BatchOperation::DeleteExploitation { id: Ref::Id(id), version }).await?;
+        Ok(true)
+    }
+
+    async fn create_element(
+        &self,
+        ctx: &Context<'_>,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+    ) -> Result<Element> {
+        let operation = BatchOperation::CreateElement {
+            exploitation_id: Ref::Id(exploitation_id),
+            nom_element,
+            quantite,
+        };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.elements.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn update_element(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<Element> {
+        let operation = BatchOperation::UpdateElement { id: Ref::Id(id), version, nom_element, quantite };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.elements.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn delete_element(&self, ctx: &Context<'_>, id: i32, version: i32) -> Result<bool> {
+        apply(ctx, BatchOperation::DeleteElement { id: Ref::Id(id), version }).await?;
+        Ok(true)
+    }
+
+    async fn create_production(
+        &self,
+        ctx: &Context<'_>,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+    ) -> Result<Production> {
+        let operation = BatchOperation::CreateProduction {
+            element_id: Ref::Id(element_id),
+            quantite_produite,
+            unite_production,
+            date_de_production,
+        };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.productions.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn update_production(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<Production> {
+        let operation = BatchOperation::UpdateProduction {
+            id: Ref::Id(id),
+            version,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+        };
+        let result = apply(ctx, operation).await?;
+        repos(ctx)?.productions.get_by_id(result.id).await.map_err(internal)
+    }
+
+    async fn delete_production(&self, ctx: &Context<'_>, id: i32, version: i32) -> Result<bool> {
+        apply(ctx, BatchOperation::DeleteProduction { id: Ref::Id(id), version }).await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,325 @@
+use actix_web::{
+    body::{self, BodySize, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderMap, HeaderValue},
+    Error, FromRequest, HttpRequest,
+};
+use actix_web::middleware::Next;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::{ready, Ready};
+use utoipa::ToSchema;
+
+use crate::repository::Repositories;
+use crate::type_translation::Translatable;
+
+// Au-delà, une réponse texte n'est pas un message du catalogue et n'est pas relue
+const MAX_MESSAGE_LEN: usize = 512;
+
+// Langue des réponses, négociée avec l'en-tête Accept-Language ; le français reste la langue par défaut
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Lang {
+    #[default]
+    Fr,
+    En,
+}
+
+impl Lang {
+    // Code de langue, repris dans Content-Language et dans les traductions stockées
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::Fr => "fr",
+            Lang::En => "en",
+        }
+    }
+
+    // Langue d'une étiquette BCP 47, d'après sa sous-étiquette principale (« en-GB » donne l'anglais)
+    fn from_tag(tag: &str) -> Option<Lang> {
+        let primary = tag.split('-').next().unwrap_or_default();
+        if primary.eq_ignore_ascii_case("fr") {
+            Some(Lang::Fr)
+        } else if primary.eq_ignore_ascii_case("en") {
+            Some(Lang::En)
+        } else {
+            None
+        }
+    }
+
+    // Langue disponible la mieux classée par le client (poids q), le français si aucune ne convient
+    pub fn negotiate(headers: &HeaderMap) -> Lang {
+        let accept = match headers.get(header::ACCEPT_LANGUAGE).and_then(|h| h.to_str().ok()) {
+            Some(accept) => accept,
+            None => return Lang::default(),
+        };
+
+        let mut best: Option<(Lang, f32)> = None;
+        for range in accept.split(',') {
+            let mut parts = range.split(';');
+            let tag = parts.next().unwrap_or_default().trim();
+            let weight = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            let lang = if tag == "*" { Some(Lang::default()) } else { Lang::from_tag(tag) };
+            if let Some(lang) = lang {
+                // À poids égal, la première langue citée l'emporte
+                if weight > 0.0 && !matches!(best, Some((_, w)) if w >= weight) {
+                    best = Some((lang, weight));
+                }
+            }
+        }
+
+        best.map(|(lang, _)| lang).unwrap_or_default()
+    }
+
+    // Texte à renvoyer parmi ses deux versions
+    pub fn pick<'a>(self, fr: &'a str, en: &'a str) -> &'a str {
+        match self {
+            Lang::Fr => fr,
+            Lang::En => en,
+        }
+    }
+}
+
+impl FromRequest for Lang {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Lang::negotiate(req.headers())))
+    }
+}
+
+// Messages des réponses texte, repérés par leur version française
+const MESSAGES: &[(&str, &str)] = &[
+    ("Bienvenue sur AquaFarm API", "Welcome to the AquaFarm API"),
+    ("Accès refusé", "Access denied"),
+    ("Non autorisé", "Unauthorized"),
+    ("Token manquant", "Missing token"),
+    ("Token invalide", "Invalid token"),
+    ("Réservé aux administrateurs", "Administrators only"),
+    ("Email ou mot de passe incorrect", "Incorrect email or password"),
+    ("Trop de requêtes, réessayez plus tard", "Too many requests, try again later"),
+    ("En-tête Idempotency-Key invalide", "Invalid Idempotency-Key header"),
+    (
+        "La clé d'idempotence a déjà été utilisée pour une autre requête",
+        "The idempotency key has already been used for another request",
+    ),
+    (
+        "Une requête avec cette clé d'idempotence est en cours de traitement",
+        "A request with this idempotency key is being processed",
+    ),
+    ("En-tête If-Match invalide", "Invalid If-Match header"),
+    (
+        "En-tête If-Match requis pour modifier la ressource",
+        "If-Match header required to modify the resource",
+    ),
+    ("La ressource a été modifiée entre-temps", "The resource has been modified in the meantime"),
+    ("Jeton de synchronisation invalide", "Invalid sync token"),
+    ("Ressource introuvable", "Resource not found"),
+    ("Référence invalide", "Invalid reference"),
+    ("Identifiant manquant", "Missing identifier"),
+    ("Identifiant client inconnu", "Unknown client identifier"),
+    ("Une création avec cet identifiant client est en cours", "A creation with this client identifier is in progress"),
+    ("Version manquante", "Missing version"),
+    ("Données invalides", "Invalid data"),
+    ("Trop d'opérations dans le lot", "Too many operations in the batch"),
+    ("Erreur lors de l'exécution du lot", "Error while running the batch"),
+    ("Erreur lors de l'application de la modification", "Error while applying the change"),
+    ("URL invalide : http ou https attendu", "Invalid URL: http or https expected"),
+    ("Au moins un événement est requis", "At least one event is required"),
+    (
+        "Le nom français est celui du type lui-même",
+        "The French name is the type's own name",
+    ),
+    ("Suppression impossible : des lignes en dépendent", "Cannot delete: other rows depend on this one"),
+    (
+        "Suppression impossible : des lignes en dépendent. Supprimez-les d'abord ou ajoutez ?cascade=true",
+        "Cannot delete: other rows depend on this one. Delete them first or add ?cascade=true",
+    ),
+    ("Cette ligne n'est pas à la corbeille", "This row is not in the trash"),
+    (
+        "Le parent est à la corbeille : restaurez-le d'abord",
+        "The parent is in the trash: restore it first",
+    ),
+    ("Ligne introuvable", "Row not found"),
+    ("Ligne restaurée avec succès", "Row restored successfully"),
+    ("Utilisateur introuvable", "User not found"),
+    ("Utilisateur non trouvé", "User not found"),
+    ("Erreur : utilisateur non trouvé", "Error: user not found"),
+    ("Domaine introuvable", "Domain not found"),
+    ("Exploitation introuvable", "Farm not found"),
+    ("Exploitation introuvable dans ce domaine", "Farm not found in this domain"),
+    ("Élément introuvable", "Element not found"),
+    ("Élément introuvable dans cette exploitation", "Element not found in this farm"),
+    ("Production introuvable", "Production not found"),
+    ("Webhook introuvable", "Webhook not found"),
+    ("Livraison introuvable pour ce webhook", "Delivery not found for this webhook"),
+    ("Type introuvable", "Type not found"),
+    ("Utilisateur mis à jour avec succès", "User updated successfully"),
+    ("Utilisateur supprimé avec succès", "User deleted successfully"),
+    ("Domaine mis à jour avec succès", "Domain updated successfully"),
+    ("Domaine supprimé avec succès", "Domain deleted successfully"),
+    ("Exploitation mise à jour avec succès", "Farm updated successfully"),
+    ("Exploitation supprimée avec succès", "Farm deleted successfully"),
+    ("Élément mis à jour avec succès", "Element updated successfully"),
+    ("Élément supprimé avec succès", "Element deleted successfully"),
+    ("Production mise à jour avec succès", "Production updated successfully"),
+    ("Production supprimée avec succès", "Production deleted successfully"),
+    ("Type d'élément mis à jour avec succès", "Element type updated successfully"),
+    ("Type d'élément supprimé avec succès", "Element type deleted successfully"),
+    ("Traduction enregistrée avec succès", "Translation saved successfully"),
+    ("Erreur lors de l'ajout", "Error while adding"),
+    ("Erreur lors de la création du domaine", "Error while creating the domain"),
+    ("Erreur lors de la création de l'exploitation", "Error while creating the farm"),
+    ("Erreur lors de la création de l'élément", "Error while creating the element"),
+    ("Erreur lors de la création de la production", "Error while creating the production"),
+    ("Erreur lors de la création du type d'élément", "Error while creating the element type"),
+    ("Erreur lors de la création du webhook", "Error while creating the webhook"),
+    ("Erreur lors de la génération des données", "Error while generating the data"),
+    ("Erreur lors de la lecture des métriques", "Error while reading the metrics"),
+    ("Erreur lors de la mise à jour", "Error while updating"),
+    ("Erreur lors de la mise à jour du domaine", "Error while updating the domain"),
+    ("Erreur lors de la mise à jour de l'exploitation", "Error while updating the farm"),
+    ("Erreur lors de la mise à jour de l'élément", "Error while updating the element"),
+    ("Erreur lors de la mise à jour de la production", "Error while updating the production"),
+    ("Erreur lors de la recherche", "Error while searching"),
+    ("Erreur lors de la relance de la livraison", "Error while retrying the delivery"),
+    ("Erreur lors de la restauration", "Error while restoring"),
+    ("Erreur lors de la récupération", "Error while fetching"),
+    ("Erreur lors de la récupération du domaine", "Error while fetching the domain"),
+    ("Erreur lors de la récupération des domaines", "Error while fetching the domains"),
+    ("Erreur lors de la récupération de l'exploitation", "Error while fetching the farm"),
+    ("Erreur lors de la récupération des exploitations", "Error while fetching the farms"),
+    ("Erreur lors de la récupération de l'élément", "Error while fetching the element"),
+    ("Erreur lors de la récupération des éléments", "Error while fetching the elements"),
+    ("Erreur lors de la récupération des productions", "Error while fetching the productions"),
+    ("Erreur lors de la récupération des types", "Error while fetching the types"),
+    ("Erreur lors de la récupération des types d'éléments", "Error while fetching the element types"),
+    ("Erreur lors de la récupération de la corbeille", "Error while fetching the trash"),
+    ("Erreur lors de la récupération du webhook", "Error while fetching the webhook"),
+    ("Erreur lors de la récupération des webhooks", "Error while fetching the webhooks"),
+    ("Erreur lors de la récupération de la livraison", "Error while fetching the delivery"),
+    ("Erreur lors de la récupération des livraisons", "Error while fetching the deliveries"),
+    ("Erreur lors de la suppression", "Error while deleting"),
+    ("Erreur lors de la suppression du domaine", "Error while deleting the domain"),
+    ("Erreur lors de la suppression de l'exploitation", "Error while deleting the farm"),
+    ("Erreur lors de la suppression de l'élément", "Error while deleting the element"),
+    ("Erreur lors de la suppression de la production", "Error while deleting the production"),
+    ("Erreur lors de la suppression du webhook", "Error while deleting the webhook"),
+    ("Erreur lors de la suppression en cascade", "Error while deleting in cascade"),
+    ("Erreur lors de l'enregistrement de la traduction", "Error while saving the translation"),
+    ("Erreur lors de la synchronisation", "Error while synchronizing"),
+    ("Erreur lors du traitement de la requête", "Error while processing the request"),
+];
+
+// Messages suivis d'une valeur propre à la requête
+const PREFIXES: &[(&str, &str)] = &[
+    ("Événement inconnu : ", "Unknown event: "),
+    ("Champ obligatoire manquant : ", "Missing required field: "),
+    ("Hôte introuvable : ", "Unknown host: "),
+    ("Adresse interdite pour un webhook : ", "Address not allowed for a webhook: "),
+];
+
+// Message du catalogue dans la langue demandée, None s'il n'y figure pas
+pub fn translate(lang: Lang, message: &str) -> Option<String> {
+    if let Some((fr, en)) = MESSAGES.iter().find(|(fr, _)| *fr == message) {
+        return Some(lang.pick(fr, en).to_string());
+    }
+
+    PREFIXES.iter().find_map(|(fr, en)| {
+        message
+            .strip_prefix(fr)
+            .map(|value| format!("{}{}", lang.pick(fr, en), value))
+    })
+}
+
+// Message d'un corps JSON dans la langue demandée ; un message absent du catalogue reste tel quel
+pub fn localize(lang: Lang, message: String) -> String {
+    translate(lang, &message).unwrap_or(message)
+}
+
+// Remplacer les noms des types par leur traduction quand elle existe ; le nom d'origine est le nom français
+pub async fn translate_types<T: Translatable>(
+    repos: &Repositories,
+    lang: Lang,
+    mut types: Vec<T>,
+) -> Result<Vec<T>, sqlx::Error> {
+    if lang == Lang::Fr {
+        return Ok(types);
+    }
+
+    let translations: HashMap<i32, String> = repos
+        .references
+        .get_type_translations(T::KIND, lang.code())
+        .await?
+        .into_iter()
+        .map(|t| (t.type_id, t.nom))
+        .collect();
+
+    for t in types.iter_mut() {
+        if let Some(nom) = translations.get(&t.id()) {
+            *t.nom_mut() = nom.clone();
+        }
+    }
+
+    Ok(types)
+}
+
+// Seules les courtes réponses en texte brut sont des messages : le JSON et les flux restent intacts
+fn is_message(res: &ServiceResponse<BoxBody>) -> bool {
+    let plain_text = match res.headers().get(header::CONTENT_TYPE) {
+        Some(content_type) => content_type
+            .to_str()
+            .map(|c| c.starts_with("text/plain"))
+            .unwrap_or(false),
+        None => true,
+    };
+    let short = matches!(
+        res.response().body().size(),
+        BodySize::Sized(len) if len > 0 && len as usize <= MAX_MESSAGE_LEN
+    );
+
+    plain_text && short
+}
+
+// Middleware : les messages texte des handlers et des autres middlewares sont rendus dans la langue négociée
+// Placé à l'extérieur des autres, il traduit aussi les réponses rejouées par l'idempotence
+pub async fn i18n_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let lang = Lang::negotiate(req.headers());
+
+    let mut res = next.call(req).await?.map_into_boxed_body();
+    res.headers_mut()
+        .append(header::VARY, HeaderValue::from_static("Accept-Language"));
+
+    if !is_message(&res) {
+        return Ok(res);
+    }
+
+    let (req, res) = res.into_parts();
+    let (mut res, body) = res.into_parts();
+    let bytes = body::to_bytes(body)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+
+    let translated = std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|message| translate(lang, message));
+
+    let res = match translated {
+        Some(message) => {
+            res.headers_mut()
+                .insert(header::CONTENT_LANGUAGE, HeaderValue::from_static(lang.code()));
+            res.set_body(message).map_into_boxed_body()
+        }
+        None => res.set_body(bytes).map_into_boxed_body(),
+    };
+
+    Ok(ServiceResponse::new(req, res))
+}
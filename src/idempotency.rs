@@ -0,0 +1,336 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::PayloadError,
+    http::{header, Method, StatusCode},
+    web, Error, HttpResponse,
+};
+use actix_web::middleware::Next;
+use chrono::{DateTime, Duration, Utc};
+use futures_util::{stream, Stream};
+use sha2::{Digest, Sha256};
+use sqlx::{mysql::MySqlPool, FromRow};
+use std::pin::Pin;
+
+use crate::repository::Repositories;
+use crate::timestamps;
+use crate::user;
+
+const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+const IDEMPOTENT_REPLAYED: &str = "Idempotent-Replayed";
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+// Réponse mémorisée pour une clé d'idempotence
+// Tant que la première requête est en cours, status_code est NULL
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyRecord {
+    pub request_hash: String,
+    pub status_code: Option<u16>,
+    pub content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub enum Reservation {
+    Acquired,
+    Existing(IdempotencyRecord),
+}
+
+// Durée de conservation des réponses, configurable via IDEMPOTENCY_TTL_SECONDS (24 h par défaut)
+fn retention() -> Duration {
+    let seconds = std::env::var("IDEMPOTENCY_TTL_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(86_400);
+
+    Duration::seconds(seconds)
+}
+
+// Délai au-delà duquel une réservation encore en cours est reprise, configurable via
+// IDEMPOTENCY_LOCK_SECONDS (60 s par défaut) : une clé n'est pas bloquée jusqu'à son expiration
+// quand le serveur s'arrête pendant le traitement
+fn lock_expiry() -> Duration {
+    let seconds = std::env::var("IDEMPOTENCY_LOCK_SECONDS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(60);
+
+    Duration::seconds(seconds)
+}
+
+// Lancer la purge périodique des clés plus anciennes que la durée de conservation
+pub fn start_purge(repos: Repositories) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            match repos.idempotency.purge(timestamps::now() - retention()).await {
+                Ok(0) => {},
+                Ok(purged) => tracing::info!(purged, "Clés d'idempotence purgées"),
+                Err(e) => tracing::error!(error = ?e, "Erreur lors de la purge des clés d'idempotence"),
+            }
+        }
+    });
+}
+
+// Empreinte de la requête : une même clé ne peut être rejouée qu'avec la même requête
+fn fingerprint(req: &ServiceRequest, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b"\n");
+    hasher.update(req.path());
+    hasher.update(b"\n");
+    hasher.update(req.query_string());
+    hasher.update(b"\n");
+    hasher.update(body);
+
+    format!("{:x}", hasher.finalize())
+}
+
+// Remettre le corps déjà lu à disposition du handler
+fn payload_from(body: web::Bytes) -> Payload {
+    let stream: Pin<Box<dyn Stream<Item = Result<web::Bytes, PayloadError>>>> =
+        Box::pin(stream::once(async move { Ok(body) }));
+    Payload::from(stream)
+}
+
+// Réserver la clé, ou récupérer la réponse déjà enregistrée
+// Une réservation restée en cours depuis `stale_before` (requête interrompue) ou une clé
+// créée avant `expired_before` et pas encore purgée est reprise par la nouvelle requête
+pub async fn reserve(
+    pool: &MySqlPool,
+    principal: &str,
+    key: &str,
+    request_hash: &str,
+    stale_before: DateTime<Utc>,
+    expired_before: DateTime<Utc>,
+) -> Result<Reservation, sqlx::Error> {
+    let now = timestamps::now();
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT IGNORE INTO idempotency_keys (principal, idempotency_key, request_hash, created_at)
+        VALUES (?, ?, ?, ?)
+        "#,
+    )
+    .bind(principal)
+    .bind(key)
+    .bind(request_hash)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    if inserted.rows_affected() > 0 {
+        return Ok(Reservation::Acquired);
+    }
+
+    let reclaimed = sqlx::query(
+        r#"
+        UPDATE idempotency_keys
+        SET request_hash = ?, status_code = NULL, content_type = NULL, response_body = NULL, created_at = ?
+        WHERE principal = ? AND idempotency_key = ?
+          AND ((status_code IS NULL AND created_at < ?) OR created_at < ?)
+        "#,
+    )
+    .bind(request_hash)
+    .bind(now)
+    .bind(principal)
+    .bind(key)
+    .bind(stale_before)
+    .bind(expired_before)
+    .execute(pool)
+    .await?;
+
+    if reclaimed.rows_affected() > 0 {
+        return Ok(Reservation::Acquired);
+    }
+
+    let record = sqlx::query_as::<_, IdempotencyRecord>(
+        r#"
+        SELECT request_hash, status_code, content_type, response_body, created_at
+        FROM idempotency_keys
+        WHERE principal = ? AND idempotency_key = ?
+        "#,
+    )
+    .bind(principal)
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(record.map_or(Reservation::Acquired, Reservation::Existing))
+}
+
+// Enregistrer la réponse de la première requête
+pub async fn store(
+    pool: &MySqlPool,
+    principal: &str,
+    key: &str,
+    status_code: u16,
+    content_type: Option<String>,
+    response_body: &[u8],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE idempotency_keys
+        SET status_code = ?, content_type = ?, response_body = ?
+        WHERE principal = ? AND idempotency_key = ?
+        "#,
+    )
+    .bind(status_code)
+    .bind(content_type)
+    .bind(response_body)
+    .bind(principal)
+    .bind(key)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Libérer la clé quand la requête a échoué, pour qu'un nouvel essai soit traité
+pub async fn release(pool: &MySqlPool, principal: &str, key: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE principal = ? AND idempotency_key = ?")
+        .bind(principal)
+        .bind(key)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+// Supprimer les clés créées avant `before`
+pub async fn purge(pool: &MySqlPool, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+    let purged = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < ?")
+        .bind(before)
+        .execute(pool)
+        .await?;
+
+    Ok(purged.rows_affected())
+}
+
+async fn release_key(repos: &Repositories, principal: &str, key: &str) {
+    if let Err(e) = repos.idempotency.release(principal, key).await {
+        tracing::error!(error = ?e, "Erreur lors de la libération de la clé d'idempotence");
+    }
+}
+
+fn replay(record: IdempotencyRecord, request_hash: &str) -> HttpResponse {
+    if record.request_hash != request_hash {
+        return HttpResponse::UnprocessableEntity()
+            .body("La clé d'idempotence a déjà été utilisée pour une autre requête");
+    }
+
+    let status_code = match record.status_code {
+        Some(status_code) => status_code,
+        None => {
+            return HttpResponse::Conflict()
+                .body("Une requête avec cette clé d'idempotence est en cours de traitement")
+        }
+    };
+
+    let mut response = HttpResponse::build(StatusCode::from_u16(status_code).unwrap_or(StatusCode::OK));
+    response.insert_header((IDEMPOTENT_REPLAYED, "true"));
+    if let Some(content_type) = record.content_type {
+        response.insert_header((header::CONTENT_TYPE, content_type));
+    }
+
+    response.body(record.response_body.unwrap_or_default())
+}
+
+// Middleware appliqué aux routes POST portant un en-tête Idempotency-Key
+pub async fn idempotency_middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let key = match req.headers().get(IDEMPOTENCY_KEY) {
+        Some(value) if req.method() == Method::POST => value.to_str().ok().map(str::to_owned),
+        _ => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+
+    let key = match key {
+        Some(key) if !key.is_empty() && key.len() <= 255 => key,
+        _ => {
+            return Ok(req.into_response(
+                HttpResponse::BadRequest().body("En-tête Idempotency-Key invalide"),
+            ))
+        }
+    };
+
+    let repos = match req.app_data::<web::Data<Repositories>>() {
+        Some(repos) => repos.clone(),
+        None => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+
+    // Les clés sont propres à chaque utilisateur authentifié ; sans utilisateur, la requête
+    // n'est pas mémorisée, pour ne jamais rejouer la réponse d'un client à un autre
+    let principal = match user::validate_token(req.request()) {
+        Ok(claims) => claims.sub,
+        Err(_) => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+
+    let body = req.extract::<web::Bytes>().await?;
+    let request_hash = fingerprint(&req, &body);
+    req.set_payload(payload_from(body));
+
+    let now = timestamps::now();
+    let reservation = repos
+        .idempotency
+        .reserve(&principal, &key, &request_hash, now - lock_expiry(), now - retention())
+        .await;
+
+    match reservation {
+        Ok(Reservation::Acquired) => {}
+        Ok(Reservation::Existing(record)) => return Ok(req.into_response(replay(record, &request_hash))),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture de la clé d'idempotence");
+            return Ok(req.into_response(
+                HttpResponse::InternalServerError().body("Erreur lors du traitement de la requête"),
+            ));
+        }
+    }
+
+    let res = match next.call(req).await {
+        Ok(res) => res,
+        Err(e) => {
+            release_key(&repos, &principal, &key).await;
+            return Err(e);
+        }
+    };
+
+    // Une réponse marquée no-store (jeton, secret) n'est pas conservée en base
+    let no_store = res
+        .headers()
+        .get(header::CACHE_CONTROL)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|value| value.contains("no-store"));
+
+    if res.status().is_server_error() || no_store {
+        release_key(&repos, &principal, &key).await;
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (res, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        actix_web::error::ErrorInternalServerError(e.to_string())
+    })?;
+
+    let content_type = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_owned);
+
+    let stored = repos
+        .idempotency
+        .store(&principal, &key, res.status().as_u16(), content_type, &bytes)
+        .await;
+
+    if let Err(e) = stored {
+        tracing::error!(error = ?e, "Erreur lors de l'enregistrement de la réponse idempotente");
+    }
+
+    Ok(ServiceResponse::new(req, res.set_body(bytes).map_into_boxed_body()))
+}
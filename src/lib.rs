@@ -0,0 +1,33 @@
+pub mod type_user;
+pub mod user;
+pub mod domaine;
+pub mod type_exploitation;
+pub mod exploitation;
+pub mod type_element;
+pub mod type_translation;
+pub mod element;
+pub mod production;
+pub mod releve;
+pub mod etag;
+pub mod timestamps;
+pub mod tombstone;
+pub mod trash;
+pub mod sync;
+pub mod idempotency;
+pub mod rate_limit;
+pub mod i18n;
+pub mod ownership;
+pub mod batch;
+pub mod tree;
+pub mod search;
+pub mod events;
+pub mod webhook;
+pub mod graphql;
+pub mod metrics;
+pub mod schema;
+pub mod telemetry;
+pub mod database;
+pub mod repository;
+pub mod routes;
+pub mod seed;
+pub mod cli;
@@ -0,0 +1,162 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    Error,
+};
+use actix_web::middleware::Next;
+use chrono::{DateTime, Utc};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use crate::repository::Repositories;
+
+// Libellé des requêtes qui ne correspondent à aucune route, pour borner le nombre de séries
+const ROUTE_INCONNUE: &str = "inconnue";
+
+// Connexions du pool sqlx au moment de la lecture
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+// Volumes métier exposés sous forme de jauges
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BusinessCounts {
+    pub domaines: i64,
+    pub elements: i64,
+    pub productions_du_jour: i64,
+}
+
+pub struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    durations: HistogramVec,
+    errors: IntCounterVec,
+    pool: IntGaugeVec,
+    domaines: IntGauge,
+    elements: IntGauge,
+    productions_du_jour: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new_custom(Some("aquafarm".to_string()), None)?;
+
+        let requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Requêtes HTTP traitées"),
+            &["method", "route", "status"],
+        )?;
+        let durations = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "Durée de traitement des requêtes HTTP"),
+            &["method", "route"],
+        )?;
+        let errors = IntCounterVec::new(
+            Opts::new("http_errors_total", "Réponses HTTP en erreur (4xx et 5xx)"),
+            &["status"],
+        )?;
+        let pool = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Connexions du pool de base de données"),
+            &["state"],
+        )?;
+        let domaines = IntGauge::new("domaines", "Nombre de domaines")?;
+        let elements = IntGauge::new("elements", "Nombre d'éléments")?;
+        let productions_du_jour =
+            IntGauge::new("productions_du_jour", "Productions enregistrées depuis minuit (UTC)")?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(durations.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+        registry.register(Box::new(pool.clone()))?;
+        registry.register(Box::new(domaines.clone()))?;
+        registry.register(Box::new(elements.clone()))?;
+        registry.register(Box::new(productions_du_jour.clone()))?;
+
+        Ok(Metrics { registry, requests, durations, errors, pool, domaines, elements, productions_du_jour })
+    }
+
+    fn observe(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let status = status.to_string();
+        self.requests.with_label_values(&[method, route, &status]).inc();
+        self.durations.with_label_values(&[method, route]).observe(seconds);
+        if status.starts_with('4') || status.starts_with('5') {
+            self.errors.with_label_values(&[&status]).inc();
+        }
+    }
+}
+
+// Registre partagé par tous les workers du serveur
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| Metrics::new().expect("Métriques mal déclarées"))
+}
+
+// Minuit (UTC) du jour courant, début du décompte des productions du jour
+pub fn start_of_day(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive().and_hms_opt(0, 0, 0).expect("minuit valide").and_utc()
+}
+
+// Middleware : compter chaque requête et mesurer sa durée, par route et par statut
+pub async fn metrics_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let method = req.method().to_string();
+    let started = Instant::now();
+
+    let res = next.call(req).await?.map_into_boxed_body();
+
+    // Le motif (`/domaines/{id}`) n'est connu qu'une fois la route résolue
+    let route = res.request().match_pattern().unwrap_or_else(|| ROUTE_INCONNUE.to_string());
+    metrics().observe(&method, &route, res.status().as_u16(), started.elapsed().as_secs_f64());
+
+    Ok(res)
+}
+
+// Mettre à jour les jauges puis encoder le registre au format texte de Prometheus
+pub async fn render(repos: &Repositories) -> Result<String, sqlx::Error> {
+    let metrics = metrics();
+
+    if let Some(pool) = repos.stats.pool() {
+        metrics.pool.with_label_values(&["open"]).set(i64::from(pool.size));
+        metrics.pool.with_label_values(&["idle"]).set(pool.idle as i64);
+        metrics.pool.with_label_values(&["max"]).set(i64::from(pool.max));
+    }
+
+    let counts = repos.stats.counts(start_of_day(Utc::now())).await?;
+    metrics.domaines.set(counts.domaines);
+    metrics.elements.set(counts.elements);
+    metrics.productions_du_jour.set(counts.productions_du_jour);
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&metrics.registry.gather(), &mut buffer)
+        .map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+// Volumes métier lus dans MySQL
+pub async fn counts(pool: &sqlx::MySqlPool, productions_since: DateTime<Utc>) -> Result<BusinessCounts, sqlx::Error> {
+    let domaines = sqlx::query_scalar("SELECT COUNT(*) FROM domaines WHERE deleted_at IS NULL").fetch_one(pool).await?;
+    let elements = sqlx::query_scalar("SELECT COUNT(*) FROM elements WHERE deleted_at IS NULL").fetch_one(pool).await?;
+    let productions_du_jour = sqlx::query_scalar("SELECT COUNT(*) FROM production WHERE deleted_at IS NULL AND created_at >= ?")
+        .bind(productions_since)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(BusinessCounts { domaines, elements, productions_du_jour })
+}
+
+// Connexions d'un pool sqlx, quel que soit le type de base
+pub fn pool_stats<DB: sqlx::Database>(pool: &sqlx::Pool<DB>) -> PoolStats {
+    PoolStats {
+        size: pool.size(),
+        idle: pool.num_idle(),
+        max: crate::database::max_connections(),
+    }
+}
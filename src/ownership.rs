@@ -0,0 +1,92 @@
+use sqlx::{MySqlConnection};
+
+use crate::tombstone::Entity;
+
+// Récupérer le domaine auquel appartient une ligne en place et l'ID de son propriétaire
+// Renvoie None si la ligne n'existe pas ou si elle (ou un de ses parents) est à la corbeille
+pub async fn owner(conn: &mut MySqlConnection, entity: Entity, id: i32) -> Result<Option<(i32, i32)>, sqlx::Error>
+{
+    lookup(conn, entity, id, false).await
+}
+
+// Même recherche en suivant aussi les lignes à la corbeille : réservée à leur restauration
+pub async fn owner_including_trash(conn: &mut MySqlConnection, entity: Entity, id: i32) -> Result<Option<(i32, i32)>, sqlx::Error>
+{
+    lookup(conn, entity, id, true).await
+}
+
+async fn lookup(conn: &mut MySqlConnection, entity: Entity, id: i32, with_trash: bool) -> Result<Option<(i32, i32)>, sqlx::Error>
+{
+    let owner: Option<(i32, i32)> = match entity {
+        Entity::Domaine => {
+            sqlx::query_as("SELECT id, user_id FROM domaines WHERE id = ? AND (? OR deleted_at IS NULL)")
+                .bind(id)
+                .bind(with_trash)
+                .fetch_optional(&mut *conn)
+                .await?
+        }
+        Entity::Exploitation => {
+            sqlx::query_as(
+                r#"
+                SELECT d.id, d.user_id
+                FROM exploitations e
+                JOIN domaines d ON d.id = e.domaine_id
+                WHERE e.id = ?
+                  AND (? OR (e.deleted_at IS NULL AND d.deleted_at IS NULL))
+                "#,
+            )
+            .bind(id)
+            .bind(with_trash)
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        Entity::Element => {
+            sqlx::query_as(
+                r#"
+                SELECT d.id, d.user_id
+                FROM elements el
+                JOIN exploitations e ON e.id = el.exploitation_id
+                JOIN domaines d ON d.id = e.domaine_id
+                WHERE el.id = ?
+                  AND (? OR (el.deleted_at IS NULL AND e.deleted_at IS NULL AND d.deleted_at IS NULL))
+                "#,
+            )
+            .bind(id)
+            .bind(with_trash)
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+        Entity::Production => {
+            sqlx::query_as(
+                r#"
+                SELECT d.id, d.user_id
+                FROM production p
+                JOIN elements el ON el.id = p.element_id
+                JOIN exploitations e ON e.id = el.exploitation_id
+                JOIN domaines d ON d.id = e.domaine_id
+                WHERE p.id = ?
+                  AND (? OR (p.deleted_at IS NULL AND el.deleted_at IS NULL
+                             AND e.deleted_at IS NULL AND d.deleted_at IS NULL))
+                "#,
+            )
+            .bind(id)
+            .bind(with_trash)
+            .fetch_optional(&mut *conn)
+            .await?
+        }
+    };
+
+    Ok(owner)
+}
+
+// Récupérer l'ID du propriétaire du domaine auquel appartient une ligne en place
+pub async fn owner_of(conn: &mut MySqlConnection, entity: Entity, id: i32) -> Result<Option<i32>, sqlx::Error>
+{
+    Ok(owner(conn, entity, id).await?.map(|(_, user_id)| user_id))
+}
+
+// Récupérer l'ID du domaine auquel appartient une ligne en place
+pub async fn domaine_of(conn: &mut MySqlConnection, entity: Entity, id: i32) -> Result<Option<i32>, sqlx::Error>
+{
+    Ok(owner(conn, entity, id).await?.map(|(domaine_id, _)| domaine_id))
+}
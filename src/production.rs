@@ -1,56 +1,101 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Serialize, Deserialize};
-use sqlx::{mysql::MySqlPool, FromRow};
+use sqlx::{Connection, MySql, MySqlConnection, FromRow, QueryBuilder};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
 pub struct Production {
     pub id: i32,                   // ID unique de la production
     pub element_id: i32,           // ID de l'élément lié
     pub quantite_produite: i32,    // Quantité produite
     pub unite_production: String,  // Unité de mesure de la production
     pub date_de_production: NaiveDate, // Date de la production
+    pub version: i32,              // Version pour le contrôle de concurrence
+    pub created_at: DateTime<Utc>, // Date de création
+    pub updated_at: DateTime<Utc>, // Date de dernière modification
+    pub created_by: Option<i32>,   // Utilisateur ayant saisi la production
 }
 
 impl Production {
     /// Récupérer toutes les productions pour un élément donné
     pub async fn get_by_element_id(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         element_id: i32,
-    ) -> Result<Vec<Self>, sqlx::Error> {
-        let productions = sqlx::query_as!(
-            Production,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let productions = sqlx::query_as::<_, Production>(
             r#"
-            SELECT id, element_id, quantite_produite, unite_production, date_de_production
+            SELECT id, element_id, quantite_produite, unite_production, date_de_production, version,
+                   created_at, updated_at, created_by
             FROM production
-            WHERE element_id = ?
+            WHERE element_id = ? AND deleted_at IS NULL AND (? IS NULL OR updated_at > ?)
             "#,
-            element_id
         )
-        .fetch_all(pool)
+        .bind(element_id)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(&mut *conn)
         .await?;
 
         Ok(productions)
     }
 
+    /// Récupérer les productions de plusieurs éléments
+    pub async fn get_by_element_ids(conn: &mut MySqlConnection, element_ids: &[i32]) -> Result<Vec<Self>, sqlx::Error>
+    {
+        // Une liste IN vide n'est pas du SQL valide
+        if element_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<MySql>::new(
+            r#"
+            SELECT id, element_id, quantite_produite, unite_production, date_de_production, version,
+                   created_at, updated_at, created_by
+            FROM production
+            WHERE deleted_at IS NULL AND element_id IN ("#,
+        );
+        let mut ids = query.separated(", ");
+        for id in element_ids {
+            ids.push_bind(*id);
+        }
+        ids.push_unseparated(") ORDER BY id");
+
+        let productions = query.build_query_as::<Self>().fetch_all(&mut *conn).await?;
+
+        Ok(productions)
+    }
+
     /// Créer une nouvelle entrée de production
     pub async fn create(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         element_id: i32,
         quantite_produite: i32,
         unite_production: String,
         date_de_production: NaiveDate,
-    ) -> Result<Self, sqlx::Error> {
-        let insert_result = sqlx::query!(
+        created_by: Option<i32>,
+    ) -> Result<Self, sqlx::Error>
+    {
+        let now = timestamps::now();
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO production (element_id, quantite_produite, unite_production, date_de_production)
-            VALUES (?, ?, ?, ?)
+            INSERT INTO production (element_id, quantite_produite, unite_production, date_de_production,
+                                    created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
-            element_id,
-            quantite_produite,
-            unite_production,
-            date_de_production
         )
-        .execute(pool)
+        .bind(element_id)
+        .bind(quantite_produite)
+        .bind(&unite_production)
+        .bind(date_de_production)
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
+        .execute(&mut *conn)
         .await?;
 
         let last_id = insert_result.last_insert_id() as i32;
@@ -61,23 +106,112 @@ impl Production {
             quantite_produite,
             unite_production,
             date_de_production,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
-    /// Supprimer une production par ID
+    /// Récupérer une production par ID
+    pub async fn get_by_id(
+        conn: &mut MySqlConnection,
+        id: i32,
+    ) -> Result<Self, sqlx::Error>
+    {
+        let production = sqlx::query_as::<_, Production>(
+            r#"
+            SELECT id, element_id, quantite_produite, unite_production, date_de_production, version,
+                   created_at, updated_at, created_by
+            FROM production
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        Ok(production)
+    }
+
+    /// Mettre à jour une production si sa version n'a pas changé
+    pub async fn update(
+        conn: &mut MySqlConnection,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, sqlx::Error>
+    {
+        let result = sqlx::query(
+            r#"
+            UPDATE production
+            SET quantite_produite = COALESCE(?, quantite_produite),
+                unite_production = COALESCE(?, unite_production),
+                date_de_production = COALESCE(?, date_de_production),
+                version = version + 1,
+                updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
+            "#,
+        )
+        .bind(quantite_produite)
+        .bind(&unite_production)
+        .bind(date_de_production)
+        .bind(timestamps::now())
+        .bind(id)
+        .bind(version)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mettre une production à la corbeille si sa version n'a pas changé, en laissant une trace
     pub async fn delete(
-        pool: &MySqlPool,
+        conn: &mut MySqlConnection,
         id: i32,
-    ) -> Result<(), sqlx::Error> {
-        sqlx::query!(
+        version: i32,
+    ) -> Result<bool, sqlx::Error>
+    {
+        let mut tx = conn.begin().await?;
+
+        let owner: Option<(i32, i32)> = sqlx::query_as(
+            r#"
+            SELECT d.id AS domaine_id, d.user_id
+            FROM production p
+            JOIN elements el ON el.id = p.element_id
+            JOIN exploitations e ON e.id = el.exploitation_id
+            JOIN domaines d ON d.id = e.domaine_id
+            WHERE p.id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut tx)
+        .await?;
+
+        let now = timestamps::now();
+        let result = sqlx::query(
             r#"
-            DELETE FROM production WHERE id = ?
+            UPDATE production
+            SET deleted_at = ?, version = version + 1, updated_at = ?
+            WHERE id = ? AND version = ? AND deleted_at IS NULL
             "#,
-            id
         )
-        .execute(pool)
+        .bind(now)
+        .bind(now)
+        .bind(id)
+        .bind(version)
+        .execute(&mut tx)
         .await?;
 
-        Ok(())
+        match owner {
+            Some((domaine_id, user_id)) if result.rows_affected() > 0 => {
+                Tombstone::record(&mut tx, Entity::Production, id, domaine_id, user_id).await?;
+                tx.commit().await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 }
@@ -0,0 +1,329 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    web, Error, HttpResponse,
+};
+use actix_web::middleware::Next;
+use ipnet::IpNet;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::user;
+
+pub const RATELIMIT_LIMIT: &str = "ratelimit-limit";
+pub const RATELIMIT_REMAINING: &str = "ratelimit-remaining";
+pub const RATELIMIT_RESET: &str = "ratelimit-reset";
+pub const RATELIMIT_POLICY: &str = "ratelimit-policy";
+
+// En-tête des scripts et capteurs qui s'identifient par une clé d'API plutôt que par un token
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// Au-delà de ce nombre de compteurs, ceux qui sont de nouveau pleins sont oubliés
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// Routes de supervision, interrogées en continu par l'infrastructure
+const EXEMPT_PREFIXES: &[&str] = &["/health", "/metrics"];
+
+// Budget d'une classe de routes : `capacity` requêtes au plus, regagnées en continu sur `window`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub capacity: u32,
+    pub window: Duration,
+}
+
+impl Budget {
+    pub fn per_minute(capacity: u32) -> Self {
+        Budget { capacity, window: Duration::from_secs(60) }
+    }
+
+    // Temps nécessaire pour regagner `tokens` jetons
+    fn refill_time(&self, tokens: f64) -> Duration {
+        self.window.mul_f64(tokens.max(0.0) / f64::from(self.capacity))
+    }
+}
+
+// Lectures (GET, HEAD, OPTIONS) et écritures ont chacune leur budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Class {
+    Read,
+    Write,
+}
+
+impl Class {
+    fn of(method: &Method) -> Self {
+        if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            Class::Read
+        } else {
+            Class::Write
+        }
+    }
+}
+
+// Client dont les requêtes sont comptées ensemble : l'utilisateur du token, la clé d'API déclarée,
+// sinon l'adresse IP
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Subject {
+    User(String),
+    ApiKey(String),
+    Ip(Option<IpAddr>),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+// Résultat d'une requête comptée, repris dans les en-têtes RateLimit-*
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub budget: Budget,
+    pub remaining: u32,
+    // Délai avant que le compteur soit de nouveau plein
+    pub reset: Duration,
+    // Délai avant qu'une requête soit de nouveau acceptée, pour Retry-After
+    pub retry_after: Duration,
+}
+
+// Compteurs en mémoire du processus, partagés par tous les workers via les données de l'application
+pub struct RateLimiter {
+    read: Option<Budget>,
+    write: Option<Budget>,
+    api_keys: HashSet<String>,
+    // Proxys dont l'en-tête X-Forwarded-For est cru
+    trusted_proxies: Vec<IpNet>,
+    buckets: Mutex<HashMap<(Subject, Class), Bucket>>,
+}
+
+impl RateLimiter {
+    // Un budget absent désactive la limite pour sa classe
+    pub fn new(read: Option<Budget>, write: Option<Budget>) -> Self {
+        RateLimiter {
+            read,
+            write,
+            api_keys: HashSet::new(),
+            trusted_proxies: Vec::new(),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Clés d'API reconnues, chacune avec son propre compteur
+    pub fn with_api_keys(mut self, keys: impl IntoIterator<Item = String>) -> Self {
+        self.api_keys.extend(keys);
+        self
+    }
+
+    // Proxys (adresses ou plages CIDR) placés devant le serveur : derrière eux, le client est lu
+    // dans X-Forwarded-For
+    pub fn with_trusted_proxies(mut self, proxies: impl IntoIterator<Item = IpNet>) -> Self {
+        self.trusted_proxies.extend(proxies);
+        self
+    }
+
+    // Budgets réglés par RATE_LIMIT_READ_PER_MINUTE (600 par défaut) et RATE_LIMIT_WRITE_PER_MINUTE
+    // (120 par défaut) ; 0 désactive la limite. Clés d'API dans RATE_LIMIT_API_KEYS, proxys de confiance
+    // dans RATE_LIMIT_TRUSTED_PROXIES, séparés par des virgules
+    pub fn from_env() -> Self {
+        let api_keys = std::env::var("RATE_LIMIT_API_KEYS").unwrap_or_default();
+        let trusted_proxies = std::env::var("RATE_LIMIT_TRUSTED_PROXIES").unwrap_or_default();
+
+        RateLimiter::new(
+            budget_from_env("RATE_LIMIT_READ_PER_MINUTE", 600),
+            budget_from_env("RATE_LIMIT_WRITE_PER_MINUTE", 120),
+        )
+        .with_api_keys(
+            api_keys
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(str::to_string),
+        )
+        .with_trusted_proxies(
+            trusted_proxies
+                .split(',')
+                .map(str::trim)
+                .filter(|proxy| !proxy.is_empty())
+                .filter_map(|proxy| match parse_network(proxy) {
+                    Some(network) => Some(network),
+                    None => {
+                        tracing::warn!(proxy, "Proxy de confiance invalide dans RATE_LIMIT_TRUSTED_PROXIES");
+                        None
+                    }
+                }),
+        )
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|network| network.contains(&ip))
+    }
+
+    fn budget(&self, class: Class) -> Option<Budget> {
+        match class {
+            Class::Read => self.read,
+            Class::Write => self.write,
+        }
+    }
+
+    // Consommer un jeton du compteur du client, si la classe est limitée
+    pub fn check(&self, subject: Subject, class: Class, now: Instant) -> Option<Decision> {
+        let budget = self.budget(class)?;
+        let capacity = f64::from(budget.capacity);
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|(_, class), bucket| match self.budget(*class) {
+                Some(budget) => refilled(bucket, budget, now) < f64::from(budget.capacity),
+                None => false,
+            });
+        }
+
+        let bucket = buckets
+            .entry((subject, class))
+            .or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = refilled(bucket, budget, now);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+
+        Some(Decision {
+            allowed,
+            budget,
+            remaining: bucket.tokens.floor() as u32,
+            reset: budget.refill_time(capacity - bucket.tokens),
+            retry_after: budget.refill_time(1.0 - bucket.tokens),
+        })
+    }
+}
+
+fn budget_from_env(name: &str, default: u32) -> Option<Budget> {
+    let capacity = std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default);
+
+    (capacity > 0).then(|| Budget::per_minute(capacity))
+}
+
+fn refilled(bucket: &Bucket, budget: Budget, now: Instant) -> f64 {
+    let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+    let capacity = f64::from(budget.capacity);
+
+    (bucket.tokens + elapsed * capacity / budget.window.as_secs_f64()).min(capacity)
+}
+
+// Secondes entières, arrondies au-dessus pour ne jamais annoncer un délai trop court
+fn whole_seconds(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+// Adresse seule ou plage CIDR
+pub fn parse_network(value: &str) -> Option<IpNet> {
+    value
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+}
+
+// Adresse du client : celle de la connexion, sauf si elle appartient à un proxy de confiance.
+// X-Forwarded-For est alors lu de droite à gauche, chaque proxy ajoutant l'adresse qu'il a vue :
+// le premier saut qui n'est pas un proxy de confiance est le client. Les sauts plus à gauche
+// sont fournis par le client et ignorés
+fn client_ip(req: &ServiceRequest, limiter: &RateLimiter) -> Option<IpAddr> {
+    let mut client = req.peer_addr()?.ip();
+    if !limiter.is_trusted_proxy(client) {
+        return Some(client);
+    }
+
+    let forwarded_for: Vec<&str> = req
+        .headers()
+        .get_all(header::X_FORWARDED_FOR)
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    for hop in forwarded_for.into_iter().rev() {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) => client = ip,
+            Err(_) => break,
+        }
+        if !limiter.is_trusted_proxy(client) {
+            break;
+        }
+    }
+
+    Some(client)
+}
+
+fn subject(req: &ServiceRequest, limiter: &RateLimiter) -> Subject {
+    if let Ok(claims) = user::validate_token(req.request()) {
+        return Subject::User(claims.sub);
+    }
+
+    // Une clé inconnue n'ouvre pas de compteur à part : en changer suffirait à contourner la limite
+    let api_key = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .filter(|key| limiter.api_keys.contains(*key));
+
+    match api_key {
+        Some(key) => Subject::ApiKey(key.to_string()),
+        None => Subject::Ip(client_ip(req, limiter)),
+    }
+}
+
+fn rate_limit_headers(headers: &mut header::HeaderMap, decision: &Decision) {
+    let values = [
+        (RATELIMIT_LIMIT, decision.budget.capacity.to_string()),
+        (RATELIMIT_REMAINING, decision.remaining.to_string()),
+        (RATELIMIT_RESET, whole_seconds(decision.reset).to_string()),
+        (
+            RATELIMIT_POLICY,
+            format!("{};w={}", decision.budget.capacity, decision.budget.window.as_secs()),
+        ),
+    ];
+
+    for (name, value) in values {
+        if let Ok(value) = header::HeaderValue::from_str(&value) {
+            headers.insert(header::HeaderName::from_static(name), value);
+        }
+    }
+}
+
+// Middleware : chaque client dispose d'un seau de jetons par classe de routes
+// Sans RateLimiter dans les données de l'application, les requêtes ne sont pas limitées
+pub async fn rate_limit_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let limiter = match req.app_data::<web::Data<RateLimiter>>() {
+        Some(limiter) if !EXEMPT_PREFIXES.iter().any(|p| req.path().starts_with(p)) => limiter.clone(),
+        _ => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+
+    let decision = match limiter.check(subject(&req, &limiter), Class::of(req.method()), Instant::now()) {
+        Some(decision) => decision,
+        None => return next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    };
+
+    if !decision.allowed {
+        tracing::warn!(path = %req.path(), "Limite de requêtes atteinte");
+        let mut res = HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, whole_seconds(decision.retry_after)))
+            .body("Trop de requêtes, réessayez plus tard");
+        rate_limit_headers(res.headers_mut(), &decision);
+        return Ok(req.into_response(res));
+    }
+
+    let mut res = next.call(req).await?.map_into_boxed_body();
+    rate_limit_headers(res.headers_mut(), &decision);
+    Ok(res)
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, MySqlConnection};
+use utoipa::ToSchema;
+
+use crate::timestamps;
+
+// Relevé quotidien de la qualité de l'eau d'une exploitation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct QualiteEau {
+    pub id: i32,
+    pub exploitation_id: i32,
+    pub date_releve: NaiveDate,
+    pub temperature: f64,     // Température de l'eau (°C)
+    pub oxygene_dissous: f64, // Oxygène dissous (mg/L)
+    pub ph: f64,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+// Aliment distribué à un élément sur une journée
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Alimentation {
+    pub id: i32,
+    pub element_id: i32,
+    pub date_alimentation: NaiveDate,
+    pub quantite_kg: f64,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+impl QualiteEau {
+    // Enregistrer un relevé dans MySQL
+    pub async fn create(
+        conn: &mut MySqlConnection,
+        exploitation_id: i32,
+        date_releve: NaiveDate,
+        temperature: f64,
+        oxygene_dissous: f64,
+        ph: f64,
+        created_by: Option<i32>,
+    ) -> Result<Self, sqlx::Error>
+    {
+        let now = timestamps::now();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO qualite_eau (exploitation_id, date_releve, temperature, oxygene_dissous, ph, created_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(exploitation_id)
+        .bind(date_releve)
+        .bind(temperature)
+        .bind(oxygene_dissous)
+        .bind(ph)
+        .bind(now)
+        .bind(created_by)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(QualiteEau {
+            id: result.last_insert_id() as i32,
+            exploitation_id,
+            date_releve,
+            temperature,
+            oxygene_dissous,
+            ph,
+            created_at: now,
+            created_by,
+        })
+    }
+
+    // Relevés d'une exploitation, du plus ancien au plus récent
+    pub async fn get_by_exploitation_id(conn: &mut MySqlConnection, exploitation_id: i32) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let releves = sqlx::query_as::<_, QualiteEau>(
+            r#"
+            SELECT id, exploitation_id, date_releve, temperature, oxygene_dissous, ph, created_at, created_by
+            FROM qualite_eau
+            WHERE exploitation_id = ?
+            ORDER BY date_releve, id
+            "#,
+        )
+        .bind(exploitation_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(releves)
+    }
+}
+
+impl Alimentation {
+    // Enregistrer une distribution d'aliment dans MySQL
+    pub async fn create(
+        conn: &mut MySqlConnection,
+        element_id: i32,
+        date_alimentation: NaiveDate,
+        quantite_kg: f64,
+        created_by: Option<i32>,
+    ) -> Result<Self, sqlx::Error>
+    {
+        let now = timestamps::now();
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO alimentations (element_id, date_alimentation, quantite_kg, created_at, created_by)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(element_id)
+        .bind(date_alimentation)
+        .bind(quantite_kg)
+        .bind(now)
+        .bind(created_by)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(Alimentation {
+            id: result.last_insert_id() as i32,
+            element_id,
+            date_alimentation,
+            quantite_kg,
+            created_at: now,
+            created_by,
+        })
+    }
+
+    // Distributions d'un élément, de la plus ancienne à la plus récente
+    pub async fn get_by_element_id(conn: &mut MySqlConnection, element_id: i32) -> Result<Vec<Self>, sqlx::Error>
+    {
+        let alimentations = sqlx::query_as::<_, Alimentation>(
+            r#"
+            SELECT id, element_id, date_alimentation, quantite_kg, created_at, created_by
+            FROM alimentations
+            WHERE element_id = ?
+            ORDER BY date_alimentation, id
+            "#,
+        )
+        .bind(element_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        Ok(alimentations)
+    }
+}
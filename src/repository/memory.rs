@@ -0,0 +1,1700 @@
+use async_trait::async_trait;
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::FutureExt;
+use sqlx::Error;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::batch::{self, BatchError, BatchOperation, BatchStore, OperationResult};
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::idempotency::{IdempotencyRecord, Reservation};
+use crate::metrics::{BusinessCounts, PoolStats};
+use crate::production::Production;
+use crate::releve::{Alimentation, QualiteEau};
+use crate::search::{self, SearchHit, SearchKind};
+use crate::sync::{self, ChangeSet, ClientIdReservation};
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+use crate::trash::{Restore, Subtree, TrashItem};
+use crate::tree::{self, DomaineTree, ResumeProduction};
+use crate::type_element::TypeElement;
+use crate::type_exploitation::TypeExploitation;
+use crate::type_translation::{TypeKind, TypeTranslation};
+use crate::type_user::TypeUser;
+use crate::user::{NewUser, User, UserChanges};
+use crate::webhook::{Attempt, DeliveryStatus, Webhook, WebhookDelivery};
+
+use super::{
+    BatchRepository, DomaineRepository, ElementRepository, ExploitationRepository, HealthRepository,
+    IdempotencyRepository, ProductionRepository, ReferenceRepository, ReleveRepository, RepoError,
+    SearchRepository, StatsRepository, SyncRepository, TrashRepository, UserRepository, WebhookRepository,
+};
+
+// Table en mémoire avec un compteur d'ID à la manière d'AUTO_INCREMENT
+#[derive(Clone)]
+struct Table<T> {
+    rows: BTreeMap<i32, T>,
+    last_id: i32,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table {
+            rows: BTreeMap::new(),
+            last_id: 0,
+        }
+    }
+}
+
+impl<T: Clone> Table<T> {
+    fn insert(&mut self, build: impl FnOnce(i32) -> T) -> T {
+        self.last_id += 1;
+        let row = build(self.last_id);
+        self.rows.insert(self.last_id, row.clone());
+        row
+    }
+
+    fn get(&self, id: i32) -> Result<T, Error> {
+        self.rows.get(&id).cloned().ok_or(Error::RowNotFound)
+    }
+
+    fn filter(&self, keep: impl Fn(&T) -> bool) -> Vec<T> {
+        self.rows.values().filter(|&row| keep(row)).cloned().collect()
+    }
+}
+
+// Ligne mise à la corbeille : elle quitte sa table jusqu'à sa restauration ou sa purge
+#[derive(Clone)]
+enum Trashed {
+    Domaine(Domaine),
+    Exploitation(Exploitation),
+    Element(Element),
+    Production(Production),
+}
+
+impl Trashed {
+    // Parent direct ; pour un domaine, son propriétaire
+    fn link(&self) -> i32 {
+        match self {
+            Trashed::Domaine(d) => d.user_id,
+            Trashed::Exploitation(e) => e.domaine_id,
+            Trashed::Element(el) => el.exploitation_id,
+            Trashed::Production(p) => p.element_id,
+        }
+    }
+
+    fn parent(&self) -> Option<(Entity, i32)> {
+        match self {
+            Trashed::Domaine(_) => None,
+            Trashed::Exploitation(e) => Some((Entity::Domaine, e.domaine_id)),
+            Trashed::Element(el) => Some((Entity::Exploitation, el.exploitation_id)),
+            Trashed::Production(p) => Some((Entity::Element, p.element_id)),
+        }
+    }
+}
+
+// Ligne à la corbeille, sa date de suppression et le lot de la suppression en cascade qui l'a emportée
+type TrashEntry = (Trashed, DateTime<Utc>, Option<String>);
+
+// ID serveur de la ligne créée pour un identifiant client, None tant que la création est en cours,
+// et date de la réservation
+type ClientIdEntry = (Option<i32>, DateTime<Utc>);
+
+// Contenu de la base en mémoire
+#[derive(Clone, Default)]
+struct State {
+    users: Table<User>,
+    types_user: Table<TypeUser>,
+    types_exploitation: Table<TypeExploitation>,
+    types_element: Table<TypeElement>,
+    type_translations: HashMap<(TypeKind, i32, String), String>,
+    domaines: Table<Domaine>,
+    exploitations: Table<Exploitation>,
+    elements: Table<Element>,
+    productions: Table<Production>,
+    qualite_eau: Table<QualiteEau>,
+    alimentations: Table<Alimentation>,
+    tombstones: Vec<Tombstone>,
+    trash: HashMap<(Entity, i32), TrashEntry>,
+    client_ids: HashMap<(i32, Entity, String), ClientIdEntry>,
+    idempotency_keys: HashMap<(String, String), IdempotencyRecord>,
+    webhooks: Table<Webhook>,
+    webhook_deliveries: Table<WebhookDelivery>,
+}
+
+// Équivalent des violations de contraintes renvoyées par MySQL
+fn constraint(message: &str) -> Error {
+    Error::Protocol(message.to_string())
+}
+
+fn changed_since(updated_at: DateTime<Utc>, updated_since: Option<DateTime<Utc>>) -> bool {
+    match updated_since {
+        Some(since) => updated_at > since,
+        None => true,
+    }
+}
+
+impl State {
+    // Parent direct d'une ligne en place ; pour un domaine, son propriétaire
+    fn live_link(&self, entity: Entity, id: i32) -> Option<i32> {
+        match entity {
+            Entity::Domaine => self.domaines.rows.get(&id).map(|d| d.user_id),
+            Entity::Exploitation => self.exploitations.rows.get(&id).map(|e| e.domaine_id),
+            Entity::Element => self.elements.rows.get(&id).map(|el| el.exploitation_id),
+            Entity::Production => self.productions.rows.get(&id).map(|p| p.element_id),
+        }
+    }
+
+    // Même lien pour une ligne en place ou à la corbeille
+    fn link(&self, entity: Entity, id: i32) -> Option<i32> {
+        self.live_link(entity, id)
+            .or_else(|| self.trash.get(&(entity, id)).map(|(row, _, _)| row.link()))
+    }
+
+    // Domaine et propriétaire d'une ligne en place, en suivant les clés étrangères
+    // Une ligne à la corbeille n'appartient plus à personne
+    fn owner(&self, entity: Entity, id: i32) -> Option<(i32, i32)> {
+        self.resolve_owner(entity, id, false)
+    }
+
+    // Même recherche en suivant aussi les lignes à la corbeille, pour les restaurer
+    fn owner_including_trash(&self, entity: Entity, id: i32) -> Option<(i32, i32)> {
+        self.resolve_owner(entity, id, true)
+    }
+
+    fn resolve_owner(&self, entity: Entity, id: i32, with_trash: bool) -> Option<(i32, i32)> {
+        let link = if with_trash { self.link(entity, id)? } else { self.live_link(entity, id)? };
+        match entity {
+            Entity::Domaine => Some((id, link)),
+            Entity::Exploitation => self.resolve_owner(Entity::Domaine, link, with_trash),
+            Entity::Element => self.resolve_owner(Entity::Exploitation, link, with_trash),
+            Entity::Production => self.resolve_owner(Entity::Element, link, with_trash),
+        }
+    }
+
+    fn live_version(&self, entity: Entity, id: i32) -> Option<i32> {
+        match entity {
+            Entity::Domaine => self.domaines.rows.get(&id).map(|d| d.version),
+            Entity::Exploitation => self.exploitations.rows.get(&id).map(|e| e.version),
+            Entity::Element => self.elements.rows.get(&id).map(|e| e.version),
+            Entity::Production => self.productions.rows.get(&id).map(|p| p.version),
+        }
+    }
+
+    fn record_tombstone(&mut self, entity: Entity, entity_id: i32, (domaine_id, user_id): (i32, i32)) {
+        self.tombstones.push(Tombstone {
+            entity,
+            entity_id,
+            domaine_id,
+            user_id,
+            deleted_at: timestamps::now(),
+        });
+    }
+
+    // Ranger une ligne retirée de sa table à la corbeille, en laissant une trace pour la synchronisation
+    fn move_to_trash(&mut self, entity: Entity, id: i32, owner: Option<(i32, i32)>, row: Trashed) {
+        self.trash.insert((entity, id), (row, timestamps::now(), None));
+        if let Some(owner) = owner {
+            self.record_tombstone(entity, id, owner);
+        }
+    }
+
+    // Enfants en place des lignes `ids`, avec leur type
+    fn children(&self, entity: Entity, ids: &[i32]) -> Option<(Entity, Vec<i32>)> {
+        match entity {
+            Entity::Domaine => Some((
+                Entity::Exploitation,
+                self.exploitations.filter(|e| ids.contains(&e.domaine_id)).iter().map(|e| e.id).collect(),
+            )),
+            Entity::Exploitation => Some((
+                Entity::Element,
+                self.elements.filter(|el| ids.contains(&el.exploitation_id)).iter().map(|el| el.id).collect(),
+            )),
+            Entity::Element => Some((
+                Entity::Production,
+                self.productions.filter(|p| ids.contains(&p.element_id)).iter().map(|p| p.id).collect(),
+            )),
+            Entity::Production => None,
+        }
+    }
+
+    fn subtree(&self, entity: Entity, id: i32) -> Subtree {
+        let mut subtree = Subtree::default();
+        let (mut entity, mut ids) = (entity, vec![id]);
+        while let Some((child, found)) = self.children(entity, &ids) {
+            subtree.ids_mut(child).extend(&found);
+            (entity, ids) = (child, found);
+        }
+        subtree
+    }
+
+    fn delete(&mut self, entity: Entity, id: i32, version: i32) -> Result<bool, RepoError> {
+        match entity {
+            Entity::Domaine => self.delete_domaine(id, version),
+            Entity::Exploitation => self.delete_exploitation(id, version),
+            Entity::Element => self.delete_element(id, version),
+            Entity::Production => Ok(self.delete_production(id, version)?),
+        }
+    }
+
+    // Un parent ne va à la corbeille qu'une fois vide : ses descendants en place sont comptés
+    fn check_no_dependents(&self, entity: Entity, id: i32) -> Result<(), RepoError> {
+        let dependents = self.subtree(entity, id);
+        if !dependents.is_empty() {
+            return Err(RepoError::Conflict(dependents.dependents()));
+        }
+        Ok(())
+    }
+
+    fn delete_cascade(&mut self, entity: Entity, id: i32, version: i32) -> Result<Option<Subtree>, RepoError> {
+        if self.live_version(entity, id) != Some(version) {
+            return Ok(None);
+        }
+
+        // Du bas vers le haut, pour que chaque parent soit vide quand vient son tour
+        let mut deleted = self.subtree(entity, id);
+        for child in [Entity::Production, Entity::Element, Entity::Exploitation] {
+            for &child_id in deleted.ids(child) {
+                if let Some(child_version) = self.live_version(child, child_id) {
+                    self.delete(child, child_id, child_version)?;
+                }
+            }
+        }
+        self.delete(entity, id, version)?;
+        deleted.ids_mut(entity).push(id);
+
+        // Toute la sous-arborescence forme un lot, avec l'horodatage de la ligne : la restauration les ramène ensemble
+        let batch = uuid::Uuid::new_v4().to_string();
+        if let Some(&(_, deleted_at, _)) = self.trash.get(&(entity, id)) {
+            for key in deleted.rows() {
+                if let Some((_, at, lot)) = self.trash.get_mut(&key) {
+                    *at = deleted_at;
+                    *lot = Some(batch.clone());
+                }
+            }
+        }
+
+        Ok(Some(deleted))
+    }
+
+    fn restore(&mut self, entity: Entity, id: i32) -> Restore {
+        let (parent_in_trash, batch) = match self.trash.get(&(entity, id)) {
+            None => return Restore::NotInTrash,
+            Some((row, _, batch)) => {
+                (row.parent().is_some_and(|parent| self.trash.contains_key(&parent)), batch.clone())
+            }
+        };
+        if parent_in_trash {
+            return Restore::ParentInTrash;
+        }
+
+        // Les descendants supprimés par la même cascade reviennent avec la ligne
+        let mut restored = match batch {
+            Some(batch) => self.trashed_subtree(entity, id, &batch),
+            None => Subtree::default(),
+        };
+        restored.ids_mut(entity).push(id);
+
+        let now = timestamps::now();
+        for (entity, id) in restored.rows() {
+            self.untrash(entity, id, now);
+        }
+        Restore::Restored(restored)
+    }
+
+    // Descendants mis à la corbeille par la même suppression en cascade qu'une ligne : ils portent son lot
+    fn trashed_subtree(&self, entity: Entity, id: i32, batch: &str) -> Subtree {
+        let mut subtree = Subtree::default();
+        let child_of = |entity| match entity {
+            Entity::Domaine => Some(Entity::Exploitation),
+            Entity::Exploitation => Some(Entity::Element),
+            Entity::Element => Some(Entity::Production),
+            Entity::Production => None,
+        };
+        let (mut entity, mut ids) = (entity, vec![id]);
+        while let Some(child) = child_of(entity) {
+            let mut found: Vec<i32> = self
+                .trash
+                .iter()
+                .filter(|((e, _), (row, _, lot))| {
+                    *e == child
+                        && lot.as_deref() == Some(batch)
+                        && row.parent().is_some_and(|(_, p)| ids.contains(&p))
+                })
+                .map(|((_, id), _)| *id)
+                .collect();
+            found.sort_unstable();
+            subtree.ids_mut(child).extend(&found);
+            (entity, ids) = (child, found);
+        }
+        subtree
+    }
+
+    // Remettre une ligne dans sa table
+    fn untrash(&mut self, entity: Entity, id: i32, now: DateTime<Utc>) {
+        // La version change : les modifications hors ligne antérieures à la suppression sont refusées
+        match self.trash.remove(&(entity, id)).map(|(row, _, _)| row) {
+            Some(Trashed::Domaine(mut d)) => {
+                d.version += 1;
+                d.updated_at = now;
+                self.domaines.rows.insert(id, d);
+            }
+            Some(Trashed::Exploitation(mut e)) => {
+                e.version += 1;
+                e.updated_at = now;
+                self.exploitations.rows.insert(id, e);
+            }
+            Some(Trashed::Element(mut el)) => {
+                el.version += 1;
+                el.updated_at = now;
+                self.elements.rows.insert(id, el);
+            }
+            Some(Trashed::Production(mut p)) => {
+                p.version += 1;
+                p.updated_at = now;
+                self.productions.rows.insert(id, p);
+            }
+            None => return,
+        }
+
+        // La ligne réapparaît dans la synchronisation comme une modification
+        self.tombstones.retain(|t| !(t.entity == entity && t.entity_id == id));
+    }
+
+    // Les enfants d'un parent purgé partent avec lui
+    // Les traces de suppression restent, comme dans les bases : les clients hors ligne en ont besoin
+    fn purge(&mut self, before: DateTime<Utc>) -> u64 {
+        let mut purged: HashSet<(Entity, i32)> = HashSet::new();
+        for entity in [Entity::Domaine, Entity::Exploitation, Entity::Element, Entity::Production] {
+            let expired: Vec<(Entity, i32)> = self
+                .trash
+                .iter()
+                .filter(|((e, _), (row, deleted_at, _))| {
+                    *e == entity && (*deleted_at < before || row.parent().is_some_and(|p| purged.contains(&p)))
+                })
+                .map(|(key, _)| *key)
+                .collect();
+            purged.extend(expired);
+        }
+
+        for key in &purged {
+            self.trash.remove(key);
+        }
+
+        // Comme la clé étrangère ON DELETE CASCADE des webhooks
+        let domaines: HashSet<i32> = purged
+            .iter()
+            .filter(|(entity, _)| *entity == Entity::Domaine)
+            .map(|(_, id)| *id)
+            .collect();
+        let webhooks: Vec<i32> = self
+            .webhooks
+            .filter(|w| domaines.contains(&w.domaine_id))
+            .iter()
+            .map(|w| w.id)
+            .collect();
+        self.webhooks.rows.retain(|_, w| !domaines.contains(&w.domaine_id));
+        self.webhook_deliveries.rows.retain(|_, d| !webhooks.contains(&d.webhook_id));
+
+        // Les relevés suivent leur exploitation ou leur élément, comme en base
+        self.qualite_eau
+            .rows
+            .retain(|_, q| !purged.contains(&(Entity::Exploitation, q.exploitation_id)));
+        self.alimentations
+            .rows
+            .retain(|_, a| !purged.contains(&(Entity::Element, a.element_id)));
+
+        purged.len() as u64
+    }
+
+    fn trash_items(&self, user_id: i32) -> Vec<TrashItem> {
+        let mut items: Vec<TrashItem> = self
+            .trash
+            .iter()
+            .filter_map(|(&(entity, id), (row, deleted_at, _))| {
+                let (domaine_id, owner) = self.owner_including_trash(entity, id)?;
+                if owner != user_id {
+                    return None;
+                }
+                let nom = match row {
+                    Trashed::Domaine(d) => d.nom_domaine.clone(),
+                    Trashed::Exploitation(e) => e.nom_exploitation.clone(),
+                    Trashed::Element(el) => el.nom_element.clone(),
+                    // Une production n'a pas de nom : celui de son élément, en place ou à la corbeille
+                    Trashed::Production(p) => match self.elements.rows.get(&p.element_id) {
+                        Some(el) => el.nom_element.clone(),
+                        None => match self.trash.get(&(Entity::Element, p.element_id)) {
+                            Some((Trashed::Element(el), _, _)) => el.nom_element.clone(),
+                            _ => return None,
+                        },
+                    },
+                };
+                Some(TrashItem { entity, id, nom, domaine_id, deleted_at: *deleted_at })
+            })
+            .collect();
+
+        items.sort_by_key(|item| (std::cmp::Reverse(item.deleted_at), item.id));
+        items
+    }
+
+    fn create_domaine(&mut self, user_id: i32, nom_domaine: String, created_by: Option<i32>) -> Result<Domaine, Error> {
+        if !self.users.rows.contains_key(&user_id) {
+            return Err(constraint("Utilisateur inexistant"));
+        }
+        let now = timestamps::now();
+        Ok(self.domaines.insert(|id| Domaine {
+            id,
+            user_id,
+            nom_domaine,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    fn update_domaine(&mut self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+        match self.domaines.rows.get_mut(&id) {
+            Some(domaine) if domaine.version == version => {
+                if let Some(nom_domaine) = nom_domaine {
+                    domaine.nom_domaine = nom_domaine;
+                }
+                domaine.version += 1;
+                domaine.updated_at = timestamps::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn delete_domaine(&mut self, id: i32, version: i32) -> Result<bool, RepoError> {
+        if self.live_version(Entity::Domaine, id) != Some(version) {
+            return Ok(false);
+        }
+        self.check_no_dependents(Entity::Domaine, id)?;
+        let owner = self.owner(Entity::Domaine, id);
+        if let Some(mut domaine) = self.domaines.rows.remove(&id) {
+            domaine.version += 1;
+            domaine.updated_at = timestamps::now();
+            self.move_to_trash(Entity::Domaine, id, owner, Trashed::Domaine(domaine));
+        }
+        Ok(true)
+    }
+
+    fn create_exploitation(
+        &mut self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error> {
+        if !self.types_exploitation.rows.contains_key(&type_exploitation_id) {
+            return Err(constraint("Type d'exploitation inexistant"));
+        }
+        if !self.domaines.rows.contains_key(&domaine_id) {
+            return Err(constraint("Domaine inexistant"));
+        }
+        let now = timestamps::now();
+        Ok(self.exploitations.insert(|id| Exploitation {
+            id,
+            type_exploitation_id,
+            domaine_id,
+            nom_exploitation,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    fn update_exploitation(
+        &mut self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error> {
+        if let Some(type_exploitation_id) = type_exploitation_id {
+            if !self.types_exploitation.rows.contains_key(&type_exploitation_id) {
+                return Err(constraint("Type d'exploitation inexistant"));
+            }
+        }
+        match self.exploitations.rows.get_mut(&id) {
+            Some(exploitation) if exploitation.version == version => {
+                if let Some(type_exploitation_id) = type_exploitation_id {
+                    exploitation.type_exploitation_id = type_exploitation_id;
+                }
+                if let Some(nom_exploitation) = nom_exploitation {
+                    exploitation.nom_exploitation = nom_exploitation;
+                }
+                exploitation.version += 1;
+                exploitation.updated_at = timestamps::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn delete_exploitation(&mut self, id: i32, version: i32) -> Result<bool, RepoError> {
+        if self.live_version(Entity::Exploitation, id) != Some(version) {
+            return Ok(false);
+        }
+        self.check_no_dependents(Entity::Exploitation, id)?;
+        let owner = self.owner(Entity::Exploitation, id);
+        if let Some(mut exploitation) = self.exploitations.rows.remove(&id) {
+            exploitation.version += 1;
+            exploitation.updated_at = timestamps::now();
+            self.move_to_trash(Entity::Exploitation, id, owner, Trashed::Exploitation(exploitation));
+        }
+        Ok(true)
+    }
+
+    fn create_element(
+        &mut self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error> {
+        if !self.exploitations.rows.contains_key(&exploitation_id) {
+            return Err(constraint("Exploitation inexistante"));
+        }
+        if quantite < 0 {
+            return Err(constraint("La quantité doit être positive"));
+        }
+        let now = timestamps::now();
+        Ok(self.elements.insert(|id| Element {
+            id,
+            exploitation_id,
+            nom_element,
+            quantite,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    fn update_element(
+        &mut self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error> {
+        if quantite.is_some_and(|q| q < 0) {
+            return Err(constraint("La quantité doit être positive"));
+        }
+        match self.elements.rows.get_mut(&id) {
+            Some(element) if element.version == version => {
+                if let Some(nom_element) = nom_element {
+                    element.nom_element = nom_element;
+                }
+                if let Some(quantite) = quantite {
+                    element.quantite = quantite;
+                }
+                element.version += 1;
+                element.updated_at = timestamps::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn delete_element(&mut self, id: i32, version: i32) -> Result<bool, RepoError> {
+        if self.live_version(Entity::Element, id) != Some(version) {
+            return Ok(false);
+        }
+        self.check_no_dependents(Entity::Element, id)?;
+        let owner = self.owner(Entity::Element, id);
+        if let Some(mut element) = self.elements.rows.remove(&id) {
+            element.version += 1;
+            element.updated_at = timestamps::now();
+            self.move_to_trash(Entity::Element, id, owner, Trashed::Element(element));
+        }
+        Ok(true)
+    }
+
+    fn create_production(
+        &mut self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error> {
+        if !self.elements.rows.contains_key(&element_id) {
+            return Err(constraint("Élément inexistant"));
+        }
+        if quantite_produite < 0 {
+            return Err(constraint("La quantité produite doit être positive"));
+        }
+        let now = timestamps::now();
+        Ok(self.productions.insert(|id| Production {
+            id,
+            element_id,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+            version: 1,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    fn update_production(
+        &mut self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        if quantite_produite.is_some_and(|q| q < 0) {
+            return Err(constraint("La quantité produite doit être positive"));
+        }
+        match self.productions.rows.get_mut(&id) {
+            Some(production) if production.version == version => {
+                if let Some(quantite_produite) = quantite_produite {
+                    production.quantite_produite = quantite_produite;
+                }
+                if let Some(unite_production) = unite_production {
+                    production.unite_production = unite_production;
+                }
+                if let Some(date_de_production) = date_de_production {
+                    production.date_de_production = date_de_production;
+                }
+                production.version += 1;
+                production.updated_at = timestamps::now();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn delete_production(&mut self, id: i32, version: i32) -> Result<bool, Error> {
+        if self.live_version(Entity::Production, id) != Some(version) {
+            return Ok(false);
+        }
+        let owner = self.owner(Entity::Production, id);
+        if let Some(mut production) = self.productions.rows.remove(&id) {
+            production.version += 1;
+            production.updated_at = timestamps::now();
+            self.move_to_trash(Entity::Production, id, owner, Trashed::Production(production));
+        }
+        Ok(true)
+    }
+}
+
+// Les opérations d'un lot s'appliquent directement à l'état ; aucune n'attend réellement
+#[async_trait]
+impl BatchStore for State {
+    async fn create_domaine(
+        &mut self,
+        user_id: i32,
+        nom_domaine: String,
+        created_by: Option<i32>,
+    ) -> Result<Domaine, Error> {
+        State::create_domaine(self, user_id, nom_domaine, created_by)
+    }
+
+    async fn create_exploitation(
+        &mut self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error> {
+        State::create_exploitation(self, type_exploitation_id, domaine_id, nom_exploitation, created_by)
+    }
+
+    async fn create_element(
+        &mut self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error> {
+        State::create_element(self, exploitation_id, nom_element, quantite, created_by)
+    }
+
+    async fn create_production(
+        &mut self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error> {
+        State::create_production(
+            self,
+            element_id,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+            created_by,
+        )
+    }
+
+    async fn update_domaine(&mut self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+        State::update_domaine(self, id, version, nom_domaine)
+    }
+
+    async fn update_exploitation(
+        &mut self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error> {
+        State::update_exploitation(self, id, version, type_exploitation_id, nom_exploitation)
+    }
+
+    async fn update_element(
+        &mut self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error> {
+        State::update_element(self, id, version, nom_element, quantite)
+    }
+
+    async fn update_production(
+        &mut self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        State::update_production(self, id, version, quantite_produite, unite_production, date_de_production)
+    }
+
+    async fn delete(&mut self, entity: Entity, id: i32, version: i32) -> Result<bool, RepoError> {
+        State::delete(self, entity, id, version)
+    }
+
+    async fn owner_of(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        Ok(self.owner(entity, id).map(|(_, user_id)| user_id))
+    }
+
+    async fn current_version(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        Ok(self.live_version(entity, id))
+    }
+}
+
+// Implémentation en mémoire, sans base de données, pour les tests
+#[derive(Clone, Default)]
+pub struct InMemoryRepository {
+    state: Arc<Mutex<State>>,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("État en mémoire inaccessible")
+    }
+}
+
+#[async_trait]
+impl UserRepository for InMemoryRepository {
+    async fn create(&self, new_user: NewUser, created_by: Option<i32>) -> Result<User, Error> {
+        let hashed_password = hash(&new_user.mot_de_passe, DEFAULT_COST).map_err(|_| Error::RowNotFound)?;
+        let mut state = self.lock();
+
+        if !state.types_user.rows.contains_key(&new_user.type_user_id) {
+            return Err(constraint("Type d'utilisateur inexistant"));
+        }
+        if state.users.rows.values().any(|u| u.email == new_user.email) {
+            return Err(constraint("Email déjà utilisé"));
+        }
+
+        let now = timestamps::now();
+        Ok(state.users.insert(|id| User {
+            id,
+            type_user_id: new_user.type_user_id,
+            nom: new_user.nom,
+            prenom: new_user.prenom,
+            email: new_user.email,
+            numero_telephone: new_user.numero_telephone,
+            mot_de_passe: hashed_password,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<User>, Error> {
+        Ok(self.lock().users.filter(|u| changed_since(u.updated_at, updated_since)))
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<User, Error> {
+        self.lock().users.get(id)
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User, Error> {
+        self.lock()
+            .users
+            .rows
+            .values()
+            .find(|u| u.email == email)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn update(&self, id: i32, changes: UserChanges) -> Result<(), Error> {
+        let mut state = self.lock();
+
+        if let Some(email) = &changes.email {
+            if state.users.rows.values().any(|u| u.id != id && &u.email == email) {
+                return Err(constraint("Email déjà utilisé"));
+            }
+        }
+
+        if let Some(user) = state.users.rows.get_mut(&id) {
+            if let Some(nom) = changes.nom {
+                user.nom = nom;
+            }
+            if let Some(prenom) = changes.prenom {
+                user.prenom = prenom;
+            }
+            if let Some(email) = changes.email {
+                user.email = email;
+            }
+            if let Some(numero_telephone) = changes.numero_telephone {
+                user.numero_telephone = numero_telephone;
+            }
+            if let Some(mot_de_passe) = changes.mot_de_passe {
+                user.mot_de_passe = mot_de_passe;
+            }
+            user.updated_at = timestamps::now();
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: i32) -> Result<(), Error> {
+        let mut state = self.lock();
+
+        if state.domaines.rows.values().any(|d| d.user_id == id) {
+            return Err(constraint("L'utilisateur possède des domaines"));
+        }
+        state.users.rows.remove(&id);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ReferenceRepository for InMemoryRepository {
+    async fn create_type_user(&self, nom_type_user: String, created_by: Option<i32>) -> Result<TypeUser, Error> {
+        let now = timestamps::now();
+        Ok(self.lock().types_user.insert(|id| TypeUser {
+            id,
+            nom_type_user,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    async fn get_types_user(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeUser>, Error> {
+        Ok(self.lock().types_user.filter(|t| changed_since(t.updated_at, updated_since)))
+    }
+
+    async fn create_type_exploitation(
+        &self,
+        nom_type_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<TypeExploitation, Error> {
+        let now = timestamps::now();
+        Ok(self.lock().types_exploitation.insert(|id| TypeExploitation {
+            id,
+            nom_type_exploitation,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    async fn get_types_exploitation(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TypeExploitation>, Error> {
+        Ok(self.lock().types_exploitation.filter(|t| changed_since(t.updated_at, updated_since)))
+    }
+
+    async fn create_type_element(&self, nom_type_element: String, created_by: Option<i32>) -> Result<TypeElement, Error> {
+        let now = timestamps::now();
+        Ok(self.lock().types_element.insert(|id| TypeElement {
+            id,
+            nom_type_element,
+            created_at: now,
+            updated_at: now,
+            created_by,
+        }))
+    }
+
+    async fn get_types_element(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeElement>, Error> {
+        Ok(self.lock().types_element.filter(|t| changed_since(t.updated_at, updated_since)))
+    }
+
+    async fn update_type_element(&self, id: i32, nom_type_element: String) -> Result<(), Error> {
+        if let Some(type_element) = self.lock().types_element.rows.get_mut(&id) {
+            type_element.nom_type_element = nom_type_element;
+            type_element.updated_at = timestamps::now();
+        }
+        Ok(())
+    }
+
+    async fn delete_type_element(&self, id: i32) -> Result<(), Error> {
+        let mut state = self.lock();
+        state.types_element.rows.remove(&id);
+        state
+            .type_translations
+            .retain(|(kind, type_id, _), _| !(*kind == TypeKind::TypeElement && *type_id == id));
+        Ok(())
+    }
+
+    async fn get_type_translations(&self, kind: TypeKind, lang: &str) -> Result<Vec<TypeTranslation>, Error> {
+        let state = self.lock();
+        let mut translations: Vec<TypeTranslation> = state
+            .type_translations
+            .iter()
+            .filter(|((k, _, l), _)| *k == kind && l == lang)
+            .map(|((_, type_id, _), nom)| TypeTranslation {
+                type_id: *type_id,
+                nom: nom.clone(),
+            })
+            .collect();
+        translations.sort_by_key(|t| t.type_id);
+        Ok(translations)
+    }
+
+    async fn set_type_translation(&self, kind: TypeKind, type_id: i32, lang: &str, nom: String) -> Result<(), Error> {
+        let mut state = self.lock();
+        let now = timestamps::now();
+        // Le type est marqué modifié pour que les clients synchronisés relisent son nom
+        let updated_at = match kind {
+            TypeKind::TypeUser => state.types_user.rows.get_mut(&type_id).map(|t| &mut t.updated_at),
+            TypeKind::TypeExploitation => state.types_exploitation.rows.get_mut(&type_id).map(|t| &mut t.updated_at),
+            TypeKind::TypeElement => state.types_element.rows.get_mut(&type_id).map(|t| &mut t.updated_at),
+        };
+        match updated_at {
+            Some(updated_at) => *updated_at = now,
+            None => return Err(Error::RowNotFound),
+        }
+        state.type_translations.insert((kind, type_id, lang.to_string()), nom);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DomaineRepository for InMemoryRepository {
+    async fn create(&self, user_id: i32, nom_domaine: String, created_by: Option<i32>) -> Result<Domaine, Error> {
+        self.lock().create_domaine(user_id, nom_domaine, created_by)
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Domaine>, Error> {
+        Ok(self.lock().domaines.filter(|d| changed_since(d.updated_at, updated_since)))
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Domaine, Error> {
+        self.lock().domaines.get(id)
+    }
+
+    async fn get_all_by_user_id(
+        &self,
+        user_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Domaine>, Error> {
+        Ok(self
+            .lock()
+            .domaines
+            .filter(|d| d.user_id == user_id && changed_since(d.updated_at, updated_since)))
+    }
+
+    async fn update(&self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+        self.lock().update_domaine(id, version, nom_domaine)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        self.lock().delete_domaine(id, version)
+    }
+
+    async fn owner_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        Ok(self.lock().owner(entity, id).map(|(_, user_id)| user_id))
+    }
+
+    async fn domaine_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        Ok(self.lock().owner(entity, id).map(|(domaine_id, _)| domaine_id))
+    }
+
+    async fn tree(&self, id: i32) -> Result<DomaineTree, Error> {
+        let state = self.lock();
+        let domaine = state.domaines.get(id)?;
+        let exploitations = state.exploitations.filter(|e| e.domaine_id == id);
+        let elements = state
+            .elements
+            .filter(|e| exploitations.iter().any(|x| x.id == e.exploitation_id));
+
+        let dernieres = elements
+            .iter()
+            .filter_map(|element| {
+                let productions = state.productions.filter(|p| p.element_id == element.id);
+                let derniere = productions.iter().max_by_key(|p| (p.date_de_production, p.id))?;
+                Some(ResumeProduction {
+                    element_id: element.id,
+                    date_de_production: derniere.date_de_production,
+                    quantite_produite: derniere.quantite_produite,
+                    unite_production: derniere.unite_production.clone(),
+                    nombre_productions: productions.len() as i64,
+                })
+            })
+            .collect();
+
+        let types = state.types_exploitation.rows.values().cloned().collect();
+        Ok(tree::assemble(domaine, exploitations, types, elements, dernieres))
+    }
+}
+
+#[async_trait]
+impl ExploitationRepository for InMemoryRepository {
+    async fn create(
+        &self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error> {
+        self.lock()
+            .create_exploitation(type_exploitation_id, domaine_id, nom_exploitation, created_by)
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Exploitation>, Error> {
+        Ok(self.lock().exploitations.filter(|e| changed_since(e.updated_at, updated_since)))
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Exploitation, Error> {
+        self.lock().exploitations.get(id)
+    }
+
+    async fn get_by_domaine_id(
+        &self,
+        domaine_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Exploitation>, Error> {
+        Ok(self
+            .lock()
+            .exploitations
+            .filter(|e| e.domaine_id == domaine_id && changed_since(e.updated_at, updated_since)))
+    }
+
+    async fn get_by_domaine_ids(&self, domaine_ids: &[i32]) -> Result<Vec<Exploitation>, Error> {
+        Ok(self.lock().exploitations.filter(|e| domaine_ids.contains(&e.domaine_id)))
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error> {
+        self.lock()
+            .update_exploitation(id, version, type_exploitation_id, nom_exploitation)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        self.lock().delete_exploitation(id, version)
+    }
+}
+
+#[async_trait]
+impl ElementRepository for InMemoryRepository {
+    async fn create(
+        &self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error> {
+        self.lock()
+            .create_element(exploitation_id, nom_element, quantite, created_by)
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Element>, Error> {
+        Ok(self.lock().elements.filter(|e| changed_since(e.updated_at, updated_since)))
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Element, Error> {
+        self.lock().elements.get(id)
+    }
+
+    async fn get_by_exploitation_id(
+        &self,
+        exploitation_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Element>, Error> {
+        Ok(self
+            .lock()
+            .elements
+            .filter(|e| e.exploitation_id == exploitation_id && changed_since(e.updated_at, updated_since)))
+    }
+
+    async fn get_by_exploitation_ids(&self, exploitation_ids: &[i32]) -> Result<Vec<Element>, Error> {
+        Ok(self.lock().elements.filter(|e| exploitation_ids.contains(&e.exploitation_id)))
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error> {
+        self.lock().update_element(id, version, nom_element, quantite)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        self.lock().delete_element(id, version)
+    }
+}
+
+#[async_trait]
+impl ProductionRepository for InMemoryRepository {
+    async fn create(
+        &self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error> {
+        self.lock().create_production(
+            element_id,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+            created_by,
+        )
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Production, Error> {
+        self.lock().productions.get(id)
+    }
+
+    async fn get_by_element_id(
+        &self,
+        element_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Production>, Error> {
+        Ok(self
+            .lock()
+            .productions
+            .filter(|p| p.element_id == element_id && changed_since(p.updated_at, updated_since)))
+    }
+
+    async fn get_by_element_ids(&self, element_ids: &[i32]) -> Result<Vec<Production>, Error> {
+        Ok(self.lock().productions.filter(|p| element_ids.contains(&p.element_id)))
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        self.lock()
+            .update_production(id, version, quantite_produite, unite_production, date_de_production)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        Ok(self.lock().delete_production(id, version)?)
+    }
+}
+
+#[async_trait]
+impl ReleveRepository for InMemoryRepository {
+    async fn create_qualite_eau(
+        &self,
+        exploitation_id: i32,
+        date_releve: NaiveDate,
+        temperature: f64,
+        oxygene_dissous: f64,
+        ph: f64,
+        created_by: Option<i32>,
+    ) -> Result<QualiteEau, Error> {
+        let mut state = self.lock();
+        if !state.exploitations.rows.contains_key(&exploitation_id) {
+            return Err(constraint("Exploitation inexistante"));
+        }
+        let now = timestamps::now();
+        Ok(state.qualite_eau.insert(|id| QualiteEau {
+            id,
+            exploitation_id,
+            date_releve,
+            temperature,
+            oxygene_dissous,
+            ph,
+            created_at: now,
+            created_by,
+        }))
+    }
+
+    async fn get_qualite_eau(&self, exploitation_id: i32) -> Result<Vec<QualiteEau>, Error> {
+        let mut releves = self.lock().qualite_eau.filter(|q| q.exploitation_id == exploitation_id);
+        releves.sort_by_key(|q| (q.date_releve, q.id));
+        Ok(releves)
+    }
+
+    async fn create_alimentation(
+        &self,
+        element_id: i32,
+        date_alimentation: NaiveDate,
+        quantite_kg: f64,
+        created_by: Option<i32>,
+    ) -> Result<Alimentation, Error> {
+        let mut state = self.lock();
+        if !state.elements.rows.contains_key(&element_id) {
+            return Err(constraint("Élément inexistant"));
+        }
+        if quantite_kg < 0.0 {
+            return Err(constraint("La quantité d'aliment doit être positive"));
+        }
+        let now = timestamps::now();
+        Ok(state.alimentations.insert(|id| Alimentation {
+            id,
+            element_id,
+            date_alimentation,
+            quantite_kg,
+            created_at: now,
+            created_by,
+        }))
+    }
+
+    async fn get_alimentations(&self, element_id: i32) -> Result<Vec<Alimentation>, Error> {
+        let mut alimentations = self.lock().alimentations.filter(|a| a.element_id == element_id);
+        alimentations.sort_by_key(|a| (a.date_alimentation, a.id));
+        Ok(alimentations)
+    }
+}
+
+#[async_trait]
+impl SearchRepository for InMemoryRepository {
+    // Même barème que la requête SQL, calculé ici sur les noms
+    async fn search(&self, user_id: i32, query: &str, terms: &[String], limit: usize) -> Result<Vec<SearchHit>, Error> {
+        let state = self.lock();
+
+        let domaines = state.domaines.filter(|d| d.user_id == user_id);
+        let domaine_ids: HashSet<i32> = domaines.iter().map(|d| d.id).collect();
+        let exploitations = state.exploitations.filter(|e| domaine_ids.contains(&e.domaine_id));
+        let domaine_of: HashMap<i32, i32> = exploitations.iter().map(|e| (e.id, e.domaine_id)).collect();
+        let elements = state.elements.filter(|el| domaine_of.contains_key(&el.exploitation_id));
+
+        let candidates = domaines
+            .into_iter()
+            .map(|d| (SearchKind::Domaine, d.id, d.nom_domaine, Some(d.id), None))
+            .chain(
+                exploitations
+                    .into_iter()
+                    .map(|e| (SearchKind::Exploitation, e.id, e.nom_exploitation, Some(e.domaine_id), None)),
+            )
+            .chain(elements.into_iter().map(|el| {
+                let domaine_id = domaine_of.get(&el.exploitation_id).copied();
+                (SearchKind::Element, el.id, el.nom_element, domaine_id, Some(el.exploitation_id))
+            }))
+            .chain(
+                state
+                    .types_user
+                    .rows
+                    .values()
+                    .map(|t| (SearchKind::TypeUser, t.id, t.nom_type_user.clone(), None, None)),
+            )
+            .chain(
+                state
+                    .types_exploitation
+                    .rows
+                    .values()
+                    .map(|t| (SearchKind::TypeExploitation, t.id, t.nom_type_exploitation.clone(), None, None)),
+            )
+            .chain(
+                state
+                    .types_element
+                    .rows
+                    .values()
+                    .map(|t| (SearchKind::TypeElement, t.id, t.nom_type_element.clone(), None, None)),
+            );
+
+        let mut hits: Vec<SearchHit> = candidates
+            .filter_map(|(kind, id, nom, domaine_id, exploitation_id)| {
+                let score = search::score(&nom, query, terms)?;
+                Some(SearchHit { kind, id, nom, domaine_id, exploitation_id, score })
+            })
+            .collect();
+
+        hits.sort_by_key(|hit| (Reverse(hit.score), hit.nom.chars().count(), hit.kind, hit.id));
+        hits.truncate(limit);
+
+        Ok(hits)
+    }
+}
+
+#[async_trait]
+impl SyncRepository for InMemoryRepository {
+    async fn changes_since(&self, user_id: i32, since: DateTime<Utc>) -> Result<ChangeSet, Error> {
+        let sync_token = sync::encode_token(timestamps::now());
+        let state = self.lock();
+        let owned = |entity: Entity, id: i32| state.owner(entity, id).map(|(_, owner)| owner) == Some(user_id);
+
+        Ok(ChangeSet {
+            sync_token,
+            domaines: state
+                .domaines
+                .filter(|d| d.user_id == user_id && d.updated_at >= since),
+            exploitations: state
+                .exploitations
+                .filter(|e| owned(Entity::Exploitation, e.id) && e.updated_at >= since),
+            elements: state
+                .elements
+                .filter(|e| owned(Entity::Element, e.id) && e.updated_at >= since),
+            productions: state
+                .productions
+                .filter(|p| owned(Entity::Production, p.id) && p.updated_at >= since),
+            deleted: state
+                .tombstones
+                .iter()
+                .filter(|t| t.user_id == user_id && t.deleted_at >= since)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    async fn lookup_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<Option<i32>, Error> {
+        Ok(self
+            .lock()
+            .client_ids
+            .get(&(user_id, entity, client_id.to_string()))
+            .and_then(|(entity_id, _)| *entity_id))
+    }
+
+    async fn reserve_client_id(
+        &self,
+        user_id: i32,
+        client_id: &str,
+        entity: Entity,
+        stale_before: DateTime<Utc>,
+    ) -> Result<ClientIdReservation, Error> {
+        let mut state = self.lock();
+        let entry = (user_id, entity, client_id.to_string());
+        match state.client_ids.get(&entry) {
+            Some((Some(entity_id), _)) => return Ok(ClientIdReservation::Recorded(*entity_id)),
+            Some((None, reserved_at)) if *reserved_at >= stale_before => return Ok(ClientIdReservation::Pending),
+            _ => {}
+        }
+
+        state.client_ids.insert(entry, (None, timestamps::now()));
+        Ok(ClientIdReservation::Acquired)
+    }
+
+    async fn record_client_id(
+        &self,
+        user_id: i32,
+        client_id: &str,
+        entity: Entity,
+        entity_id: i32,
+    ) -> Result<(), Error> {
+        if let Some(entry) = self
+            .lock()
+            .client_ids
+            .get_mut(&(user_id, entity, client_id.to_string()))
+        {
+            entry.0 = Some(entity_id);
+        }
+        Ok(())
+    }
+
+    async fn release_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<(), Error> {
+        let mut state = self.lock();
+        let entry = (user_id, entity, client_id.to_string());
+        if let Some((None, _)) = state.client_ids.get(&entry) {
+            state.client_ids.remove(&entry);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BatchRepository for InMemoryRepository {
+    async fn execute(
+        &self,
+        user_id: i32,
+        operations: Vec<BatchOperation>,
+    ) -> Result<Vec<OperationResult>, BatchError> {
+        // Les opérations sont appliquées à une copie, conservée seulement si tout réussit
+        let mut state = self.lock();
+        let mut scratch = state.clone();
+
+        // Le verrou n'est pas conservé au-delà d'un point d'attente : l'état en mémoire répond immédiatement
+        let results = batch::run_all(&mut scratch, user_id, operations)
+            .now_or_never()
+            .expect("Lot en mémoire suspendu")?;
+
+        *state = scratch;
+
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for InMemoryRepository {
+    async fn reserve(
+        &self,
+        principal: &str,
+        key: &str,
+        request_hash: &str,
+        stale_before: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Reservation, Error> {
+        let mut state = self.lock();
+        let entry = (principal.to_string(), key.to_string());
+        if let Some(record) = state.idempotency_keys.get(&entry) {
+            let stale = record.status_code.is_none() && record.created_at < stale_before;
+            if !stale && record.created_at >= expired_before {
+                return Ok(Reservation::Existing(record.clone()));
+            }
+        }
+
+        state.idempotency_keys.insert(
+            entry,
+            IdempotencyRecord {
+                request_hash: request_hash.to_string(),
+                status_code: None,
+                content_type: None,
+                response_body: None,
+                created_at: timestamps::now(),
+            },
+        );
+
+        Ok(Reservation::Acquired)
+    }
+
+    async fn store(
+        &self,
+        principal: &str,
+        key: &str,
+        status_code: u16,
+        content_type: Option<String>,
+        response_body: &[u8],
+    ) -> Result<(), Error> {
+        let mut state = self.lock();
+        if let Some(record) = state
+            .idempotency_keys
+            .get_mut(&(principal.to_string(), key.to_string()))
+        {
+            record.status_code = Some(status_code);
+            record.content_type = content_type;
+            record.response_body = Some(response_body.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn release(&self, principal: &str, key: &str) -> Result<(), Error> {
+        self.lock()
+            .idempotency_keys
+            .remove(&(principal.to_string(), key.to_string()));
+        Ok(())
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+        let mut state = self.lock();
+        let count = state.idempotency_keys.len();
+        state
+            .idempotency_keys
+            .retain(|_, record| record.created_at >= before);
+        Ok((count - state.idempotency_keys.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl StatsRepository for InMemoryRepository {
+    async fn counts(&self, productions_since: DateTime<Utc>) -> Result<BusinessCounts, Error> {
+        let state = self.lock();
+        Ok(BusinessCounts {
+            domaines: state.domaines.rows.len() as i64,
+            elements: state.elements.rows.len() as i64,
+            productions_du_jour: state.productions.filter(|p| p.created_at >= productions_since).len() as i64,
+        })
+    }
+
+    fn pool(&self) -> Option<PoolStats> {
+        None
+    }
+}
+
+// Sans base ni migrations, le dépôt en mémoire est toujours prêt
+#[async_trait]
+impl HealthRepository for InMemoryRepository {
+    async fn ping(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<i64>, Error> {
+        Ok(Vec::new())
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for InMemoryRepository {
+    async fn create_webhook(
+        &self,
+        domaine_id: i32,
+        url: String,
+        events: String,
+        secret: String,
+        created_by: Option<i32>,
+    ) -> Result<Webhook, Error> {
+        let mut state = self.lock();
+        if !state.domaines.rows.contains_key(&domaine_id) {
+            return Err(constraint("Domaine inexistant"));
+        }
+
+        Ok(state.webhooks.insert(|id| Webhook {
+            id,
+            domaine_id,
+            url,
+            events,
+            secret,
+            created_at: timestamps::now(),
+            created_by,
+        }))
+    }
+
+    async fn get_webhooks_by_domaine_id(&self, domaine_id: i32) -> Result<Vec<Webhook>, Error> {
+        Ok(self.lock().webhooks.filter(|w| w.domaine_id == domaine_id))
+    }
+
+    async fn get_webhook(&self, id: i32) -> Result<Webhook, Error> {
+        self.lock().webhooks.get(id)
+    }
+
+    async fn delete_webhook(&self, id: i32) -> Result<(), Error> {
+        let mut state = self.lock();
+        state.webhooks.rows.remove(&id);
+        state.webhook_deliveries.rows.retain(|_, d| d.webhook_id != id);
+        Ok(())
+    }
+
+    async fn create_delivery(&self, webhook_id: i32, event_type: &str, payload: String) -> Result<WebhookDelivery, Error> {
+        let now = timestamps::now();
+        Ok(self.lock().webhook_deliveries.insert(|id| WebhookDelivery {
+            id,
+            webhook_id,
+            event_type: event_type.to_string(),
+            payload,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_status_code: None,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        }))
+    }
+
+    async fn get_deliveries(&self, webhook_id: i32) -> Result<Vec<WebhookDelivery>, Error> {
+        let mut deliveries = self.lock().webhook_deliveries.filter(|d| d.webhook_id == webhook_id);
+        deliveries.reverse();
+        Ok(deliveries)
+    }
+
+    async fn get_delivery(&self, id: i32) -> Result<WebhookDelivery, Error> {
+        self.lock().webhook_deliveries.get(id)
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, Error> {
+        let mut state = self.lock();
+        let mut due: Vec<&mut WebhookDelivery> = state
+            .webhook_deliveries
+            .rows
+            .values_mut()
+            .filter(|d| d.status == DeliveryStatus::Pending && d.next_attempt_at <= now)
+            .collect();
+        due.sort_by_key(|d| (d.next_attempt_at, d.id));
+
+        let mut claimed: Vec<WebhookDelivery> = due
+            .into_iter()
+            .take(limit.max(0) as usize)
+            .map(|delivery| {
+                delivery.next_attempt_at = lease_until;
+                delivery.clone()
+            })
+            .collect();
+        claimed.sort_by_key(|d| d.id);
+        Ok(claimed)
+    }
+
+    async fn record_attempt(&self, id: i32, attempt: Attempt) -> Result<(), Error> {
+        if let Some(delivery) = self.lock().webhook_deliveries.rows.get_mut(&id) {
+            delivery.status = attempt.status;
+            delivery.attempts = attempt.attempts;
+            delivery.last_status_code = attempt.status_code;
+            delivery.last_error = attempt.error;
+            delivery.next_attempt_at = attempt.next_attempt_at;
+            delivery.delivered_at = attempt.delivered_at.or(delivery.delivered_at);
+        }
+        Ok(())
+    }
+
+    async fn redeliver(&self, id: i32) -> Result<(), Error> {
+        if let Some(delivery) = self.lock().webhook_deliveries.rows.get_mut(&id) {
+            delivery.status = DeliveryStatus::Pending;
+            delivery.attempts = 0;
+            delivery.next_attempt_at = timestamps::now();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TrashRepository for InMemoryRepository {
+    async fn get_trash(&self, user_id: i32) -> Result<Vec<TrashItem>, Error> {
+        Ok(self.lock().trash_items(user_id))
+    }
+
+    async fn restore(&self, entity: Entity, id: i32) -> Result<Restore, Error> {
+        Ok(self.lock().restore(entity, id))
+    }
+
+    async fn owner_including_trash(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        Ok(self.lock().owner_including_trash(entity, id).map(|(_, user_id)| user_id))
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+        Ok(self.lock().purge(before))
+    }
+
+    async fn subtree(&self, entity: Entity, id: i32) -> Result<Subtree, Error> {
+        Ok(self.lock().subtree(entity, id))
+    }
+
+    async fn delete_cascade(&self, entity: Entity, id: i32, version: i32) -> Result<Option<Subtree>, RepoError> {
+        self.lock().delete_cascade(entity, id, version)
+    }
+}
@@ -0,0 +1,454 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::Error;
+use std::sync::Arc;
+
+use crate::batch::{BatchError, BatchOperation, OperationResult};
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::events::EventBus;
+use crate::exploitation::Exploitation;
+use crate::idempotency::Reservation;
+use crate::metrics::{BusinessCounts, PoolStats};
+use crate::production::Production;
+use crate::releve::{Alimentation, QualiteEau};
+use crate::search::SearchHit;
+use crate::sync::{ChangeSet, ClientIdReservation};
+use crate::tombstone::Entity;
+use crate::trash::{Dependents, Restore, Subtree, TrashItem};
+use crate::tree::DomaineTree;
+use crate::type_element::TypeElement;
+use crate::type_exploitation::TypeExploitation;
+use crate::type_translation::{TypeKind, TypeTranslation};
+use crate::type_user::TypeUser;
+use crate::user::{NewUser, User, UserChanges};
+use crate::webhook::{Attempt, Outbox, Webhook, WebhookDelivery};
+
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+#[macro_use]
+mod portable;
+
+pub mod memory;
+pub mod mysql;
+mod publishing;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+pub use memory::InMemoryRepository;
+pub use mysql::MySqlRepository;
+use publishing::Publishing;
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresRepository;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteRepository;
+
+// Échec d'une écriture refusée par une règle du dépôt, ou erreur de la base
+#[derive(Debug)]
+pub enum RepoError {
+    // Des lignes en place dépendent de celle à supprimer
+    Conflict(Dependents),
+    Database(Error),
+}
+
+impl From<Error> for RepoError {
+    fn from(e: Error) -> Self {
+        RepoError::Database(e)
+    }
+}
+
+// Accès aux utilisateurs
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn create(&self, new_user: NewUser, created_by: Option<i32>) -> Result<User, Error>;
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<User>, Error>;
+    async fn get_by_id(&self, id: i32) -> Result<User, Error>;
+    async fn get_by_email(&self, email: &str) -> Result<User, Error>;
+    async fn update(&self, id: i32, changes: UserChanges) -> Result<(), Error>;
+    async fn delete(&self, id: i32) -> Result<(), Error>;
+}
+
+// Accès aux tables de référence (types d'utilisateur, d'exploitation et d'élément)
+#[async_trait]
+pub trait ReferenceRepository: Send + Sync {
+    async fn create_type_user(&self, nom_type_user: String, created_by: Option<i32>) -> Result<TypeUser, Error>;
+    async fn get_types_user(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeUser>, Error>;
+    async fn create_type_exploitation(
+        &self,
+        nom_type_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<TypeExploitation, Error>;
+    async fn get_types_exploitation(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TypeExploitation>, Error>;
+    async fn create_type_element(&self, nom_type_element: String, created_by: Option<i32>) -> Result<TypeElement, Error>;
+    async fn get_types_element(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeElement>, Error>;
+    async fn update_type_element(&self, id: i32, nom_type_element: String) -> Result<(), Error>;
+    async fn delete_type_element(&self, id: i32) -> Result<(), Error>;
+    // Noms traduits d'une famille de types dans une langue
+    async fn get_type_translations(&self, kind: TypeKind, lang: &str) -> Result<Vec<TypeTranslation>, Error>;
+    // Ajouter ou remplacer la traduction d'un type ; RowNotFound si le type n'existe pas
+    async fn set_type_translation(&self, kind: TypeKind, type_id: i32, lang: &str, nom: String) -> Result<(), Error>;
+}
+
+// Accès aux domaines ; update et delete renvoient false si la version ne correspond plus
+// delete refuse par RepoError::Conflict une ligne dont d'autres dépendent encore
+#[async_trait]
+pub trait DomaineRepository: Send + Sync {
+    async fn create(&self, user_id: i32, nom_domaine: String, created_by: Option<i32>) -> Result<Domaine, Error>;
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Domaine>, Error>;
+    async fn get_by_id(&self, id: i32) -> Result<Domaine, Error>;
+    async fn get_all_by_user_id(
+        &self,
+        user_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Domaine>, Error>;
+    async fn update(&self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error>;
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError>;
+    // Propriétaire du domaine auquel appartient une ligne, None si elle n'existe pas ou est à la corbeille
+    async fn owner_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error>;
+    // Domaine auquel appartient une ligne, None si elle n'existe pas ou est à la corbeille
+    async fn domaine_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error>;
+    // Domaine avec ses exploitations, éléments et dernières productions, en un nombre fixe de requêtes
+    async fn tree(&self, id: i32) -> Result<DomaineTree, Error>;
+}
+
+// Accès aux exploitations
+#[async_trait]
+pub trait ExploitationRepository: Send + Sync {
+    async fn create(
+        &self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error>;
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Exploitation>, Error>;
+    async fn get_by_id(&self, id: i32) -> Result<Exploitation, Error>;
+    async fn get_by_domaine_id(
+        &self,
+        domaine_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Exploitation>, Error>;
+    // Exploitations de plusieurs domaines en une requête (chargement groupé de GraphQL)
+    async fn get_by_domaine_ids(&self, domaine_ids: &[i32]) -> Result<Vec<Exploitation>, Error>;
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error>;
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError>;
+}
+
+// Accès aux éléments
+#[async_trait]
+pub trait ElementRepository: Send + Sync {
+    async fn create(
+        &self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error>;
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Element>, Error>;
+    async fn get_by_id(&self, id: i32) -> Result<Element, Error>;
+    async fn get_by_exploitation_id(
+        &self,
+        exploitation_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Element>, Error>;
+    // Éléments de plusieurs exploitations en une requête
+    async fn get_by_exploitation_ids(&self, exploitation_ids: &[i32]) -> Result<Vec<Element>, Error>;
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error>;
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError>;
+}
+
+// Accès aux productions
+#[async_trait]
+pub trait ProductionRepository: Send + Sync {
+    async fn create(
+        &self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error>;
+    async fn get_by_id(&self, id: i32) -> Result<Production, Error>;
+    async fn get_by_element_id(
+        &self,
+        element_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Production>, Error>;
+    // Productions de plusieurs éléments en une requête
+    async fn get_by_element_ids(&self, element_ids: &[i32]) -> Result<Vec<Production>, Error>;
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error>;
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError>;
+}
+
+// Relevés de qualité de l'eau et distributions d'aliment
+#[async_trait]
+pub trait ReleveRepository: Send + Sync {
+    async fn create_qualite_eau(
+        &self,
+        exploitation_id: i32,
+        date_releve: NaiveDate,
+        temperature: f64,
+        oxygene_dissous: f64,
+        ph: f64,
+        created_by: Option<i32>,
+    ) -> Result<QualiteEau, Error>;
+    async fn get_qualite_eau(&self, exploitation_id: i32) -> Result<Vec<QualiteEau>, Error>;
+    async fn create_alimentation(
+        &self,
+        element_id: i32,
+        date_alimentation: NaiveDate,
+        quantite_kg: f64,
+        created_by: Option<i32>,
+    ) -> Result<Alimentation, Error>;
+    async fn get_alimentations(&self, element_id: i32) -> Result<Vec<Alimentation>, Error>;
+}
+
+// Recherche par nom dans les domaines d'un utilisateur et dans les types
+#[async_trait]
+pub trait SearchRepository: Send + Sync {
+    // `query` et ses termes sont déjà passés par search::normalize ; les meilleurs résultats d'abord
+    async fn search(&self, user_id: i32, query: &str, terms: &[String], limit: usize) -> Result<Vec<SearchHit>, Error>;
+}
+
+// Stockage propre à la synchronisation hors ligne
+#[async_trait]
+pub trait SyncRepository: Send + Sync {
+    async fn changes_since(&self, user_id: i32, since: DateTime<Utc>) -> Result<ChangeSet, Error>;
+    async fn lookup_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<Option<i32>, Error>;
+    // Réserver l'identifiant avant la création ; une réservation sans ligne faite avant
+    // `stale_before` est reprise
+    async fn reserve_client_id(
+        &self,
+        user_id: i32,
+        client_id: &str,
+        entity: Entity,
+        stale_before: DateTime<Utc>,
+    ) -> Result<ClientIdReservation, Error>;
+    async fn record_client_id(
+        &self,
+        user_id: i32,
+        client_id: &str,
+        entity: Entity,
+        entity_id: i32,
+    ) -> Result<(), Error>;
+    async fn release_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<(), Error>;
+}
+
+// Exécution atomique d'un lot : tout est appliqué, ou rien
+#[async_trait]
+pub trait BatchRepository: Send + Sync {
+    async fn execute(
+        &self,
+        user_id: i32,
+        operations: Vec<BatchOperation>,
+    ) -> Result<Vec<OperationResult>, BatchError>;
+}
+
+// Stockage des réponses associées aux clés d'idempotence
+#[async_trait]
+pub trait IdempotencyRepository: Send + Sync {
+    // Une réservation en cours créée avant `stale_before`, ou toute clé créée avant
+    // `expired_before`, est reprise par la nouvelle requête
+    async fn reserve(
+        &self,
+        principal: &str,
+        key: &str,
+        request_hash: &str,
+        stale_before: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Reservation, Error>;
+    async fn store(
+        &self,
+        principal: &str,
+        key: &str,
+        status_code: u16,
+        content_type: Option<String>,
+        response_body: &[u8],
+    ) -> Result<(), Error>;
+    async fn release(&self, principal: &str, key: &str) -> Result<(), Error>;
+    // Supprimer les clés créées avant `before`, en renvoyant leur nombre
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error>;
+}
+
+// Statistiques lues par /metrics
+#[async_trait]
+pub trait StatsRepository: Send + Sync {
+    // Productions comptées à partir de `productions_since` (minuit du jour courant)
+    async fn counts(&self, productions_since: DateTime<Utc>) -> Result<BusinessCounts, Error>;
+    // Connexions du pool, None pour un dépôt sans base
+    fn pool(&self) -> Option<PoolStats>;
+}
+
+// Vérifications de /health/ready
+#[async_trait]
+pub trait HealthRepository: Send + Sync {
+    // Aller-retour avec la base sur une connexion du pool
+    async fn ping(&self) -> Result<(), Error>;
+    // Versions des migrations embarquées qui ne sont pas encore appliquées
+    async fn pending_migrations(&self) -> Result<Vec<i64>, Error>;
+}
+
+// Corbeille : lignes supprimées, conservées jusqu'à leur purge
+#[async_trait]
+pub trait TrashRepository: Send + Sync {
+    async fn get_trash(&self, user_id: i32) -> Result<Vec<TrashItem>, Error>;
+    async fn restore(&self, entity: Entity, id: i32) -> Result<Restore, Error>;
+    // Propriétaire d'une ligne en place ou à la corbeille, pour autoriser sa restauration
+    async fn owner_including_trash(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error>;
+    // Supprimer définitivement ce qui a été mis à la corbeille avant `before` ; renvoie le nombre de lignes
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error>;
+    // Descendants en place d'une ligne
+    async fn subtree(&self, entity: Entity, id: i32) -> Result<Subtree, Error>;
+    // Mettre à la corbeille une ligne et toute sa descendance en une transaction
+    // None si la version de la ligne a changé
+    async fn delete_cascade(&self, entity: Entity, id: i32, version: i32) -> Result<Option<Subtree>, RepoError>;
+}
+
+// Abonnements aux webhooks et file persistante de leurs livraisons
+#[async_trait]
+pub trait WebhookRepository: Send + Sync {
+    async fn create_webhook(
+        &self,
+        domaine_id: i32,
+        url: String,
+        events: String,
+        secret: String,
+        created_by: Option<i32>,
+    ) -> Result<Webhook, Error>;
+    async fn get_webhooks_by_domaine_id(&self, domaine_id: i32) -> Result<Vec<Webhook>, Error>;
+    async fn get_webhook(&self, id: i32) -> Result<Webhook, Error>;
+    async fn delete_webhook(&self, id: i32) -> Result<(), Error>;
+    async fn create_delivery(&self, webhook_id: i32, event_type: &str, payload: String) -> Result<WebhookDelivery, Error>;
+    async fn get_deliveries(&self, webhook_id: i32) -> Result<Vec<WebhookDelivery>, Error>;
+    async fn get_delivery(&self, id: i32) -> Result<WebhookDelivery, Error>;
+    // Livraisons en attente dont l'échéance est passée, réservées jusqu'à `lease_until`
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, Error>;
+    async fn record_attempt(&self, id: i32, attempt: Attempt) -> Result<(), Error>;
+    async fn redeliver(&self, id: i32) -> Result<(), Error>;
+}
+
+// Ensemble des dépôts partagé par les handlers
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub references: Arc<dyn ReferenceRepository>,
+    pub domaines: Arc<dyn DomaineRepository>,
+    pub exploitations: Arc<dyn ExploitationRepository>,
+    pub elements: Arc<dyn ElementRepository>,
+    pub productions: Arc<dyn ProductionRepository>,
+    pub releves: Arc<dyn ReleveRepository>,
+    pub search: Arc<dyn SearchRepository>,
+    pub sync: Arc<dyn SyncRepository>,
+    pub batch: Arc<dyn BatchRepository>,
+    pub idempotency: Arc<dyn IdempotencyRepository>,
+    pub stats: Arc<dyn StatsRepository>,
+    pub health: Arc<dyn HealthRepository>,
+    pub webhooks: Arc<dyn WebhookRepository>,
+    pub trash: Arc<dyn TrashRepository>,
+    // Écritures diffusées aux flux d'événements
+    pub events: EventBus,
+}
+
+impl Repositories {
+    // Tous les dépôts sont servis par une même implémentation
+    fn from_backend<R>(backend: R) -> Self
+    where
+        R: UserRepository
+            + ReferenceRepository
+            + DomaineRepository
+            + ExploitationRepository
+            + ElementRepository
+            + ProductionRepository
+            + ReleveRepository
+            + SearchRepository
+            + SyncRepository
+            + BatchRepository
+            + IdempotencyRepository
+            + StatsRepository
+            + HealthRepository
+            + WebhookRepository
+            + TrashRepository
+            + 'static,
+    {
+        let backend = Arc::new(backend);
+        let events = EventBus::default();
+        // Les écritures sur les fermes passent par un dépôt qui les publie
+        let outbox = Outbox {
+            webhooks: backend.clone(),
+            domaines: backend.clone(),
+            exploitations: backend.clone(),
+            elements: backend.clone(),
+            productions: backend.clone(),
+        };
+        let publishing = Arc::new(Publishing::new(backend.clone(), events.clone(), outbox));
+
+        Repositories {
+            users: backend.clone(),
+            references: backend.clone(),
+            domaines: publishing.clone(),
+            exploitations: publishing.clone(),
+            elements: publishing.clone(),
+            productions: publishing.clone(),
+            releves: backend.clone(),
+            search: backend.clone(),
+            sync: backend.clone(),
+            batch: publishing.clone(),
+            idempotency: backend.clone(),
+            stats: backend.clone(),
+            health: backend.clone(),
+            webhooks: backend,
+            trash: publishing,
+            events,
+        }
+    }
+
+    // Dépôts adossés à la base MySQL
+    pub fn mysql(pool: sqlx::MySqlPool) -> Self {
+        Self::from_backend(MySqlRepository::new(pool))
+    }
+
+    // Dépôts adossés à une base PostgreSQL
+    #[cfg(feature = "postgres")]
+    pub fn postgres(pool: sqlx::PgPool) -> Self {
+        Self::from_backend(PostgresRepository::new(pool))
+    }
+
+    // Dépôts adossés à un fichier SQLite
+    #[cfg(feature = "sqlite")]
+    pub fn sqlite(pool: sqlx::SqlitePool) -> Self {
+        Self::from_backend(SqliteRepository::new(pool))
+    }
+
+    // Dépôts en mémoire, utilisés par les tests
+    pub fn in_memory() -> Self {
+        Self::from_backend(InMemoryRepository::default())
+    }
+}
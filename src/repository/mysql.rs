@@ -0,0 +1,647 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{mysql::MySqlPool, Connection, Error, MySqlConnection};
+
+use crate::batch::{self, BatchError, BatchOperation, BatchStore, OperationResult};
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::idempotency::{self, Reservation};
+use crate::metrics::{self, BusinessCounts, PoolStats};
+use crate::ownership;
+use crate::production::Production;
+use crate::releve::{Alimentation, QualiteEau};
+use crate::schema;
+use crate::search::{self, SearchHit};
+use crate::sync::{self, ChangeSet, ClientIdReservation};
+use crate::tombstone::Entity;
+use crate::trash::{self, Restore, Subtree, TrashItem};
+use crate::tree::{self, DomaineTree};
+use crate::type_element::TypeElement;
+use crate::type_exploitation::TypeExploitation;
+use crate::type_translation::{TypeKind, TypeTranslation};
+use crate::type_user::TypeUser;
+use crate::user::{NewUser, User, UserChanges};
+use crate::webhook::{self, Attempt, Webhook, WebhookDelivery};
+
+use super::{
+    BatchRepository, DomaineRepository, ElementRepository, ExploitationRepository, HealthRepository,
+    IdempotencyRepository, ProductionRepository, ReferenceRepository, ReleveRepository, RepoError, SearchRepository, StatsRepository,
+    SyncRepository, TrashRepository, UserRepository, WebhookRepository,
+};
+
+// Implémentation MySQL : chaque méthode délègue aux requêtes des modèles
+#[derive(Clone)]
+pub struct MySqlRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlRepository {
+    pub fn new(pool: MySqlPool) -> Self {
+        MySqlRepository { pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for MySqlRepository {
+    async fn create(&self, new_user: NewUser, created_by: Option<i32>) -> Result<User, Error> {
+        User::create(&self.pool, new_user, created_by).await
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<User>, Error> {
+        User::get_all(&self.pool, updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<User, Error> {
+        User::get_by_id(&self.pool, id).await
+    }
+
+    async fn get_by_email(&self, email: &str) -> Result<User, Error> {
+        User::get_by_email(&self.pool, email).await
+    }
+
+    async fn update(&self, id: i32, changes: UserChanges) -> Result<(), Error> {
+        User::update(&self.pool, id, changes).await
+    }
+
+    async fn delete(&self, id: i32) -> Result<(), Error> {
+        User::delete(&self.pool, id).await
+    }
+}
+
+#[async_trait]
+impl ReferenceRepository for MySqlRepository {
+    async fn create_type_user(&self, nom_type_user: String, created_by: Option<i32>) -> Result<TypeUser, Error> {
+        TypeUser::create(&self.pool, nom_type_user, created_by).await
+    }
+
+    async fn get_types_user(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeUser>, Error> {
+        TypeUser::get_all(&self.pool, updated_since).await
+    }
+
+    async fn create_type_exploitation(
+        &self,
+        nom_type_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<TypeExploitation, Error> {
+        TypeExploitation::create(&self.pool, nom_type_exploitation, created_by).await
+    }
+
+    async fn get_types_exploitation(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<TypeExploitation>, Error> {
+        TypeExploitation::get_all(&self.pool, updated_since).await
+    }
+
+    async fn create_type_element(&self, nom_type_element: String, created_by: Option<i32>) -> Result<TypeElement, Error> {
+        TypeElement::create(&self.pool, nom_type_element, created_by).await
+    }
+
+    async fn get_types_element(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeElement>, Error> {
+        TypeElement::get_all(&self.pool, updated_since).await
+    }
+
+    async fn update_type_element(&self, id: i32, nom_type_element: String) -> Result<(), Error> {
+        TypeElement::update(&self.pool, id, nom_type_element).await
+    }
+
+    async fn delete_type_element(&self, id: i32) -> Result<(), Error> {
+        TypeElement::delete(&self.pool, id).await?;
+        TypeTranslation::delete_for(&self.pool, TypeKind::TypeElement, id).await
+    }
+
+    async fn get_type_translations(&self, kind: TypeKind, lang: &str) -> Result<Vec<TypeTranslation>, Error> {
+        TypeTranslation::get_all(&self.pool, kind, lang).await
+    }
+
+    async fn set_type_translation(&self, kind: TypeKind, type_id: i32, lang: &str, nom: String) -> Result<(), Error> {
+        TypeTranslation::set(&self.pool, kind, type_id, lang, nom).await
+    }
+}
+
+#[async_trait]
+impl DomaineRepository for MySqlRepository {
+    async fn create(&self, user_id: i32, nom_domaine: String, created_by: Option<i32>) -> Result<Domaine, Error> {
+        Domaine::create(&mut *self.pool.acquire().await?, user_id, nom_domaine, created_by).await
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Domaine>, Error> {
+        Domaine::get_all(&mut *self.pool.acquire().await?, updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Domaine, Error> {
+        Domaine::get_by_id(&mut *self.pool.acquire().await?, id).await
+    }
+
+    async fn get_all_by_user_id(
+        &self,
+        user_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Domaine>, Error> {
+        Domaine::get_all_by_user_id(&mut *self.pool.acquire().await?, user_id, updated_since).await
+    }
+
+    async fn update(&self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+        Domaine::update_domaine(&mut *self.pool.acquire().await?, id, version, nom_domaine).await
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        Domaine::delete_domaine(&mut *self.pool.acquire().await?, id, version).await
+    }
+
+    async fn owner_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        ownership::owner_of(&mut *self.pool.acquire().await?, entity, id).await
+    }
+
+    async fn domaine_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        ownership::domaine_of(&mut *self.pool.acquire().await?, entity, id).await
+    }
+
+    async fn tree(&self, id: i32) -> Result<DomaineTree, Error> {
+        tree::load(&self.pool, id).await
+    }
+}
+
+#[async_trait]
+impl ExploitationRepository for MySqlRepository {
+    async fn create(
+        &self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error> {
+        Exploitation::create(&mut *self.pool.acquire().await?, type_exploitation_id, domaine_id, nom_exploitation, created_by).await
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Exploitation>, Error> {
+        Exploitation::get_all(&mut *self.pool.acquire().await?, updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Exploitation, Error> {
+        Exploitation::get_by_id(&mut *self.pool.acquire().await?, id).await
+    }
+
+    async fn get_by_domaine_id(
+        &self,
+        domaine_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Exploitation>, Error> {
+        Exploitation::get_by_domaine_id(&mut *self.pool.acquire().await?, domaine_id, updated_since).await
+    }
+
+    async fn get_by_domaine_ids(&self, domaine_ids: &[i32]) -> Result<Vec<Exploitation>, Error> {
+        Exploitation::get_by_domaine_ids(&mut *self.pool.acquire().await?, domaine_ids).await
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error> {
+        Exploitation::update(&mut *self.pool.acquire().await?, id, version, type_exploitation_id, nom_exploitation).await
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        Exploitation::delete(&mut *self.pool.acquire().await?, id, version).await
+    }
+}
+
+#[async_trait]
+impl ElementRepository for MySqlRepository {
+    async fn create(
+        &self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error> {
+        Element::create(&mut *self.pool.acquire().await?, exploitation_id, nom_element, quantite, created_by).await
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Element>, Error> {
+        Element::get_all(&mut *self.pool.acquire().await?, updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Element, Error> {
+        Element::get_by_id(&mut *self.pool.acquire().await?, id).await
+    }
+
+    async fn get_by_exploitation_id(
+        &self,
+        exploitation_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Element>, Error> {
+        Element::get_by_exploitation_id(&mut *self.pool.acquire().await?, exploitation_id, updated_since).await
+    }
+
+    async fn get_by_exploitation_ids(&self, exploitation_ids: &[i32]) -> Result<Vec<Element>, Error> {
+        Element::get_by_exploitation_ids(&mut *self.pool.acquire().await?, exploitation_ids).await
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error> {
+        Element::update(&mut *self.pool.acquire().await?, id, version, nom_element, quantite).await
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        Element::delete(&mut *self.pool.acquire().await?, id, version).await
+    }
+}
+
+#[async_trait]
+impl ProductionRepository for MySqlRepository {
+    async fn create(
+        &self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error> {
+        Production::create(
+            &mut *self.pool.acquire().await?,
+            element_id,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+            created_by,
+        )
+        .await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Production, Error> {
+        Production::get_by_id(&mut *self.pool.acquire().await?, id).await
+    }
+
+    async fn get_by_element_id(
+        &self,
+        element_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Production>, Error> {
+        Production::get_by_element_id(&mut *self.pool.acquire().await?, element_id, updated_since).await
+    }
+
+    async fn get_by_element_ids(&self, element_ids: &[i32]) -> Result<Vec<Production>, Error> {
+        Production::get_by_element_ids(&mut *self.pool.acquire().await?, element_ids).await
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        Production::update(&mut *self.pool.acquire().await?, id, version, quantite_produite, unite_production, date_de_production).await
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        Ok(Production::delete(&mut *self.pool.acquire().await?, id, version).await?)
+    }
+}
+
+#[async_trait]
+impl ReleveRepository for MySqlRepository {
+    async fn create_qualite_eau(
+        &self,
+        exploitation_id: i32,
+        date_releve: NaiveDate,
+        temperature: f64,
+        oxygene_dissous: f64,
+        ph: f64,
+        created_by: Option<i32>,
+    ) -> Result<QualiteEau, Error> {
+        QualiteEau::create(&mut *self.pool.acquire().await?, exploitation_id, date_releve, temperature, oxygene_dissous, ph, created_by).await
+    }
+
+    async fn get_qualite_eau(&self, exploitation_id: i32) -> Result<Vec<QualiteEau>, Error> {
+        QualiteEau::get_by_exploitation_id(&mut *self.pool.acquire().await?, exploitation_id).await
+    }
+
+    async fn create_alimentation(
+        &self,
+        element_id: i32,
+        date_alimentation: NaiveDate,
+        quantite_kg: f64,
+        created_by: Option<i32>,
+    ) -> Result<Alimentation, Error> {
+        Alimentation::create(&mut *self.pool.acquire().await?, element_id, date_alimentation, quantite_kg, created_by).await
+    }
+
+    async fn get_alimentations(&self, element_id: i32) -> Result<Vec<Alimentation>, Error> {
+        Alimentation::get_by_element_id(&mut *self.pool.acquire().await?, element_id).await
+    }
+}
+
+#[async_trait]
+impl SearchRepository for MySqlRepository {
+    async fn search(&self, user_id: i32, query: &str, terms: &[String], limit: usize) -> Result<Vec<SearchHit>, Error> {
+        search::find(&mut *self.pool.acquire().await?, user_id, query, terms, limit).await
+    }
+}
+
+#[async_trait]
+impl SyncRepository for MySqlRepository {
+    async fn changes_since(&self, user_id: i32, since: DateTime<Utc>) -> Result<ChangeSet, Error> {
+        sync::changes_since(&self.pool, user_id, since).await
+    }
+
+    async fn lookup_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<Option<i32>, Error> {
+        sync::lookup_client_id(&self.pool, user_id, client_id, entity).await
+    }
+
+    async fn reserve_client_id(
+        &self,
+        user_id: i32,
+        client_id: &str,
+        entity: Entity,
+        stale_before: DateTime<Utc>,
+    ) -> Result<ClientIdReservation, Error> {
+        sync::reserve_client_id(&self.pool, user_id, client_id, entity, stale_before).await
+    }
+
+    async fn record_client_id(
+        &self,
+        user_id: i32,
+        client_id: &str,
+        entity: Entity,
+        entity_id: i32,
+    ) -> Result<(), Error> {
+        sync::record_client_id(&self.pool, user_id, client_id, entity, entity_id).await
+    }
+
+    async fn release_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<(), Error> {
+        sync::release_client_id(&self.pool, user_id, client_id, entity).await
+    }
+}
+
+#[async_trait]
+impl BatchRepository for MySqlRepository {
+    async fn execute(
+        &self,
+        user_id: i32,
+        operations: Vec<BatchOperation>,
+    ) -> Result<Vec<OperationResult>, BatchError> {
+        // À la première erreur, la transaction est abandonnée et rien n'est enregistré
+        let mut tx = self.pool.begin().await?;
+        let results = batch::run_all(&mut *tx, user_id, operations).await?;
+        tx.commit().await?;
+
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl IdempotencyRepository for MySqlRepository {
+    async fn reserve(
+        &self,
+        principal: &str,
+        key: &str,
+        request_hash: &str,
+        stale_before: DateTime<Utc>,
+        expired_before: DateTime<Utc>,
+    ) -> Result<Reservation, Error> {
+        idempotency::reserve(&self.pool, principal, key, request_hash, stale_before, expired_before).await
+    }
+
+    async fn store(
+        &self,
+        principal: &str,
+        key: &str,
+        status_code: u16,
+        content_type: Option<String>,
+        response_body: &[u8],
+    ) -> Result<(), Error> {
+        idempotency::store(&self.pool, principal, key, status_code, content_type, response_body).await
+    }
+
+    async fn release(&self, principal: &str, key: &str) -> Result<(), Error> {
+        idempotency::release(&self.pool, principal, key).await
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+        idempotency::purge(&self.pool, before).await
+    }
+}
+
+#[async_trait]
+impl StatsRepository for MySqlRepository {
+    async fn counts(&self, productions_since: DateTime<Utc>) -> Result<BusinessCounts, Error> {
+        metrics::counts(&self.pool, productions_since).await
+    }
+
+    fn pool(&self) -> Option<PoolStats> {
+        Some(metrics::pool_stats(&self.pool))
+    }
+}
+
+#[async_trait]
+impl HealthRepository for MySqlRepository {
+    async fn ping(&self) -> Result<(), Error> {
+        self.pool.acquire().await?.ping().await
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<i64>, Error> {
+        let applied = sqlx::query_scalar::<_, i64>(schema::APPLIED_MIGRATIONS).fetch_all(&self.pool).await?;
+        Ok(schema::pending(&schema::MIGRATOR, &applied))
+    }
+}
+
+// Écritures d'un lot sur la connexion de sa transaction
+#[async_trait]
+impl BatchStore for MySqlConnection {
+    async fn create_domaine(
+        &mut self,
+        user_id: i32,
+        nom_domaine: String,
+        created_by: Option<i32>,
+    ) -> Result<Domaine, Error> {
+        Domaine::create(self, user_id, nom_domaine, created_by).await
+    }
+
+    async fn create_exploitation(
+        &mut self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error> {
+        Exploitation::create(self, type_exploitation_id, domaine_id, nom_exploitation, created_by).await
+    }
+
+    async fn create_element(
+        &mut self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error> {
+        Element::create(self, exploitation_id, nom_element, quantite, created_by).await
+    }
+
+    async fn create_production(
+        &mut self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error> {
+        Production::create(self, element_id, quantite_produite, unite_production, date_de_production, created_by).await
+    }
+
+    async fn update_domaine(&mut self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+        Domaine::update_domaine(self, id, version, nom_domaine).await
+    }
+
+    async fn update_exploitation(
+        &mut self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error> {
+        Exploitation::update(self, id, version, type_exploitation_id, nom_exploitation).await
+    }
+
+    async fn update_element(
+        &mut self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error> {
+        Element::update(self, id, version, nom_element, quantite).await
+    }
+
+    async fn update_production(
+        &mut self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        Production::update(self, id, version, quantite_produite, unite_production, date_de_production).await
+    }
+
+    async fn delete(&mut self, entity: Entity, id: i32, version: i32) -> Result<bool, RepoError> {
+        match entity {
+            Entity::Domaine => Domaine::delete_domaine(self, id, version).await,
+            Entity::Exploitation => Exploitation::delete(self, id, version).await,
+            Entity::Element => Element::delete(self, id, version).await,
+            Entity::Production => Ok(Production::delete(self, id, version).await?),
+        }
+    }
+
+    async fn owner_of(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        ownership::owner_of(self, entity, id).await
+    }
+
+    async fn current_version(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        let current = match entity {
+            Entity::Domaine => Domaine::get_by_id(self, id).await.map(|d| d.version),
+            Entity::Exploitation => Exploitation::get_by_id(self, id).await.map(|e| e.version),
+            Entity::Element => Element::get_by_id(self, id).await.map(|e| e.version),
+            Entity::Production => Production::get_by_id(self, id).await.map(|p| p.version),
+        };
+
+        match current {
+            Ok(version) => Ok(Some(version)),
+            Err(Error::RowNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[async_trait]
+impl WebhookRepository for MySqlRepository {
+    async fn create_webhook(
+        &self,
+        domaine_id: i32,
+        url: String,
+        events: String,
+        secret: String,
+        created_by: Option<i32>,
+    ) -> Result<Webhook, Error> {
+        webhook::create(&self.pool, domaine_id, url, events, secret, created_by).await
+    }
+
+    async fn get_webhooks_by_domaine_id(&self, domaine_id: i32) -> Result<Vec<Webhook>, Error> {
+        webhook::get_by_domaine_id(&self.pool, domaine_id).await
+    }
+
+    async fn get_webhook(&self, id: i32) -> Result<Webhook, Error> {
+        webhook::get_by_id(&self.pool, id).await
+    }
+
+    async fn delete_webhook(&self, id: i32) -> Result<(), Error> {
+        webhook::delete(&self.pool, id).await
+    }
+
+    async fn create_delivery(&self, webhook_id: i32, event_type: &str, payload: String) -> Result<WebhookDelivery, Error> {
+        webhook::create_delivery(&self.pool, webhook_id, event_type, payload).await
+    }
+
+    async fn get_deliveries(&self, webhook_id: i32) -> Result<Vec<WebhookDelivery>, Error> {
+        webhook::get_deliveries(&self.pool, webhook_id).await
+    }
+
+    async fn get_delivery(&self, id: i32) -> Result<WebhookDelivery, Error> {
+        webhook::get_delivery(&self.pool, id).await
+    }
+
+    async fn claim_due_deliveries(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookDelivery>, Error> {
+        webhook::claim_due(&self.pool, now, lease_until, limit).await
+    }
+
+    async fn record_attempt(&self, id: i32, attempt: Attempt) -> Result<(), Error> {
+        webhook::record_attempt(&self.pool, id, attempt).await
+    }
+
+    async fn redeliver(&self, id: i32) -> Result<(), Error> {
+        webhook::redeliver(&self.pool, id).await
+    }
+}
+
+#[async_trait]
+impl TrashRepository for MySqlRepository {
+    async fn get_trash(&self, user_id: i32) -> Result<Vec<TrashItem>, Error> {
+        trash::list(&self.pool, user_id).await
+    }
+
+    async fn restore(&self, entity: Entity, id: i32) -> Result<Restore, Error> {
+        trash::restore(&mut *self.pool.acquire().await?, entity, id).await
+    }
+
+    async fn owner_including_trash(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        Ok(ownership::owner_including_trash(&mut *self.pool.acquire().await?, entity, id).await?.map(|(_, user_id)| user_id))
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+        trash::purge(&mut *self.pool.acquire().await?, before).await
+    }
+
+    async fn subtree(&self, entity: Entity, id: i32) -> Result<Subtree, Error> {
+        trash::subtree(&mut *self.pool.acquire().await?, entity, id).await
+    }
+
+    async fn delete_cascade(&self, entity: Entity, id: i32, version: i32) -> Result<Option<Subtree>, RepoError> {
+        trash::delete_cascade(&mut *self.pool.acquire().await?, entity, id, version).await
+    }
+}
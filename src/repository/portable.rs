@@ -0,0 +1,2117 @@
+use chrono::{DateTime, Utc};
+use sqlx::FromRow;
+
+use crate::idempotency::IdempotencyRecord;
+use crate::tombstone::Entity;
+
+// Requêtes partagées par les bases accessibles en SQL « portable » (SQLite et PostgreSQL) :
+// paramètres numérotés $N, RETURNING après INSERT et ON CONFLICT DO NOTHING.
+// Comme celles de MySQL, elles sont vérifiées à l'exécution : la compilation ne demande pas de base.
+
+pub(super) fn table(entity: Entity) -> &'static str {
+    match entity {
+        Entity::Domaine => "domaines",
+        Entity::Exploitation => "exploitations",
+        Entity::Element => "elements",
+        Entity::Production => "production",
+    }
+}
+
+// Type des enfants directs d'une ligne et leur colonne vers elle
+pub(super) fn children(entity: Entity) -> Option<(Entity, &'static str)> {
+    match entity {
+        Entity::Domaine => Some((Entity::Exploitation, "domaine_id")),
+        Entity::Exploitation => Some((Entity::Element, "exploitation_id")),
+        Entity::Element => Some((Entity::Production, "element_id")),
+        Entity::Production => None,
+    }
+}
+
+// Table et colonne du parent direct d'une ligne
+pub(super) fn parent(entity: Entity) -> Option<(&'static str, &'static str)> {
+    match entity {
+        Entity::Domaine => None,
+        Entity::Exploitation => Some(("domaines", "domaine_id")),
+        Entity::Element => Some(("exploitations", "exploitation_id")),
+        Entity::Production => Some(("elements", "element_id")),
+    }
+}
+
+// Réponse mémorisée, lue avec un statut signé : PostgreSQL n'a pas d'entier non signé
+#[derive(FromRow)]
+pub(super) struct IdempotencyRow {
+    request_hash: String,
+    status_code: Option<i32>,
+    content_type: Option<String>,
+    response_body: Option<Vec<u8>>,
+    created_at: DateTime<Utc>,
+}
+
+impl From<IdempotencyRow> for IdempotencyRecord {
+    fn from(row: IdempotencyRow) -> Self {
+        IdempotencyRecord {
+            request_hash: row.request_hash,
+            status_code: row.status_code.and_then(|s| u16::try_from(s).ok()),
+            content_type: row.content_type,
+            response_body: row.response_body,
+            created_at: row.created_at,
+        }
+    }
+}
+
+// Dépôt complet pour un type de base : nom du dépôt, base, pool et connexion sqlx,
+// puis migrations embarquées du dialecte (static de `schema`)
+macro_rules! portable_repository {
+    ($repository:ident, $database:ty, $pool:ty, $connection:ty, $migrator:ident, $lock:literal) => {
+        use async_trait::async_trait;
+        use bcrypt::{hash, DEFAULT_COST};
+        use chrono::{DateTime, NaiveDate, Utc};
+        use sqlx::{Connection, Error, QueryBuilder};
+
+        use $crate::batch::{self, BatchError, BatchOperation, BatchStore, OperationResult};
+        use $crate::domaine::Domaine;
+        use $crate::element::Element;
+        use $crate::exploitation::Exploitation;
+        use $crate::idempotency::Reservation;
+        use $crate::metrics::{self, BusinessCounts, PoolStats};
+        use $crate::production::Production;
+        use $crate::releve::{Alimentation, QualiteEau};
+        use $crate::schema;
+        use $crate::search::{self, SearchHit, SearchRow};
+        use $crate::repository::portable::{children, parent, table, IdempotencyRow};
+        use $crate::repository::{
+            BatchRepository, DomaineRepository, ElementRepository, ExploitationRepository, HealthRepository,
+            IdempotencyRepository, ProductionRepository, ReferenceRepository, ReleveRepository, RepoError,
+            SearchRepository, StatsRepository, SyncRepository, TrashRepository, UserRepository, WebhookRepository,
+        };
+        use $crate::sync::{self, ChangeSet, ClientIdReservation};
+        use $crate::timestamps;
+        use $crate::tombstone::{Entity, Tombstone};
+        use $crate::trash::{Restore, Subtree, TrashItem};
+        use $crate::tree::{self, DomaineTree, ResumeProduction};
+        use $crate::type_element::TypeElement;
+        use $crate::type_exploitation::TypeExploitation;
+        use $crate::type_translation::{TypeKind, TypeTranslation};
+        use $crate::type_user::TypeUser;
+        use $crate::user::{NewUser, User, UserChanges};
+        use $crate::webhook::{Attempt, DeliveryStatus, Webhook, WebhookDelivery};
+
+        #[derive(Clone)]
+        pub struct $repository {
+            pool: $pool,
+        }
+
+        impl $repository {
+            pub fn new(pool: $pool) -> Self {
+                $repository { pool }
+            }
+        }
+
+        // Domaine et propriétaire auxquels appartient une ligne en place
+        // None si elle n'existe pas ou si elle (ou un de ses parents) est à la corbeille
+        async fn owner(conn: &mut $connection, entity: Entity, id: i32) -> Result<Option<(i32, i32)>, Error> {
+            lookup_owner(conn, entity, id, false).await
+        }
+
+        // Même recherche en suivant aussi les lignes à la corbeille : réservée à leur restauration
+        async fn owner_including_trash(conn: &mut $connection, entity: Entity, id: i32) -> Result<Option<(i32, i32)>, Error> {
+            lookup_owner(conn, entity, id, true).await
+        }
+
+        async fn lookup_owner(conn: &mut $connection, entity: Entity, id: i32, with_trash: bool) -> Result<Option<(i32, i32)>, Error> {
+            let query = match entity {
+                Entity::Domaine => "SELECT d.id, d.user_id FROM domaines d WHERE d.id = $1 AND ($2 OR d.deleted_at IS NULL)",
+                Entity::Exploitation => {
+                    r#"
+                    SELECT d.id, d.user_id
+                    FROM exploitations e
+                    JOIN domaines d ON d.id = e.domaine_id
+                    WHERE e.id = $1
+                      AND ($2 OR (e.deleted_at IS NULL AND d.deleted_at IS NULL))
+                    "#
+                }
+                Entity::Element => {
+                    r#"
+                    SELECT d.id, d.user_id
+                    FROM elements el
+                    JOIN exploitations e ON e.id = el.exploitation_id
+                    JOIN domaines d ON d.id = e.domaine_id
+                    WHERE el.id = $1
+                      AND ($2 OR (el.deleted_at IS NULL AND e.deleted_at IS NULL AND d.deleted_at IS NULL))
+                    "#
+                }
+                Entity::Production => {
+                    r#"
+                    SELECT d.id, d.user_id
+                    FROM production p
+                    JOIN elements el ON el.id = p.element_id
+                    JOIN exploitations e ON e.id = el.exploitation_id
+                    JOIN domaines d ON d.id = e.domaine_id
+                    WHERE p.id = $1
+                      AND ($2 OR (p.deleted_at IS NULL AND el.deleted_at IS NULL
+                                  AND e.deleted_at IS NULL AND d.deleted_at IS NULL))
+                    "#
+                }
+            };
+
+            sqlx::query_as(query).bind(id).bind(with_trash).fetch_optional(conn).await
+        }
+
+        // Trace d'une suppression, pour la synchronisation
+        async fn record_tombstone(
+            conn: &mut $connection,
+            entity: Entity,
+            id: i32,
+            (domaine_id, user_id): (i32, i32),
+            deleted_at: DateTime<Utc>,
+        ) -> Result<(), Error> {
+            sqlx::query(
+                r#"
+                INSERT INTO tombstones (entity, entity_id, domaine_id, user_id, deleted_at)
+                VALUES ($1, $2, $3, $4, $5)
+                "#,
+            )
+            .bind(entity)
+            .bind(id)
+            .bind(domaine_id)
+            .bind(user_id)
+            .bind(deleted_at)
+            .execute(conn)
+            .await?;
+
+            Ok(())
+        }
+
+        // Descendants en place d'une ligne, lus niveau par niveau
+        async fn subtree(conn: &mut $connection, entity: Entity, id: i32) -> Result<Subtree, Error> {
+            descendants(conn, entity, id, None).await
+        }
+
+        // Descendants mis à la corbeille par la même suppression en cascade que la ligne : ils portent son lot
+        async fn trashed_subtree(
+            conn: &mut $connection,
+            entity: Entity,
+            id: i32,
+            batch: &str,
+        ) -> Result<Subtree, Error> {
+            descendants(conn, entity, id, Some(batch)).await
+        }
+
+        async fn descendants(
+            conn: &mut $connection,
+            entity: Entity,
+            id: i32,
+            batch: Option<&str>,
+        ) -> Result<Subtree, Error> {
+            let mut subtree = Subtree::default();
+            let (mut entity, mut ids) = (entity, vec![id]);
+
+            while let Some((child, column)) = children(entity) {
+                if ids.is_empty() {
+                    break;
+                }
+
+                let mut query = QueryBuilder::<$database>::new(format!("SELECT id FROM {} WHERE ", table(child)));
+                match batch {
+                    Some(batch) => query.push("deletion_batch = ").push_bind(batch.to_string()),
+                    None => query.push("deleted_at IS NULL"),
+                };
+                query.push(format!(" AND {} IN (", column));
+                let mut parents = query.separated(", ");
+                for id in &ids {
+                    parents.push_bind(*id);
+                }
+                parents.push_unseparated(") ORDER BY id");
+
+                let found: Vec<i32> = query
+                    .build_query_as::<(i32,)>()
+                    .fetch_all(&mut *conn)
+                    .await?
+                    .into_iter()
+                    .map(|(id,)| id)
+                    .collect();
+                subtree.ids_mut(child).extend(&found);
+                (entity, ids) = (child, found);
+            }
+
+            Ok(subtree)
+        }
+
+        #[async_trait]
+        impl BatchStore for $connection {
+            async fn create_domaine(
+                &mut self,
+                user_id: i32,
+                nom_domaine: String,
+                created_by: Option<i32>,
+            ) -> Result<Domaine, Error> {
+                let nom_recherche = search::normalize(&nom_domaine);
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO domaines (user_id, nom_domaine, created_at, updated_at, created_by, nom_recherche)
+                    VALUES ($1, $2, $3, $3, $4, $5)
+                    RETURNING id, user_id, nom_domaine, version, created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(user_id)
+                .bind(nom_domaine)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .bind(nom_recherche)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn create_exploitation(
+                &mut self,
+                type_exploitation_id: i32,
+                domaine_id: i32,
+                nom_exploitation: String,
+                created_by: Option<i32>,
+            ) -> Result<Exploitation, Error> {
+                let nom_recherche = search::normalize(&nom_exploitation);
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO exploitations (type_exploitation_id, domaine_id, nom_exploitation,
+                                               created_at, updated_at, created_by, nom_recherche)
+                    VALUES ($1, $2, $3, $4, $4, $5, $6)
+                    RETURNING id, type_exploitation_id, domaine_id, nom_exploitation, version,
+                              created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(type_exploitation_id)
+                .bind(domaine_id)
+                .bind(nom_exploitation)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .bind(nom_recherche)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn create_element(
+                &mut self,
+                exploitation_id: i32,
+                nom_element: String,
+                quantite: i32,
+                created_by: Option<i32>,
+            ) -> Result<Element, Error> {
+                let nom_recherche = search::normalize(&nom_element);
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO elements (exploitation_id, nom_element, quantite, created_at, updated_at, created_by,
+                                          nom_recherche)
+                    VALUES ($1, $2, $3, $4, $4, $5, $6)
+                    RETURNING id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(exploitation_id)
+                .bind(nom_element)
+                .bind(quantite)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .bind(nom_recherche)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn create_production(
+                &mut self,
+                element_id: i32,
+                quantite_produite: i32,
+                unite_production: String,
+                date_de_production: NaiveDate,
+                created_by: Option<i32>,
+            ) -> Result<Production, Error> {
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO production (element_id, quantite_produite, unite_production, date_de_production,
+                                            created_at, updated_at, created_by)
+                    VALUES ($1, $2, $3, $4, $5, $5, $6)
+                    RETURNING id, element_id, quantite_produite, unite_production, date_de_production,
+                              version, created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(element_id)
+                .bind(quantite_produite)
+                .bind(unite_production)
+                .bind(date_de_production)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .fetch_one(self)
+                .await
+            }
+
+            async fn update_domaine(
+                &mut self,
+                id: i32,
+                version: i32,
+                nom_domaine: Option<String>,
+            ) -> Result<bool, Error> {
+                let nom_recherche = nom_domaine.as_deref().map(search::normalize);
+                let result = sqlx::query(
+                    r#"
+                    UPDATE domaines
+                    SET nom_domaine = COALESCE($1, nom_domaine), nom_recherche = COALESCE($5, nom_recherche),
+                        version = version + 1, updated_at = $2
+                    WHERE id = $3 AND version = $4 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(nom_domaine)
+                .bind(timestamps::now())
+                .bind(id)
+                .bind(version)
+                .bind(nom_recherche)
+                .execute(self)
+                .await?;
+
+                Ok(result.rows_affected() > 0)
+            }
+
+            async fn update_exploitation(
+                &mut self,
+                id: i32,
+                version: i32,
+                type_exploitation_id: Option<i32>,
+                nom_exploitation: Option<String>,
+            ) -> Result<bool, Error> {
+                let nom_recherche = nom_exploitation.as_deref().map(search::normalize);
+                let result = sqlx::query(
+                    r#"
+                    UPDATE exploitations
+                    SET type_exploitation_id = COALESCE($1, type_exploitation_id),
+                        nom_exploitation = COALESCE($2, nom_exploitation),
+                        nom_recherche = COALESCE($6, nom_recherche),
+                        version = version + 1,
+                        updated_at = $3
+                    WHERE id = $4 AND version = $5 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(type_exploitation_id)
+                .bind(nom_exploitation)
+                .bind(timestamps::now())
+                .bind(id)
+                .bind(version)
+                .bind(nom_recherche)
+                .execute(self)
+                .await?;
+
+                Ok(result.rows_affected() > 0)
+            }
+
+            async fn update_element(
+                &mut self,
+                id: i32,
+                version: i32,
+                nom_element: Option<String>,
+                quantite: Option<i32>,
+            ) -> Result<bool, Error> {
+                let nom_recherche = nom_element.as_deref().map(search::normalize);
+                let result = sqlx::query(
+                    r#"
+                    UPDATE elements
+                    SET nom_element = COALESCE($1, nom_element),
+                        nom_recherche = COALESCE($6, nom_recherche),
+                        quantite = COALESCE($2, quantite),
+                        version = version + 1,
+                        updated_at = $3
+                    WHERE id = $4 AND version = $5 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(nom_element)
+                .bind(quantite)
+                .bind(timestamps::now())
+                .bind(id)
+                .bind(version)
+                .bind(nom_recherche)
+                .execute(self)
+                .await?;
+
+                Ok(result.rows_affected() > 0)
+            }
+
+            async fn update_production(
+                &mut self,
+                id: i32,
+                version: i32,
+                quantite_produite: Option<i32>,
+                unite_production: Option<String>,
+                date_de_production: Option<NaiveDate>,
+            ) -> Result<bool, Error> {
+                let result = sqlx::query(
+                    r#"
+                    UPDATE production
+                    SET quantite_produite = COALESCE($1, quantite_produite),
+                        unite_production = COALESCE($2, unite_production),
+                        date_de_production = COALESCE($3, date_de_production),
+                        version = version + 1,
+                        updated_at = $4
+                    WHERE id = $5 AND version = $6 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(quantite_produite)
+                .bind(unite_production)
+                .bind(date_de_production)
+                .bind(timestamps::now())
+                .bind(id)
+                .bind(version)
+                .execute(self)
+                .await?;
+
+                Ok(result.rows_affected() > 0)
+            }
+
+            // Mettre une ligne à la corbeille si sa version n'a pas changé, en laissant une trace pour la synchronisation
+            async fn delete(&mut self, entity: Entity, id: i32, version: i32) -> Result<bool, RepoError> {
+                let mut tx = self.begin().await?;
+
+                let owner = owner(&mut tx, entity, id).await?;
+
+                // La ligne reste verrouillée jusqu'à la fin de la transaction : aucun enfant ne peut s'y rattacher
+                // entre le décompte de ses descendants et sa mise à la corbeille
+                let current: Option<i32> = sqlx::query_scalar(&format!(
+                    "SELECT version FROM {} WHERE id = $1 AND deleted_at IS NULL{}",
+                    table(entity),
+                    $lock
+                ))
+                .bind(id)
+                .fetch_optional(&mut *tx)
+                .await?;
+                if current != Some(version) {
+                    return Ok(false);
+                }
+                let dependents = subtree(&mut tx, entity, id).await?;
+                if !dependents.is_empty() {
+                    return Err(RepoError::Conflict(dependents.dependents()));
+                }
+
+                let now = timestamps::now();
+                let result = sqlx::query(&format!(
+                    r#"
+                    UPDATE {}
+                    SET deleted_at = $1, version = version + 1, updated_at = $1
+                    WHERE id = $2 AND version = $3 AND deleted_at IS NULL
+                    "#,
+                    table(entity)
+                ))
+                .bind(now)
+                .bind(id)
+                .bind(version)
+                .execute(&mut *tx)
+                .await?;
+
+                match owner {
+                    Some(owner) if result.rows_affected() > 0 => {
+                        record_tombstone(&mut tx, entity, id, owner, now).await?;
+                        tx.commit().await?;
+                        Ok(true)
+                    }
+                    _ => Ok(false),
+                }
+            }
+
+            async fn owner_of(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+                Ok(owner(self, entity, id).await?.map(|(_, user_id)| user_id))
+            }
+
+            async fn current_version(&mut self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+                sqlx::query_scalar(&format!("SELECT version FROM {} WHERE id = $1 AND deleted_at IS NULL", table(entity)))
+                    .bind(id)
+                    .fetch_optional(self)
+                    .await
+            }
+        }
+
+        #[async_trait]
+        impl UserRepository for $repository {
+            async fn create(&self, new_user: NewUser, created_by: Option<i32>) -> Result<User, Error> {
+                let NewUser { type_user_id, nom, prenom, email, numero_telephone, mot_de_passe } = new_user;
+                let hashed_password = hash(mot_de_passe, DEFAULT_COST).map_err(|_| Error::RowNotFound)?;
+
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO users (type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                                       created_at, updated_at, created_by)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $7, $8)
+                    RETURNING id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                              created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(type_user_id)
+                .bind(nom)
+                .bind(prenom)
+                .bind(email)
+                .bind(numero_telephone)
+                .bind(hashed_password)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<User>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                           created_at, updated_at, created_by
+                    FROM users
+                    WHERE $1 IS NULL OR updated_at > $1
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_id(&self, id: i32) -> Result<User, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                           created_at, updated_at, created_by
+                    FROM users
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_by_email(&self, email: &str) -> Result<User, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                           created_at, updated_at, created_by
+                    FROM users
+                    WHERE email = $1
+                    "#,
+                )
+                .bind(email)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn update(&self, id: i32, changes: UserChanges) -> Result<(), Error> {
+                let mut query = QueryBuilder::<$database>::new("UPDATE users SET ");
+                let mut fields = query.separated(", ");
+
+                if let Some(nom) = changes.nom {
+                    fields.push("nom = ").push_bind_unseparated(nom);
+                }
+                if let Some(prenom) = changes.prenom {
+                    fields.push("prenom = ").push_bind_unseparated(prenom);
+                }
+                if let Some(email) = changes.email {
+                    fields.push("email = ").push_bind_unseparated(email);
+                }
+                if let Some(numero_telephone) = changes.numero_telephone {
+                    fields.push("numero_telephone = ").push_bind_unseparated(numero_telephone);
+                }
+                if let Some(mot_de_passe) = changes.mot_de_passe {
+                    fields.push("mot_de_passe = ").push_bind_unseparated(mot_de_passe);
+                }
+                fields.push("updated_at = ").push_bind_unseparated(timestamps::now());
+
+                query.push(" WHERE id = ").push_bind(id);
+                query.build().execute(&self.pool).await?;
+
+                Ok(())
+            }
+
+            async fn delete(&self, id: i32) -> Result<(), Error> {
+                sqlx::query("DELETE FROM users WHERE id = $1")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl ReferenceRepository for $repository {
+            async fn create_type_user(
+                &self,
+                nom_type_user: String,
+                created_by: Option<i32>,
+            ) -> Result<TypeUser, Error> {
+                let nom_recherche = search::normalize(&nom_type_user);
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO types_user (nom_type_user, created_at, updated_at, created_by, nom_recherche)
+                    VALUES ($1, $2, $2, $3, $4)
+                    RETURNING id, nom_type_user, created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(nom_type_user)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .bind(nom_recherche)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_types_user(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<TypeUser>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, nom_type_user, created_at, updated_at, created_by
+                    FROM types_user
+                    WHERE $1 IS NULL OR updated_at > $1
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn create_type_exploitation(
+                &self,
+                nom_type_exploitation: String,
+                created_by: Option<i32>,
+            ) -> Result<TypeExploitation, Error> {
+                let nom_recherche = search::normalize(&nom_type_exploitation);
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO types_exploitation (nom_type_exploitation, created_at, updated_at, created_by, nom_recherche)
+                    VALUES ($1, $2, $2, $3, $4)
+                    RETURNING id, nom_type_exploitation, created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(nom_type_exploitation)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .bind(nom_recherche)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_types_exploitation(
+                &self,
+                updated_since: Option<DateTime<Utc>>,
+            ) -> Result<Vec<TypeExploitation>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, nom_type_exploitation, created_at, updated_at, created_by
+                    FROM types_exploitation
+                    WHERE $1 IS NULL OR updated_at > $1
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn create_type_element(
+                &self,
+                nom_type_element: String,
+                created_by: Option<i32>,
+            ) -> Result<TypeElement, Error> {
+                let nom_recherche = search::normalize(&nom_type_element);
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO types_element (nom_type_element, created_at, updated_at, created_by, nom_recherche)
+                    VALUES ($1, $2, $2, $3, $4)
+                    RETURNING id, nom_type_element, created_at, updated_at, created_by
+                    "#,
+                )
+                .bind(nom_type_element)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .bind(nom_recherche)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_types_element(
+                &self,
+                updated_since: Option<DateTime<Utc>>,
+            ) -> Result<Vec<TypeElement>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, nom_type_element, created_at, updated_at, created_by
+                    FROM types_element
+                    WHERE $1 IS NULL OR updated_at > $1
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn update_type_element(&self, id: i32, nom_type_element: String) -> Result<(), Error> {
+                let nom_recherche = search::normalize(&nom_type_element);
+                sqlx::query("UPDATE types_element SET nom_type_element = $1, nom_recherche = $4, updated_at = $2 WHERE id = $3")
+                    .bind(nom_type_element)
+                    .bind(timestamps::now())
+                    .bind(id)
+                    .bind(nom_recherche)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(())
+            }
+
+            async fn delete_type_element(&self, id: i32) -> Result<(), Error> {
+                let mut tx = self.pool.begin().await?;
+                sqlx::query("DELETE FROM types_element WHERE id = $1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("DELETE FROM types_traductions WHERE kind = $1 AND type_id = $2")
+                    .bind(TypeKind::TypeElement)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                tx.commit().await
+            }
+
+            async fn get_type_translations(
+                &self,
+                kind: TypeKind,
+                lang: &str,
+            ) -> Result<Vec<TypeTranslation>, Error> {
+                sqlx::query_as("SELECT type_id, nom FROM types_traductions WHERE kind = $1 AND lang = $2")
+                    .bind(kind)
+                    .bind(lang)
+                    .fetch_all(&self.pool)
+                    .await
+            }
+
+            async fn set_type_translation(
+                &self,
+                kind: TypeKind,
+                type_id: i32,
+                lang: &str,
+                nom: String,
+            ) -> Result<(), Error> {
+                let mut tx = self.pool.begin().await?;
+                let now = timestamps::now();
+
+                // Le type est marqué modifié pour que les clients synchronisés relisent son nom
+                let touched = sqlx::query(&format!("UPDATE {} SET updated_at = $1 WHERE id = $2", kind.table()))
+                    .bind(now)
+                    .bind(type_id)
+                    .execute(&mut *tx)
+                    .await?;
+                if touched.rows_affected() == 0 {
+                    return Err(Error::RowNotFound);
+                }
+
+                sqlx::query(
+                    r#"
+                    INSERT INTO types_traductions (kind, type_id, lang, nom, updated_at)
+                    VALUES ($1, $2, $3, $4, $5)
+                    ON CONFLICT (kind, type_id, lang) DO UPDATE SET nom = excluded.nom, updated_at = excluded.updated_at
+                    "#,
+                )
+                .bind(kind)
+                .bind(type_id)
+                .bind(lang)
+                .bind(nom)
+                .bind(now)
+                .execute(&mut *tx)
+                .await?;
+
+                tx.commit().await
+            }
+        }
+
+        #[async_trait]
+        impl DomaineRepository for $repository {
+            async fn create(
+                &self,
+                user_id: i32,
+                nom_domaine: String,
+                created_by: Option<i32>,
+            ) -> Result<Domaine, Error> {
+                self.pool.acquire().await?.create_domaine(user_id, nom_domaine, created_by).await
+            }
+
+            async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Domaine>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
+                    FROM domaines
+                    WHERE deleted_at IS NULL AND ($1 IS NULL OR updated_at > $1)
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_id(&self, id: i32) -> Result<Domaine, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
+                    FROM domaines
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_all_by_user_id(
+                &self,
+                user_id: i32,
+                updated_since: Option<DateTime<Utc>>,
+            ) -> Result<Vec<Domaine>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
+                    FROM domaines
+                    WHERE user_id = $1 AND deleted_at IS NULL AND ($2 IS NULL OR updated_at > $2)
+                    "#,
+                )
+                .bind(user_id)
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn update(&self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+                self.pool.acquire().await?.update_domaine(id, version, nom_domaine).await
+            }
+
+            async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+                BatchStore::delete(&mut *self.pool.acquire().await?, Entity::Domaine, id, version).await
+            }
+
+            async fn owner_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+                BatchStore::owner_of(&mut *self.pool.acquire().await?, entity, id).await
+            }
+
+            async fn domaine_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+                Ok(owner(&mut *self.pool.acquire().await?, entity, id).await?.map(|(domaine_id, _)| domaine_id))
+            }
+
+            async fn tree(&self, id: i32) -> Result<DomaineTree, Error> {
+                let domaine = DomaineRepository::get_by_id(self, id).await?;
+                let exploitations = ExploitationRepository::get_by_domaine_id(self, id, None).await?;
+                let types = ReferenceRepository::get_types_exploitation(self, None).await?;
+
+                let elements: Vec<Element> = sqlx::query_as(
+                    r#"
+                    SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+                    FROM elements
+                    WHERE deleted_at IS NULL
+                      AND exploitation_id IN (
+                          SELECT id FROM exploitations WHERE domaine_id = $1 AND deleted_at IS NULL
+                      )
+                    ORDER BY id
+                    "#,
+                )
+                .bind(id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let dernieres: Vec<ResumeProduction> = sqlx::query_as(
+                    r#"
+                    SELECT element_id, date_de_production, quantite_produite, unite_production, nombre_productions
+                    FROM (
+                        SELECT p.element_id, p.date_de_production, p.quantite_produite, p.unite_production,
+                               ROW_NUMBER() OVER (
+                                   PARTITION BY p.element_id ORDER BY p.date_de_production DESC, p.id DESC
+                               ) AS rang,
+                               COUNT(*) OVER (PARTITION BY p.element_id) AS nombre_productions
+                        FROM production p
+                        JOIN elements e ON e.id = p.element_id
+                        JOIN exploitations x ON x.id = e.exploitation_id
+                        WHERE x.domaine_id = $1
+                          AND p.deleted_at IS NULL AND e.deleted_at IS NULL AND x.deleted_at IS NULL
+                    ) dernieres
+                    WHERE rang = 1
+                    "#,
+                )
+                .bind(id)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(tree::assemble(domaine, exploitations, types, elements, dernieres))
+            }
+        }
+
+        #[async_trait]
+        impl ExploitationRepository for $repository {
+            async fn create(
+                &self,
+                type_exploitation_id: i32,
+                domaine_id: i32,
+                nom_exploitation: String,
+                created_by: Option<i32>,
+            ) -> Result<Exploitation, Error> {
+                self.pool
+                    .acquire()
+                    .await?
+                    .create_exploitation(type_exploitation_id, domaine_id, nom_exploitation, created_by)
+                    .await
+            }
+
+            async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Exploitation>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version,
+                           created_at, updated_at, created_by
+                    FROM exploitations
+                    WHERE deleted_at IS NULL AND ($1 IS NULL OR updated_at > $1)
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_id(&self, id: i32) -> Result<Exploitation, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version,
+                           created_at, updated_at, created_by
+                    FROM exploitations
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_by_domaine_id(
+                &self,
+                domaine_id: i32,
+                updated_since: Option<DateTime<Utc>>,
+            ) -> Result<Vec<Exploitation>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version,
+                           created_at, updated_at, created_by
+                    FROM exploitations
+                    WHERE domaine_id = $1 AND deleted_at IS NULL AND ($2 IS NULL OR updated_at > $2)
+                    "#,
+                )
+                .bind(domaine_id)
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_domaine_ids(&self, domaine_ids: &[i32]) -> Result<Vec<Exploitation>, Error> {
+                if domaine_ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let mut query = QueryBuilder::<$database>::new(
+                    r#"
+                    SELECT id, type_exploitation_id, domaine_id, nom_exploitation, version,
+                           created_at, updated_at, created_by
+                    FROM exploitations
+                    WHERE deleted_at IS NULL AND domaine_id IN ("#,
+                );
+                let mut ids = query.separated(", ");
+                for id in domaine_ids {
+                    ids.push_bind(*id);
+                }
+                ids.push_unseparated(") ORDER BY id");
+
+                query.build_query_as().fetch_all(&self.pool).await
+            }
+
+            async fn update(
+                &self,
+                id: i32,
+                version: i32,
+                type_exploitation_id: Option<i32>,
+                nom_exploitation: Option<String>,
+            ) -> Result<bool, Error> {
+                self.pool
+                    .acquire()
+                    .await?
+                    .update_exploitation(id, version, type_exploitation_id, nom_exploitation)
+                    .await
+            }
+
+            async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+                BatchStore::delete(&mut *self.pool.acquire().await?, Entity::Exploitation, id, version).await
+            }
+        }
+
+        #[async_trait]
+        impl ElementRepository for $repository {
+            async fn create(
+                &self,
+                exploitation_id: i32,
+                nom_element: String,
+                quantite: i32,
+                created_by: Option<i32>,
+            ) -> Result<Element, Error> {
+                self.pool
+                    .acquire()
+                    .await?
+                    .create_element(exploitation_id, nom_element, quantite, created_by)
+                    .await
+            }
+
+            async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Element>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+                    FROM elements
+                    WHERE deleted_at IS NULL AND ($1 IS NULL OR updated_at > $1)
+                    "#,
+                )
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_id(&self, id: i32) -> Result<Element, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+                    FROM elements
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_by_exploitation_id(
+                &self,
+                exploitation_id: i32,
+                updated_since: Option<DateTime<Utc>>,
+            ) -> Result<Vec<Element>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+                    FROM elements
+                    WHERE exploitation_id = $1 AND deleted_at IS NULL AND ($2 IS NULL OR updated_at > $2)
+                    "#,
+                )
+                .bind(exploitation_id)
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_exploitation_ids(&self, exploitation_ids: &[i32]) -> Result<Vec<Element>, Error> {
+                if exploitation_ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let mut query = QueryBuilder::<$database>::new(
+                    r#"
+                    SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+                    FROM elements
+                    WHERE deleted_at IS NULL AND exploitation_id IN ("#,
+                );
+                let mut ids = query.separated(", ");
+                for id in exploitation_ids {
+                    ids.push_bind(*id);
+                }
+                ids.push_unseparated(") ORDER BY id");
+
+                query.build_query_as().fetch_all(&self.pool).await
+            }
+
+            async fn update(
+                &self,
+                id: i32,
+                version: i32,
+                nom_element: Option<String>,
+                quantite: Option<i32>,
+            ) -> Result<bool, Error> {
+                self.pool
+                    .acquire()
+                    .await?
+                    .update_element(id, version, nom_element, quantite)
+                    .await
+            }
+
+            async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+                BatchStore::delete(&mut *self.pool.acquire().await?, Entity::Element, id, version).await
+            }
+        }
+
+        #[async_trait]
+        impl ProductionRepository for $repository {
+            async fn create(
+                &self,
+                element_id: i32,
+                quantite_produite: i32,
+                unite_production: String,
+                date_de_production: NaiveDate,
+                created_by: Option<i32>,
+            ) -> Result<Production, Error> {
+                self.pool
+                    .acquire()
+                    .await?
+                    .create_production(
+                        element_id,
+                        quantite_produite,
+                        unite_production,
+                        date_de_production,
+                        created_by,
+                    )
+                    .await
+            }
+
+            async fn get_by_id(&self, id: i32) -> Result<Production, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, element_id, quantite_produite, unite_production, date_de_production,
+                           version, created_at, updated_at, created_by
+                    FROM production
+                    WHERE id = $1 AND deleted_at IS NULL
+                    "#,
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_by_element_id(
+                &self,
+                element_id: i32,
+                updated_since: Option<DateTime<Utc>>,
+            ) -> Result<Vec<Production>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, element_id, quantite_produite, unite_production, date_de_production,
+                           version, created_at, updated_at, created_by
+                    FROM production
+                    WHERE element_id = $1 AND deleted_at IS NULL AND ($2 IS NULL OR updated_at > $2)
+                    "#,
+                )
+                .bind(element_id)
+                .bind(updated_since)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_by_element_ids(&self, element_ids: &[i32]) -> Result<Vec<Production>, Error> {
+                if element_ids.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                let mut query = QueryBuilder::<$database>::new(
+                    r#"
+                    SELECT id, element_id, quantite_produite, unite_production, date_de_production,
+                           version, created_at, updated_at, created_by
+                    FROM production
+                    WHERE deleted_at IS NULL AND element_id IN ("#,
+                );
+                let mut ids = query.separated(", ");
+                for id in element_ids {
+                    ids.push_bind(*id);
+                }
+                ids.push_unseparated(") ORDER BY id");
+
+                query.build_query_as().fetch_all(&self.pool).await
+            }
+
+            async fn update(
+                &self,
+                id: i32,
+                version: i32,
+                quantite_produite: Option<i32>,
+                unite_production: Option<String>,
+                date_de_production: Option<NaiveDate>,
+            ) -> Result<bool, Error> {
+                self.pool
+                    .acquire()
+                    .await?
+                    .update_production(id, version, quantite_produite, unite_production, date_de_production)
+                    .await
+            }
+
+            async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+                BatchStore::delete(&mut *self.pool.acquire().await?, Entity::Production, id, version).await
+            }
+        }
+
+        #[async_trait]
+        impl ReleveRepository for $repository {
+            async fn create_qualite_eau(
+                &self,
+                exploitation_id: i32,
+                date_releve: NaiveDate,
+                temperature: f64,
+                oxygene_dissous: f64,
+                ph: f64,
+                created_by: Option<i32>,
+            ) -> Result<QualiteEau, Error> {
+                // Seul l'ID est relu : SQLite renvoie un REAL sans partie décimale comme un entier après RETURNING
+                let now = timestamps::now();
+                let id = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO qualite_eau (exploitation_id, date_releve, temperature, oxygene_dissous, ph,
+                                             created_at, created_by)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    RETURNING id
+                    "#,
+                )
+                .bind(exploitation_id)
+                .bind(date_releve)
+                .bind(temperature)
+                .bind(oxygene_dissous)
+                .bind(ph)
+                .bind(now)
+                .bind(created_by)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(QualiteEau {
+                    id,
+                    exploitation_id,
+                    date_releve,
+                    temperature,
+                    oxygene_dissous,
+                    ph,
+                    created_at: now,
+                    created_by,
+                })
+            }
+
+            async fn get_qualite_eau(&self, exploitation_id: i32) -> Result<Vec<QualiteEau>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, exploitation_id, date_releve, temperature, oxygene_dissous, ph, created_at, created_by
+                    FROM qualite_eau
+                    WHERE exploitation_id = $1
+                    ORDER BY date_releve, id
+                    "#,
+                )
+                .bind(exploitation_id)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn create_alimentation(
+                &self,
+                element_id: i32,
+                date_alimentation: NaiveDate,
+                quantite_kg: f64,
+                created_by: Option<i32>,
+            ) -> Result<Alimentation, Error> {
+                // Même précaution que pour les relevés de qualité de l'eau
+                let now = timestamps::now();
+                let id = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO alimentations (element_id, date_alimentation, quantite_kg, created_at, created_by)
+                    VALUES ($1, $2, $3, $4, $5)
+                    RETURNING id
+                    "#,
+                )
+                .bind(element_id)
+                .bind(date_alimentation)
+                .bind(quantite_kg)
+                .bind(now)
+                .bind(created_by)
+                .fetch_one(&self.pool)
+                .await?;
+
+                Ok(Alimentation { id, element_id, date_alimentation, quantite_kg, created_at: now, created_by })
+            }
+
+            async fn get_alimentations(&self, element_id: i32) -> Result<Vec<Alimentation>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, element_id, date_alimentation, quantite_kg, created_at, created_by
+                    FROM alimentations
+                    WHERE element_id = $1
+                    ORDER BY date_alimentation, id
+                    "#,
+                )
+                .bind(element_id)
+                .fetch_all(&self.pool)
+                .await
+            }
+        }
+
+        #[async_trait]
+        impl SearchRepository for $repository {
+            async fn search(&self, user_id: i32, query: &str, terms: &[String], limit: usize) -> Result<Vec<SearchHit>, Error> {
+                let mut query = search::matching::<$database>(user_id, query, terms, limit, "LENGTH");
+                let rows = query.build_query_as::<SearchRow>().fetch_all(&self.pool).await?;
+
+                Ok(rows.into_iter().map(SearchHit::from).collect())
+            }
+        }
+
+        #[async_trait]
+        impl SyncRepository for $repository {
+            async fn changes_since(&self, user_id: i32, since: DateTime<Utc>) -> Result<ChangeSet, Error> {
+                let sync_token = sync::encode_token(timestamps::now());
+
+                let domaines = sqlx::query_as(
+                    r#"
+                    SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
+                    FROM domaines
+                    WHERE user_id = $1 AND deleted_at IS NULL AND updated_at >= $2
+                    "#,
+                )
+                .bind(user_id)
+                .bind(since)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let exploitations = sqlx::query_as(
+                    r#"
+                    SELECT e.id, e.type_exploitation_id, e.domaine_id, e.nom_exploitation, e.version,
+                           e.created_at, e.updated_at, e.created_by
+                    FROM exploitations e
+                    JOIN domaines d ON d.id = e.domaine_id
+                    WHERE d.user_id = $1 AND e.deleted_at IS NULL AND e.updated_at >= $2
+                    "#,
+                )
+                .bind(user_id)
+                .bind(since)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let elements = sqlx::query_as(
+                    r#"
+                    SELECT el.id, el.exploitation_id, el.nom_element, el.quantite, el.version,
+                           el.created_at, el.updated_at, el.created_by
+                    FROM elements el
+                    JOIN exploitations e ON e.id = el.exploitation_id
+                    JOIN domaines d ON d.id = e.domaine_id
+                    WHERE d.user_id = $1 AND el.deleted_at IS NULL AND el.updated_at >= $2
+                    "#,
+                )
+                .bind(user_id)
+                .bind(since)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let productions = sqlx::query_as(
+                    r#"
+                    SELECT p.id, p.element_id, p.quantite_produite, p.unite_production, p.date_de_production,
+                           p.version, p.created_at, p.updated_at, p.created_by
+                    FROM production p
+                    JOIN elements el ON el.id = p.element_id
+                    JOIN exploitations e ON e.id = el.exploitation_id
+                    JOIN domaines d ON d.id = e.domaine_id
+                    WHERE d.user_id = $1 AND p.deleted_at IS NULL AND p.updated_at >= $2
+                    "#,
+                )
+                .bind(user_id)
+                .bind(since)
+                .fetch_all(&self.pool)
+                .await?;
+
+                let deleted: Vec<Tombstone> = sqlx::query_as(
+                    r#"
+                    SELECT entity, entity_id, domaine_id, user_id, deleted_at
+                    FROM tombstones
+                    WHERE user_id = $1 AND deleted_at >= $2
+                    ORDER BY deleted_at
+                    "#,
+                )
+                .bind(user_id)
+                .bind(since)
+                .fetch_all(&self.pool)
+                .await?;
+
+                Ok(ChangeSet {
+                    sync_token,
+                    domaines,
+                    exploitations,
+                    elements,
+                    productions,
+                    deleted,
+                })
+            }
+
+            async fn lookup_client_id(
+                &self,
+                user_id: i32,
+                client_id: &str,
+                entity: Entity,
+            ) -> Result<Option<i32>, Error> {
+                sqlx::query_scalar(
+                    r#"
+                    SELECT entity_id FROM sync_client_ids
+                    WHERE user_id = $1 AND client_id = $2 AND entity = $3 AND entity_id IS NOT NULL
+                    "#,
+                )
+                .bind(user_id)
+                .bind(client_id)
+                .bind(entity)
+                .fetch_optional(&self.pool)
+                .await
+            }
+
+            async fn reserve_client_id(
+                &self,
+                user_id: i32,
+                client_id: &str,
+                entity: Entity,
+                stale_before: DateTime<Utc>,
+            ) -> Result<ClientIdReservation, Error> {
+                let now = timestamps::now();
+
+                let inserted = sqlx::query(
+                    r#"
+                    INSERT INTO sync_client_ids (user_id, client_id, entity, entity_id, reserved_at)
+                    VALUES ($1, $2, $3, NULL, $4)
+                    ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(user_id)
+                .bind(client_id)
+                .bind(entity)
+                .bind(now)
+                .execute(&self.pool)
+                .await?;
+
+                if inserted.rows_affected() > 0 {
+                    return Ok(ClientIdReservation::Acquired);
+                }
+
+                let reclaimed = sqlx::query(
+                    r#"
+                    UPDATE sync_client_ids SET reserved_at = $1
+                    WHERE user_id = $2 AND client_id = $3 AND entity = $4
+                      AND entity_id IS NULL AND reserved_at < $5
+                    "#,
+                )
+                .bind(now)
+                .bind(user_id)
+                .bind(client_id)
+                .bind(entity)
+                .bind(stale_before)
+                .execute(&self.pool)
+                .await?;
+
+                if reclaimed.rows_affected() > 0 {
+                    return Ok(ClientIdReservation::Acquired);
+                }
+
+                let entity_id: Option<Option<i32>> = sqlx::query_scalar(
+                    r#"
+                    SELECT entity_id FROM sync_client_ids
+                    WHERE user_id = $1 AND client_id = $2 AND entity = $3
+                    "#,
+                )
+                .bind(user_id)
+                .bind(client_id)
+                .bind(entity)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(entity_id.flatten().map_or(ClientIdReservation::Pending, ClientIdReservation::Recorded))
+            }
+
+            async fn record_client_id(
+                &self,
+                user_id: i32,
+                client_id: &str,
+                entity: Entity,
+                entity_id: i32,
+            ) -> Result<(), Error> {
+                sqlx::query(
+                    r#"
+                    UPDATE sync_client_ids SET entity_id = $1
+                    WHERE user_id = $2 AND client_id = $3 AND entity = $4
+                    "#,
+                )
+                .bind(entity_id)
+                .bind(user_id)
+                .bind(client_id)
+                .bind(entity)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn release_client_id(&self, user_id: i32, client_id: &str, entity: Entity) -> Result<(), Error> {
+                sqlx::query(
+                    r#"
+                    DELETE FROM sync_client_ids
+                    WHERE user_id = $1 AND client_id = $2 AND entity = $3 AND entity_id IS NULL
+                    "#,
+                )
+                .bind(user_id)
+                .bind(client_id)
+                .bind(entity)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl BatchRepository for $repository {
+            async fn execute(
+                &self,
+                user_id: i32,
+                operations: Vec<BatchOperation>,
+            ) -> Result<Vec<OperationResult>, BatchError> {
+                // À la première erreur, la transaction est abandonnée et rien n'est enregistré
+                let mut tx = self.pool.begin().await?;
+                let results = batch::run_all(&mut *tx, user_id, operations).await?;
+                tx.commit().await?;
+
+                Ok(results)
+            }
+        }
+
+        #[async_trait]
+        impl IdempotencyRepository for $repository {
+            async fn reserve(
+                &self,
+                principal: &str,
+                key: &str,
+                request_hash: &str,
+                stale_before: DateTime<Utc>,
+                expired_before: DateTime<Utc>,
+            ) -> Result<Reservation, Error> {
+                let now = timestamps::now();
+
+                let inserted = sqlx::query(
+                    r#"
+                    INSERT INTO idempotency_keys (principal, idempotency_key, request_hash, created_at)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT DO NOTHING
+                    "#,
+                )
+                .bind(principal)
+                .bind(key)
+                .bind(request_hash)
+                .bind(now)
+                .execute(&self.pool)
+                .await?;
+
+                if inserted.rows_affected() > 0 {
+                    return Ok(Reservation::Acquired);
+                }
+
+                let reclaimed = sqlx::query(
+                    r#"
+                    UPDATE idempotency_keys
+                    SET request_hash = $1, status_code = NULL, content_type = NULL, response_body = NULL, created_at = $2
+                    WHERE principal = $3 AND idempotency_key = $4
+                      AND ((status_code IS NULL AND created_at < $5) OR created_at < $6)
+                    "#,
+                )
+                .bind(request_hash)
+                .bind(now)
+                .bind(principal)
+                .bind(key)
+                .bind(stale_before)
+                .bind(expired_before)
+                .execute(&self.pool)
+                .await?;
+
+                if reclaimed.rows_affected() > 0 {
+                    return Ok(Reservation::Acquired);
+                }
+
+                let record: Option<IdempotencyRow> = sqlx::query_as(
+                    r#"
+                    SELECT request_hash, status_code, content_type, response_body, created_at
+                    FROM idempotency_keys
+                    WHERE principal = $1 AND idempotency_key = $2
+                    "#,
+                )
+                .bind(principal)
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?;
+
+                Ok(record.map_or(Reservation::Acquired, |row| Reservation::Existing(row.into())))
+            }
+
+            async fn store(
+                &self,
+                principal: &str,
+                key: &str,
+                status_code: u16,
+                content_type: Option<String>,
+                response_body: &[u8],
+            ) -> Result<(), Error> {
+                sqlx::query(
+                    r#"
+                    UPDATE idempotency_keys
+                    SET status_code = $1, content_type = $2, response_body = $3
+                    WHERE principal = $4 AND idempotency_key = $5
+                    "#,
+                )
+                .bind(i32::from(status_code))
+                .bind(content_type)
+                .bind(response_body)
+                .bind(principal)
+                .bind(key)
+                .execute(&self.pool)
+                .await?;
+
+                Ok(())
+            }
+
+            async fn release(&self, principal: &str, key: &str) -> Result<(), Error> {
+                sqlx::query("DELETE FROM idempotency_keys WHERE principal = $1 AND idempotency_key = $2")
+                    .bind(principal)
+                    .bind(key)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(())
+            }
+
+            async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+                let purged = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < $1")
+                    .bind(before)
+                    .execute(&self.pool)
+                    .await?;
+
+                Ok(purged.rows_affected())
+            }
+        }
+
+        #[async_trait]
+        impl StatsRepository for $repository {
+            async fn counts(&self, productions_since: DateTime<Utc>) -> Result<BusinessCounts, Error> {
+                let count = |sql: &'static str| sqlx::query_scalar::<$database, i64>(sql);
+
+                Ok(BusinessCounts {
+                    domaines: count("SELECT COUNT(*) FROM domaines WHERE deleted_at IS NULL").fetch_one(&self.pool).await?,
+                    elements: count("SELECT COUNT(*) FROM elements WHERE deleted_at IS NULL").fetch_one(&self.pool).await?,
+                    productions_du_jour: count("SELECT COUNT(*) FROM production WHERE deleted_at IS NULL AND created_at >= $1")
+                        .bind(productions_since)
+                        .fetch_one(&self.pool)
+                        .await?,
+                })
+            }
+
+            fn pool(&self) -> Option<PoolStats> {
+                Some(metrics::pool_stats(&self.pool))
+            }
+        }
+
+        #[async_trait]
+        impl HealthRepository for $repository {
+            async fn ping(&self) -> Result<(), Error> {
+                self.pool.acquire().await?.ping().await
+            }
+
+            async fn pending_migrations(&self) -> Result<Vec<i64>, Error> {
+                let applied = sqlx::query_scalar::<$database, i64>(schema::APPLIED_MIGRATIONS)
+                    .fetch_all(&self.pool)
+                    .await?;
+                Ok(schema::pending(&schema::$migrator, &applied))
+            }
+        }
+
+        #[async_trait]
+        impl WebhookRepository for $repository {
+            async fn create_webhook(
+                &self,
+                domaine_id: i32,
+                url: String,
+                events: String,
+                secret: String,
+                created_by: Option<i32>,
+            ) -> Result<Webhook, Error> {
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO webhooks (domaine_id, url, events, secret, created_at, created_by)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    RETURNING id, domaine_id, url, events, secret, created_at, created_by
+                    "#,
+                )
+                .bind(domaine_id)
+                .bind(url)
+                .bind(events)
+                .bind(secret)
+                .bind(timestamps::now())
+                .bind(created_by)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_webhooks_by_domaine_id(&self, domaine_id: i32) -> Result<Vec<Webhook>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, domaine_id, url, events, secret, created_at, created_by
+                    FROM webhooks
+                    WHERE domaine_id = $1
+                    ORDER BY id
+                    "#,
+                )
+                .bind(domaine_id)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_webhook(&self, id: i32) -> Result<Webhook, Error> {
+                sqlx::query_as(
+                    "SELECT id, domaine_id, url, events, secret, created_at, created_by FROM webhooks WHERE id = $1",
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn delete_webhook(&self, id: i32) -> Result<(), Error> {
+                sqlx::query("DELETE FROM webhooks WHERE id = $1")
+                    .bind(id)
+                    .execute(&self.pool)
+                    .await?;
+                Ok(())
+            }
+
+            async fn create_delivery(
+                &self,
+                webhook_id: i32,
+                event_type: &str,
+                payload: String,
+            ) -> Result<WebhookDelivery, Error> {
+                let now = timestamps::now();
+                sqlx::query_as(
+                    r#"
+                    INSERT INTO webhook_deliveries (webhook_id, event_type, payload, status, attempts,
+                                                    next_attempt_at, created_at)
+                    VALUES ($1, $2, $3, $4, 0, $5, $5)
+                    RETURNING id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+                              last_status_code, last_error, created_at, delivered_at
+                    "#,
+                )
+                .bind(webhook_id)
+                .bind(event_type)
+                .bind(payload)
+                .bind(DeliveryStatus::Pending)
+                .bind(now)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn get_deliveries(&self, webhook_id: i32) -> Result<Vec<WebhookDelivery>, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+                           last_status_code, last_error, created_at, delivered_at
+                    FROM webhook_deliveries
+                    WHERE webhook_id = $1
+                    ORDER BY id DESC
+                    "#,
+                )
+                .bind(webhook_id)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn get_delivery(&self, id: i32) -> Result<WebhookDelivery, Error> {
+                sqlx::query_as(
+                    r#"
+                    SELECT id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+                           last_status_code, last_error, created_at, delivered_at
+                    FROM webhook_deliveries
+                    WHERE id = $1
+                    "#,
+                )
+                .bind(id)
+                .fetch_one(&self.pool)
+                .await
+            }
+
+            async fn claim_due_deliveries(
+                &self,
+                now: DateTime<Utc>,
+                lease_until: DateTime<Utc>,
+                limit: i64,
+            ) -> Result<Vec<WebhookDelivery>, Error> {
+                let lease_token = uuid::Uuid::new_v4().to_string();
+
+                // L'échéance est revérifiée sur chaque ligne : une livraison réservée entre-temps
+                // par un autre processus n'est pas reprise
+                sqlx::query(&format!(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET next_attempt_at = $1, lease_token = $2
+                    WHERE status = $3 AND next_attempt_at <= $4 AND id IN (
+                        SELECT id FROM webhook_deliveries
+                        WHERE status = $3 AND next_attempt_at <= $4
+                        ORDER BY next_attempt_at, id
+                        LIMIT $5{}
+                    )
+                    "#,
+                    $lock
+                ))
+                .bind(lease_until)
+                .bind(&lease_token)
+                .bind(DeliveryStatus::Pending)
+                .bind(now)
+                .bind(limit)
+                .execute(&self.pool)
+                .await?;
+
+                sqlx::query_as(
+                    r#"
+                    SELECT id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+                           last_status_code, last_error, created_at, delivered_at
+                    FROM webhook_deliveries
+                    WHERE lease_token = $1
+                    ORDER BY id
+                    "#,
+                )
+                .bind(&lease_token)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn record_attempt(&self, id: i32, attempt: Attempt) -> Result<(), Error> {
+                sqlx::query(
+                    r#"
+                    UPDATE webhook_deliveries
+                    SET status = $1, attempts = $2, last_status_code = $3, last_error = $4, next_attempt_at = $5,
+                        delivered_at = COALESCE($6, delivered_at)
+                    WHERE id = $7
+                    "#,
+                )
+                .bind(attempt.status)
+                .bind(attempt.attempts)
+                .bind(attempt.status_code)
+                .bind(attempt.error)
+                .bind(attempt.next_attempt_at)
+                .bind(attempt.delivered_at)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            }
+
+            async fn redeliver(&self, id: i32) -> Result<(), Error> {
+                sqlx::query(
+                    "UPDATE webhook_deliveries SET status = $1, attempts = 0, next_attempt_at = $2 WHERE id = $3",
+                )
+                .bind(DeliveryStatus::Pending)
+                .bind(timestamps::now())
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+                Ok(())
+            }
+        }
+
+        #[async_trait]
+        impl TrashRepository for $repository {
+            async fn get_trash(&self, user_id: i32) -> Result<Vec<TrashItem>, Error> {
+                // Les entités sont liées en paramètres pour garder le type de la colonne des traces
+                sqlx::query_as(
+                    r#"
+                    SELECT entity, id, nom, domaine_id, deleted_at
+                    FROM (
+                        SELECT $2 AS entity, d.id, d.nom_domaine AS nom, d.id AS domaine_id, d.deleted_at
+                        FROM domaines d
+                        WHERE d.user_id = $1 AND d.deleted_at IS NOT NULL
+                        UNION ALL
+                        SELECT $3, e.id, e.nom_exploitation, d.id, e.deleted_at
+                        FROM exploitations e
+                        JOIN domaines d ON d.id = e.domaine_id
+                        WHERE d.user_id = $1 AND e.deleted_at IS NOT NULL
+                        UNION ALL
+                        SELECT $4, el.id, el.nom_element, d.id, el.deleted_at
+                        FROM elements el
+                        JOIN exploitations e ON e.id = el.exploitation_id
+                        JOIN domaines d ON d.id = e.domaine_id
+                        WHERE d.user_id = $1 AND el.deleted_at IS NOT NULL
+                        UNION ALL
+                        SELECT $5, p.id, el.nom_element, d.id, p.deleted_at
+                        FROM production p
+                        JOIN elements el ON el.id = p.element_id
+                        JOIN exploitations e ON e.id = el.exploitation_id
+                        JOIN domaines d ON d.id = e.domaine_id
+                        WHERE d.user_id = $1 AND p.deleted_at IS NOT NULL
+                    ) corbeille
+                    ORDER BY deleted_at DESC, entity, id
+                    "#,
+                )
+                .bind(user_id)
+                .bind(Entity::Domaine)
+                .bind(Entity::Exploitation)
+                .bind(Entity::Element)
+                .bind(Entity::Production)
+                .fetch_all(&self.pool)
+                .await
+            }
+
+            async fn restore(&self, entity: Entity, id: i32) -> Result<Restore, Error> {
+                let mut tx = self.pool.begin().await?;
+
+                // Pour une ligne à la corbeille : son parent y est-il aussi ?
+                let query = match parent(entity) {
+                    Some((parents, column)) => format!(
+                        r#"
+                        SELECT p.deleted_at IS NOT NULL, c.deletion_batch
+                        FROM {} c
+                        JOIN {} p ON p.id = c.{}
+                        WHERE c.id = $1 AND c.deleted_at IS NOT NULL
+                        "#,
+                        table(entity),
+                        parents,
+                        column
+                    ),
+                    None => format!(
+                        "SELECT FALSE, deletion_batch FROM {} WHERE id = $1 AND deleted_at IS NOT NULL",
+                        table(entity)
+                    ),
+                };
+                let trashed: Option<(bool, Option<String>)> =
+                    sqlx::query_as(&query).bind(id).fetch_optional(&mut *tx).await?;
+
+                let batch = match trashed {
+                    None => return Ok(Restore::NotInTrash),
+                    Some((true, _)) => return Ok(Restore::ParentInTrash),
+                    Some((false, batch)) => batch,
+                };
+
+                // Les descendants supprimés par la même cascade reviennent avec la ligne
+                let mut restored = match batch {
+                    Some(batch) => trashed_subtree(&mut tx, entity, id, &batch).await?,
+                    None => Subtree::default(),
+                };
+                restored.ids_mut(entity).push(id);
+
+                // La version change : les modifications hors ligne antérieures à la suppression sont refusées
+                let now = timestamps::now();
+                for (entity, id) in restored.rows() {
+                    sqlx::query(&format!(
+                        "UPDATE {} SET deleted_at = NULL, deletion_batch = NULL, version = version + 1, updated_at = $1 WHERE id = $2",
+                        table(entity)
+                    ))
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                    // La ligne réapparaît dans la synchronisation comme une modification
+                    sqlx::query("DELETE FROM tombstones WHERE entity = $1 AND entity_id = $2")
+                        .bind(entity)
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+
+                tx.commit().await?;
+                Ok(Restore::Restored(restored))
+            }
+
+            async fn owner_including_trash(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+                Ok(owner_including_trash(&mut *self.pool.acquire().await?, entity, id).await?.map(|(_, user_id)| user_id))
+            }
+
+            // Les enfants d'un parent purgé partent avec lui, en commençant par le bas de la hiérarchie
+            // Les traces de suppression (tombstones) sont gardées exprès : un client resté hors ligne au-delà
+            // de la rétention doit encore apprendre la suppression à sa prochaine synchronisation
+            async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+                let mut tx = self.pool.begin().await?;
+
+                let mut purged = 0;
+                for query in [
+                    r#"
+                    DELETE FROM production
+                    WHERE deleted_at < $1
+                       OR element_id IN (
+                           SELECT id FROM elements
+                           WHERE deleted_at < $1
+                              OR exploitation_id IN (
+                                  SELECT id FROM exploitations
+                                  WHERE deleted_at < $1
+                                     OR domaine_id IN (SELECT id FROM domaines WHERE deleted_at < $1)
+                              )
+                       )
+                    "#,
+                    r#"
+                    DELETE FROM elements
+                    WHERE deleted_at < $1
+                       OR exploitation_id IN (
+                           SELECT id FROM exploitations
+                           WHERE deleted_at < $1
+                              OR domaine_id IN (SELECT id FROM domaines WHERE deleted_at < $1)
+                       )
+                    "#,
+                    r#"
+                    DELETE FROM exploitations
+                    WHERE deleted_at < $1
+                       OR domaine_id IN (SELECT id FROM domaines WHERE deleted_at < $1)
+                    "#,
+                    // Les webhooks du domaine et leurs livraisons suivent par ON DELETE CASCADE
+                    "DELETE FROM domaines WHERE deleted_at < $1",
+                ] {
+                    purged += sqlx::query(query).bind(before).execute(&mut *tx).await?.rows_affected();
+                }
+
+                tx.commit().await?;
+                Ok(purged)
+            }
+
+            async fn subtree(&self, entity: Entity, id: i32) -> Result<Subtree, Error> {
+                subtree(&mut *self.pool.acquire().await?, entity, id).await
+            }
+
+            async fn delete_cascade(
+                &self,
+                entity: Entity,
+                id: i32,
+                version: i32,
+            ) -> Result<Option<Subtree>, RepoError> {
+                let mut tx = self.pool.begin().await?;
+
+                // La ligne reste verrouillée pendant toute la cascade : aucun enfant ne peut s'y rattacher en chemin
+                sqlx::query(&format!("SELECT id FROM {} WHERE id = $1{}", table(entity), $lock))
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+
+                let mut deleted = subtree(&mut tx, entity, id).await?;
+                let owner = owner(&mut tx, entity, id).await?;
+                let now = timestamps::now();
+                let batch = uuid::Uuid::new_v4().to_string();
+
+                // Du bas vers le haut, pour que chaque parent soit vide quand vient son tour
+                for child in [Entity::Production, Entity::Element, Entity::Exploitation] {
+                    for &child_id in deleted.ids(child) {
+                        sqlx::query(&format!(
+                            "UPDATE {} SET deleted_at = $1, deletion_batch = $2, version = version + 1, updated_at = $1 WHERE id = $3",
+                            table(child)
+                        ))
+                        .bind(now)
+                        .bind(&batch)
+                        .bind(child_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    }
+                }
+                if let Some(owner) = owner {
+                    for (child, child_id) in deleted.rows() {
+                        record_tombstone(&mut tx, child, child_id, owner, now).await?;
+                    }
+                }
+
+                // La ligne elle-même, avec le contrôle de version habituel ; en cas d'échec la transaction est abandonnée
+                if !BatchStore::delete(&mut *tx, entity, id, version).await? {
+                    return Ok(None);
+                }
+
+                // La ligne rejoint le lot de sa descendance : la restauration les ramène ensemble
+                sqlx::query(&format!("UPDATE {} SET deleted_at = $1, deletion_batch = $2 WHERE id = $3", table(entity)))
+                    .bind(now)
+                    .bind(&batch)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                deleted.ids_mut(entity).push(id);
+
+                tx.commit().await?;
+                Ok(Some(deleted))
+            }
+        }
+    };
+}
@@ -0,0 +1,5 @@
+use sqlx::postgres::{PgConnection, PgPool, Postgres};
+
+// Implémentation PostgreSQL ; les ID des lignes insérées sont lus avec RETURNING
+// Une suppression verrouille sa ligne par FOR UPDATE avant de compter ses descendants
+portable_repository!(PostgresRepository, Postgres, PgPool, PgConnection, POSTGRES_MIGRATOR, " FOR UPDATE");
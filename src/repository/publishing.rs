@@ -0,0 +1,409 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::Error;
+use std::sync::Arc;
+
+use crate::batch::{BatchError, BatchOperation, OperationResult, Ref};
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::events::{EventBus, FarmEvent};
+use crate::exploitation::Exploitation;
+use crate::production::Production;
+use crate::sync::Operation;
+use crate::tombstone::Entity;
+use crate::trash::{Restore, Subtree, TrashItem};
+use crate::tree::DomaineTree;
+use crate::webhook::{self, Outbox};
+
+use super::{
+    BatchRepository, DomaineRepository, ElementRepository, ExploitationRepository, ProductionRepository, RepoError,
+    TrashRepository,
+};
+
+// Dépôt qui diffuse un événement après chaque écriture réussie sur les données d'une ferme
+// Placé devant le backend, il voit aussi bien les routes REST que la synchronisation, les lots et GraphQL
+pub struct Publishing<R> {
+    inner: Arc<R>,
+    events: EventBus,
+    outbox: Outbox,
+}
+
+impl<R: DomaineRepository> Publishing<R> {
+    pub fn new(inner: Arc<R>, events: EventBus, outbox: Outbox) -> Self {
+        Publishing { inner, events, outbox }
+    }
+
+    // Les livraisons de webhooks sont enregistrées avant de rendre la main à l'appelant, sans dépendre
+    // des abonnés du bus : seul un arrêt du processus entre l'écriture et leur insertion peut les perdre
+    async fn publish(&self, domaine_id: i32, entity: Entity, operation: Operation, id: i32, version: Option<i32>) {
+        let event = FarmEvent { domaine_id, entity, operation, id, version };
+        if let Err(e) = webhook::enqueue(&self.outbox, &event).await {
+            tracing::error!(error = ?e, "Livraisons de webhooks non mises en file");
+        }
+        self.events.publish(event);
+    }
+
+    // L'écriture est déjà faite : une erreur en lisant son domaine ne fait que perdre l'événement
+    async fn publish_written(&self, entity: Entity, operation: Operation, id: i32, version: Option<i32>) {
+        match self.inner.domaine_of(entity, id).await {
+            Ok(Some(domaine_id)) => self.publish(domaine_id, entity, operation, id, version).await,
+            Ok(None) => {},
+            Err(e) => tracing::warn!(error = ?e, "Événement non diffusé"),
+        }
+    }
+
+    // Une suppression est publiée avec le domaine lu avant que la ligne ne disparaisse
+    async fn publish_deleted(&self, domaine_id: Option<i32>, entity: Entity, id: i32) {
+        if let Some(domaine_id) = domaine_id {
+            self.publish(domaine_id, entity, Operation::Delete, id, None).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<R: DomaineRepository + 'static> DomaineRepository for Publishing<R> {
+    async fn create(&self, user_id: i32, nom_domaine: String, created_by: Option<i32>) -> Result<Domaine, Error> {
+        let domaine = self.inner.create(user_id, nom_domaine, created_by).await?;
+        self.publish(domaine.id, Entity::Domaine, Operation::Create, domaine.id, Some(domaine.version)).await;
+        Ok(domaine)
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Domaine>, Error> {
+        self.inner.get_all(updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Domaine, Error> {
+        self.inner.get_by_id(id).await
+    }
+
+    async fn get_all_by_user_id(
+        &self,
+        user_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Domaine>, Error> {
+        self.inner.get_all_by_user_id(user_id, updated_since).await
+    }
+
+    async fn update(&self, id: i32, version: i32, nom_domaine: Option<String>) -> Result<bool, Error> {
+        let updated = self.inner.update(id, version, nom_domaine).await?;
+        if updated {
+            self.publish(id, Entity::Domaine, Operation::Update, id, Some(version + 1)).await;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        let deleted = self.inner.delete(id, version).await?;
+        if deleted {
+            self.publish_deleted(Some(id), Entity::Domaine, id).await;
+        }
+        Ok(deleted)
+    }
+
+    async fn owner_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        self.inner.owner_of(entity, id).await
+    }
+
+    async fn domaine_of(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        self.inner.domaine_of(entity, id).await
+    }
+
+    async fn tree(&self, id: i32) -> Result<DomaineTree, Error> {
+        self.inner.tree(id).await
+    }
+}
+
+#[async_trait]
+impl<R: DomaineRepository + ExploitationRepository + 'static> ExploitationRepository for Publishing<R> {
+    async fn create(
+        &self,
+        type_exploitation_id: i32,
+        domaine_id: i32,
+        nom_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Exploitation, Error> {
+        let exploitation =
+            ExploitationRepository::create(&*self.inner, type_exploitation_id, domaine_id, nom_exploitation, created_by)
+                .await?;
+        self.publish(
+            exploitation.domaine_id,
+            Entity::Exploitation,
+            Operation::Create,
+            exploitation.id,
+            Some(exploitation.version),
+        )
+        .await;
+        Ok(exploitation)
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Exploitation>, Error> {
+        ExploitationRepository::get_all(&*self.inner, updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Exploitation, Error> {
+        ExploitationRepository::get_by_id(&*self.inner, id).await
+    }
+
+    async fn get_by_domaine_id(
+        &self,
+        domaine_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Exploitation>, Error> {
+        self.inner.get_by_domaine_id(domaine_id, updated_since).await
+    }
+
+    async fn get_by_domaine_ids(&self, domaine_ids: &[i32]) -> Result<Vec<Exploitation>, Error> {
+        self.inner.get_by_domaine_ids(domaine_ids).await
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        type_exploitation_id: Option<i32>,
+        nom_exploitation: Option<String>,
+    ) -> Result<bool, Error> {
+        let updated =
+            ExploitationRepository::update(&*self.inner, id, version, type_exploitation_id, nom_exploitation).await?;
+        if updated {
+            self.publish_written(Entity::Exploitation, Operation::Update, id, Some(version + 1)).await;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        let domaine_id = self.inner.domaine_of(Entity::Exploitation, id).await?;
+        let deleted = ExploitationRepository::delete(&*self.inner, id, version).await?;
+        if deleted {
+            self.publish_deleted(domaine_id, Entity::Exploitation, id).await;
+        }
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl<R: DomaineRepository + ElementRepository + 'static> ElementRepository for Publishing<R> {
+    async fn create(
+        &self,
+        exploitation_id: i32,
+        nom_element: String,
+        quantite: i32,
+        created_by: Option<i32>,
+    ) -> Result<Element, Error> {
+        let element = ElementRepository::create(&*self.inner, exploitation_id, nom_element, quantite, created_by).await?;
+        self.publish_written(Entity::Element, Operation::Create, element.id, Some(element.version)).await;
+        Ok(element)
+    }
+
+    async fn get_all(&self, updated_since: Option<DateTime<Utc>>) -> Result<Vec<Element>, Error> {
+        ElementRepository::get_all(&*self.inner, updated_since).await
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Element, Error> {
+        ElementRepository::get_by_id(&*self.inner, id).await
+    }
+
+    async fn get_by_exploitation_id(
+        &self,
+        exploitation_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Element>, Error> {
+        self.inner.get_by_exploitation_id(exploitation_id, updated_since).await
+    }
+
+    async fn get_by_exploitation_ids(&self, exploitation_ids: &[i32]) -> Result<Vec<Element>, Error> {
+        self.inner.get_by_exploitation_ids(exploitation_ids).await
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        nom_element: Option<String>,
+        quantite: Option<i32>,
+    ) -> Result<bool, Error> {
+        let updated = ElementRepository::update(&*self.inner, id, version, nom_element, quantite).await?;
+        if updated {
+            self.publish_written(Entity::Element, Operation::Update, id, Some(version + 1)).await;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        let domaine_id = self.inner.domaine_of(Entity::Element, id).await?;
+        let deleted = ElementRepository::delete(&*self.inner, id, version).await?;
+        if deleted {
+            self.publish_deleted(domaine_id, Entity::Element, id).await;
+        }
+        Ok(deleted)
+    }
+}
+
+#[async_trait]
+impl<R: DomaineRepository + ProductionRepository + 'static> ProductionRepository for Publishing<R> {
+    async fn create(
+        &self,
+        element_id: i32,
+        quantite_produite: i32,
+        unite_production: String,
+        date_de_production: NaiveDate,
+        created_by: Option<i32>,
+    ) -> Result<Production, Error> {
+        let production = ProductionRepository::create(
+            &*self.inner,
+            element_id,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+            created_by,
+        )
+        .await?;
+        self.publish_written(Entity::Production, Operation::Create, production.id, Some(production.version)).await;
+        Ok(production)
+    }
+
+    async fn get_by_id(&self, id: i32) -> Result<Production, Error> {
+        ProductionRepository::get_by_id(&*self.inner, id).await
+    }
+
+    async fn get_by_element_id(
+        &self,
+        element_id: i32,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Production>, Error> {
+        self.inner.get_by_element_id(element_id, updated_since).await
+    }
+
+    async fn get_by_element_ids(&self, element_ids: &[i32]) -> Result<Vec<Production>, Error> {
+        self.inner.get_by_element_ids(element_ids).await
+    }
+
+    async fn update(
+        &self,
+        id: i32,
+        version: i32,
+        quantite_produite: Option<i32>,
+        unite_production: Option<String>,
+        date_de_production: Option<NaiveDate>,
+    ) -> Result<bool, Error> {
+        let updated = ProductionRepository::update(
+            &*self.inner,
+            id,
+            version,
+            quantite_produite,
+            unite_production,
+            date_de_production,
+        )
+        .await?;
+        if updated {
+            self.publish_written(Entity::Production, Operation::Update, id, Some(version + 1)).await;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, id: i32, version: i32) -> Result<bool, RepoError> {
+        let domaine_id = self.inner.domaine_of(Entity::Production, id).await?;
+        let deleted = ProductionRepository::delete(&*self.inner, id, version).await?;
+        if deleted {
+            self.publish_deleted(domaine_id, Entity::Production, id).await;
+        }
+        Ok(deleted)
+    }
+}
+
+// Nature d'une opération de lot, et ligne existante qu'elle supprime
+fn describe(operation: &BatchOperation) -> (Operation, Option<(Entity, i32)>) {
+    let existing = |entity: Entity, id: &Ref| match id {
+        Ref::Id(id) => Some((entity, *id)),
+        // Ligne créée par le même lot : elle n'a jamais été visible des abonnés
+        Ref::Result(_) => None,
+    };
+
+    match operation {
+        BatchOperation::CreateDomaine { .. }
+        | BatchOperation::CreateExploitation { .. }
+        | BatchOperation::CreateElement { .. }
+        | BatchOperation::CreateProduction { .. } => (Operation::Create, None),
+        BatchOperation::UpdateDomaine { .. }
+        | BatchOperation::UpdateExploitation { .. }
+        | BatchOperation::UpdateElement { .. }
+        | BatchOperation::UpdateProduction { .. } => (Operation::Update, None),
+        BatchOperation::DeleteDomaine { id, .. } => (Operation::Delete, existing(Entity::Domaine, id)),
+        BatchOperation::DeleteExploitation { id, .. } => (Operation::Delete, existing(Entity::Exploitation, id)),
+        BatchOperation::DeleteElement { id, .. } => (Operation::Delete, existing(Entity::Element, id)),
+        BatchOperation::DeleteProduction { id, .. } => (Operation::Delete, existing(Entity::Production, id)),
+    }
+}
+
+#[async_trait]
+impl<R: DomaineRepository + BatchRepository + 'static> BatchRepository for Publishing<R> {
+    async fn execute(
+        &self,
+        user_id: i32,
+        operations: Vec<BatchOperation>,
+    ) -> Result<Vec<OperationResult>, BatchError> {
+        let mut steps = Vec::with_capacity(operations.len());
+        for operation in &operations {
+            let (kind, existing) = describe(operation);
+            let domaine_id = match existing {
+                Some((entity, id)) => self.inner.domaine_of(entity, id).await?,
+                None => None,
+            };
+            steps.push((kind, domaine_id));
+        }
+
+        let results = self.inner.execute(user_id, operations).await?;
+
+        // Le lot est validé : ses écritures sont diffusées dans l'ordre
+        for result in &results {
+            match steps[result.index] {
+                (Operation::Delete, domaine_id) => self.publish_deleted(domaine_id, result.entity, result.id).await,
+                (operation, _) => self.publish_written(result.entity, operation, result.id, result.version).await,
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl<R: DomaineRepository + TrashRepository + 'static> TrashRepository for Publishing<R> {
+    async fn get_trash(&self, user_id: i32) -> Result<Vec<TrashItem>, Error> {
+        self.inner.get_trash(user_id).await
+    }
+
+    // Pour les abonnés, la ligne restaurée réapparaît comme une création
+    async fn restore(&self, entity: Entity, id: i32) -> Result<Restore, Error> {
+        let restore = self.inner.restore(entity, id).await?;
+        if let Restore::Restored(restored) = &restore {
+            for (entity, id) in restored.rows() {
+                self.publish_written(entity, Operation::Create, id, None).await;
+            }
+        }
+        Ok(restore)
+    }
+
+    async fn owner_including_trash(&self, entity: Entity, id: i32) -> Result<Option<i32>, Error> {
+        self.inner.owner_including_trash(entity, id).await
+    }
+
+    async fn purge(&self, before: DateTime<Utc>) -> Result<u64, Error> {
+        self.inner.purge(before).await
+    }
+
+    async fn subtree(&self, entity: Entity, id: i32) -> Result<Subtree, Error> {
+        self.inner.subtree(entity, id).await
+    }
+
+    // Une suppression par ligne de la sous-arborescence
+    async fn delete_cascade(&self, entity: Entity, id: i32, version: i32) -> Result<Option<Subtree>, RepoError> {
+        let domaine_id = self.inner.domaine_of(entity, id).await?;
+        let deleted = self.inner.delete_cascade(entity, id, version).await?;
+        if let Some(deleted) = &deleted {
+            for (entity, id) in deleted.rows() {
+                self.publish_deleted(domaine_id, entity, id).await;
+            }
+        }
+        Ok(deleted)
+    }
+}
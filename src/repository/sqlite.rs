@@ -0,0 +1,5 @@
+use sqlx::sqlite::{Sqlite, SqliteConnection, SqlitePool};
+
+// Implémentation SQLite, pour les installations sans serveur MySQL
+// SQLite ne connaît pas FOR UPDATE : ses transactions sont sérialisées, une écriture concurrente échoue
+portable_repository!(SqliteRepository, Sqlite, SqlitePool, SqliteConnection, SQLITE_MIGRATOR, "");
@@ -0,0 +1,2785 @@
+use actix_web::{web, Responder, HttpResponse, HttpRequest};
+use actix_web::middleware::DefaultHeaders;
+use actix_web::http::header;
+use actix_web_lab::sse;
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use serde::Deserialize;
+use serde::Serialize;
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::NaiveDate;
+use std::time::Duration;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::batch::{self, BatchError};
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::etag;
+use crate::events::{self, FarmEvent};
+use crate::exploitation::Exploitation;
+use crate::graphql::{self, FarmSchema};
+use crate::i18n::{self, Lang};
+use crate::metrics;
+use crate::production::Production;
+use crate::repository::{RepoError, Repositories};
+use crate::search::{self, SearchHit};
+use crate::seed::{self, SeedReport};
+use crate::sync::{self, ChangeSet};
+use crate::timestamps::{self, UpdatedSince};
+use crate::tombstone::Entity;
+use crate::trash::{Dependents, Restore, Subtree, TrashItem};
+use crate::tree::DomaineTree;
+use crate::type_element::TypeElement;
+use crate::type_exploitation::TypeExploitation;
+use crate::type_translation::{TranslationForm, TypeKind};
+use crate::type_user::TypeUser;
+use crate::user::{self, NewUser, User, UserChanges};
+use crate::webhook::{self, Webhook, WebhookDelivery};
+
+pub mod openapi;
+
+use openapi::ApiDoc;
+
+#[derive(Deserialize, ToSchema)]
+struct CreateTypeUser {
+    nom_type_user: String,
+}
+
+/// Message d'accueil
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "Supervision",
+    responses(
+        (status = 200, description = "Message d'accueil", body = String, content_type = "text/plain"),
+    )
+)]
+async fn hello_world() -> impl Responder {
+    "Bienvenue sur AquaFarm API"
+}
+
+/// Ajouter un type d'utilisateur
+#[utoipa::path(
+    post,
+    path = "/api/v1/types_user",
+    tag = "Références",
+    request_body = CreateTypeUser,
+    responses(
+        (status = 200, description = "Type créé", body = TypeUser),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_type_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateTypeUser>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+    let type_user = repos.references.create_type_user(form.nom_type_user.clone(), created_by).await;
+
+    match type_user {
+        Ok(type_user) => HttpResponse::Ok().json(type_user),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de l'ajout du type_user");
+            HttpResponse::InternalServerError().body("Erreur lors de l'ajout")
+        },
+    }
+}
+
+/// Lister les types d'utilisateur
+#[utoipa::path(
+    get,
+    path = "/api/v1/types_user",
+    tag = "Références",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Types d'utilisateur", body = [TypeUser]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_all_type_user(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+    lang: Lang,
+) -> impl Responder {
+    let types_user = match repos.references.get_types_user(query.updated_since).await {
+        Ok(types_user) => i18n::translate_types(&repos, lang, types_user).await,
+        Err(e) => Err(e),
+    };
+
+    match types_user {
+        Ok(types_user) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+            .json(types_user),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la récupération des types_user");
+            HttpResponse::InternalServerError().body("Erreur lors de la récupération des types")
+        },
+    }
+}
+
+/// Créer un utilisateur
+#[utoipa::path(
+    post,
+    path = "/api/v1/users",
+    tag = "Utilisateurs",
+    request_body = NewUser,
+    responses(
+        (status = 200, description = "Utilisateur créé", body = User),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<NewUser>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+    let user = repos.users.create(form.into_inner(), created_by).await;
+
+    match user {
+        Ok(user) => HttpResponse::Ok().json(user),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de l'ajout de l'utilisateur");
+            HttpResponse::InternalServerError().body("Erreur lors de l'ajout")
+        },
+    }
+}
+
+/// Lister les utilisateurs
+#[utoipa::path(
+    get,
+    path = "/api/v1/users",
+    tag = "Utilisateurs",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Utilisateurs", body = [User]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_users(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.users.get_all(query.updated_since).await {
+        Ok(users) => HttpResponse::Ok().json(users),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la récupération des utilisateurs");
+            HttpResponse::InternalServerError().body("Erreur lors de la récupération")
+        },
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct LoginUser {
+    email: String,
+    mot_de_passe: String,
+}
+
+// Réponse de /login : profil sans mot de passe et token JWT
+#[derive(Serialize, ToSchema)]
+struct LoginResponse {
+    user: LoginProfile,
+    token: String,
+}
+
+#[derive(Serialize, ToSchema)]
+struct LoginProfile {
+    id: i32,
+    nom: String,
+    prenom: String,
+    email: String,
+    numero_telephone: String,
+    type_user_id: i32,
+}
+
+/// Se connecter et obtenir un token JWT
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "Utilisateurs",
+    request_body = LoginUser,
+    responses(
+        (status = 200, description = "Utilisateur connecté et son token", body = LoginResponse),
+        (status = 401, description = "Email ou mot de passe incorrect", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn login_user(
+    repos: web::Data<Repositories>,
+    form: web::Json<LoginUser>,
+) -> impl Responder {
+    match user::authenticate(repos.users.as_ref(), form.email.clone(), form.mot_de_passe.clone()).await {
+        // Le token ne doit être ni mis en cache ni conservé par l'idempotence
+        Ok((user, token)) => HttpResponse::Ok()
+            .insert_header((header::CACHE_CONTROL, "no-store"))
+            .json(LoginResponse {
+                user: LoginProfile {
+                    id: user.id,
+                    nom: user.nom,
+                    prenom: user.prenom,
+                    email: user.email,
+                    numero_telephone: user.numero_telephone,
+                    type_user_id: user.type_user_id,
+                },
+                token,
+            }),
+        Err(_) => HttpResponse::Unauthorized().body("Email ou mot de passe incorrect"),
+    }
+}
+
+/// Modifier son propre compte
+#[utoipa::path(
+    put,
+    path = "/api/v1/users/{id}",
+    tag = "Utilisateurs",
+    params(("id" = i32, Path, description = "ID de l'utilisateur")),
+    request_body = UserChanges,
+    responses(
+        (status = 200, description = "Utilisateur mis à jour", body = String, content_type = "text/plain"),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn update_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Json<UserChanges>,
+) -> impl Responder {
+    let claims = match user::validate_token(&req) {
+        Ok(claims) => claims,
+        Err(e) => return e.into(),
+    };
+
+    let user_id = *id;
+
+    let user_email = repos.users.get_by_id(user_id).await.map(|user| user.email);
+
+    if let Ok(user_email) = user_email {
+        if claims.sub != user_email {
+            return HttpResponse::Unauthorized().body("Non autorisé");
+        }
+    } else {
+        return HttpResponse::Unauthorized().body("Utilisateur introuvable");
+    }
+
+    let form = form.into_inner();
+    let changes = UserChanges {
+        mot_de_passe: form.mot_de_passe.map(|mot_de_passe| hash(mot_de_passe, DEFAULT_COST).unwrap()),
+        ..form
+    };
+
+    match repos.users.update(user_id, changes).await {
+        Ok(_) => HttpResponse::Ok().body("Utilisateur mis à jour avec succès"),
+        Err(err) => {
+            tracing::error!(error = ?err, "Erreur SQL");
+            HttpResponse::InternalServerError().body("Erreur lors de la mise à jour")
+        }
+    }
+}
+
+/// Supprimer son propre compte
+#[utoipa::path(
+    delete,
+    path = "/api/v1/users/{id}",
+    tag = "Utilisateurs",
+    params(("id" = i32, Path, description = "ID de l'utilisateur")),
+    responses(
+        (status = 200, description = "Utilisateur supprimé", body = String, content_type = "text/plain"),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn delete_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    let claims = match user::validate_token(&req) {
+        Ok(claims) => claims,
+        Err(e) => return e.into(),
+    };
+
+    let user_id = *id;
+    if claims.sub != user_id.to_string() {
+        return HttpResponse::Unauthorized().body("Non autorisé");
+    }
+
+    match repos.users.delete(user_id).await {
+        Ok(_) => HttpResponse::Ok().body("Utilisateur supprimé avec succès"),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression"),
+    }
+}
+
+/// Lire un utilisateur
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{id}",
+    tag = "Utilisateurs",
+    params(("id" = i32, Path, description = "ID de l'utilisateur")),
+    responses(
+        (status = 200, description = "Utilisateur", body = User),
+        (status = 304, description = "Inchangé depuis If-Modified-Since"),
+        (status = 404, description = "Utilisateur introuvable", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_user_by_id(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    match repos.users.get_by_id(id.into_inner()).await {
+        Ok(user) => {
+            if timestamps::not_modified_since(&req, user.updated_at) {
+                return HttpResponse::NotModified().finish();
+            }
+            HttpResponse::Ok()
+                .insert_header((header::LAST_MODIFIED, timestamps::last_modified(user.updated_at)))
+                .json(user)
+        },
+        Err(_) => HttpResponse::NotFound().body("Utilisateur introuvable"),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateDomaine {
+    pub user_id: i32,
+    pub nom_domaine: String,
+}
+
+// Ajouter un domaine
+/// Ajouter un domaine pour un utilisateur
+#[utoipa::path(
+    post,
+    path = "/api/v1/domaines",
+    tag = "Domaines",
+    request_body = CreateDomaine,
+    responses(
+        (status = 200, description = "Domaine créé", body = Domaine),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_domaine(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateDomaine>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    match repos.domaines.create(form.user_id, form.nom_domaine.clone(), created_by).await {
+        Ok(domaine) => HttpResponse::Ok().json(domaine),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création du domaine"),
+    }
+}
+
+
+// Récupérer tous les domaines
+/// Lister tous les domaines
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines",
+    tag = "Domaines",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Domaines", body = [Domaine]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_domaines(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.domaines.get_all(query.updated_since).await {
+        Ok(domaines) => HttpResponse::Ok().json(domaines),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des domaines"),
+    }
+}
+
+// Récupérer un domaine par ID
+/// Lire un domaine
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{id}",
+    tag = "Domaines",
+    params(("id" = i32, Path, description = "ID du domaine")),
+    responses(
+        (status = 200, description = "Domaine", body = Domaine, headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 304, description = "Inchangé depuis If-Modified-Since"),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_domaine(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    match repos.domaines.get_by_id(*id).await {
+        Ok(domaine) => {
+            if timestamps::not_modified_since(&req, domaine.updated_at) {
+                return HttpResponse::NotModified()
+                    .insert_header((header::ETAG, etag::etag(domaine.version)))
+                    .finish();
+            }
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag::etag(domaine.version)))
+                .insert_header((header::LAST_MODIFIED, timestamps::last_modified(domaine.updated_at)))
+                .json(domaine)
+        },
+        Err(_) => HttpResponse::NotFound().body("Domaine introuvable"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UpdateDomaine {
+    nom_domaine: Option<String>,
+}
+
+// Mettre à jour un domaine
+/// Renommer un domaine
+#[utoipa::path(
+    put,
+    path = "/api/v1/domaines/{id}",
+    tag = "Domaines",
+    params(("id" = i32, Path, description = "ID du domaine"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés")),
+    request_body = UpdateDomaine,
+    responses(
+        (status = 200, description = "Domaine mis à jour", headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn update_domaine(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Json<UpdateDomaine>,
+) -> impl Responder {
+    let current = async { repos.domaines.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Domaine introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+
+    match repos.domaines.update(*id, version, form.nom_domaine.clone()).await {
+        Ok(true) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag::etag(version + 1)))
+            .body("Domaine mis à jour avec succès"),
+        Ok(false) => match repos.domaines.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Domaine introuvable"),
+        },
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la mise à jour du domaine"),
+    }
+}
+
+// Supprimer un domaine
+/// Supprimer un domaine
+#[utoipa::path(
+    delete,
+    path = "/api/v1/domaines/{id}",
+    tag = "Domaines",
+    params(("id" = i32, Path, description = "ID du domaine"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés"), DeleteOptions),
+    responses(
+        (status = 200, description = "Domaine mis à la corbeille ; avec cascade=true, le détail des lignes supprimées", content(("text/plain" = String), ("application/json" = Subtree))),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 409, description = "Descendants en place, sans cascade=true", body = DeleteConflict),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn delete_domaine(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    options: web::Query<DeleteOptions>,
+    lang: Lang,
+) -> impl Responder {
+    let current = async { repos.domaines.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Domaine introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+    if let Some(response) =
+        delete_with_dependents(&repos, Entity::Domaine, *id, version, options.cascade, lang).await
+    {
+        return response;
+    }
+
+    match repos.domaines.delete(*id, version).await {
+        Ok(true) => HttpResponse::Ok().body("Domaine supprimé avec succès"),
+        Ok(false) => match repos.domaines.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Domaine introuvable"),
+        },
+        Err(RepoError::Conflict(dependents)) => dependents_conflict(dependents, lang),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression du domaine"),
+    }
+}
+
+// Récupérer tous les domaines par user_id
+/// Lister les domaines d'un utilisateur
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/{user_id}/domaines",
+    tag = "Domaines",
+    params(("user_id" = i32, Path, description = "ID du propriétaire"), UpdatedSince),
+    responses(
+        (status = 200, description = "Domaines", body = [Domaine]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_domaines_by_user_id(
+    repos: web::Data<Repositories>,
+    user_id: web::Path<i32>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.domaines.get_all_by_user_id(*user_id, query.updated_since).await {
+        Ok(domaines) => HttpResponse::Ok().json(domaines),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des domaines"),
+    }
+}
+
+
+#[derive(Deserialize, ToSchema)]
+struct CreateTypeExploitation {
+    nom_type_exploitation: String,
+}
+
+/// Ajouter un type d'exploitation
+#[utoipa::path(
+    post,
+    path = "/api/v1/types_exploitation",
+    tag = "Références",
+    request_body = CreateTypeExploitation,
+    responses(
+        (status = 200, description = "Type créé", body = TypeExploitation),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_type_exploitation(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateTypeExploitation>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    match repos.references.create_type_exploitation(form.nom_type_exploitation.clone(), created_by).await {
+        Ok(type_exploitation) => HttpResponse::Ok().json(type_exploitation),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de l'ajout"),
+    }
+}
+
+/// Lister les types d'exploitation
+#[utoipa::path(
+    get,
+    path = "/api/v1/types_exploitation",
+    tag = "Références",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Types d'exploitation", body = [TypeExploitation]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_all_types_exploitation(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+    lang: Lang,
+) -> impl Responder {
+    let types_exploitation = match repos.references.get_types_exploitation(query.updated_since).await {
+        Ok(types_exploitation) => i18n::translate_types(&repos, lang, types_exploitation).await,
+        Err(e) => Err(e),
+    };
+
+    match types_exploitation {
+        Ok(types_exploitation) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+            .json(types_exploitation),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération"),
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateExploitationRequest {
+    pub type_exploitation_id: i32,
+    pub domaine_id: i32,
+    pub nom_exploitation: String,
+}
+
+// Ajouter une exploitation
+/// Ajouter une exploitation (route historique)
+///
+/// Dépréciée : préférer POST /api/v1/domaines/{domaine_id}/exploitations.
+#[utoipa::path(
+    post,
+    path = "/exploitations",
+    tag = "Exploitations",
+    request_body = CreateExploitationRequest,
+    responses(
+        (status = 200, description = "Exploitation créée", body = Exploitation),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_exploitation(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateExploitationRequest>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    match repos.exploitations.create(
+        form.type_exploitation_id,
+        form.domaine_id,
+        form.nom_exploitation.clone(),
+        created_by,
+    )
+    .await
+    {
+        Ok(exploitation) => HttpResponse::Ok().json(exploitation),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création de l'exploitation"),
+    }
+}
+
+// Récupérer toutes les exploitations
+/// Lister les exploitations
+#[utoipa::path(
+    get,
+    path = "/api/v1/exploitations",
+    tag = "Exploitations",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Exploitations", body = [Exploitation]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_all_exploitations(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.exploitations.get_all(query.updated_since).await {
+        Ok(exploitations) => HttpResponse::Ok().json(exploitations),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des exploitations"),
+    }
+}
+
+// Récupérer une exploitation par ID
+/// Lire une exploitation
+#[utoipa::path(
+    get,
+    path = "/api/v1/exploitations/{id}",
+    tag = "Exploitations",
+    params(("id" = i32, Path, description = "ID de l'exploitation")),
+    responses(
+        (status = 200, description = "Exploitation", body = Exploitation, headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 304, description = "Inchangé depuis If-Modified-Since"),
+        (status = 404, description = "Exploitation introuvable", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_exploitation(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    match repos.exploitations.get_by_id(*id).await {
+        Ok(exploitation) => {
+            if timestamps::not_modified_since(&req, exploitation.updated_at) {
+                return HttpResponse::NotModified()
+                    .insert_header((header::ETAG, etag::etag(exploitation.version)))
+                    .finish();
+            }
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag::etag(exploitation.version)))
+                .insert_header((header::LAST_MODIFIED, timestamps::last_modified(exploitation.updated_at)))
+                .json(exploitation)
+        },
+        Err(_) => HttpResponse::NotFound().body("Exploitation introuvable"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UpdateExploitation {
+    type_exploitation_id: Option<i32>,
+    nom_exploitation: Option<String>,
+}
+
+// Mettre à jour une exploitation
+/// Modifier une exploitation
+#[utoipa::path(
+    put,
+    path = "/api/v1/exploitations/{id}",
+    tag = "Exploitations",
+    params(("id" = i32, Path, description = "ID de l'exploitation"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés")),
+    request_body = UpdateExploitation,
+    responses(
+        (status = 200, description = "Exploitation mise à jour", headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 404, description = "Exploitation introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn update_exploitation(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Json<UpdateExploitation>,
+) -> impl Responder {
+    let current = async { repos.exploitations.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Exploitation introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+
+    match repos.exploitations.update(
+        *id,
+        version,
+        form.type_exploitation_id,
+        form.nom_exploitation.clone(),
+    )
+    .await
+    {
+        Ok(true) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag::etag(version + 1)))
+            .body("Exploitation mise à jour avec succès"),
+        Ok(false) => match repos.exploitations.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Exploitation introuvable"),
+        },
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la mise à jour de l'exploitation"),
+    }
+}
+
+// Supprimer une exploitation par ID
+/// Supprimer une exploitation
+#[utoipa::path(
+    delete,
+    path = "/api/v1/exploitations/{id}",
+    tag = "Exploitations",
+    params(("id" = i32, Path, description = "ID de l'exploitation"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés"), DeleteOptions),
+    responses(
+        (status = 200, description = "Exploitation mise à la corbeille ; avec cascade=true, le détail des lignes supprimées", content(("text/plain" = String), ("application/json" = Subtree))),
+        (status = 404, description = "Exploitation introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 409, description = "Descendants en place, sans cascade=true", body = DeleteConflict),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn delete_exploitation(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    options: web::Query<DeleteOptions>,
+    lang: Lang,
+) -> impl Responder {
+    let current = async { repos.exploitations.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Exploitation introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+    if let Some(response) =
+        delete_with_dependents(&repos, Entity::Exploitation, *id, version, options.cascade, lang).await
+    {
+        return response;
+    }
+
+    match repos.exploitations.delete(*id, version).await {
+        Ok(true) => HttpResponse::Ok().body("Exploitation supprimée avec succès"),
+        Ok(false) => match repos.exploitations.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Exploitation introuvable"),
+        },
+        Err(RepoError::Conflict(dependents)) => dependents_conflict(dependents, lang),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression de l'exploitation"),
+    }
+}
+
+// Récupérer toutes les exploitations d'un domaine
+/// Lister les exploitations d'un domaine (route historique)
+///
+/// Dépréciée : préférer GET /api/v1/domaines/{domaine_id}/exploitations.
+#[utoipa::path(
+    get,
+    path = "/exploitations/domaine/{domaine_id}",
+    tag = "Exploitations",
+    params(("domaine_id" = i32, Path, description = "ID du domaine"), UpdatedSince),
+    responses(
+        (status = 200, description = "Exploitations du domaine", body = [Exploitation]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    )
+)]
+async fn get_exploitations_by_domaine(
+    repos: web::Data<Repositories>,
+    domaine_id: web::Path<i32>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.exploitations.get_by_domaine_id(*domaine_id, query.updated_since).await {
+        Ok(exploitations) => HttpResponse::Ok().json(exploitations),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des exploitations"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct CreateTypeElement {
+    nom_type_element: String,
+}
+
+// Ajouter un type d'élément
+/// Ajouter un type d'élément
+#[utoipa::path(
+    post,
+    path = "/api/v1/types_element",
+    tag = "Références",
+    request_body = CreateTypeElement,
+    responses(
+        (status = 200, description = "Type créé", body = TypeElement),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_type_element(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateTypeElement>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    match repos.references.create_type_element(form.nom_type_element.clone(), created_by).await {
+        Ok(type_element) => HttpResponse::Ok().json(type_element),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création du type d'élément"),
+    }
+}
+
+// Récupérer tous les types d'éléments
+/// Lister les types d'élément
+#[utoipa::path(
+    get,
+    path = "/api/v1/types_element",
+    tag = "Références",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Types d'élément", body = [TypeElement]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_all_type_elements(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+    lang: Lang,
+) -> impl Responder {
+    let type_elements = match repos.references.get_types_element(query.updated_since).await {
+        Ok(type_elements) => i18n::translate_types(&repos, lang, type_elements).await,
+        Err(e) => Err(e),
+    };
+
+    match type_elements {
+        Ok(type_elements) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+            .json(type_elements),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des types d'éléments"),
+    }
+}
+
+// Supprimer un type d'élément
+/// Supprimer un type d'élément
+#[utoipa::path(
+    delete,
+    path = "/api/v1/types_element/{id}",
+    tag = "Références",
+    params(("id" = i32, Path, description = "ID du type d'élément")),
+    responses(
+        (status = 200, description = "Type d'élément supprimé", body = String, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn delete_type_element(
+    repos: web::Data<Repositories>,
+    id: web::Path<i32>,
+) -> impl Responder {
+    match repos.references.delete_type_element(*id).await {
+        Ok(_) => HttpResponse::Ok().body("Type d'élément supprimé avec succès"),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression"),
+    }
+}
+
+// Mettre à jour un type d'élément
+/// Renommer un type d'élément
+#[utoipa::path(
+    put,
+    path = "/api/v1/types_element/{id}",
+    tag = "Références",
+    params(("id" = i32, Path, description = "ID du type d'élément")),
+    request_body = CreateTypeElement,
+    responses(
+        (status = 200, description = "Type d'élément mis à jour", body = String, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn update_type_element(
+    repos: web::Data<Repositories>,
+    id: web::Path<i32>,
+    form: web::Json<CreateTypeElement>,
+) -> impl Responder {
+    match repos.references.update_type_element(*id, form.nom_type_element.clone()).await {
+        Ok(_) => HttpResponse::Ok().body("Type d'élément mis à jour avec succès"),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la mise à jour"),
+    }
+}
+
+// Ajouter ou remplacer le nom d'un type de référence dans une autre langue que le français
+/// Traduire le nom d'un type
+#[utoipa::path(
+    put,
+    path = "/api/v1/{kind}/{id}/translations/{lang}",
+    tag = "Références",
+    params(
+        ("kind" = TypeKind, Path, description = "Famille du type : types_user, types_exploitation ou types_element"),
+        ("id" = i32, Path, description = "ID du type"),
+        ("lang" = Lang, Path, description = "Langue de la traduction"),
+    ),
+    request_body = TranslationForm,
+    responses(
+        (status = 200, description = "Traduction enregistrée", body = String, content_type = "text/plain"),
+        (status = 400, description = "Le nom français est celui du type", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Type introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn put_type_translation(
+    repos: web::Data<Repositories>,
+    path: web::Path<(TypeKind, i32, Lang)>,
+    form: web::Json<TranslationForm>,
+) -> impl Responder {
+    let (kind, id, lang) = path.into_inner();
+    if lang == Lang::Fr {
+        return HttpResponse::BadRequest().body("Le nom français est celui du type lui-même");
+    }
+
+    match repos.references.set_type_translation(kind, id, lang.code(), form.into_inner().nom).await {
+        Ok(_) => HttpResponse::Ok().body("Traduction enregistrée avec succès"),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().body("Type introuvable"),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de l'enregistrement de la traduction");
+            HttpResponse::InternalServerError().body("Erreur lors de l'enregistrement de la traduction")
+        },
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct CreateElement {
+    exploitation_id: i32,
+    nom_element: String,
+    quantite: i32,
+}
+
+// Ajouter un nouvel élément
+/// Ajouter un élément (route historique)
+///
+/// Dépréciée : préférer POST /api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements.
+#[utoipa::path(
+    post,
+    path = "/elements",
+    tag = "Éléments",
+    request_body = CreateElement,
+    responses(
+        (status = 200, description = "Élément créé", body = Element),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_element(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateElement>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    match repos.elements.create(
+        form.exploitation_id,
+        form.nom_element.clone(),
+        form.quantite,
+        created_by,
+    )
+    .await
+    {
+        Ok(element) => HttpResponse::Ok().json(element),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création de l'élément"),
+    }
+}
+
+// Récupérer tous les éléments
+/// Lister les éléments
+#[utoipa::path(
+    get,
+    path = "/api/v1/elements",
+    tag = "Éléments",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Éléments", body = [Element]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_all_elements(
+    repos: web::Data<Repositories>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.elements.get_all(query.updated_since).await {
+        Ok(elements) => HttpResponse::Ok().json(elements),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des éléments"),
+    }
+}
+
+// Récupérer les éléments d'une exploitation spécifique
+/// Lister les éléments d'une exploitation (route historique)
+///
+/// Dépréciée : préférer GET /api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements.
+#[utoipa::path(
+    get,
+    path = "/elements/exploitation/{exploitation_id}",
+    tag = "Éléments",
+    params(("exploitation_id" = i32, Path, description = "ID de l'exploitation"), UpdatedSince),
+    responses(
+        (status = 200, description = "Éléments de l'exploitation", body = [Element]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    )
+)]
+async fn get_elements_by_exploitation(
+    repos: web::Data<Repositories>,
+    exploitation_id: web::Path<i32>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.elements.get_by_exploitation_id(*exploitation_id, query.updated_since).await {
+        Ok(elements) => HttpResponse::Ok().json(elements),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des éléments"),
+    }
+}
+
+// Récupérer un élément par ID
+/// Lire un élément
+#[utoipa::path(
+    get,
+    path = "/api/v1/elements/{id}",
+    tag = "Éléments",
+    params(("id" = i32, Path, description = "ID de l'élément")),
+    responses(
+        (status = 200, description = "Élément", body = Element, headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 304, description = "Inchangé depuis If-Modified-Since"),
+        (status = 404, description = "Élément introuvable", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_element(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    match repos.elements.get_by_id(*id).await {
+        Ok(element) => {
+            if timestamps::not_modified_since(&req, element.updated_at) {
+                return HttpResponse::NotModified()
+                    .insert_header((header::ETAG, etag::etag(element.version)))
+                    .finish();
+            }
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag::etag(element.version)))
+                .insert_header((header::LAST_MODIFIED, timestamps::last_modified(element.updated_at)))
+                .json(element)
+        },
+        Err(_) => HttpResponse::NotFound().body("Élément introuvable"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UpdateElement {
+    nom_element: Option<String>,
+    quantite: Option<i32>,
+}
+
+// Mettre à jour un élément
+/// Modifier un élément
+#[utoipa::path(
+    put,
+    path = "/api/v1/elements/{id}",
+    tag = "Éléments",
+    params(("id" = i32, Path, description = "ID de l'élément"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés")),
+    request_body = UpdateElement,
+    responses(
+        (status = 200, description = "Élément mis à jour", headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 404, description = "Élément introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn update_element(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Json<UpdateElement>,
+) -> impl Responder {
+    let current = async { repos.elements.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Élément introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+
+    match repos.elements.update(*id, version, form.nom_element.clone(), form.quantite).await {
+        Ok(true) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag::etag(version + 1)))
+            .body("Élément mis à jour avec succès"),
+        Ok(false) => match repos.elements.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Élément introuvable"),
+        },
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la mise à jour de l'élément"),
+    }
+}
+
+// Supprimer un élément
+/// Supprimer un élément
+#[utoipa::path(
+    delete,
+    path = "/api/v1/elements/{id}",
+    tag = "Éléments",
+    params(("id" = i32, Path, description = "ID de l'élément"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés"), DeleteOptions),
+    responses(
+        (status = 200, description = "Élément mis à la corbeille ; avec cascade=true, le détail des lignes supprimées", content(("text/plain" = String), ("application/json" = Subtree))),
+        (status = 404, description = "Élément introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 409, description = "Descendants en place, sans cascade=true", body = DeleteConflict),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn delete_element(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    options: web::Query<DeleteOptions>,
+    lang: Lang,
+) -> impl Responder {
+    let current = async { repos.elements.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Élément introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+    if let Some(response) =
+        delete_with_dependents(&repos, Entity::Element, *id, version, options.cascade, lang).await
+    {
+        return response;
+    }
+
+    match repos.elements.delete(*id, version).await {
+        Ok(true) => HttpResponse::Ok().body("Élément supprimé avec succès"),
+        Ok(false) => match repos.elements.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Élément introuvable"),
+        },
+        Err(RepoError::Conflict(dependents)) => dependents_conflict(dependents, lang),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression de l'élément"),
+    }
+}
+
+// Récupérer les productions d'un élément
+/// Lister les productions d'un élément (route historique)
+///
+/// Dépréciée : préférer GET /api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements/{element_id}/productions.
+#[utoipa::path(
+    get,
+    path = "/productions/element/{element_id}",
+    tag = "Productions",
+    params(("element_id" = i32, Path, description = "ID de l'élément"), UpdatedSince),
+    responses(
+        (status = 200, description = "Productions de l'élément", body = [Production]),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    )
+)]
+async fn get_productions_by_element_id(
+    repos: web::Data<Repositories>,
+    element_id: web::Path<i32>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    match repos.productions.get_by_element_id(*element_id, query.updated_since).await {
+        Ok(productions) => HttpResponse::Ok().json(productions),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des productions"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct CreateProduction {
+    element_id: i32,
+    quantite_produite: i32,
+    unite_production: String,
+    date_de_production: NaiveDate,
+}
+
+// Ajouter une production
+/// Ajouter une production (route historique)
+///
+/// Dépréciée : préférer POST /api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements/{element_id}/productions.
+#[utoipa::path(
+    post,
+    path = "/productions",
+    tag = "Productions",
+    request_body = CreateProduction,
+    responses(
+        (status = 200, description = "Production créée", body = Production),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_production(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateProduction>,
+) -> impl Responder {
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    match repos.productions.create(
+        form.element_id,
+        form.quantite_produite,
+        form.unite_production.clone(),
+        form.date_de_production,
+        created_by,
+    )
+    .await
+    {
+        Ok(production) => HttpResponse::Ok().json(production),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création de la production"),
+    }
+}
+
+// Récupérer une production par ID
+/// Lire une production
+#[utoipa::path(
+    get,
+    path = "/api/v1/productions/{id}",
+    tag = "Productions",
+    params(("id" = i32, Path, description = "ID de la production")),
+    responses(
+        (status = 200, description = "Production", body = Production, headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 304, description = "Inchangé depuis If-Modified-Since"),
+        (status = 404, description = "Production introuvable", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_production(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    match repos.productions.get_by_id(*id).await {
+        Ok(production) => {
+            if timestamps::not_modified_since(&req, production.updated_at) {
+                return HttpResponse::NotModified()
+                    .insert_header((header::ETAG, etag::etag(production.version)))
+                    .finish();
+            }
+            HttpResponse::Ok()
+                .insert_header((header::ETAG, etag::etag(production.version)))
+                .insert_header((header::LAST_MODIFIED, timestamps::last_modified(production.updated_at)))
+                .json(production)
+        },
+        Err(_) => HttpResponse::NotFound().body("Production introuvable"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct UpdateProduction {
+    quantite_produite: Option<i32>,
+    unite_production: Option<String>,
+    date_de_production: Option<NaiveDate>,
+}
+
+// Mettre à jour une production
+/// Modifier une production
+#[utoipa::path(
+    put,
+    path = "/api/v1/productions/{id}",
+    tag = "Productions",
+    params(("id" = i32, Path, description = "ID de la production"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés")),
+    request_body = UpdateProduction,
+    responses(
+        (status = 200, description = "Production mise à jour", headers(("ETag" = String, description = "Version de la ligne"))),
+        (status = 404, description = "Production introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn update_production(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+    form: web::Json<UpdateProduction>,
+) -> impl Responder {
+    let current = async { repos.productions.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Production introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+
+    match repos.productions.update(
+        *id,
+        version,
+        form.quantite_produite,
+        form.unite_production.clone(),
+        form.date_de_production,
+    )
+    .await
+    {
+        Ok(true) => HttpResponse::Ok()
+            .insert_header((header::ETAG, etag::etag(version + 1)))
+            .body("Production mise à jour avec succès"),
+        Ok(false) => match repos.productions.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Production introuvable"),
+        },
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la mise à jour de la production"),
+    }
+}
+
+// Supprimer une production
+/// Supprimer une production
+#[utoipa::path(
+    delete,
+    path = "/api/v1/productions/{id}",
+    tag = "Productions",
+    params(("id" = i32, Path, description = "ID de la production"), ("If-Match" = String, Header, description = "Version attendue, lue dans l'ETag ; « * » ou plusieurs ETags séparés par des virgules sont acceptés")),
+    responses(
+        (status = 200, description = "Production mise à la corbeille", body = String, content_type = "text/plain"),
+        (status = 404, description = "Production introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 412, description = "Version périmée ou If-Match invalide ; l'en-tête ETag porte la version courante", body = MessageErreur, content_type = "text/plain"),
+        (status = 428, description = "En-tête If-Match absent", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn delete_production(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    let current = async { repos.productions.get_by_id(*id).await.map(|current| current.version) };
+    let version = match etag::expected_version(&req, current, "Production introuvable").await {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+
+    match repos.productions.delete(*id, version).await {
+        Ok(true) => HttpResponse::Ok().body("Production supprimée avec succès"),
+        Ok(false) => match repos.productions.get_by_id(*id).await {
+            Ok(current) => etag::precondition_failed(current.version),
+            Err(_) => HttpResponse::NotFound().body("Production introuvable"),
+        },
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression de la production"),
+    }
+}
+
+/// Lister les domaines de l'utilisateur connecté
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/domaines",
+    tag = "Domaines",
+    params(UpdatedSince),
+    responses(
+        (status = 200, description = "Domaines", body = [Domaine]),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn get_domaines_for_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    // Récupérer les claims du token
+    let claims = match user::validate_token(&req) {
+        Ok(claims) => claims,
+        Err(e) => return e.into(), // Retourne une erreur 401 si le token est invalide
+    };
+
+    // Récupérer l'utilisateur connecté à partir des claims
+    let user_email = claims.sub;
+
+    // Optionnel : Trouver l'user_id à partir de l'email (si user_id n'est pas dans le JWT)
+    let user_id_result = repos.users.get_by_email(&user_email).await.map(|user| user.id);
+
+    let user_id = match user_id_result {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::InternalServerError().body("Erreur : utilisateur non trouvé"),
+    };
+
+    // Récupérer les domaines pour cet utilisateur
+    let domaines = repos.domaines.get_all_by_user_id(user_id, query.updated_since).await;
+
+    match domaines {
+        Ok(domaines) => HttpResponse::Ok().json(domaines),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des domaines"),
+    }
+}
+
+/// Lire l'utilisateur connecté
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me",
+    tag = "Utilisateurs",
+    responses(
+        (status = 200, description = "Utilisateur connecté", body = User),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Utilisateur introuvable", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn get_connected_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+) -> impl Responder {
+    // Valider le token JWT pour récupérer les claims
+    let claims = match user::validate_token(&req) {
+        Ok(claims) => claims,
+        Err(e) => return e.into(), // Retourne une erreur 401 si le token est invalide
+    };
+
+    // Extraire l'email (ou autre identifiant) des claims
+    let user_email = claims.sub;
+
+    // Récupérer les informations de l'utilisateur à partir de l'email
+    let user = repos.users.get_by_email(&user_email).await;
+
+    match user {
+        Ok(user) => HttpResponse::Ok().json(user), // Retourne les informations de l'utilisateur
+        Err(_) => HttpResponse::NotFound().body("Utilisateur non trouvé"), // Retourne une erreur si l'utilisateur n'est pas trouvé
+    }
+}
+
+/// Ajouter un domaine à l'utilisateur connecté
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/domaines",
+    tag = "Domaines",
+    request_body = CreateDomaine,
+    responses(
+        (status = 200, description = "Domaine créé", body = Domaine),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn add_domaine_for_user(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<CreateDomaine>,
+) -> impl Responder {
+    // Récupérer les claims à partir du token JWT
+    let claims = match user::validate_token(&req) {
+        Ok(claims) => claims,
+        Err(e) => return e.into(), // Retourne une erreur 401 si le token est invalide
+    };
+
+    // Extraire l'email de l'utilisateur connecté depuis les claims
+    let user_email = claims.sub;
+
+    // Trouver l'ID de l'utilisateur à partir de l'email
+    let user_id_result = repos.users.get_by_email(&user_email).await.map(|user| user.id);
+
+    let user_id = match user_id_result {
+        Ok(id) => id,
+        Err(_) => return HttpResponse::InternalServerError().body("Erreur : utilisateur non trouvé"),
+    };
+
+    // Utiliser la méthode `create` pour insérer le domaine
+    match repos.domaines.create(user_id, form.nom_domaine.clone(), Some(user_id)).await {
+        Ok(domaine) => HttpResponse::Ok().json(domaine), // Retourne le domaine ajouté
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création du domaine"),
+    }
+}
+
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SyncQuery {
+    /// Jeton `sync_token` de la réponse précédente ; absent, l'état complet est renvoyé
+    since: Option<String>,
+}
+
+// Récupérer les changements des domaines de l'utilisateur connecté depuis un jeton
+/// Changements depuis un jeton de synchronisation
+#[utoipa::path(
+    get,
+    path = "/api/v1/sync",
+    tag = "Synchronisation",
+    params(SyncQuery),
+    responses(
+        (status = 200, description = "Changements et nouveau jeton", body = ChangeSet),
+        (status = 400, description = "Jeton de synchronisation invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn get_sync_changes(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    query: web::Query<SyncQuery>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+
+    // Sans jeton, le client reçoit l'état complet de ses domaines
+    let since = match query.since.as_deref() {
+        Some(token) => match sync::decode_token(token) {
+            Some(since) => since,
+            None => return HttpResponse::BadRequest().body("Jeton de synchronisation invalide"),
+        },
+        None => chrono::DateTime::UNIX_EPOCH,
+    };
+
+    match repos.sync.changes_since(user_id, since).await {
+        Ok(changes) => HttpResponse::Ok().json(changes),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la synchronisation");
+            HttpResponse::InternalServerError().body("Erreur lors de la synchronisation")
+        },
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct SyncPush {
+    mutations: Vec<sync::Mutation>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct MutationResults {
+    results: Vec<sync::MutationResult>,
+}
+
+// Appliquer les modifications effectuées hors ligne par l'utilisateur connecté
+/// Appliquer des modifications faites hors ligne
+#[utoipa::path(
+    post,
+    path = "/api/v1/sync",
+    tag = "Synchronisation",
+    request_body = SyncPush,
+    responses(
+        (status = 200, description = "Résultat de chaque modification, dans l'ordre", body = MutationResults),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn push_sync_mutations(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    lang: Lang,
+    form: web::Json<SyncPush>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+
+    let mut results = sync::apply_mutations(&repos, user_id, form.into_inner().mutations).await;
+    for result in results.iter_mut() {
+        result.message = result.message.take().map(|message| i18n::localize(lang, message));
+    }
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+        .json(MutationResults { results })
+}
+
+#[derive(Deserialize, ToSchema)]
+struct BatchRequest {
+    operations: Vec<batch::BatchOperation>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct BatchResults {
+    results: Vec<batch::OperationResult>,
+}
+
+// Exécuter une liste ordonnée d'opérations dans une seule transaction
+/// Exécuter un lot d'opérations en une transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/batch",
+    tag = "Synchronisation",
+    request_body = BatchRequest,
+    responses(
+        (status = 200, description = "Résultat de chaque opération", body = BatchResults),
+        (status = 400, description = "Lot invalide", body = BatchError),
+        (status = 403, description = "Ligne d'un autre utilisateur", body = BatchError),
+        (status = 404, description = "Ligne introuvable", body = BatchError),
+        (status = 409, description = "Suppression d'une ligne dont d'autres dépendent", body = BatchError),
+        (status = 412, description = "Version périmée", body = BatchError),
+        (status = 500, description = "Erreur interne", body = BatchError),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn run_batch(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    lang: Lang,
+    form: web::Json<BatchRequest>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+
+    match repos.batch.execute(user_id, form.into_inner().operations).await {
+        Ok(results) => HttpResponse::Ok().json(BatchResults { results }),
+        Err(mut error) => {
+            error.message = i18n::localize(lang, error.message);
+            let mut response = match error.kind {
+                batch::BatchErrorKind::Invalid => HttpResponse::BadRequest(),
+                batch::BatchErrorKind::Forbidden => HttpResponse::Forbidden(),
+                batch::BatchErrorKind::NotFound => HttpResponse::NotFound(),
+                batch::BatchErrorKind::Conflict => HttpResponse::PreconditionFailed(),
+                batch::BatchErrorKind::Dependents => HttpResponse::Conflict(),
+                batch::BatchErrorKind::Database => HttpResponse::InternalServerError(),
+            };
+            response.insert_header((header::CONTENT_LANGUAGE, lang.code())).json(error)
+        },
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct SeedRequest {
+    // Propriétaire des fermes générées, l'administrateur connecté par défaut
+    user_id: Option<i32>,
+    #[serde(flatten)]
+    options: seed::SeedOptions,
+}
+
+// Générer des données de démonstration (réservé aux administrateurs)
+/// Générer des données de démonstration
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/seed",
+    tag = "Administration",
+    request_body = SeedRequest,
+    responses(
+        (status = 200, description = "Lignes créées", body = SeedReport),
+        (status = 403, description = "Réservé aux administrateurs", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    ),
+    security(("bearer" = []))
+)]
+async fn seed_demo(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    form: web::Json<SeedRequest>,
+) -> impl Responder {
+    let admin_id = match user::connected_admin_id(repos.users.as_ref(), repos.references.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Forbidden().body("Réservé aux administrateurs"),
+    };
+
+    let form = form.into_inner();
+    match seed::demo(&repos, form.user_id.unwrap_or(admin_id), &form.options).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la génération des données de démonstration");
+            HttpResponse::InternalServerError().body("Erreur lors de la génération des données")
+        },
+    }
+}
+
+// Récupérer un domaine de l'utilisateur connecté et toute sa hiérarchie en une réponse
+/// Arbre complet d'un domaine
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{id}/tree",
+    tag = "Domaines",
+    params(("id" = i32, Path, description = "ID du domaine")),
+    responses(
+        (status = 200, description = "Domaine, exploitations avec leur type, éléments et dernière production", body = DomaineTree),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn get_domaine_tree(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+    if let Err(response) = check_domaine_owner(&repos, user_id, *id).await {
+        return response;
+    }
+
+    match repos.domaines.tree(*id).await {
+        Ok(tree) => HttpResponse::Ok().json(tree),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().body("Domaine introuvable"),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture de l'arbre du domaine");
+            HttpResponse::InternalServerError().body("Erreur lors de la récupération du domaine")
+        },
+    }
+}
+
+// Suivre en direct les écritures sur un domaine de l'utilisateur connecté (Server-Sent Events)
+/// Flux des modifications d'un domaine
+///
+/// Flux `text/event-stream`. Chaque création, modification ou suppression d'exploitation,
+/// d'élément ou de production du domaine produit un événement `change` dont les données sont un
+/// `FarmEvent` en JSON. Un événement `resync` signale des événements perdus : le domaine doit être relu.
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{id}/events",
+    tag = "Domaines",
+    params(("id" = i32, Path, description = "ID du domaine")),
+    responses(
+        (status = 200, description = "Flux d'événements", body = FarmEvent, content_type = "text/event-stream"),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn stream_domaine_events(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    id: web::Path<i32>,
+) -> HttpResponse {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+
+    // Abonnement pris avant la vérification, pour ne perdre aucune écriture qui la suivrait
+    let receiver = repos.events.subscribe();
+
+    if let Err(response) = check_domaine_owner(&repos, user_id, *id).await {
+        return response;
+    }
+
+    sse::Sse::from_stream(events::domaine_stream(receiver, *id))
+        .with_keep_alive(SSE_KEEP_ALIVE)
+        .respond_to(&req)
+        .map_into_boxed_body()
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SearchQuery {
+    /// Texte recherché, sans tenir compte des accents ni de la casse
+    q: String,
+    /// Nombre maximal de résultats (20 par défaut, 100 au plus)
+    limit: Option<usize>,
+}
+
+// Rechercher par nom dans les domaines de l'utilisateur connecté et dans les types
+/// Rechercher par nom
+///
+/// Cherche chaque mot de `q` dans les noms des domaines de l'utilisateur connecté, de leurs
+/// exploitations et éléments, et des types. La comparaison ignore les accents, la casse et la ponctuation :
+/// `elevage` trouve « Bassin d'élevage ». Les résultats sont classés par pertinence (mot entier, début de
+/// mot, puis ailleurs dans le nom).
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "Recherche",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Résultats, les plus pertinents d'abord", body = [SearchHit]),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn search_by_name(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    query: web::Query<SearchQuery>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+    let limit = query.limit.unwrap_or(search::DEFAULT_LIMIT).clamp(1, search::MAX_LIMIT);
+
+    match search::search(&repos, user_id, &query.q, limit).await {
+        Ok(hits) => HttpResponse::Ok().json(hits),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la recherche");
+            HttpResponse::InternalServerError().body("Erreur lors de la recherche")
+        },
+    }
+}
+
+// Corbeille de l'utilisateur connecté
+/// Lister la corbeille
+///
+/// Lignes supprimées des domaines de l'utilisateur connecté, les plus récentes d'abord. Elles
+/// restent restaurables jusqu'à leur purge, après la durée de conservation (TRASH_RETENTION_DAYS, 30 jours
+/// par défaut).
+#[utoipa::path(
+    get,
+    path = "/api/v1/users/me/trash",
+    tag = "Corbeille",
+    responses(
+        (status = 200, description = "Lignes à la corbeille", body = [TrashItem]),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn get_trash(repos: web::Data<Repositories>, req: HttpRequest) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+
+    match repos.trash.get_trash(user_id).await {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture de la corbeille");
+            HttpResponse::InternalServerError().body("Erreur lors de la récupération de la corbeille")
+        },
+    }
+}
+
+// Sortir une ligne de la corbeille
+/// Restaurer une ligne
+///
+/// Remet la ligne à sa place, avec une nouvelle version. Les descendants mis à la corbeille par la
+/// même suppression (`?cascade=true`) reviennent avec elle. Une ligne dont le parent est aussi à la corbeille ne
+/// peut être restaurée qu'après lui.
+#[utoipa::path(
+    post,
+    path = "/api/v1/users/me/trash/{entity}/{id}/restore",
+    tag = "Corbeille",
+    params(
+        ("entity" = Entity, Path, description = "Type de la ligne"),
+        ("id" = i32, Path, description = "ID de la ligne")
+    ),
+    responses(
+        (status = 200, description = "Lignes restaurées, par type", body = Subtree),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Ligne d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Ligne introuvable ou absente de la corbeille", body = MessageErreur, content_type = "text/plain"),
+        (status = 409, description = "Le parent est à la corbeille", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn restore_from_trash(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    path: web::Path<(Entity, i32)>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+    let (entity, id) = path.into_inner();
+
+    match repos.trash.owner_including_trash(entity, id).await {
+        Ok(Some(owner)) if owner == user_id => {},
+        Ok(Some(_)) => return HttpResponse::Forbidden().body("Accès refusé"),
+        Ok(None) => return HttpResponse::NotFound().body("Ligne introuvable"),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la vérification du propriétaire");
+            return HttpResponse::InternalServerError().body("Erreur lors de la restauration");
+        },
+    }
+
+    match repos.trash.restore(entity, id).await {
+        Ok(Restore::Restored(restored)) => HttpResponse::Ok().json(restored),
+        Ok(Restore::NotInTrash) => HttpResponse::NotFound().body("Cette ligne n'est pas à la corbeille"),
+        Ok(Restore::ParentInTrash) => {
+            HttpResponse::Conflict().body("Le parent est à la corbeille : restaurez-le d'abord")
+        },
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la restauration");
+            HttpResponse::InternalServerError().body("Erreur lors de la restauration")
+        },
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DeleteOptions {
+    /// Mettre aussi à la corbeille toute la descendance, en une transaction
+    #[serde(default)]
+    cascade: bool,
+}
+
+// Réponse 409 : la ligne a encore des descendants
+#[derive(Serialize, ToSchema)]
+struct DeleteConflict {
+    message: String,
+    dependents: Dependents,
+}
+
+// Suppression en cascade demandée par ?cascade=true : toute la sous-arborescence part à la corbeille
+// None laisse la suppression ordinaire répondre : sans cascade, ou version périmée (412 ou 404)
+async fn delete_with_dependents(
+    repos: &Repositories,
+    entity: Entity,
+    id: i32,
+    version: i32,
+    cascade: bool,
+    lang: Lang,
+) -> Option<HttpResponse> {
+    if !cascade {
+        return None;
+    }
+
+    match repos.trash.delete_cascade(entity, id, version).await {
+        Ok(Some(deleted)) => Some(HttpResponse::Ok().json(deleted)),
+        Ok(None) => None,
+        Err(RepoError::Conflict(dependents)) => Some(dependents_conflict(dependents, lang)),
+        Err(RepoError::Database(e)) => {
+            tracing::error!(error = ?e, "Erreur lors de la suppression en cascade");
+            Some(HttpResponse::InternalServerError().body("Erreur lors de la suppression en cascade"))
+        },
+    }
+}
+
+// Réponse 409 : des descendants en place, comptés dans la transaction de la suppression
+fn dependents_conflict(dependents: Dependents, lang: Lang) -> HttpResponse {
+    let message = "Suppression impossible : des lignes en dépendent. Supprimez-les d'abord ou ajoutez ?cascade=true";
+    HttpResponse::Conflict()
+        .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+        .json(DeleteConflict {
+            message: i18n::localize(lang, message.to_string()),
+            dependents,
+        })
+}
+
+// Domaine de l'URL, 404 s'il n'existe pas
+async fn find_domaine(repos: &Repositories, domaine_id: i32) -> Result<Domaine, HttpResponse> {
+    match repos.domaines.get_by_id(domaine_id).await {
+        Ok(domaine) => Ok(domaine),
+        Err(sqlx::Error::RowNotFound) => Err(HttpResponse::NotFound().body("Domaine introuvable")),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture du domaine");
+            Err(HttpResponse::InternalServerError().body("Erreur lors de la récupération du domaine"))
+        },
+    }
+}
+
+// Exploitation de l'URL, 404 si elle n'existe pas ou n'appartient pas au domaine
+async fn find_exploitation(
+    repos: &Repositories,
+    domaine_id: i32,
+    exploitation_id: i32,
+) -> Result<Exploitation, HttpResponse> {
+    match repos.exploitations.get_by_id(exploitation_id).await {
+        Ok(exploitation) if exploitation.domaine_id == domaine_id => Ok(exploitation),
+        Ok(_) | Err(sqlx::Error::RowNotFound) => {
+            Err(HttpResponse::NotFound().body("Exploitation introuvable dans ce domaine"))
+        },
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture de l'exploitation");
+            Err(HttpResponse::InternalServerError().body("Erreur lors de la récupération de l'exploitation"))
+        },
+    }
+}
+
+// Élément de l'URL, 404 s'il n'existe pas ou n'appartient pas à l'exploitation du domaine
+async fn find_element(
+    repos: &Repositories,
+    domaine_id: i32,
+    exploitation_id: i32,
+    element_id: i32,
+) -> Result<Element, HttpResponse> {
+    let exploitation = find_exploitation(repos, domaine_id, exploitation_id).await?;
+
+    match repos.elements.get_by_id(element_id).await {
+        Ok(element) if element.exploitation_id == exploitation.id => Ok(element),
+        Ok(_) | Err(sqlx::Error::RowNotFound) => {
+            Err(HttpResponse::NotFound().body("Élément introuvable dans cette exploitation"))
+        },
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture de l'élément");
+            Err(HttpResponse::InternalServerError().body("Erreur lors de la récupération de l'élément"))
+        },
+    }
+}
+
+// Domaine de l'URL appartenant à l'utilisateur connecté : 404 s'il n'existe pas, 403 sinon
+async fn check_domaine_owner(repos: &Repositories, user_id: i32, domaine_id: i32) -> Result<(), HttpResponse> {
+    match repos.domaines.owner_of(Entity::Domaine, domaine_id).await {
+        Ok(Some(owner)) if owner == user_id => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().body("Accès refusé")),
+        Ok(None) => Err(HttpResponse::NotFound().body("Domaine introuvable")),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la vérification du propriétaire du domaine");
+            Err(HttpResponse::InternalServerError().body("Erreur lors de la récupération du domaine"))
+        },
+    }
+}
+
+// Webhook de l'URL, sur un domaine de l'utilisateur connecté : 401 sans token, 404 s'il n'existe pas, 403 sinon
+async fn find_webhook(repos: &Repositories, req: &HttpRequest, id: i32) -> Result<Webhook, HttpResponse> {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), req).await {
+        Some(id) => id,
+        None => return Err(HttpResponse::Unauthorized().body("Non autorisé")),
+    };
+    let webhook = match repos.webhooks.get_webhook(id).await {
+        Ok(webhook) => webhook,
+        Err(sqlx::Error::RowNotFound) => return Err(HttpResponse::NotFound().body("Webhook introuvable")),
+        Err(_) => return Err(HttpResponse::InternalServerError().body("Erreur lors de la récupération du webhook")),
+    };
+    check_domaine_owner(repos, user_id, webhook.domaine_id).await?;
+
+    Ok(webhook)
+}
+
+#[derive(Deserialize, ToSchema)]
+struct NewExploitation {
+    type_exploitation_id: i32,
+    nom_exploitation: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct NewElement {
+    nom_element: String,
+    quantite: i32,
+}
+
+#[derive(Deserialize, ToSchema)]
+struct NewProduction {
+    quantite_produite: i32,
+    unite_production: String,
+    date_de_production: NaiveDate,
+}
+
+// Lister les exploitations d'un domaine
+/// Lister les exploitations d'un domaine
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{domaine_id}/exploitations",
+    tag = "Exploitations",
+    params(("domaine_id" = i32, Path, description = "ID du domaine"), UpdatedSince),
+    responses(
+        (status = 200, description = "Exploitations", body = [Exploitation]),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    )
+)]
+async fn list_domaine_exploitations(
+    repos: web::Data<Repositories>,
+    domaine_id: web::Path<i32>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    if let Err(response) = find_domaine(&repos, *domaine_id).await {
+        return response;
+    }
+
+    match repos.exploitations.get_by_domaine_id(*domaine_id, query.updated_since).await {
+        Ok(exploitations) => HttpResponse::Ok().json(exploitations),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des exploitations"),
+    }
+}
+
+// Ajouter une exploitation à un domaine
+/// Ajouter une exploitation à un domaine
+#[utoipa::path(
+    post,
+    path = "/api/v1/domaines/{domaine_id}/exploitations",
+    tag = "Exploitations",
+    params(("domaine_id" = i32, Path, description = "ID du domaine")),
+    request_body = NewExploitation,
+    responses(
+        (status = 200, description = "Exploitation créée", body = Exploitation),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_domaine_exploitation(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    domaine_id: web::Path<i32>,
+    form: web::Json<NewExploitation>,
+) -> impl Responder {
+    if let Err(response) = find_domaine(&repos, *domaine_id).await {
+        return response;
+    }
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+    let form = form.into_inner();
+
+    match repos.exploitations.create(form.type_exploitation_id, *domaine_id, form.nom_exploitation, created_by).await {
+        Ok(exploitation) => HttpResponse::Ok().json(exploitation),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création de l'exploitation"),
+    }
+}
+
+// Lister les éléments d'une exploitation
+/// Lister les éléments d'une exploitation
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements",
+    tag = "Éléments",
+    params(
+        ("domaine_id" = i32, Path, description = "ID du domaine"),
+        ("exploitation_id" = i32, Path, description = "ID de l'exploitation"),
+        UpdatedSince
+    ),
+    responses(
+        (status = 200, description = "Éléments", body = [Element]),
+        (status = 404, description = "Exploitation introuvable dans ce domaine", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    )
+)]
+async fn list_exploitation_elements(
+    repos: web::Data<Repositories>,
+    path: web::Path<(i32, i32)>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    let (domaine_id, exploitation_id) = path.into_inner();
+    if let Err(response) = find_exploitation(&repos, domaine_id, exploitation_id).await {
+        return response;
+    }
+
+    match repos.elements.get_by_exploitation_id(exploitation_id, query.updated_since).await {
+        Ok(elements) => HttpResponse::Ok().json(elements),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des éléments"),
+    }
+}
+
+// Ajouter un élément à une exploitation
+/// Ajouter un élément à une exploitation
+#[utoipa::path(
+    post,
+    path = "/api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements",
+    tag = "Éléments",
+    params(
+        ("domaine_id" = i32, Path, description = "ID du domaine"),
+        ("exploitation_id" = i32, Path, description = "ID de l'exploitation")
+    ),
+    request_body = NewElement,
+    responses(
+        (status = 200, description = "Élément créé", body = Element),
+        (status = 404, description = "Exploitation introuvable dans ce domaine", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_exploitation_element(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+    form: web::Json<NewElement>,
+) -> impl Responder {
+    let (domaine_id, exploitation_id) = path.into_inner();
+    if let Err(response) = find_exploitation(&repos, domaine_id, exploitation_id).await {
+        return response;
+    }
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+    let form = form.into_inner();
+
+    match repos.elements.create(exploitation_id, form.nom_element, form.quantite, created_by).await {
+        Ok(element) => HttpResponse::Ok().json(element),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création de l'élément"),
+    }
+}
+
+// Lister les productions d'un élément
+/// Lister les productions d'un élément
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements/{element_id}/productions",
+    tag = "Productions",
+    params(
+        ("domaine_id" = i32, Path, description = "ID du domaine"),
+        ("exploitation_id" = i32, Path, description = "ID de l'exploitation"),
+        ("element_id" = i32, Path, description = "ID de l'élément"),
+        UpdatedSince
+    ),
+    responses(
+        (status = 200, description = "Productions", body = [Production]),
+        (status = 404, description = "Élément introuvable dans cette exploitation", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    )
+)]
+async fn list_element_productions(
+    repos: web::Data<Repositories>,
+    path: web::Path<(i32, i32, i32)>,
+    query: web::Query<UpdatedSince>,
+) -> impl Responder {
+    let (domaine_id, exploitation_id, element_id) = path.into_inner();
+    if let Err(response) = find_element(&repos, domaine_id, exploitation_id, element_id).await {
+        return response;
+    }
+
+    match repos.productions.get_by_element_id(element_id, query.updated_since).await {
+        Ok(productions) => HttpResponse::Ok().json(productions),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des productions"),
+    }
+}
+
+// Enregistrer une production pour un élément
+/// Enregistrer une production pour un élément
+#[utoipa::path(
+    post,
+    path = "/api/v1/domaines/{domaine_id}/exploitations/{exploitation_id}/elements/{element_id}/productions",
+    tag = "Productions",
+    params(
+        ("domaine_id" = i32, Path, description = "ID du domaine"),
+        ("exploitation_id" = i32, Path, description = "ID de l'exploitation"),
+        ("element_id" = i32, Path, description = "ID de l'élément")
+    ),
+    request_body = NewProduction,
+    responses(
+        (status = 200, description = "Production créée", body = Production),
+        (status = 404, description = "Élément introuvable dans cette exploitation", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security((), ("bearer" = []))
+)]
+async fn add_element_production(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32, i32)>,
+    form: web::Json<NewProduction>,
+) -> impl Responder {
+    let (domaine_id, exploitation_id, element_id) = path.into_inner();
+    if let Err(response) = find_element(&repos, domaine_id, exploitation_id, element_id).await {
+        return response;
+    }
+    let created_by = user::connected_user_id(repos.users.as_ref(), &req).await;
+    let form = form.into_inner();
+
+    match repos
+        .productions
+        .create(element_id, form.quantite_produite, form.unite_production, form.date_de_production, created_by)
+        .await
+    {
+        Ok(production) => HttpResponse::Ok().json(production),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création de la production"),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct NewWebhook {
+    // URL http(s) appelée en POST à chaque événement
+    #[schema(example = "https://example.com/aquafarm")]
+    url: String,
+    #[schema(example = json!(["production.create", "element.delete"]))]
+    events: Vec<String>,
+    // Clé de signature ; générée si absente
+    secret: Option<String>,
+}
+
+// Abonnement créé, avec sa clé de signature qui n'est plus renvoyée ensuite
+#[derive(Serialize, ToSchema)]
+struct CreatedWebhook {
+    #[serde(flatten)]
+    webhook: Webhook,
+    secret: String,
+}
+
+// Lister les webhooks d'un domaine de l'utilisateur connecté
+/// Lister les webhooks d'un domaine
+#[utoipa::path(
+    get,
+    path = "/api/v1/domaines/{domaine_id}/webhooks",
+    tag = "Webhooks",
+    params(("domaine_id" = i32, Path, description = "ID du domaine")),
+    responses(
+        (status = 200, description = "Webhooks du domaine", body = [Webhook]),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn list_domaine_webhooks(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    domaine_id: web::Path<i32>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+    if let Err(response) = check_domaine_owner(&repos, user_id, *domaine_id).await {
+        return response;
+    }
+
+    match repos.webhooks.get_webhooks_by_domaine_id(*domaine_id).await {
+        Ok(webhooks) => HttpResponse::Ok().json(webhooks),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des webhooks"),
+    }
+}
+
+// Abonner une URL aux événements d'un domaine de l'utilisateur connecté
+/// Abonner une URL aux événements d'un domaine
+///
+/// Chaque événement choisi (`<entité>.<opération>`, par ex. `production.create`) est envoyé en POST
+/// à l'URL, signé dans l'en-tête `X-Aquafarm-Signature` : `t=<horodatage>,v1=<HMAC-SHA256 hexadécimal de
+/// "<horodatage>.<corps>">`. Une livraison refusée est retentée avec un délai doublé à chaque échec.
+/// La clé de signature n'est renvoyée que dans cette réponse. Les adresses internes (boucle locale,
+/// réseaux privés, lien local) sont refusées, et les redirections du destinataire ne sont pas suivies.
+#[utoipa::path(
+    post,
+    path = "/api/v1/domaines/{domaine_id}/webhooks",
+    tag = "Webhooks",
+    params(("domaine_id" = i32, Path, description = "ID du domaine")),
+    request_body = NewWebhook,
+    responses(
+        (status = 201, description = "Webhook créé", body = CreatedWebhook),
+        (status = 400, description = "URL invalide ou vers une adresse interne, ou événements invalides", body = MessageErreur, content_type = "text/plain"),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Domaine introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn add_domaine_webhook(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    domaine_id: web::Path<i32>,
+    form: web::Json<NewWebhook>,
+) -> impl Responder {
+    let user_id = match user::connected_user_id(repos.users.as_ref(), &req).await {
+        Some(id) => id,
+        None => return HttpResponse::Unauthorized().body("Non autorisé"),
+    };
+    if let Err(response) = check_domaine_owner(&repos, user_id, *domaine_id).await {
+        return response;
+    }
+    let form = form.into_inner();
+
+    if let Err(message) = webhook::check_url(&form.url).await {
+        return HttpResponse::BadRequest().body(message);
+    }
+    let events = match webhook::parse_events(&form.events) {
+        Ok(events) => events,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    let secret = match form.secret {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => webhook::generate_secret(),
+    };
+
+    match repos.webhooks.create_webhook(*domaine_id, form.url, events, secret.clone(), Some(user_id)).await {
+        Ok(webhook) => HttpResponse::Created().json(CreatedWebhook { webhook, secret }),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la création du webhook"),
+    }
+}
+
+// Récupérer un webhook par ID
+/// Récupérer un webhook par ID
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}",
+    tag = "Webhooks",
+    params(("id" = i32, Path, description = "ID du webhook")),
+    responses(
+        (status = 200, description = "Webhook trouvé", body = Webhook),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Webhook d'un domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Webhook introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn get_webhook(repos: web::Data<Repositories>, req: HttpRequest, id: web::Path<i32>) -> impl Responder {
+    match find_webhook(&repos, &req, *id).await {
+        Ok(webhook) => HttpResponse::Ok().json(webhook),
+        Err(response) => response,
+    }
+}
+
+// Supprimer un webhook et son historique de livraisons
+/// Supprimer un webhook
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "Webhooks",
+    params(("id" = i32, Path, description = "ID du webhook")),
+    responses(
+        (status = 204, description = "Webhook supprimé"),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Webhook d'un domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Webhook introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn delete_webhook(repos: web::Data<Repositories>, req: HttpRequest, id: web::Path<i32>) -> impl Responder {
+    if let Err(response) = find_webhook(&repos, &req, *id).await {
+        return response;
+    }
+
+    match repos.webhooks.delete_webhook(*id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la suppression du webhook"),
+    }
+}
+
+// Journal des livraisons d'un webhook, de la plus récente à la plus ancienne
+/// Lister les livraisons d'un webhook
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "Webhooks",
+    params(("id" = i32, Path, description = "ID du webhook")),
+    responses(
+        (status = 200, description = "Livraisons, les plus récentes d'abord", body = [WebhookDelivery]),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Webhook d'un domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Webhook introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn list_webhook_deliveries(repos: web::Data<Repositories>, req: HttpRequest, id: web::Path<i32>) -> impl Responder {
+    if let Err(response) = find_webhook(&repos, &req, *id).await {
+        return response;
+    }
+
+    match repos.webhooks.get_deliveries(*id).await {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération des livraisons"),
+    }
+}
+
+// Remettre une livraison en file pour un envoi immédiat, quel que soit son statut
+/// Relancer une livraison
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{id}/deliveries/{delivery_id}/redeliver",
+    tag = "Webhooks",
+    params(
+        ("id" = i32, Path, description = "ID du webhook"),
+        ("delivery_id" = i32, Path, description = "ID de la livraison")
+    ),
+    responses(
+        (status = 202, description = "Livraison remise en file", body = WebhookDelivery),
+        (status = 401, description = "Token absent ou invalide", body = MessageErreur, content_type = "text/plain"),
+        (status = 403, description = "Webhook d'un domaine d'un autre utilisateur", body = MessageErreur, content_type = "text/plain"),
+        (status = 404, description = "Webhook ou livraison introuvable", body = MessageErreur, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain")
+    ),
+    security(("bearer" = []))
+)]
+async fn redeliver_webhook_delivery(
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    path: web::Path<(i32, i32)>,
+) -> impl Responder {
+    let (id, delivery_id) = path.into_inner();
+    if let Err(response) = find_webhook(&repos, &req, id).await {
+        return response;
+    }
+
+    match repos.webhooks.get_delivery(delivery_id).await {
+        Ok(delivery) if delivery.webhook_id == id => {},
+        Ok(_) | Err(sqlx::Error::RowNotFound) => {
+            return HttpResponse::NotFound().body("Livraison introuvable pour ce webhook")
+        },
+        Err(_) => return HttpResponse::InternalServerError().body("Erreur lors de la récupération de la livraison"),
+    }
+    if repos.webhooks.redeliver(delivery_id).await.is_err() {
+        return HttpResponse::InternalServerError().body("Erreur lors de la relance de la livraison");
+    }
+    webhook::wake();
+
+    match repos.webhooks.get_delivery(delivery_id).await {
+        Ok(delivery) => HttpResponse::Accepted().json(delivery),
+        Err(_) => HttpResponse::InternalServerError().body("Erreur lors de la récupération de la livraison"),
+    }
+}
+
+// Vivacité : le processus répond, sans interroger la base
+/// Vivacité du processus
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "Supervision",
+    responses(
+        (status = 200, description = "Le processus répond", body = Object),
+    )
+)]
+async fn health_live() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "statut": "ok" }))
+}
+
+// Disponibilité : la base répond et toutes les migrations embarquées sont appliquées
+/// Disponibilité de la base et des migrations
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "Supervision",
+    responses(
+        (status = 200, description = "Prêt à servir", body = Object),
+        (status = 503, description = "Base injoignable ou migrations en attente", body = Object),
+    )
+)]
+async fn health_ready(repos: web::Data<Repositories>) -> impl Responder {
+    if let Err(e) = repos.health.ping().await {
+        tracing::warn!(error = ?e, "Base de données injoignable");
+        return HttpResponse::ServiceUnavailable()
+            .json(serde_json::json!({ "statut": "indisponible", "base": "injoignable" }));
+    }
+
+    match repos.health.pending_migrations().await {
+        Ok(pending) if pending.is_empty() => {
+            HttpResponse::Ok().json(serde_json::json!({ "statut": "ok", "base": "ok", "migrations": "ok" }))
+        },
+        Ok(pending) => HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "statut": "indisponible",
+            "base": "ok",
+            "migrations_en_attente": pending,
+        })),
+        Err(e) => {
+            tracing::warn!(error = ?e, "Lecture des migrations appliquées impossible");
+            HttpResponse::ServiceUnavailable()
+                .json(serde_json::json!({ "statut": "indisponible", "base": "ok", "migrations": "inconnues" }))
+        },
+    }
+}
+
+// Exposer les métriques au format texte de Prometheus
+/// Métriques au format Prometheus
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "Supervision",
+    responses(
+        (status = 200, description = "Métriques", body = String, content_type = "text/plain"),
+        (status = 500, description = "Erreur interne", body = MessageErreur, content_type = "text/plain"),
+    )
+)]
+async fn get_metrics(repos: web::Data<Repositories>) -> impl Responder {
+    match metrics::render(&repos).await {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4; charset=utf-8")
+            .body(body),
+        Err(e) => {
+            tracing::error!(error = ?e, "Erreur lors de la lecture des métriques");
+            HttpResponse::InternalServerError().body("Erreur lors de la lecture des métriques")
+        },
+    }
+}
+
+// Exécuter une requête GraphQL avec les droits de l'utilisateur connecté
+async fn graphql_query(
+    schema: web::Data<FarmSchema>,
+    repos: web::Data<Repositories>,
+    req: HttpRequest,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    let user_id = user::connected_user_id(repos.users.as_ref(), &req).await;
+
+    graphql::execute(&schema, repos.get_ref().clone(), user_id, request.into_inner())
+        .await
+        .into()
+}
+
+// Explorateur GraphiQL
+async fn graphiql() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(graphql::graphiql("/api/v1/graphql"))
+}
+
+// Commentaire envoyé sur les flux SSE inactifs, pour que les proxys ne coupent pas la connexion
+const SSE_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+// Fin annoncée des routes historiques, remplacées par /api/v1
+const LEGACY_SUNSET: &str = "Wed, 30 Jun 2027 23:59:59 GMT";
+
+// Déclarer toutes les routes de l'API
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/", web::get().to(hello_world))
+        .route("/metrics", web::get().to(get_metrics))
+        .route("/health/live", web::get().to(health_live))
+        .route("/health/ready", web::get().to(health_ready))
+
+        // Document OpenAPI et Swagger UI (fichiers embarqués dans le binaire)
+        .service(SwaggerUi::new("/docs/{_:.*}").url("/openapi.json", ApiDoc::openapi()))
+
+        .service(web::scope("/api/v1").configure(configure_v1))
+
+        // Routes historiques, conservées pendant la transition et signalées comme dépréciées
+        .service(
+            web::scope("")
+                .wrap(
+                    DefaultHeaders::new()
+                        .add(("Deprecation", "true"))
+                        .add(("Sunset", LEGACY_SUNSET))
+                        .add((header::LINK, "</api/v1>; rel=\"successor-version\"")),
+                )
+                .configure(configure_legacy),
+        );
+}
+
+// Routes de /api/v1 : collections imbriquées sous leur parent, lignes adressées par leur ID
+fn configure_v1(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/auth/login", web::post().to(login_user))
+
+        .route("/users", web::get().to(get_users))
+        .route("/users", web::post().to(add_user))
+        .route("/users/me", web::get().to(get_connected_user))
+        .route("/users/me/domaines", web::get().to(get_domaines_for_user))
+        .route("/users/me/domaines", web::post().to(add_domaine_for_user))
+        .route("/users/me/trash", web::get().to(get_trash))
+        .route("/users/me/trash/{entity}/{id}/restore", web::post().to(restore_from_trash))
+        .route("/users/{id}", web::get().to(get_user_by_id))
+        .route("/users/{id}", web::put().to(update_user))
+        .route("/users/{id}", web::delete().to(delete_user))
+        .route("/users/{user_id}/domaines", web::get().to(get_domaines_by_user_id))
+
+        .route("/types_user", web::get().to(get_all_type_user))
+        .route("/types_user", web::post().to(add_type_user))
+        .route("/types_exploitation", web::get().to(get_all_types_exploitation))
+        .route("/types_exploitation", web::post().to(add_type_exploitation))
+        .route("/types_element", web::get().to(get_all_type_elements))
+        .route("/types_element", web::post().to(add_type_element))
+        .route("/types_element/{id}", web::put().to(update_type_element))
+        .route("/types_element/{id}", web::delete().to(delete_type_element))
+        .route(
+            "/{kind:types_(?:user|exploitation|element)}/{id}/translations/{lang}",
+            web::put().to(put_type_translation),
+        )
+
+        .route("/domaines", web::get().to(get_domaines))
+        .route("/domaines", web::post().to(add_domaine))
+        .route("/domaines/{id}", web::get().to(get_domaine))
+        .route("/domaines/{id}", web::put().to(update_domaine))
+        .route("/domaines/{id}", web::delete().to(delete_domaine))
+        .route("/domaines/{id}/tree", web::get().to(get_domaine_tree))
+        .route("/domaines/{id}/events", web::get().to(stream_domaine_events))
+        .route("/domaines/{domaine_id}/exploitations", web::get().to(list_domaine_exploitations))
+        .route("/domaines/{domaine_id}/exploitations", web::post().to(add_domaine_exploitation))
+        .route(
+            "/domaines/{domaine_id}/exploitations/{exploitation_id}/elements",
+            web::get().to(list_exploitation_elements),
+        )
+        .route(
+            "/domaines/{domaine_id}/exploitations/{exploitation_id}/elements",
+            web::post().to(add_exploitation_element),
+        )
+        .route(
+            "/domaines/{domaine_id}/exploitations/{exploitation_id}/elements/{element_id}/productions",
+            web::get().to(list_element_productions),
+        )
+        .route(
+            "/domaines/{domaine_id}/exploitations/{exploitation_id}/elements/{element_id}/productions",
+            web::post().to(add_element_production),
+        )
+        .route("/domaines/{domaine_id}/webhooks", web::get().to(list_domaine_webhooks))
+        .route("/domaines/{domaine_id}/webhooks", web::post().to(add_domaine_webhook))
+
+        .route("/exploitations", web::get().to(get_all_exploitations))
+        .route("/exploitations/{id}", web::get().to(get_exploitation))
+        .route("/exploitations/{id}", web::put().to(update_exploitation))
+        .route("/exploitations/{id}", web::delete().to(delete_exploitation))
+
+        .route("/elements", web::get().to(get_all_elements))
+        .route("/elements/{id}", web::get().to(get_element))
+        .route("/elements/{id}", web::put().to(update_element))
+        .route("/elements/{id}", web::delete().to(delete_element))
+
+        .route("/productions/{id}", web::get().to(get_production))
+        .route("/productions/{id}", web::put().to(update_production))
+        .route("/productions/{id}", web::delete().to(delete_production))
+
+        .route("/webhooks/{id}", web::get().to(get_webhook))
+        .route("/webhooks/{id}", web::delete().to(delete_webhook))
+        .route("/webhooks/{id}/deliveries", web::get().to(list_webhook_deliveries))
+        .route("/webhooks/{id}/deliveries/{delivery_id}/redeliver", web::post().to(redeliver_webhook_delivery))
+
+        .route("/search", web::get().to(search_by_name))
+
+        .route("/sync", web::get().to(get_sync_changes))
+        .route("/sync", web::post().to(push_sync_mutations))
+        .route("/batch", web::post().to(run_batch))
+        .route("/admin/seed", web::post().to(seed_demo))
+
+        // GraphQL : POST pour les requêtes, GET pour l'explorateur GraphiQL
+        .app_data(web::Data::new(graphql::schema()))
+        .route("/graphql", web::post().to(graphql_query))
+        .route("/graphql", web::get().to(graphiql));
+}
+
+// Routes d'avant /api/v1
+fn configure_legacy(cfg: &mut web::ServiceConfig) {
+    cfg
+        .route("/type_user", web::get().to(get_all_type_user))
+        .route("/type_user", web::post().to(add_type_user))
+        .route("/users", web::post().to(add_user))
+        .route("/users", web::get().to(get_users))
+        .route("/login", web::post().to(login_user))
+
+        .route("/users/{id}", web::put().to(update_user))
+        .route("/users/{id}", web::delete().to(delete_user))
+        .route("/users/{id}", web::get().to(get_user_by_id))
+        .route("/users/user/connected", web::get().to(get_connected_user))
+
+        // Chemins fixes avant les chemins paramétrés : /metrics étiquette une requête avec le premier motif
+        // dont le chemin correspond, sans tenir compte de la méthode
+        .route("/domaines", web::post().to(add_domaine))
+        .route("/domaines", web::get().to(get_domaines))
+        .route("/domaines/user", web::get().to(get_domaines_for_user))
+        .route("/domaines/user/add", web::post().to(add_domaine_for_user))
+        .route("/domaines/user/{user_id}", web::get().to(get_domaines_by_user_id))
+        .route("/domaines/{id}", web::put().to(update_domaine))
+        .route("/domaines/{id}", web::delete().to(delete_domaine))
+        .route("/domaines/{id}", web::get().to(get_domaine))
+
+        .route("/type_exploitation", web::post().to(add_type_exploitation))
+        .route("/type_exploitation", web::get().to(get_all_types_exploitation))
+
+        .route("/exploitations", web::post().to(add_exploitation))
+        .route("/exploitations", web::get().to(get_all_exploitations))
+        .route("/exploitations/{id}", web::get().to(get_exploitation))
+        .route("/exploitations/{id}", web::put().to(update_exploitation))
+        .route("/exploitations/{id}", web::delete().to(delete_exploitation))
+        .route("/exploitations/domaine/{domaine_id}", web::get().to(get_exploitations_by_domaine))
+
+        .route("/type_elements", web::post().to(add_type_element))
+        .route("/type_elements", web::get().to(get_all_type_elements))
+        .route("/type_elements/{id}", web::delete().to(delete_type_element))
+        .route("/type_elements/{id}", web::put().to(update_type_element))
+
+        .route("/elements", web::post().to(add_element))
+        .route("/elements", web::get().to(get_all_elements))
+        .route("/elements/exploitation/{exploitation_id}", web::get().to(get_elements_by_exploitation))
+        .route("/elements/{id}", web::get().to(get_element))
+        .route("/elements/{id}", web::put().to(update_element))
+        .route("/elements/{id}", web::delete().to(delete_element))
+
+        .route("/productions", web::post().to(add_production))
+        .route("/productions/{id}", web::get().to(get_production))
+        .route("/productions/{id}", web::put().to(update_production))
+        .route("/productions/{id}", web::delete().to(delete_production))
+        .route("/productions/element/{element_id}", web::get().to(get_productions_by_element_id))
+
+        .route("/sync", web::get().to(get_sync_changes))
+        .route("/sync", web::post().to(push_sync_mutations))
+
+        .route("/batch", web::post().to(run_batch))
+
+        .route("/admin/seed", web::post().to(seed_demo));
+}
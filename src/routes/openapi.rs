@@ -0,0 +1,210 @@
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::batch::{BatchOperation, OperationResult, Ref};
+use crate::seed::SeedOptions;
+use crate::sync::{IdRef, Mutation, MutationResult, MutationStatus, Operation};
+use crate::tombstone::{Entity, Tombstone};
+use crate::search::SearchKind;
+use crate::tree::{ElementNode, ExploitationNode, ResumeProduction};
+use crate::webhook::DeliveryStatus;
+
+// Handlers, DTO et modèles déjà importés par les routes
+use super::*;
+
+// Corps des réponses d'erreur : un message en texte brut
+#[derive(ToSchema)]
+#[schema(example = "Non autorisé")]
+pub struct MessageErreur(pub String);
+
+// Token JWT renvoyé par /login, à envoyer dans l'en-tête `Authorization: Bearer <token>`
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(
+                    HttpBuilder::new()
+                        .scheme(HttpAuthScheme::Bearer)
+                        .bearer_format("JWT")
+                        .build(),
+                ),
+            );
+        }
+    }
+}
+
+// Document OpenAPI 3, servi sur /openapi.json et affiché par Swagger UI sur /docs/
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "AquaFarm API",
+        description = "API de gestion des domaines, exploitations, éléments et productions aquacoles.\n\n\
+            Les POST acceptent un en-tête `Idempotency-Key` : une requête rejouée avec la même clé \
+            renvoie la première réponse. Chaque réponse porte un en-tête `X-Request-Id`.\n\n\
+            Les requêtes sont limitées par utilisateur, par clé d'API déclarée (en-tête `X-Api-Key`) ou, \
+            à défaut, par adresse IP, avec des budgets \
+            distincts pour les lectures et les écritures : les en-têtes `RateLimit-*` indiquent le budget \
+            restant, et une requête refusée reçoit 429 avec `Retry-After`.\n\n\
+            Les mêmes données sont exposées en GraphQL sur /api/v1/graphql (POST), avec \
+            l'explorateur GraphiQL en GET.\n\n\
+            Les messages sont rédigés en français ou en anglais selon l'en-tête `Accept-Language` \
+            (français par défaut), tout comme les noms des types de référence qui ont une traduction.\n\n\
+            Les routes hors de /api/v1 sont dépréciées : leurs réponses portent les en-têtes \
+            `Deprecation` et `Sunset`."
+    ),
+    paths(
+        hello_world,
+        login_user,
+        add_user,
+        get_users,
+        get_user_by_id,
+        update_user,
+        delete_user,
+        get_connected_user,
+        add_type_user,
+        get_all_type_user,
+        add_type_exploitation,
+        get_all_types_exploitation,
+        add_type_element,
+        get_all_type_elements,
+        update_type_element,
+        delete_type_element,
+        put_type_translation,
+        add_domaine,
+        get_domaines,
+        get_domaine,
+        update_domaine,
+        delete_domaine,
+        get_domaines_by_user_id,
+        get_domaine_tree,
+        stream_domaine_events,
+        search_by_name,
+        get_domaines_for_user,
+        add_domaine_for_user,
+        get_trash,
+        restore_from_trash,
+        get_all_exploitations,
+        get_exploitation,
+        update_exploitation,
+        delete_exploitation,
+        add_exploitation,
+        get_exploitations_by_domaine,
+        list_domaine_exploitations,
+        add_domaine_exploitation,
+        get_all_elements,
+        get_element,
+        update_element,
+        delete_element,
+        add_element,
+        get_elements_by_exploitation,
+        list_exploitation_elements,
+        add_exploitation_element,
+        get_production,
+        update_production,
+        delete_production,
+        add_production,
+        get_productions_by_element_id,
+        list_element_productions,
+        add_element_production,
+        list_domaine_webhooks,
+        add_domaine_webhook,
+        get_webhook,
+        delete_webhook,
+        list_webhook_deliveries,
+        redeliver_webhook_delivery,
+        get_sync_changes,
+        push_sync_mutations,
+        run_batch,
+        seed_demo,
+        health_live,
+        health_ready,
+        get_metrics,
+    ),
+    components(schemas(
+        MessageErreur,
+        User,
+        NewUser,
+        UserChanges,
+        LoginUser,
+        LoginResponse,
+        LoginProfile,
+        TypeUser,
+        CreateTypeUser,
+        TypeExploitation,
+        CreateTypeExploitation,
+        TypeElement,
+        CreateTypeElement,
+        TypeKind,
+        Lang,
+        TranslationForm,
+        Domaine,
+        CreateDomaine,
+        UpdateDomaine,
+        Exploitation,
+        NewExploitation,
+        CreateExploitationRequest,
+        UpdateExploitation,
+        Element,
+        NewElement,
+        CreateElement,
+        UpdateElement,
+        Production,
+        NewProduction,
+        CreateProduction,
+        UpdateProduction,
+        Entity,
+        Tombstone,
+        ChangeSet,
+        IdRef,
+        Operation,
+        Mutation,
+        MutationStatus,
+        MutationResult,
+        SyncPush,
+        MutationResults,
+        Ref,
+        BatchOperation,
+        BatchRequest,
+        OperationResult,
+        BatchResults,
+        BatchError,
+        SeedOptions,
+        SeedRequest,
+        SeedReport,
+        DomaineTree,
+        ExploitationNode,
+        ElementNode,
+        ResumeProduction,
+        FarmEvent,
+        SearchHit,
+        SearchKind,
+        TrashItem,
+        Subtree,
+        Dependents,
+        DeleteConflict,
+        Webhook,
+        NewWebhook,
+        CreatedWebhook,
+        WebhookDelivery,
+        DeliveryStatus,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "Utilisateurs", description = "Comptes et connexion"),
+        (name = "Références", description = "Types d'utilisateur, d'exploitation et d'élément"),
+        (name = "Domaines"),
+        (name = "Exploitations"),
+        (name = "Éléments"),
+        (name = "Productions"),
+        (name = "Recherche"),
+        (name = "Corbeille", description = "Lignes supprimées, restaurables jusqu'à leur purge"),
+        (name = "Webhooks", description = "Notifications HTTP signées des modifications d'un domaine"),
+        (name = "Synchronisation", description = "Synchronisation hors ligne et lots transactionnels"),
+        (name = "Administration"),
+        (name = "Supervision", description = "Santé et métriques"),
+    )
+)]
+pub struct ApiDoc;
@@ -0,0 +1,46 @@
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::mysql::MySqlPool;
+
+// Migrations du dossier migrations/mysql/, embarquées dans le binaire à la compilation
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
+
+// Mêmes migrations (versions et noms) dans les dialectes SQLite et PostgreSQL
+#[cfg(feature = "sqlite")]
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+
+#[cfg(feature = "postgres")]
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+// Appliquer les migrations qui ne l'ont pas encore été
+pub async fn migrate(pool: &MySqlPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+#[cfg(feature = "sqlite")]
+pub async fn migrate_sqlite(pool: &sqlx::SqlitePool) -> Result<(), MigrateError> {
+    SQLITE_MIGRATOR.run(pool).await
+}
+
+#[cfg(feature = "postgres")]
+pub async fn migrate_postgres(pool: &sqlx::PgPool) -> Result<(), MigrateError> {
+    POSTGRES_MIGRATOR.run(pool).await
+}
+
+// Versions déjà appliquées, lues dans la table de suivi de sqlx
+pub const APPLIED_MIGRATIONS: &str = "SELECT version FROM _sqlx_migrations WHERE success";
+
+// Versions embarquées dans le binaire mais absentes de `applied`
+pub fn pending(migrator: &Migrator, applied: &[i64]) -> Vec<i64> {
+    migrator
+        .iter()
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect()
+}
+
+// Les migrations sont appliquées au démarrage, sauf si RUN_MIGRATIONS=false
+pub fn run_at_startup() -> bool {
+    std::env::var("RUN_MIGRATIONS")
+        .map(|v| v != "false" && v != "0")
+        .unwrap_or(true)
+}
@@ -0,0 +1,254 @@
+use serde::Serialize;
+use sqlx::database::HasArguments;
+use sqlx::{Database, Encode, FromRow, MySql, MySqlConnection, QueryBuilder, Type};
+use utoipa::ToSchema;
+
+use crate::repository::Repositories;
+
+// Nombre de résultats par défaut et nombre maximal
+pub const DEFAULT_LIMIT: usize = 20;
+pub const MAX_LIMIT: usize = 100;
+
+// Points par terme : mot identique, début de mot, ailleurs dans le nom
+const WORD_SCORE: u32 = 3;
+const PREFIX_SCORE: u32 = 2;
+const INFIX_SCORE: u32 = 1;
+// Bonus quand le nom entier est la recherche
+const EXACT_BONUS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Domaine,
+    Exploitation,
+    Element,
+    TypeUser,
+    TypeExploitation,
+    TypeElement,
+}
+
+// Ligne trouvée, avec ses parents pour construire le lien vers /api/v1
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: i32,
+    pub nom: String,
+    pub domaine_id: Option<i32>,
+    pub exploitation_id: Option<i32>,
+    // Pertinence : plus elle est élevée, plus le résultat est classé haut
+    pub score: u32,
+}
+
+// Forme comparée : minuscules sans accents, ponctuation remplacée par des espaces
+// "Bassin d'Élevage" devient "bassin d elevage"
+pub fn normalize(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars().flat_map(char::to_lowercase) {
+        match c {
+            'à' | 'â' | 'ä' | 'á' | 'ã' | 'å' => folded.push('a'),
+            'ç' => folded.push('c'),
+            'é' | 'è' | 'ê' | 'ë' => folded.push('e'),
+            'î' | 'ï' | 'í' | 'ì' => folded.push('i'),
+            'ô' | 'ö' | 'ó' | 'ò' | 'õ' => folded.push('o'),
+            'ù' | 'û' | 'ü' | 'ú' => folded.push('u'),
+            'ÿ' | 'ý' => folded.push('y'),
+            'ñ' => folded.push('n'),
+            'œ' => folded.push_str("oe"),
+            'æ' => folded.push_str("ae"),
+            // Accents combinants d'un texte décomposé (NFD)
+            '\u{300}'..='\u{36f}' => {},
+            c if c.is_alphanumeric() => folded.push(c),
+            _ => folded.push(' '),
+        }
+    }
+
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Pertinence d'un nom pour les termes recherchés ; None si un terme est absent
+// Même barème que la requête SQL de `matching`, pour le dépôt en mémoire
+pub fn score(nom: &str, query: &str, terms: &[String]) -> Option<u32> {
+    let nom = normalize(nom);
+    let words: Vec<&str> = nom.split(' ').collect();
+
+    let mut total = if nom == query { EXACT_BONUS } else { 0 };
+    for term in terms {
+        total += if words.contains(&term.as_str()) {
+            WORD_SCORE
+        } else if words.iter().any(|w| w.starts_with(term)) {
+            PREFIX_SCORE
+        } else if nom.contains(term) {
+            INFIX_SCORE
+        } else {
+            return None;
+        };
+    }
+
+    Some(total)
+}
+
+// Ligne lue par la requête de recherche ; le type est son rang dans SearchKind
+#[derive(FromRow)]
+pub struct SearchRow {
+    kind: i32,
+    id: i32,
+    nom: String,
+    domaine_id: Option<i32>,
+    exploitation_id: Option<i32>,
+    score: i32,
+}
+
+impl From<SearchRow> for SearchHit {
+    fn from(row: SearchRow) -> Self {
+        SearchHit {
+            kind: SearchKind::from_rank(row.kind),
+            id: row.id,
+            nom: row.nom,
+            domaine_id: row.domaine_id,
+            exploitation_id: row.exploitation_id,
+            score: row.score.max(0) as u32,
+        }
+    }
+}
+
+impl SearchKind {
+    fn from_rank(rank: i32) -> Self {
+        match rank {
+            0 => SearchKind::Domaine,
+            1 => SearchKind::Exploitation,
+            2 => SearchKind::Element,
+            3 => SearchKind::TypeUser,
+            4 => SearchKind::TypeExploitation,
+            _ => SearchKind::TypeElement,
+        }
+    }
+}
+
+// Requête de recherche, commune aux bases SQL : seules les lignes dont la colonne nom_recherche
+// (le nom replié comme par `normalize`) contient chaque terme sont lues, classées par la base et limitées
+// `char_length` est la fonction du dialecte qui compte les caractères d'un texte
+pub fn matching<'args, DB>(
+    user_id: i32,
+    query: &str,
+    terms: &[String],
+    limit: usize,
+    char_length: &str,
+) -> QueryBuilder<'args, DB>
+where
+    DB: Database,
+    <DB as HasArguments<'args>>::Arguments: Default,
+    i32: Encode<'args, DB> + Type<DB>,
+    i64: Encode<'args, DB> + Type<DB>,
+    String: Encode<'args, DB> + Type<DB>,
+{
+    let mut builder = QueryBuilder::new("SELECT kind, id, nom, domaine_id, exploitation_id, (CASE WHEN cle = ");
+    builder.push_bind(query.to_string());
+    builder.push(format!(" THEN {} ELSE 0 END)", EXACT_BONUS));
+    for term in terms {
+        builder.push(" + (CASE WHEN cle = ");
+        builder.push_bind(term.clone());
+        builder.push(" OR cle LIKE ");
+        builder.push_bind(format!("{} %", term));
+        builder.push(" OR cle LIKE ");
+        builder.push_bind(format!("% {}", term));
+        builder.push(" OR cle LIKE ");
+        builder.push_bind(format!("% {} %", term));
+        builder.push(format!(" THEN {} WHEN cle LIKE ", WORD_SCORE));
+        builder.push_bind(format!("{}%", term));
+        builder.push(" OR cle LIKE ");
+        builder.push_bind(format!("% {}%", term));
+        builder.push(format!(" THEN {} ELSE {} END)", PREFIX_SCORE, INFIX_SCORE));
+    }
+
+    // Les éléments d'abord : leurs deux parents sont des entiers, ce qui type les NULL des autres branches
+    builder.push(format!(
+        r#" AS score
+        FROM (
+            SELECT {} AS kind, el.id, el.nom_element AS nom, el.nom_recherche AS cle,
+                   d.id AS domaine_id, el.exploitation_id
+            FROM elements el
+            JOIN exploitations e ON e.id = el.exploitation_id AND e.deleted_at IS NULL
+            JOIN domaines d ON d.id = e.domaine_id AND d.deleted_at IS NULL
+            WHERE el.deleted_at IS NULL AND d.user_id = "#,
+        SearchKind::Element as i32
+    ));
+    builder.push_bind(user_id);
+    builder.push(format!(
+        r#"
+            UNION ALL
+            SELECT {}, d.id, d.nom_domaine, d.nom_recherche, d.id, NULL
+            FROM domaines d
+            WHERE d.deleted_at IS NULL AND d.user_id = "#,
+        SearchKind::Domaine as i32
+    ));
+    builder.push_bind(user_id);
+    builder.push(format!(
+        r#"
+            UNION ALL
+            SELECT {}, e.id, e.nom_exploitation, e.nom_recherche, e.domaine_id, NULL
+            FROM exploitations e
+            JOIN domaines d ON d.id = e.domaine_id AND d.deleted_at IS NULL
+            WHERE e.deleted_at IS NULL AND d.user_id = "#,
+        SearchKind::Exploitation as i32
+    ));
+    builder.push_bind(user_id);
+    builder.push(format!(
+        r#"
+            UNION ALL
+            SELECT {}, id, nom_type_user, nom_recherche, NULL, NULL FROM types_user
+            UNION ALL
+            SELECT {}, id, nom_type_exploitation, nom_recherche, NULL, NULL FROM types_exploitation
+            UNION ALL
+            SELECT {}, id, nom_type_element, nom_recherche, NULL, NULL FROM types_element
+        ) candidats
+        WHERE "#,
+        SearchKind::TypeUser as i32,
+        SearchKind::TypeExploitation as i32,
+        SearchKind::TypeElement as i32
+    ));
+
+    // Chaque terme doit apparaître dans le nom
+    let mut conditions = builder.separated(" AND ");
+    for term in terms {
+        conditions.push("cle LIKE ");
+        conditions.push_bind_unseparated(format!("%{}%", term));
+    }
+
+    // À pertinence égale, les noms courts (plus proches de la recherche) d'abord
+    builder.push(format!(" ORDER BY score DESC, {}(nom), kind, id LIMIT ", char_length));
+    builder.push_bind(limit as i64);
+
+    builder
+}
+
+// Recherche dans MySQL
+pub async fn find(
+    conn: &mut MySqlConnection,
+    user_id: i32,
+    query: &str,
+    terms: &[String],
+    limit: usize,
+) -> Result<Vec<SearchHit>, sqlx::Error>
+{
+    let mut query = matching::<MySql>(user_id, query, terms, limit, "CHAR_LENGTH");
+    let rows = query.build_query_as::<SearchRow>().fetch_all(&mut *conn).await?;
+
+    Ok(rows.into_iter().map(SearchHit::from).collect())
+}
+
+// Rechercher dans les domaines de l'utilisateur, leurs exploitations et éléments, et dans les types
+// La base ne renvoie que les `limit` meilleurs résultats
+pub async fn search(
+    repos: &Repositories,
+    user_id: i32,
+    q: &str,
+    limit: usize,
+) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let query = normalize(q);
+    let terms: Vec<String> = query.split(' ').filter(|t| !t.is_empty()).map(str::to_string).collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    repos.search.search(user_id, &query, &terms, limit).await
+}
@@ -0,0 +1,232 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::Repositories;
+
+// Types de référence insérés par `seed` s'ils n'existent pas encore
+pub const TYPES_USER: &[&str] = &[crate::user::TYPE_ADMINISTRATEUR, "Pisciculteur"];
+pub const TYPES_EXPLOITATION: &[&str] = &["Étang", "Bassin", "Cage flottante", "Écloserie"];
+pub const TYPES_ELEMENT: &[&str] = &["Poisson", "Crevette", "Alevin", "Aliment"];
+
+const NOMS_DOMAINE: &[&str] = &[
+    "Ferme des Saules",
+    "Domaine de la Source",
+    "Pisciculture du Moulin",
+    "Étangs de la Brenne",
+    "Ferme aquacole du Lac",
+    "Domaine des Roseaux",
+];
+const NOMS_EXPLOITATION: &[&str] = &["Nord", "Sud", "Est", "Ouest", "Amont", "Aval"];
+
+// Espèce élevée : nom du lot, effectif initial et récolte quotidienne moyenne (kg) pour 1000 individus
+struct Espece {
+    nom: &'static str,
+    effectif: (i32, i32),
+    kg_pour_mille: f64,
+}
+
+const ESPECES: &[Espece] = &[
+    Espece { nom: "Tilapia", effectif: (2_000, 8_000), kg_pour_mille: 4.0 },
+    Espece { nom: "Carpe commune", effectif: (500, 3_000), kg_pour_mille: 6.5 },
+    Espece { nom: "Truite arc-en-ciel", effectif: (1_000, 5_000), kg_pour_mille: 3.5 },
+    Espece { nom: "Silure", effectif: (300, 1_500), kg_pour_mille: 9.0 },
+    Espece { nom: "Crevette géante", effectif: (10_000, 40_000), kg_pour_mille: 0.6 },
+];
+
+// Paramètres de génération ; une même graine avec les mêmes paramètres produit les mêmes données
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SeedOptions {
+    pub graine: u64,
+    pub domaines: u32,
+    pub annees: u32,
+    pub debut: NaiveDate,
+}
+
+impl Default for SeedOptions {
+    fn default() -> Self {
+        SeedOptions {
+            graine: 1,
+            domaines: 2,
+            annees: 1,
+            debut: NaiveDate::from_ymd_opt(2023, 1, 1).expect("date valide"),
+        }
+    }
+}
+
+// Kilogrammes d'aliment distribués pour un kilogramme récolté
+const INDICE_CONVERSION: f64 = 1.6;
+
+// Nombre de lignes créées
+#[derive(Debug, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct SeedReport {
+    pub domaines: usize,
+    pub exploitations: usize,
+    pub elements: usize,
+    pub productions: usize,
+    pub qualite_eau: usize,
+    pub alimentations: usize,
+}
+
+// Générateur SplitMix64 : suite identique pour une même graine, quelle que soit la plateforme
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Entier dans [min, max]
+    fn range(&mut self, min: i32, max: i32) -> i32 {
+        min + (self.next_u64() % (max - min + 1) as u64) as i32
+    }
+
+    // Réel dans [0, 1)
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.next_u64() as usize % items.len()]
+    }
+}
+
+// Cycle annuel dans [-1, 1] : maximum fin juillet, minimum fin janvier
+fn cycle(date: NaiveDate) -> f64 {
+    let jour = date.ordinal0() as f64;
+    (2.0 * std::f64::consts::PI * (jour - 115.0) / 365.25).sin()
+}
+
+// Facteur saisonnier de croissance
+fn saison(date: NaiveDate) -> f64 {
+    1.0 + 0.45 * cycle(date)
+}
+
+fn arrondi(valeur: f64, decimales: i32) -> f64 {
+    let facteur = 10f64.powi(decimales);
+    (valeur * facteur).round() / facteur
+}
+
+// Insérer les types de référence manquants ; peut être relancé sans créer de doublon
+pub async fn references(repos: &Repositories) -> Result<(), sqlx::Error> {
+    let types_user = repos.references.get_types_user(None).await?;
+    for nom in TYPES_USER {
+        if !types_user.iter().any(|t| t.nom_type_user == *nom) {
+            repos.references.create_type_user(nom.to_string(), None).await?;
+        }
+    }
+
+    let types_exploitation = repos.references.get_types_exploitation(None).await?;
+    for nom in TYPES_EXPLOITATION {
+        if !types_exploitation.iter().any(|t| t.nom_type_exploitation == *nom) {
+            repos.references.create_type_exploitation(nom.to_string(), None).await?;
+        }
+    }
+
+    let types_element = repos.references.get_types_element(None).await?;
+    for nom in TYPES_ELEMENT {
+        if !types_element.iter().any(|t| t.nom_type_element == *nom) {
+            repos.references.create_type_element(nom.to_string(), None).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Générer des fermes de démonstration appartenant à `user_id`
+// Chaque exploitation reçoit un relevé de qualité de l'eau par jour : l'eau se réchauffe l'été,
+// l'oxygène dissous baisse quand elle se réchauffe et le pH reste proche de la neutralité
+// Chaque lot reçoit une récolte par jour, modulée par la saison, la croissance et un bruit,
+// et la ration d'aliment qui l'a produite
+pub async fn demo(repos: &Repositories, user_id: i32, options: &SeedOptions) -> Result<SeedReport, sqlx::Error> {
+    references(repos).await?;
+
+    let mut types_exploitation = repos.references.get_types_exploitation(None).await?;
+    types_exploitation.sort_by_key(|t| t.id);
+
+    let mut rng = Rng(options.graine);
+    let mut report = SeedReport::default();
+    let jours = (365.25 * options.annees as f64) as i64;
+
+    for d in 0..options.domaines as usize {
+        let nom_domaine = match d / NOMS_DOMAINE.len() {
+            0 => NOMS_DOMAINE[d].to_string(),
+            n => format!("{} {}", NOMS_DOMAINE[d % NOMS_DOMAINE.len()], n + 1),
+        };
+        let domaine = repos.domaines.create(user_id, nom_domaine, Some(user_id)).await?;
+        report.domaines += 1;
+
+        for e in 0..rng.range(2, 4) as usize {
+            let type_exploitation = rng.pick(&types_exploitation);
+            let nom_exploitation = format!(
+                "{} {}",
+                type_exploitation.nom_type_exploitation,
+                NOMS_EXPLOITATION[e % NOMS_EXPLOITATION.len()]
+            );
+            let exploitation = repos
+                .exploitations
+                .create(type_exploitation.id, domaine.id, nom_exploitation, Some(user_id))
+                .await?;
+            report.exploitations += 1;
+
+            for jour in 0..jours {
+                let date = options.debut + Duration::days(jour);
+                let temperature = 17.0 + 8.0 * cycle(date) + 1.5 * (rng.unit() - 0.5);
+                let oxygene_dissous = 14.6 - 0.35 * temperature + 0.8 * (rng.unit() - 0.5);
+                let ph = 7.2 + 0.1 * cycle(date) + 0.4 * (rng.unit() - 0.5);
+
+                repos
+                    .releves
+                    .create_qualite_eau(
+                        exploitation.id,
+                        date,
+                        arrondi(temperature, 1),
+                        arrondi(oxygene_dissous.max(0.0), 1),
+                        arrondi(ph, 2),
+                        Some(user_id),
+                    )
+                    .await?;
+                report.qualite_eau += 1;
+            }
+
+            for lot in 1..=rng.range(1, 3) {
+                let espece = rng.pick(ESPECES);
+                let effectif = rng.range(espece.effectif.0, espece.effectif.1);
+                let element = repos
+                    .elements
+                    .create(exploitation.id, format!("{} lot {}", espece.nom, lot), effectif, Some(user_id))
+                    .await?;
+                report.elements += 1;
+
+                let base = espece.kg_pour_mille * effectif as f64 / 1000.0;
+                for jour in 0..jours {
+                    let date = options.debut + Duration::days(jour);
+                    let croissance = 1.0 + 0.15 * jour as f64 / 365.25;
+                    let bruit = 0.8 + 0.4 * rng.unit();
+                    let recolte = base * saison(date) * croissance;
+                    let quantite = (recolte * bruit).round() as i32;
+
+                    repos
+                        .productions
+                        .create(element.id, quantite.max(0), "kg".to_string(), date, Some(user_id))
+                        .await?;
+                    report.productions += 1;
+
+                    let ration = recolte * INDICE_CONVERSION * (0.9 + 0.2 * rng.unit());
+                    repos
+                        .releves
+                        .create_alimentation(element.id, date, arrondi(ration.max(0.0), 1), Some(user_id))
+                        .await?;
+                    report.alimentations += 1;
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
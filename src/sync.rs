@@ -0,0 +1,638 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlPool;
+use utoipa::ToSchema;
+
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::production::Production;
+use crate::repository::{RepoError, Repositories};
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+
+// Ensemble des changements renvoyés à un client depuis son dernier jeton
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChangeSet {
+    pub sync_token: String,
+    pub domaines: Vec<Domaine>,
+    pub exploitations: Vec<Exploitation>,
+    pub elements: Vec<Element>,
+    pub productions: Vec<Production>,
+    pub deleted: Vec<Tombstone>,
+}
+
+// Réservation d'un identifiant client pour une création hors ligne
+pub enum ClientIdReservation {
+    Acquired,
+    // La ligne a déjà été créée pour cet identifiant
+    Recorded(i32),
+    // Une autre requête est en train de la créer
+    Pending,
+}
+
+// Délai au-delà duquel une réservation sans ligne (création interrompue) est reprise
+const CLIENT_ID_LOCK: Duration = Duration::seconds(60);
+
+// Le jeton de synchronisation est l'horodatage (en secondes) du début de la lecture.
+// La lecture suivante inclut cette seconde : un client peut recevoir deux fois
+// la même version d'une ligne, mais ne manque aucune modification.
+pub fn encode_token(at: DateTime<Utc>) -> String {
+    at.timestamp().to_string()
+}
+
+pub fn decode_token(token: &str) -> Option<DateTime<Utc>> {
+    token
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+// Récupérer tous les changements concernant les domaines d'un utilisateur
+pub async fn changes_since(
+    pool: &MySqlPool,
+    user_id: i32,
+    since: DateTime<Utc>,
+) -> Result<ChangeSet, sqlx::Error> {
+    let sync_token = encode_token(timestamps::now());
+
+    let domaines = sqlx::query_as::<_, Domaine>(
+        r#"
+        SELECT id, user_id, nom_domaine, version, created_at, updated_at, created_by
+        FROM domaines
+        WHERE user_id = ? AND deleted_at IS NULL AND updated_at >= ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let exploitations = sqlx::query_as::<_, Exploitation>(
+        r#"
+        SELECT e.id, e.type_exploitation_id, e.domaine_id, e.nom_exploitation, e.version,
+               e.created_at, e.updated_at, e.created_by
+        FROM exploitations e
+        JOIN domaines d ON d.id = e.domaine_id
+        WHERE d.user_id = ? AND e.deleted_at IS NULL AND e.updated_at >= ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let elements = sqlx::query_as::<_, Element>(
+        r#"
+        SELECT el.id, el.exploitation_id, el.nom_element, el.quantite, el.version,
+               el.created_at, el.updated_at, el.created_by
+        FROM elements el
+        JOIN exploitations e ON e.id = el.exploitation_id
+        JOIN domaines d ON d.id = e.domaine_id
+        WHERE d.user_id = ? AND el.deleted_at IS NULL AND el.updated_at >= ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let productions = sqlx::query_as::<_, Production>(
+        r#"
+        SELECT p.id, p.element_id, p.quantite_produite, p.unite_production, p.date_de_production,
+               p.version, p.created_at, p.updated_at, p.created_by
+        FROM production p
+        JOIN elements el ON el.id = p.element_id
+        JOIN exploitations e ON e.id = el.exploitation_id
+        JOIN domaines d ON d.id = e.domaine_id
+        WHERE d.user_id = ? AND p.deleted_at IS NULL AND p.updated_at >= ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let deleted = Tombstone::get_since(pool, user_id, since).await?;
+
+    Ok(ChangeSet {
+        sync_token,
+        domaines,
+        exploitations,
+        elements,
+        productions,
+        deleted,
+    })
+}
+
+// Retrouver l'ID serveur d'une ligne créée hors ligne (les créations en cours sont ignorées)
+pub async fn lookup_client_id(
+    pool: &MySqlPool,
+    user_id: i32,
+    client_id: &str,
+    entity: Entity,
+) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+        SELECT entity_id FROM sync_client_ids
+        WHERE user_id = ? AND client_id = ? AND entity = ? AND entity_id IS NOT NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(entity)
+    .fetch_optional(pool)
+    .await
+}
+
+// Réserver un identifiant client avant de créer la ligne, pour qu'un même client_id
+// envoyé deux fois en parallèle ne crée qu'une ligne
+// Une réservation faite avant `stale_before` et restée sans ligne est reprise
+pub async fn reserve_client_id(
+    pool: &MySqlPool,
+    user_id: i32,
+    client_id: &str,
+    entity: Entity,
+    stale_before: DateTime<Utc>,
+) -> Result<ClientIdReservation, sqlx::Error> {
+    let now = timestamps::now();
+
+    let inserted = sqlx::query(
+        r#"
+        INSERT IGNORE INTO sync_client_ids (user_id, client_id, entity, entity_id, reserved_at)
+        VALUES (?, ?, ?, NULL, ?)
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(entity)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    if inserted.rows_affected() > 0 {
+        return Ok(ClientIdReservation::Acquired);
+    }
+
+    let reclaimed = sqlx::query(
+        r#"
+        UPDATE sync_client_ids SET reserved_at = ?
+        WHERE user_id = ? AND client_id = ? AND entity = ? AND entity_id IS NULL AND reserved_at < ?
+        "#,
+    )
+    .bind(now)
+    .bind(user_id)
+    .bind(client_id)
+    .bind(entity)
+    .bind(stale_before)
+    .execute(pool)
+    .await?;
+
+    if reclaimed.rows_affected() > 0 {
+        return Ok(ClientIdReservation::Acquired);
+    }
+
+    let entity_id: Option<Option<i32>> = sqlx::query_scalar(
+        r#"
+        SELECT entity_id FROM sync_client_ids
+        WHERE user_id = ? AND client_id = ? AND entity = ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(entity)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(entity_id.flatten().map_or(ClientIdReservation::Pending, ClientIdReservation::Recorded))
+}
+
+// Mémoriser l'ID serveur de la ligne créée pour un identifiant réservé
+pub async fn record_client_id(
+    pool: &MySqlPool,
+    user_id: i32,
+    client_id: &str,
+    entity: Entity,
+    entity_id: i32,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE sync_client_ids SET entity_id = ?
+        WHERE user_id = ? AND client_id = ? AND entity = ?
+        "#,
+    )
+    .bind(entity_id)
+    .bind(user_id)
+    .bind(client_id)
+    .bind(entity)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Libérer la réservation quand la création a échoué, pour qu'un nouvel essai soit traité
+pub async fn release_client_id(
+    pool: &MySqlPool,
+    user_id: i32,
+    client_id: &str,
+    entity: Entity,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        DELETE FROM sync_client_ids
+        WHERE user_id = ? AND client_id = ? AND entity = ? AND entity_id IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .bind(client_id)
+    .bind(entity)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Référence vers une ligne : ID serveur, ou ID généré par le client pour une création hors ligne
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum IdRef {
+    Server(i32),
+    Client(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+// Modification effectuée hors ligne par un client
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Mutation {
+    pub client_id: String,
+    pub entity: Entity,
+    pub op: Operation,
+    pub id: Option<IdRef>,
+    pub version: Option<i32>,
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationStatus {
+    Applied,
+    Conflict,
+    NotFound,
+    Forbidden,
+    Invalid,
+    Error,
+}
+
+// Résultat d'une modification, renvoyé dans l'ordre de la requête
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MutationResult {
+    pub client_id: String,
+    pub status: MutationStatus,
+    pub id: Option<i32>,
+    pub version: Option<i32>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DomaineData {
+    nom_domaine: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ExploitationData {
+    domaine_id: Option<IdRef>,
+    type_exploitation_id: Option<i32>,
+    nom_exploitation: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ElementData {
+    exploitation_id: Option<IdRef>,
+    nom_element: Option<String>,
+    quantite: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProductionData {
+    element_id: Option<IdRef>,
+    quantite_produite: Option<i32>,
+    unite_production: Option<String>,
+    date_de_production: Option<NaiveDate>,
+}
+
+// Échec d'une modification, converti en MutationResult
+struct Failure {
+    status: MutationStatus,
+    version: Option<i32>,
+    message: String,
+}
+
+impl Failure {
+    fn new(status: MutationStatus, message: &str) -> Self {
+        Failure {
+            status,
+            version: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<sqlx::Error> for Failure {
+    fn from(e: sqlx::Error) -> Self {
+        tracing::error!(error = ?e, "Erreur lors de la synchronisation");
+        Failure::new(MutationStatus::Error, "Erreur lors de l'application de la modification")
+    }
+}
+
+impl From<RepoError> for Failure {
+    fn from(e: RepoError) -> Self {
+        match e {
+            RepoError::Conflict(_) => {
+                Failure::new(MutationStatus::Conflict, "Suppression impossible : des lignes en dépendent")
+            }
+            RepoError::Database(e) => e.into(),
+        }
+    }
+}
+
+// Appliquer un lot de modifications hors ligne, une par une
+//
+// Chaque modification est indépendante : un conflit n'empêche pas les suivantes.
+// Les créations sont rejouables : un client_id déjà vu renvoie la ligne déjà créée.
+pub async fn apply_mutations(
+    repos: &Repositories,
+    user_id: i32,
+    mutations: Vec<Mutation>,
+) -> Vec<MutationResult> {
+    let mut results = Vec::with_capacity(mutations.len());
+
+    for mutation in mutations {
+        let client_id = mutation.client_id.clone();
+        let result = match apply(repos, user_id, mutation).await {
+            Ok((id, version)) => MutationResult {
+                client_id,
+                status: MutationStatus::Applied,
+                id: Some(id),
+                version,
+                message: None,
+            },
+            Err(failure) => MutationResult {
+                client_id,
+                status: failure.status,
+                id: None,
+                version: failure.version,
+                message: Some(failure.message),
+            },
+        };
+        results.push(result);
+    }
+
+    results
+}
+
+async fn apply(
+    repos: &Repositories,
+    user_id: i32,
+    mutation: Mutation,
+) -> Result<(i32, Option<i32>), Failure> {
+    match mutation.op {
+        Operation::Create => {
+            let stale_before = timestamps::now() - CLIENT_ID_LOCK;
+            let reservation = repos
+                .sync
+                .reserve_client_id(user_id, &mutation.client_id, mutation.entity, stale_before)
+                .await?;
+            match reservation {
+                ClientIdReservation::Acquired => {}
+                ClientIdReservation::Recorded(id) => {
+                    return Ok((id, current_version(repos, mutation.entity, id).await?))
+                }
+                ClientIdReservation::Pending => {
+                    return Err(Failure::new(
+                        MutationStatus::Conflict,
+                        "Une création avec cet identifiant client est en cours",
+                    ))
+                }
+            }
+
+            let id = match create(repos, user_id, mutation.entity, mutation.data).await {
+                Ok(id) => id,
+                Err(failure) => {
+                    if let Err(e) = repos
+                        .sync
+                        .release_client_id(user_id, &mutation.client_id, mutation.entity)
+                        .await
+                    {
+                        tracing::error!(error = ?e, "Erreur lors de la libération de l'identifiant client");
+                    }
+                    return Err(failure);
+                }
+            };
+            repos
+                .sync
+                .record_client_id(user_id, &mutation.client_id, mutation.entity, id)
+                .await?;
+            Ok((id, Some(1)))
+        }
+        Operation::Update | Operation::Delete => {
+            let id = match &mutation.id {
+                Some(id) => resolve(repos, user_id, id, mutation.entity).await?,
+                None => return Err(Failure::new(MutationStatus::Invalid, "Identifiant manquant")),
+            };
+            let version = mutation
+                .version
+                .ok_or_else(|| Failure::new(MutationStatus::Invalid, "Version manquante"))?;
+            check_owner(repos, user_id, mutation.entity, id).await?;
+
+            let applied = if let Operation::Update = mutation.op {
+                update(repos, mutation.entity, id, version, mutation.data).await?
+            } else {
+                delete(repos, mutation.entity, id, version).await?
+            };
+
+            if applied {
+                let new_version = match mutation.op {
+                    Operation::Update => Some(version + 1),
+                    _ => None,
+                };
+                Ok((id, new_version))
+            } else {
+                match current_version(repos, mutation.entity, id).await? {
+                    Some(current) => Err(Failure {
+                        status: MutationStatus::Conflict,
+                        version: Some(current),
+                        message: "La ressource a été modifiée entre-temps".to_string(),
+                    }),
+                    None => Err(Failure::new(MutationStatus::NotFound, "Ressource introuvable")),
+                }
+            }
+        }
+    }
+}
+
+fn parse_data<T: serde::de::DeserializeOwned + Default>(data: serde_json::Value) -> Result<T, Failure> {
+    if data.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(data).map_err(|_| Failure::new(MutationStatus::Invalid, "Données invalides"))
+}
+
+fn required<T>(value: Option<T>, field: &str) -> Result<T, Failure> {
+    value.ok_or_else(|| Failure {
+        status: MutationStatus::Invalid,
+        version: None,
+        message: format!("Champ obligatoire manquant : {}", field),
+    })
+}
+
+async fn create(
+    repos: &Repositories,
+    user_id: i32,
+    entity: Entity,
+    data: serde_json::Value,
+) -> Result<i32, Failure> {
+    let id = match entity {
+        Entity::Domaine => {
+            let data: DomaineData = parse_data(data)?;
+            let nom_domaine = required(data.nom_domaine, "nom_domaine")?;
+            repos.domaines.create( user_id, nom_domaine, Some(user_id)).await?.id
+        }
+        Entity::Exploitation => {
+            let data: ExploitationData = parse_data(data)?;
+            let domaine_id = resolve(repos, user_id, &required(data.domaine_id, "domaine_id")?, Entity::Domaine).await?;
+            check_owner(repos, user_id, Entity::Domaine, domaine_id).await?;
+            repos.exploitations.create(
+                required(data.type_exploitation_id, "type_exploitation_id")?,
+                domaine_id,
+                required(data.nom_exploitation, "nom_exploitation")?,
+                Some(user_id),
+            )
+            .await?
+            .id
+        }
+        Entity::Element => {
+            let data: ElementData = parse_data(data)?;
+            let exploitation_id =
+                resolve(repos, user_id, &required(data.exploitation_id, "exploitation_id")?, Entity::Exploitation).await?;
+            check_owner(repos, user_id, Entity::Exploitation, exploitation_id).await?;
+            repos.elements.create(
+                exploitation_id,
+                required(data.nom_element, "nom_element")?,
+                required(data.quantite, "quantite")?,
+                Some(user_id),
+            )
+            .await?
+            .id
+        }
+        Entity::Production => {
+            let data: ProductionData = parse_data(data)?;
+            let element_id = resolve(repos, user_id, &required(data.element_id, "element_id")?, Entity::Element).await?;
+            check_owner(repos, user_id, Entity::Element, element_id).await?;
+            repos.productions.create(
+                element_id,
+                required(data.quantite_produite, "quantite_produite")?,
+                required(data.unite_production, "unite_production")?,
+                required(data.date_de_production, "date_de_production")?,
+                Some(user_id),
+            )
+            .await?
+            .id
+        }
+    };
+
+    Ok(id)
+}
+
+async fn update(
+    repos: &Repositories,
+    entity: Entity,
+    id: i32,
+    version: i32,
+    data: serde_json::Value,
+) -> Result<bool, Failure> {
+    let applied = match entity {
+        Entity::Domaine => {
+            let data: DomaineData = parse_data(data)?;
+            repos.domaines.update(id, version, data.nom_domaine).await?
+        }
+        Entity::Exploitation => {
+            let data: ExploitationData = parse_data(data)?;
+            repos.exploitations.update(id, version, data.type_exploitation_id, data.nom_exploitation).await?
+        }
+        Entity::Element => {
+            let data: ElementData = parse_data(data)?;
+            repos.elements.update(id, version, data.nom_element, data.quantite).await?
+        }
+        Entity::Production => {
+            let data: ProductionData = parse_data(data)?;
+            repos.productions.update(
+                id,
+                version,
+                data.quantite_produite,
+                data.unite_production,
+                data.date_de_production,
+            )
+            .await?
+        }
+    };
+
+    Ok(applied)
+}
+
+async fn delete(repos: &Repositories, entity: Entity, id: i32, version: i32) -> Result<bool, Failure> {
+    let applied = match entity {
+        Entity::Domaine => repos.domaines.delete(id, version).await?,
+        Entity::Exploitation => repos.exploitations.delete(id, version).await?,
+        Entity::Element => repos.elements.delete(id, version).await?,
+        Entity::Production => repos.productions.delete(id, version).await?,
+    };
+
+    Ok(applied)
+}
+
+// Version actuelle d'une ligne, None si elle n'existe plus
+async fn current_version(repos: &Repositories, entity: Entity, id: i32) -> Result<Option<i32>, sqlx::Error> {
+    let result = match entity {
+        Entity::Domaine => repos.domaines.get_by_id(id).await.map(|d| d.version),
+        Entity::Exploitation => repos.exploitations.get_by_id(id).await.map(|e| e.version),
+        Entity::Element => repos.elements.get_by_id(id).await.map(|e| e.version),
+        Entity::Production => repos.productions.get_by_id(id).await.map(|p| p.version),
+    };
+
+    match result {
+        Ok(version) => Ok(Some(version)),
+        Err(sqlx::Error::RowNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Traduire une référence (ID serveur ou ID client) en ID serveur
+async fn resolve(repos: &Repositories, user_id: i32, id: &IdRef, entity: Entity) -> Result<i32, Failure> {
+    match id {
+        IdRef::Server(id) => Ok(*id),
+        IdRef::Client(client_id) => repos
+            .sync
+            .lookup_client_id(user_id, client_id, entity)
+            .await?
+            .ok_or_else(|| Failure::new(MutationStatus::NotFound, "Identifiant client inconnu")),
+    }
+}
+
+// Vérifier que la ligne appartient à un domaine de l'utilisateur
+async fn check_owner(repos: &Repositories, user_id: i32, entity: Entity, id: i32) -> Result<(), Failure> {
+    match repos.domaines.owner_of(entity, id).await? {
+        Some(owner) if owner == user_id => Ok(()),
+        Some(_) => Err(Failure::new(MutationStatus::Forbidden, "Non autorisé")),
+        None => Err(Failure::new(MutationStatus::NotFound, "Ressource introuvable")),
+    }
+}
@@ -0,0 +1,106 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    Error, HttpMessage,
+};
+use actix_web::middleware::Next;
+use std::time::Instant;
+use tracing::Instrument;
+use tracing_subscriber::EnvFilter;
+use uuid::Uuid;
+
+pub const REQUEST_ID: &str = "x-request-id";
+
+// Paramètres de requête dont la valeur n'est jamais écrite dans les journaux
+const SECRETS: &[&str] = &["token", "password", "mot_de_passe", "secret", "key"];
+
+// Identifiant de la requête en cours, disponible dans les extensions
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+// Installer le collecteur de journaux
+// Niveau réglé par RUST_LOG (info par défaut), sortie JSON si LOG_FORMAT=json
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info,sqlx=warn"));
+    let json = std::env::var("LOG_FORMAT").map(|v| v == "json").unwrap_or(false);
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
+    let result = if json {
+        subscriber.json().with_current_span(true).with_span_list(false).try_init()
+    } else {
+        subscriber.try_init()
+    };
+
+    // Un collecteur déjà installé (tests) est conservé
+    if let Err(e) = result {
+        tracing::debug!("Collecteur de journaux déjà installé : {}", e);
+    }
+}
+
+// Remplacer la valeur des paramètres sensibles d'une chaîne de requête
+pub fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if SECRETS.iter().any(|s| name.to_ascii_lowercase().contains(s)) => {
+                format!("{}=***", name)
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Un identifiant fourni par le client est repris s'il est court et sans caractère spécial
+fn incoming_request_id(req: &ServiceRequest) -> Option<String> {
+    let value = req.headers().get(REQUEST_ID)?.to_str().ok()?;
+    let valid = !value.is_empty()
+        && value.len() <= 64
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    valid.then(|| value.to_string())
+}
+
+// Middleware : chaque requête reçoit un identifiant, repris dans ses journaux et dans l'en-tête X-Request-Id
+pub async fn request_id_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request_id = incoming_request_id(&req).unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(request_id.clone()));
+
+    let span = tracing::info_span!(
+        "requete",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.path(),
+        query = %redact_query(req.query_string()),
+    );
+    let started = Instant::now();
+
+    let mut res = next
+        .call(req)
+        .instrument(span.clone())
+        .await
+        .map(ServiceResponse::map_into_boxed_body)?;
+
+    let status = res.status().as_u16();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    span.in_scope(|| {
+        if res.status().is_server_error() {
+            tracing::error!(status, duration_ms, "Requête terminée en erreur");
+        } else if res.status().is_client_error() {
+            tracing::warn!(status, duration_ms, "Requête refusée");
+        } else {
+            tracing::info!(status, duration_ms, "Requête terminée");
+        }
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        res.headers_mut().insert(HeaderName::from_static(REQUEST_ID), value);
+    }
+
+    Ok(res)
+}
@@ -0,0 +1,31 @@
+use actix_web::{http::header, HttpRequest};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+// Filtre `?updated_since=` accepté par les routes de liste
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UpdatedSince {
+    pub updated_since: Option<DateTime<Utc>>,
+}
+
+// Horodatage courant, tronqué à la seconde comme les colonnes TIMESTAMP
+pub fn now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(0)
+}
+
+// Formater une date pour l'en-tête Last-Modified (format HTTP-date)
+pub fn last_modified(updated_at: DateTime<Utc>) -> String {
+    updated_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+// Vérifier si la ressource n'a pas changé depuis la date de If-Modified-Since
+pub fn not_modified_since(req: &HttpRequest, updated_at: DateTime<Utc>) -> bool {
+    req.headers()
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| DateTime::parse_from_rfc2822(h).ok())
+        .map(|since| updated_at.timestamp() <= since.timestamp())
+        .unwrap_or(false)
+}
@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::{MySqlPool, MySqlRow};
+use sqlx::{FromRow, MySql, Row, Transaction};
+use utoipa::ToSchema;
+
+use crate::timestamps;
+
+// Type d'entité synchronisable
+// MySQL le range dans un VARCHAR : sqlx n'y accepte une énumération dérivée que lue avec try_get_unchecked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum Entity {
+    Domaine,
+    Exploitation,
+    Element,
+    Production,
+}
+
+// Trace d'une suppression, conservée pour les clients hors ligne
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct Tombstone {
+    pub entity: Entity,
+    pub entity_id: i32,
+    pub domaine_id: i32,
+    #[serde(skip_serializing)]
+    pub user_id: i32,
+    pub deleted_at: DateTime<Utc>,
+}
+
+impl Tombstone {
+    // Enregistrer une suppression dans la transaction en cours
+    pub async fn record(
+        tx: &mut Transaction<'_, MySql>,
+        entity: Entity,
+        entity_id: i32,
+        domaine_id: i32,
+        user_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO tombstones (entity, entity_id, domaine_id, user_id, deleted_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(entity)
+        .bind(entity_id)
+        .bind(domaine_id)
+        .bind(user_id)
+        .bind(timestamps::now())
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(())
+    }
+
+    // Récupérer les suppressions concernant les domaines d'un utilisateur depuis une date
+    pub async fn get_since(
+        pool: &MySqlPool,
+        user_id: i32,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let tombstones = sqlx::query(
+            r#"
+            SELECT entity, entity_id, domaine_id, user_id, deleted_at
+            FROM tombstones
+            WHERE user_id = ? AND deleted_at >= ?
+            ORDER BY deleted_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(since)
+        .try_map(|row: MySqlRow| {
+            Ok(Tombstone {
+                entity: row.try_get_unchecked("entity")?,
+                entity_id: row.try_get("entity_id")?,
+                domaine_id: row.try_get("domaine_id")?,
+                user_id: row.try_get("user_id")?,
+                deleted_at: row.try_get("deleted_at")?,
+            })
+        })
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tombstones)
+    }
+}
@@ -0,0 +1,583 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::mysql::{MySqlPool, MySqlRow};
+use sqlx::{Connection, FromRow, MySqlConnection, Row};
+use utoipa::ToSchema;
+
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::ownership;
+use crate::production::Production;
+use crate::repository::{RepoError, Repositories};
+use crate::timestamps;
+use crate::tombstone::{Entity, Tombstone};
+
+// Intervalle entre deux purges de la corbeille
+const PURGE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+// Ligne mise à la corbeille par son propriétaire
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct TrashItem {
+    pub entity: Entity,
+    pub id: i32,
+    // Nom de la ligne ; pour une production, celui de son élément
+    pub nom: String,
+    pub domaine_id: i32,
+    pub deleted_at: DateTime<Utc>,
+}
+
+// Résultat d'une demande de restauration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Restore {
+    // Lignes restaurées : la ligne demandée et les descendants supprimés avec elle
+    Restored(Subtree),
+    // La ligne n'existe pas ou n'est pas à la corbeille
+    NotInTrash,
+    // Le parent doit être restauré d'abord
+    ParentInTrash,
+}
+
+// Lignes d'une sous-arborescence, par type : les descendants d'une ligne, ou tout ce qu'une suppression
+// en cascade a mis à la corbeille
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Subtree {
+    pub domaines: Vec<i32>,
+    pub exploitations: Vec<i32>,
+    pub elements: Vec<i32>,
+    pub productions: Vec<i32>,
+}
+
+// Nombre de descendants qui empêchent une suppression sans cascade
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Dependents {
+    pub exploitations: usize,
+    pub elements: usize,
+    pub productions: usize,
+}
+
+impl Subtree {
+    pub fn ids(&self, entity: Entity) -> &[i32] {
+        match entity {
+            Entity::Domaine => &self.domaines,
+            Entity::Exploitation => &self.exploitations,
+            Entity::Element => &self.elements,
+            Entity::Production => &self.productions,
+        }
+    }
+
+    pub fn ids_mut(&mut self, entity: Entity) -> &mut Vec<i32> {
+        match entity {
+            Entity::Domaine => &mut self.domaines,
+            Entity::Exploitation => &mut self.exploitations,
+            Entity::Element => &mut self.elements,
+            Entity::Production => &mut self.productions,
+        }
+    }
+
+    // Lignes du haut vers le bas de la hiérarchie
+    pub fn rows(&self) -> impl Iterator<Item = (Entity, i32)> + '_ {
+        [Entity::Domaine, Entity::Exploitation, Entity::Element, Entity::Production]
+            .into_iter()
+            .flat_map(move |entity| self.ids(entity).iter().map(move |id| (entity, *id)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows().next().is_none()
+    }
+
+    pub fn dependents(&self) -> Dependents {
+        Dependents {
+            exploitations: self.exploitations.len(),
+            elements: self.elements.len(),
+            productions: self.productions.len(),
+        }
+    }
+}
+
+// Durée de conservation à la corbeille, réglée par TRASH_RETENTION_DAYS (30 jours par défaut)
+pub fn retention() -> Duration {
+    let days = std::env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(30);
+
+    Duration::days(days)
+}
+
+// Lancer la purge périodique des lignes plus anciennes que la durée de conservation
+pub fn start_purge(repos: Repositories) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            match repos.trash.purge(timestamps::now() - retention()).await {
+                Ok(0) => {},
+                Ok(purged) => tracing::info!(purged, "Corbeille purgée"),
+                Err(e) => tracing::error!(error = ?e, "Erreur lors de la purge de la corbeille"),
+            }
+        }
+    });
+}
+
+// Lister la corbeille d'un utilisateur dans MySQL, les suppressions les plus récentes d'abord
+pub async fn list(pool: &MySqlPool, user_id: i32) -> Result<Vec<TrashItem>, sqlx::Error> {
+    let items = sqlx::query(
+        r#"
+        SELECT entity, id, nom, domaine_id, deleted_at
+        FROM (
+            SELECT 'domaine' AS entity, d.id, d.nom_domaine AS nom, d.id AS domaine_id, d.deleted_at
+            FROM domaines d
+            WHERE d.user_id = ? AND d.deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'exploitation', e.id, e.nom_exploitation, d.id, e.deleted_at
+            FROM exploitations e
+            JOIN domaines d ON d.id = e.domaine_id
+            WHERE d.user_id = ? AND e.deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'element', el.id, el.nom_element, d.id, el.deleted_at
+            FROM elements el
+            JOIN exploitations e ON e.id = el.exploitation_id
+            JOIN domaines d ON d.id = e.domaine_id
+            WHERE d.user_id = ? AND el.deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'production', p.id, el.nom_element, d.id, p.deleted_at
+            FROM production p
+            JOIN elements el ON el.id = p.element_id
+            JOIN exploitations e ON e.id = el.exploitation_id
+            JOIN domaines d ON d.id = e.domaine_id
+            WHERE d.user_id = ? AND p.deleted_at IS NOT NULL
+        ) corbeille
+        ORDER BY deleted_at DESC, entity, id
+        "#,
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(user_id)
+    .bind(user_id)
+    .try_map(|row: MySqlRow| {
+        Ok(TrashItem {
+            entity: row.try_get_unchecked("entity")?,
+            id: row.try_get("id")?,
+            nom: row.try_get("nom")?,
+            domaine_id: row.try_get("domaine_id")?,
+            deleted_at: row.try_get("deleted_at")?,
+        })
+    })
+    .fetch_all(pool)
+    .await?;
+
+    Ok(items)
+}
+
+// Sortir une ligne de la corbeille dans MySQL ; sa trace de suppression disparaît avec
+// Les descendants mis à la corbeille par la même cascade qu'elle (même deletion_batch) reviennent aussi
+pub async fn restore(conn: &mut MySqlConnection, entity: Entity, id: i32) -> Result<Restore, sqlx::Error>
+{
+    let mut tx = conn.begin().await?;
+
+    // Pour une ligne à la corbeille : son parent y est-il aussi ?
+    let trashed: Option<(bool, Option<String>)> = match entity {
+        Entity::Domaine => {
+            sqlx::query_as(
+                r#"
+                SELECT FALSE AS parent_in_trash, deletion_batch
+                FROM domaines
+                WHERE id = ? AND deleted_at IS NOT NULL
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?
+        }
+        Entity::Exploitation => {
+            sqlx::query_as(
+                r#"
+                SELECT d.deleted_at IS NOT NULL AS parent_in_trash, e.deletion_batch
+                FROM exploitations e
+                JOIN domaines d ON d.id = e.domaine_id
+                WHERE e.id = ? AND e.deleted_at IS NOT NULL
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?
+        }
+        Entity::Element => {
+            sqlx::query_as(
+                r#"
+                SELECT e.deleted_at IS NOT NULL AS parent_in_trash, el.deletion_batch
+                FROM elements el
+                JOIN exploitations e ON e.id = el.exploitation_id
+                WHERE el.id = ? AND el.deleted_at IS NOT NULL
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?
+        }
+        Entity::Production => {
+            sqlx::query_as(
+                r#"
+                SELECT el.deleted_at IS NOT NULL AS parent_in_trash, p.deletion_batch
+                FROM production p
+                JOIN elements el ON el.id = p.element_id
+                WHERE p.id = ? AND p.deleted_at IS NOT NULL
+                "#,
+            )
+            .bind(id)
+            .fetch_optional(&mut tx)
+            .await?
+        }
+    };
+
+    let batch = match trashed {
+        None => return Ok(Restore::NotInTrash),
+        Some((true, _)) => return Ok(Restore::ParentInTrash),
+        Some((false, batch)) => batch,
+    };
+
+    let mut restored = match batch {
+        Some(batch) => trashed_subtree(&mut tx, entity, id, &batch).await?,
+        None => Subtree::default(),
+    };
+    restored.ids_mut(entity).push(id);
+
+    // La version change : les modifications hors ligne antérieures à la suppression sont refusées
+    let now = timestamps::now();
+    for (entity, id) in restored.rows() {
+        match entity {
+            Entity::Domaine => {
+                sqlx::query("UPDATE domaines SET deleted_at = NULL, deletion_batch = NULL, version = version + 1, updated_at = ? WHERE id = ?")
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?
+            }
+            Entity::Exploitation => {
+                sqlx::query("UPDATE exploitations SET deleted_at = NULL, deletion_batch = NULL, version = version + 1, updated_at = ? WHERE id = ?")
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?
+            }
+            Entity::Element => {
+                sqlx::query("UPDATE elements SET deleted_at = NULL, deletion_batch = NULL, version = version + 1, updated_at = ? WHERE id = ?")
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?
+            }
+            Entity::Production => {
+                sqlx::query("UPDATE production SET deleted_at = NULL, deletion_batch = NULL, version = version + 1, updated_at = ? WHERE id = ?")
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut tx)
+                    .await?
+            }
+        };
+
+        // La ligne réapparaît dans la synchronisation comme une modification
+        sqlx::query("DELETE FROM tombstones WHERE entity = ? AND entity_id = ?").bind(entity).bind(id)
+            .execute(&mut tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(Restore::Restored(restored))
+}
+
+// Descendants d'une ligne mis à la corbeille par la même suppression en cascade qu'elle, dans MySQL
+// Ils portent son lot : un enfant supprimé plus tôt, même dans la même seconde, reste à la corbeille
+async fn trashed_subtree(conn: &mut MySqlConnection, entity: Entity, id: i32, batch: &str) -> Result<Subtree, sqlx::Error>
+{
+    let subtree = match entity {
+        Entity::Domaine => Subtree {
+            exploitations: sqlx::query_scalar("SELECT id FROM exploitations WHERE domaine_id = ? AND deletion_batch = ? ORDER BY id")
+                .bind(id)
+                .bind(batch)
+                .fetch_all(&mut *conn)
+                .await?,
+            elements: sqlx::query_scalar(
+                r#"
+                SELECT el.id
+                FROM elements el
+                JOIN exploitations e ON e.id = el.exploitation_id
+                WHERE e.domaine_id = ? AND el.deletion_batch = ?
+                ORDER BY el.id
+                "#,
+            )
+            .bind(id)
+            .bind(batch)
+            .fetch_all(&mut *conn)
+            .await?,
+            productions: sqlx::query_scalar(
+                r#"
+                SELECT p.id
+                FROM production p
+                JOIN elements el ON el.id = p.element_id
+                JOIN exploitations e ON e.id = el.exploitation_id
+                WHERE e.domaine_id = ? AND p.deletion_batch = ?
+                ORDER BY p.id
+                "#,
+            )
+            .bind(id)
+            .bind(batch)
+            .fetch_all(&mut *conn)
+            .await?,
+            ..Subtree::default()
+        },
+        Entity::Exploitation => Subtree {
+            elements: sqlx::query_scalar("SELECT id FROM elements WHERE exploitation_id = ? AND deletion_batch = ? ORDER BY id")
+                .bind(id)
+                .bind(batch)
+                .fetch_all(&mut *conn)
+                .await?,
+            productions: sqlx::query_scalar(
+                r#"
+                SELECT p.id
+                FROM production p
+                JOIN elements el ON el.id = p.element_id
+                WHERE el.exploitation_id = ? AND p.deletion_batch = ?
+                ORDER BY p.id
+                "#,
+            )
+            .bind(id)
+            .bind(batch)
+            .fetch_all(&mut *conn)
+            .await?,
+            ..Subtree::default()
+        },
+        Entity::Element => Subtree {
+            productions: sqlx::query_scalar("SELECT id FROM production WHERE element_id = ? AND deletion_batch = ? ORDER BY id")
+                .bind(id)
+                .bind(batch)
+                .fetch_all(&mut *conn)
+                .await?,
+                ..Subtree::default()
+        },
+        Entity::Production => Subtree::default(),
+    };
+
+    Ok(subtree)
+}
+
+// Supprimer définitivement de MySQL les lignes mises à la corbeille avant `before`
+// Les enfants d'un parent purgé partent avec lui, en commençant par le bas de la hiérarchie
+// Les traces de suppression (tombstones) sont gardées exprès : un client resté hors ligne au-delà
+// de la rétention doit encore apprendre la suppression à sa prochaine synchronisation
+pub async fn purge(conn: &mut MySqlConnection, before: DateTime<Utc>) -> Result<u64, sqlx::Error>
+{
+    let mut tx = conn.begin().await?;
+
+    let productions = sqlx::query(
+        r#"
+        DELETE FROM production
+        WHERE deleted_at < ?
+           OR element_id IN (
+               SELECT id FROM elements
+               WHERE deleted_at < ?
+                  OR exploitation_id IN (
+                      SELECT id FROM exploitations
+                      WHERE deleted_at < ?
+                         OR domaine_id IN (SELECT id FROM domaines WHERE deleted_at < ?)
+                  )
+           )
+        "#,
+    )
+    .bind(before)
+    .bind(before)
+    .bind(before)
+    .bind(before)
+    .execute(&mut tx)
+    .await?;
+
+    let elements = sqlx::query(
+        r#"
+        DELETE FROM elements
+        WHERE deleted_at < ?
+           OR exploitation_id IN (
+               SELECT id FROM exploitations
+               WHERE deleted_at < ?
+                  OR domaine_id IN (SELECT id FROM domaines WHERE deleted_at < ?)
+           )
+        "#,
+    )
+    .bind(before)
+    .bind(before)
+    .bind(before)
+    .execute(&mut tx)
+    .await?;
+
+    let exploitations = sqlx::query(
+        r#"
+        DELETE FROM exploitations
+        WHERE deleted_at < ?
+           OR domaine_id IN (SELECT id FROM domaines WHERE deleted_at < ?)
+        "#,
+    )
+    .bind(before)
+    .bind(before)
+    .execute(&mut tx)
+    .await?;
+
+    // Les webhooks du domaine et leurs livraisons suivent par ON DELETE CASCADE
+    let domaines = sqlx::query("DELETE FROM domaines WHERE deleted_at < ?").bind(before)
+        .execute(&mut tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(productions.rows_affected()
+        + elements.rows_affected()
+        + exploitations.rows_affected()
+        + domaines.rows_affected())
+}
+
+// Descendants en place d'une ligne dans MySQL
+pub async fn subtree(conn: &mut MySqlConnection, entity: Entity, id: i32) -> Result<Subtree, sqlx::Error>
+{
+    let subtree = match entity {
+        Entity::Domaine => Subtree {
+            exploitations: sqlx::query_scalar("SELECT id FROM exploitations WHERE domaine_id = ? AND deleted_at IS NULL ORDER BY id")
+                .bind(id)
+                .fetch_all(&mut *conn)
+                .await?,
+            elements: sqlx::query_scalar(
+                r#"
+                SELECT el.id
+                FROM elements el
+                JOIN exploitations e ON e.id = el.exploitation_id
+                WHERE e.domaine_id = ? AND e.deleted_at IS NULL AND el.deleted_at IS NULL
+                ORDER BY el.id
+                "#,
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?,
+            productions: sqlx::query_scalar(
+                r#"
+                SELECT p.id
+                FROM production p
+                JOIN elements el ON el.id = p.element_id
+                JOIN exploitations e ON e.id = el.exploitation_id
+                WHERE e.domaine_id = ? AND e.deleted_at IS NULL AND el.deleted_at IS NULL AND p.deleted_at IS NULL
+                ORDER BY p.id
+                "#,
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?,
+            ..Subtree::default()
+        },
+        Entity::Exploitation => Subtree {
+            elements: sqlx::query_scalar("SELECT id FROM elements WHERE exploitation_id = ? AND deleted_at IS NULL ORDER BY id")
+                .bind(id)
+                .fetch_all(&mut *conn)
+                .await?,
+            productions: sqlx::query_scalar(
+                r#"
+                SELECT p.id
+                FROM production p
+                JOIN elements el ON el.id = p.element_id
+                WHERE el.exploitation_id = ? AND el.deleted_at IS NULL AND p.deleted_at IS NULL
+                ORDER BY p.id
+                "#,
+            )
+            .bind(id)
+            .fetch_all(&mut *conn)
+            .await?,
+            ..Subtree::default()
+        },
+        Entity::Element => Subtree {
+            productions: sqlx::query_scalar("SELECT id FROM production WHERE element_id = ? AND deleted_at IS NULL ORDER BY id")
+                .bind(id)
+                .fetch_all(&mut *conn)
+                .await?,
+                ..Subtree::default()
+        },
+        Entity::Production => Subtree::default(),
+    };
+
+    Ok(subtree)
+}
+
+// Mettre à la corbeille une ligne et toute sa descendance dans MySQL, en une transaction
+// None si la version de la ligne a changé : rien n'est alors supprimé
+pub async fn delete_cascade(conn: &mut MySqlConnection, entity: Entity, id: i32, version: i32) -> Result<Option<Subtree>, RepoError>
+{
+    let mut tx = conn.begin().await?;
+
+    // La ligne reste verrouillée pendant toute la cascade : aucun enfant ne peut s'y rattacher en chemin
+    let query = match entity {
+        Entity::Domaine => "SELECT id FROM domaines WHERE id = ? FOR UPDATE",
+        Entity::Exploitation => "SELECT id FROM exploitations WHERE id = ? FOR UPDATE",
+        Entity::Element => "SELECT id FROM elements WHERE id = ? FOR UPDATE",
+        Entity::Production => "SELECT id FROM production WHERE id = ? FOR UPDATE",
+    };
+    sqlx::query(query).bind(id).execute(&mut tx).await?;
+
+    let mut deleted = subtree(&mut tx, entity, id).await?;
+    let owner = ownership::owner(&mut tx, entity, id).await?;
+    let now = timestamps::now();
+    let batch = uuid::Uuid::new_v4().to_string();
+
+    // Du bas vers le haut, pour que chaque parent soit vide quand vient son tour
+    for &production_id in &deleted.productions {
+        sqlx::query("UPDATE production SET deleted_at = ?, deletion_batch = ?, version = version + 1, updated_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(&batch)
+            .bind(now)
+            .bind(production_id)
+            .execute(&mut tx)
+            .await?;
+    }
+    for &element_id in &deleted.elements {
+        sqlx::query("UPDATE elements SET deleted_at = ?, deletion_batch = ?, version = version + 1, updated_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(&batch)
+            .bind(now)
+            .bind(element_id)
+            .execute(&mut tx)
+            .await?;
+    }
+    for &exploitation_id in &deleted.exploitations {
+        sqlx::query("UPDATE exploitations SET deleted_at = ?, deletion_batch = ?, version = version + 1, updated_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(&batch)
+            .bind(now)
+            .bind(exploitation_id)
+            .execute(&mut tx)
+            .await?;
+    }
+    if let Some((domaine_id, user_id)) = owner {
+        for (entity, entity_id) in deleted.rows() {
+            Tombstone::record(&mut tx, entity, entity_id, domaine_id, user_id).await?;
+        }
+    }
+
+    // La ligne elle-même, avec le contrôle de version habituel ; en cas d'échec la transaction est abandonnée
+    let trashed = match entity {
+        Entity::Domaine => Domaine::delete_domaine(&mut tx, id, version).await?,
+        Entity::Exploitation => Exploitation::delete(&mut tx, id, version).await?,
+        Entity::Element => Element::delete(&mut tx, id, version).await?,
+        Entity::Production => Production::delete(&mut tx, id, version).await?,
+    };
+    if !trashed {
+        return Ok(None);
+    }
+
+    // La ligne rejoint le lot de sa descendance : la restauration les ramène ensemble
+    let query = match entity {
+        Entity::Domaine => "UPDATE domaines SET deleted_at = ?, deletion_batch = ? WHERE id = ?",
+        Entity::Exploitation => "UPDATE exploitations SET deleted_at = ?, deletion_batch = ? WHERE id = ?",
+        Entity::Element => "UPDATE elements SET deleted_at = ?, deletion_batch = ? WHERE id = ?",
+        Entity::Production => "UPDATE production SET deleted_at = ?, deletion_batch = ? WHERE id = ?",
+    };
+    sqlx::query(query).bind(now).bind(&batch).bind(id).execute(&mut tx).await?;
+    deleted.ids_mut(entity).push(id);
+
+    tx.commit().await?;
+    Ok(Some(deleted))
+}
@@ -0,0 +1,120 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{mysql::MySqlPool, FromRow};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+use crate::domaine::Domaine;
+use crate::element::Element;
+use crate::exploitation::Exploitation;
+use crate::type_exploitation::TypeExploitation;
+
+// Dernière production d'un élément (la plus récente par date) et nombre total de productions
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct ResumeProduction {
+    #[serde(skip)]
+    pub element_id: i32,
+    pub date_de_production: NaiveDate,
+    pub quantite_produite: i32,
+    pub unite_production: String,
+    pub nombre_productions: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ElementNode {
+    #[serde(flatten)]
+    pub element: Element,
+    pub derniere_production: Option<ResumeProduction>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExploitationNode {
+    #[serde(flatten)]
+    pub exploitation: Exploitation,
+    pub type_exploitation: Option<TypeExploitation>,
+    pub elements: Vec<ElementNode>,
+}
+
+// Domaine complet : exploitations, leurs éléments et le résumé des productions
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DomaineTree {
+    #[serde(flatten)]
+    pub domaine: Domaine,
+    pub exploitations: Vec<ExploitationNode>,
+}
+
+// Assembler l'arbre à partir des lignes lues niveau par niveau
+// Chaque backend lit ces cinq listes en cinq requêtes, quelle que soit la taille du domaine
+pub fn assemble(
+    domaine: Domaine,
+    mut exploitations: Vec<Exploitation>,
+    types: Vec<TypeExploitation>,
+    elements: Vec<Element>,
+    dernieres: Vec<ResumeProduction>,
+) -> DomaineTree {
+    let types: HashMap<i32, TypeExploitation> = types.into_iter().map(|t| (t.id, t)).collect();
+    let mut dernieres: HashMap<i32, ResumeProduction> = dernieres.into_iter().map(|r| (r.element_id, r)).collect();
+
+    let mut elements_par_exploitation: HashMap<i32, Vec<ElementNode>> = HashMap::new();
+    for element in elements {
+        let derniere_production = dernieres.remove(&element.id);
+        elements_par_exploitation
+            .entry(element.exploitation_id)
+            .or_default()
+            .push(ElementNode { element, derniere_production });
+    }
+
+    exploitations.sort_by_key(|e| e.id);
+    let exploitations = exploitations
+        .into_iter()
+        .map(|exploitation| ExploitationNode {
+            type_exploitation: types.get(&exploitation.type_exploitation_id).cloned(),
+            elements: elements_par_exploitation.remove(&exploitation.id).unwrap_or_default(),
+            exploitation,
+        })
+        .collect();
+
+    DomaineTree { domaine, exploitations }
+}
+
+// Lire l'arbre d'un domaine dans MySQL
+pub async fn load(pool: &MySqlPool, id: i32) -> Result<DomaineTree, sqlx::Error> {
+    let domaine = Domaine::get_by_id(&mut *pool.acquire().await?, id).await?;
+    let exploitations = Exploitation::get_by_domaine_id(&mut *pool.acquire().await?, id, None).await?;
+    let types = TypeExploitation::get_all(pool, None).await?;
+
+    let elements = sqlx::query_as::<_, Element>(
+        r#"
+        SELECT id, exploitation_id, nom_element, quantite, version, created_at, updated_at, created_by
+        FROM elements
+        WHERE deleted_at IS NULL
+          AND exploitation_id IN (SELECT id FROM exploitations WHERE domaine_id = ? AND deleted_at IS NULL)
+        ORDER BY id
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    let dernieres = sqlx::query_as::<_, ResumeProduction>(
+        r#"
+        SELECT element_id, date_de_production, quantite_produite, unite_production,
+               nombre_productions
+        FROM (
+            SELECT p.element_id, p.date_de_production, p.quantite_produite, p.unite_production,
+                   ROW_NUMBER() OVER (PARTITION BY p.element_id ORDER BY p.date_de_production DESC, p.id DESC) AS rang,
+                   COUNT(*) OVER (PARTITION BY p.element_id) AS nombre_productions
+            FROM production p
+            JOIN elements e ON e.id = p.element_id
+            JOIN exploitations x ON x.id = e.exploitation_id
+            WHERE x.domaine_id = ? AND p.deleted_at IS NULL AND e.deleted_at IS NULL AND x.deleted_at IS NULL
+        ) dernieres
+        WHERE rang = 1
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(assemble(domaine, exploitations, types, elements, dernieres))
+}
@@ -1,22 +1,41 @@
 use serde::{Deserialize, Serialize};
 use sqlx::{mysql::MySqlPool, FromRow, Error};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::search;
+use crate::timestamps;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
 pub struct TypeElement {
     pub id: i32,                 // ID unique
     pub nom_type_element: String,     // Nom du type d'élément
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
 }
 
 impl TypeElement {
     // Créer un nouveau type d'élément
-    pub async fn create(pool: &MySqlPool, nom_type_element: String) -> Result<Self, Error> {
-        let insert_result = sqlx::query!(
+    pub async fn create(
+        pool: &MySqlPool,
+        nom_type_element: String,
+        created_by: Option<i32>,
+    ) -> Result<Self, Error> {
+        let now = timestamps::now();
+
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO types_element (nom_type_element)
-            VALUES (?)
+            INSERT INTO types_element (nom_type_element, nom_recherche, created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?)
             "#,
-            nom_type_element
         )
+        .bind(&nom_type_element)
+        .bind(search::normalize(&nom_type_element))
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
         .execute(pool)
         .await?;
 
@@ -25,18 +44,26 @@ impl TypeElement {
         Ok(TypeElement {
             id: last_id,
             nom_type_element,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
     // Récupérer tous les types d'éléments
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, Error> {
-        let type_elements = sqlx::query_as!(
-            TypeElement,
+    pub async fn get_all(
+        pool: &MySqlPool,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, Error> {
+        let type_elements = sqlx::query_as::<_, TypeElement>(
             r#"
-            SELECT id, nom_type_element
+            SELECT id, nom_type_element, created_at, updated_at, created_by
             FROM types_element
-            "#
+            WHERE ? IS NULL OR updated_at > ?
+            "#,
         )
+        .bind(updated_since)
+        .bind(updated_since)
         .fetch_all(pool)
         .await?;
 
@@ -45,12 +72,12 @@ impl TypeElement {
 
     // Supprimer un type d'élément
     pub async fn delete(pool: &MySqlPool, id: i32) -> Result<(), Error> {
-        sqlx::query!(
+        sqlx::query(
             r#"
             DELETE FROM types_element WHERE id = ?
             "#,
-            id
         )
+        .bind(id)
         .execute(pool)
         .await?;
 
@@ -59,15 +86,17 @@ impl TypeElement {
 
     // Mettre à jour un type d'élément
     pub async fn update(pool: &MySqlPool, id: i32, nom_element: String) -> Result<(), Error> {
-        sqlx::query!(
+        sqlx::query(
             r#"
             UPDATE types_element
-            SET nom_type_element = ?
+            SET nom_type_element = ?, nom_recherche = ?, updated_at = ?
             WHERE id = ?
             "#,
-            nom_element,
-            id
         )
+        .bind(&nom_element)
+        .bind(search::normalize(&nom_element))
+        .bind(timestamps::now())
+        .bind(id)
         .execute(pool)
         .await?;
 
@@ -1,22 +1,41 @@
 use serde::{Deserialize, Serialize};
 use sqlx::{mysql::MySqlPool, FromRow, Error};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::search;
+use crate::timestamps;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
 pub struct TypeExploitation {
     pub id: i32,                  // Non nullable
     pub nom_type_exploitation: String, // Nom du type d'exploitation
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
 }
 
 impl TypeExploitation {
     // Ajouter un type d'exploitation
-    pub async fn create(pool: &MySqlPool, nom_type_exploitation: String) -> Result<Self, Error> {
-        let insert_result = sqlx::query!(
+    pub async fn create(
+        pool: &MySqlPool,
+        nom_type_exploitation: String,
+        created_by: Option<i32>,
+    ) -> Result<Self, Error> {
+        let now = timestamps::now();
+
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO types_exploitation (nom_type_exploitation)
-            VALUES (?)
+            INSERT INTO types_exploitation (nom_type_exploitation, nom_recherche, created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?)
             "#,
-            nom_type_exploitation
         )
+        .bind(&nom_type_exploitation)
+        .bind(search::normalize(&nom_type_exploitation))
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
         .execute(pool)
         .await?;
 
@@ -26,18 +45,26 @@ impl TypeExploitation {
         Ok(TypeExploitation {
             id: last_id,
             nom_type_exploitation,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
     // Récupérer tous les types d'exploitation
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, Error> {
-        let types_exploitation = sqlx::query_as!(
-            TypeExploitation,
+    pub async fn get_all(
+        pool: &MySqlPool,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, Error> {
+        let types_exploitation = sqlx::query_as::<_, TypeExploitation>(
             r#"
-            SELECT id, nom_type_exploitation
+            SELECT id, nom_type_exploitation, created_at, updated_at, created_by
             FROM types_exploitation
-            "#
+            WHERE ? IS NULL OR updated_at > ?
+            "#,
         )
+        .bind(updated_since)
+        .bind(updated_since)
         .fetch_all(pool)
         .await?;
 
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{mysql::MySqlPool, FromRow, Error};
+use utoipa::ToSchema;
+
+use crate::timestamps;
+use crate::type_element::TypeElement;
+use crate::type_exploitation::TypeExploitation;
+use crate::type_user::TypeUser;
+
+// Famille de types de référence traduisibles ; sérialisée comme le segment de route correspondant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type, ToSchema)]
+#[sqlx(type_name = "type_kind", rename_all = "snake_case")]
+pub enum TypeKind {
+    #[serde(rename = "types_user")]
+    TypeUser,
+    #[serde(rename = "types_exploitation")]
+    TypeExploitation,
+    #[serde(rename = "types_element")]
+    TypeElement,
+}
+
+impl TypeKind {
+    // Table où sont rangés les types de cette famille
+    pub fn table(self) -> &'static str {
+        match self {
+            TypeKind::TypeUser => "types_user",
+            TypeKind::TypeExploitation => "types_exploitation",
+            TypeKind::TypeElement => "types_element",
+        }
+    }
+}
+
+// Nom d'un type dans une autre langue que le français
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct TypeTranslation {
+    pub type_id: i32,
+    pub nom: String,
+}
+
+// Corps de la requête d'ajout ou de remplacement d'une traduction
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TranslationForm {
+    pub nom: String,
+}
+
+// Type dont le nom peut être remplacé par sa traduction
+pub trait Translatable {
+    const KIND: TypeKind;
+
+    fn id(&self) -> i32;
+    fn nom_mut(&mut self) -> &mut String;
+}
+
+impl Translatable for TypeUser {
+    const KIND: TypeKind = TypeKind::TypeUser;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn nom_mut(&mut self) -> &mut String {
+        &mut self.nom_type_user
+    }
+}
+
+impl Translatable for TypeExploitation {
+    const KIND: TypeKind = TypeKind::TypeExploitation;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn nom_mut(&mut self) -> &mut String {
+        &mut self.nom_type_exploitation
+    }
+}
+
+impl Translatable for TypeElement {
+    const KIND: TypeKind = TypeKind::TypeElement;
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn nom_mut(&mut self) -> &mut String {
+        &mut self.nom_type_element
+    }
+}
+
+impl TypeTranslation {
+    // Récupérer les traductions d'une famille de types dans une langue
+    pub async fn get_all(pool: &MySqlPool, kind: TypeKind, lang: &str) -> Result<Vec<Self>, Error> {
+        let translations = sqlx::query_as::<_, TypeTranslation>(
+            r#"
+            SELECT type_id, nom
+            FROM types_traductions
+            WHERE kind = ? AND lang = ?
+            "#,
+        )
+        .bind(kind)
+        .bind(lang)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(translations)
+    }
+
+    // Ajouter ou remplacer la traduction d'un type ; RowNotFound si le type n'existe pas
+    pub async fn set(pool: &MySqlPool, kind: TypeKind, type_id: i32, lang: &str, nom: String) -> Result<(), Error> {
+        let mut tx = pool.begin().await?;
+        let now = timestamps::now();
+
+        let exists: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {} WHERE id = ?", kind.table()))
+            .bind(type_id)
+            .fetch_one(&mut tx)
+            .await?;
+        if exists == 0 {
+            return Err(Error::RowNotFound);
+        }
+
+        // Le type est marqué modifié pour que les clients synchronisés relisent son nom
+        sqlx::query(&format!("UPDATE {} SET updated_at = ? WHERE id = ?", kind.table()))
+            .bind(now)
+            .bind(type_id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO types_traductions (kind, type_id, lang, nom, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE nom = VALUES(nom), updated_at = VALUES(updated_at)
+            "#,
+        )
+        .bind(kind)
+        .bind(type_id)
+        .bind(lang)
+        .bind(nom)
+        .bind(now)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await
+    }
+
+    // Supprimer les traductions d'un type supprimé
+    pub async fn delete_for(pool: &MySqlPool, kind: TypeKind, type_id: i32) -> Result<(), Error> {
+        sqlx::query("DELETE FROM types_traductions WHERE kind = ? AND type_id = ?").bind(kind).bind(type_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
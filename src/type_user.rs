@@ -1,23 +1,42 @@
 use serde::{Deserialize, Serialize};
 use sqlx::{mysql::MySqlPool, FromRow, Error};
+use chrono::{DateTime, Utc};
+use utoipa::ToSchema;
+use async_graphql::SimpleObject;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+use crate::search;
+use crate::timestamps;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, ToSchema, SimpleObject)]
 pub struct TypeUser {
     pub id: i32,                 // Non nullable
     pub nom_type_user: String,   // Nom du type utilisateur
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
 }
 
 
 impl TypeUser {
     // Ajouter un type d'utilisateur
-    pub async fn create(pool: &MySqlPool, nom_type_user: String) -> Result<Self, Error> {
-        let insert_result = sqlx::query!(
+    pub async fn create(
+        pool: &MySqlPool,
+        nom_type_user: String,
+        created_by: Option<i32>,
+    ) -> Result<Self, Error> {
+        let now = timestamps::now();
+
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO types_user (nom_type_user)
-            VALUES (?)
+            INSERT INTO types_user (nom_type_user, nom_recherche, created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?)
             "#,
-            nom_type_user
         )
+        .bind(&nom_type_user)
+        .bind(search::normalize(&nom_type_user))
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
         .execute(pool)
         .await?;
 
@@ -27,18 +46,26 @@ impl TypeUser {
         Ok(TypeUser {
             id: last_id,
             nom_type_user,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
 
     // Récupérer tous les types d'utilisateur
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, Error> {
-        let types_user = sqlx::query_as!(
-            TypeUser,
+    pub async fn get_all(
+        pool: &MySqlPool,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, Error> {
+        let types_user = sqlx::query_as::<_, TypeUser>(
             r#"
-            SELECT id, nom_type_user
+            SELECT id, nom_type_user, created_at, updated_at, created_by
             FROM types_user
-            "#
+            WHERE ? IS NULL OR updated_at > ?
+            "#,
         )
+        .bind(updated_since)
+        .bind(updated_since)
         .fetch_all(pool)
         .await?;
 
@@ -1,13 +1,19 @@
 use actix_web::{Error as ActixError, HttpRequest};
 use sqlx::{mysql::MySqlPool, FromRow, Error as SqlxError};
 use serde::{Deserialize, Serialize};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use bcrypt::{hash, verify, DEFAULT_COST};
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
+use std::fmt;
+use utoipa::ToSchema;
 
+use crate::repository::{ReferenceRepository, UserRepository};
+use crate::timestamps;
 
+// Type d'utilisateur des comptes d'administration, créé par `aquafarm-backend create-admin`
+pub const TYPE_ADMINISTRATEUR: &str = "Administrateur";
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Clone, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct User {
     pub id: i32,                    // ID unique
     pub type_user_id: i32,          // Référence au type d'utilisateur
@@ -16,6 +22,75 @@ pub struct User {
     pub email: String,              // Adresse email unique
     pub numero_telephone: String,   // Numéro de téléphone
     pub mot_de_passe: String,       // Mot de passe (haché)
+    pub created_at: DateTime<Utc>,  // Date de création
+    pub updated_at: DateTime<Utc>,  // Date de dernière modification
+    pub created_by: Option<i32>,    // Utilisateur ayant créé le compte
+}
+
+// Données nécessaires à la création d'un utilisateur
+#[derive(Clone, Deserialize, ToSchema)]
+pub struct NewUser {
+    pub type_user_id: i32,
+    pub nom: String,
+    pub prenom: String,
+    pub email: String,
+    pub numero_telephone: String,
+    pub mot_de_passe: String, // Mot de passe en clair, haché à l'enregistrement
+}
+
+// Champs modifiables d'un utilisateur ; le mot de passe est déjà haché
+#[derive(Clone, Default, Deserialize, ToSchema)]
+pub struct UserChanges {
+    pub nom: Option<String>,
+    pub prenom: Option<String>,
+    pub email: Option<String>,
+    pub numero_telephone: Option<String>,
+    pub mot_de_passe: Option<String>,
+}
+
+// Les mots de passe, hachés ou en clair, sont masqués dans les journaux
+const MASQUE: &str = "***";
+
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("id", &self.id)
+            .field("type_user_id", &self.type_user_id)
+            .field("nom", &self.nom)
+            .field("prenom", &self.prenom)
+            .field("email", &self.email)
+            .field("numero_telephone", &self.numero_telephone)
+            .field("mot_de_passe", &MASQUE)
+            .field("created_at", &self.created_at)
+            .field("updated_at", &self.updated_at)
+            .field("created_by", &self.created_by)
+            .finish()
+    }
+}
+
+impl fmt::Debug for NewUser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NewUser")
+            .field("type_user_id", &self.type_user_id)
+            .field("nom", &self.nom)
+            .field("prenom", &self.prenom)
+            .field("email", &self.email)
+            .field("numero_telephone", &self.numero_telephone)
+            .field("mot_de_passe", &MASQUE)
+            .finish()
+    }
+}
+
+impl fmt::Debug for UserChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserChanges")
+            .field("nom", &self.nom)
+            .field("prenom", &self.prenom)
+            .field("email", &self.email)
+            .field("numero_telephone", &self.numero_telephone)
+            .field("mot_de_passe", &self.mot_de_passe.as_ref().map(|_| MASQUE))
+            .finish()
+    }
 }
 
 // Structure pour le contenu du JWT
@@ -35,47 +110,108 @@ pub fn validate_token(req: &HttpRequest) -> Result<Claims, ActixError> {
         .and_then(|h| h.strip_prefix("Bearer "))
         .ok_or(actix_web::error::ErrorUnauthorized("Token manquant"))?;
 
-    println!("Received token: {}", token); // Debug
-
     let token_data = decode::<Claims>(
         token,
         &DecodingKey::from_secret(jwt_secret.as_ref()),
         &Validation::default(),
     )
     .map_err(|e| {
-        println!("Token validation error: {:?}", e); // Debug
+        // Le token lui-même n'est jamais journalisé
+        tracing::debug!(error = %e, "Token refusé");
         actix_web::error::ErrorUnauthorized("Token invalide")
     })?;
 
     Ok(token_data.claims)
 }
 
+/// Récupérer l'ID de l'utilisateur connecté, si un token valide est fourni
+pub async fn connected_user_id(users: &dyn UserRepository, req: &HttpRequest) -> Option<i32> {
+    let claims = validate_token(req).ok()?;
+
+    users.get_by_email(&claims.sub).await.ok().map(|user| user.id)
+}
+
+/// Récupérer l'ID de l'utilisateur connecté, s'il a le type administrateur
+pub async fn connected_admin_id(
+    users: &dyn UserRepository,
+    references: &dyn ReferenceRepository,
+    req: &HttpRequest,
+) -> Option<i32> {
+    let claims = validate_token(req).ok()?;
+    let user = users.get_by_email(&claims.sub).await.ok()?;
+    let types_user = references.get_types_user(None).await.ok()?;
+
+    types_user
+        .iter()
+        .any(|t| t.id == user.type_user_id && t.nom_type_user == TYPE_ADMINISTRATEUR)
+        .then_some(user.id)
+}
+
+/// Vérifier les identifiants de l'utilisateur (email + mot de passe) et générer un token
+pub async fn authenticate(
+    users: &dyn UserRepository,
+    email: String,
+    mot_de_passe: String,
+) -> Result<(User, String), SqlxError> {
+    let user = users.get_by_email(&email).await?;
+
+    let is_valid = verify(mot_de_passe, &user.mot_de_passe).map_err(|_| SqlxError::RowNotFound)?;
+
+    if is_valid {
+        let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET doit être défini");
+
+        // Définir l'expiration à 48 heures
+        let expiration = Utc::now()
+            .checked_add_signed(chrono::Duration::days(2)) // 48 heures
+            .expect("Erreur de génération de la durée")
+            .timestamp() as usize;
+
+        let claims = Claims {
+            sub: user.email.clone(),
+            exp: expiration,
+        };
+
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(jwt_secret.as_ref()),
+        )
+        .map_err(|_| SqlxError::RowNotFound)?;
+
+        Ok((user, token))
+    } else {
+        Err(SqlxError::RowNotFound)
+    }
+}
+
 impl User {
     /// Ajouter un utilisateur avec hachage du mot de passe
     pub async fn create(
         pool: &MySqlPool,
-        type_user_id: i32,
-        nom: String,
-        prenom: String,
-        email: String,
-        numero_telephone: String,
-        mot_de_passe: String,
+        new_user: NewUser,
+        created_by: Option<i32>,
     ) -> Result<Self, SqlxError> { // Utilisation de SqlxError
+        let now = timestamps::now();
+        let NewUser { type_user_id, nom, prenom, email, numero_telephone, mot_de_passe } = new_user;
         let hashed_password = hash(mot_de_passe, DEFAULT_COST)
             .map_err(|_| SqlxError::RowNotFound)?;
     
-        let insert_result = sqlx::query!(
+        let insert_result = sqlx::query(
             r#"
-            INSERT INTO users (type_user_id, nom, prenom, email, numero_telephone, mot_de_passe)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO users (type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                               created_at, updated_at, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
-            type_user_id,
-            nom,
-            prenom,
-            email,
-            numero_telephone,
-            hashed_password
         )
+        .bind(type_user_id)
+        .bind(&nom)
+        .bind(&prenom)
+        .bind(&email)
+        .bind(&numero_telephone)
+        .bind(&hashed_password)
+        .bind(now)
+        .bind(now)
+        .bind(created_by)
         .execute(pool)
         .await?;
     
@@ -89,89 +225,116 @@ impl User {
             email,
             numero_telephone,
             mot_de_passe: hashed_password,
+            created_at: now,
+            updated_at: now,
+            created_by,
         })
     }
     
 
-    /// Vérifier les identifiants de l'utilisateur (email + mot de passe)
-    pub async fn authenticate(
+    /// Récupérer tous les utilisateurs
+    pub async fn get_all(
         pool: &MySqlPool,
-        email: String,
-        mot_de_passe: String,
-    ) -> Result<(Self, String), SqlxError> { // Utilisation de SqlxError
-        let user = sqlx::query_as!(
-            User,
+        updated_since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Self>, SqlxError> {
+        let users = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe
+            SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                   created_at, updated_at, created_by
             FROM users
-            WHERE email = ?
+            WHERE ? IS NULL OR updated_at > ?
             "#,
-            email
         )
-        .fetch_one(pool)
+        .bind(updated_since)
+        .bind(updated_since)
+        .fetch_all(pool)
         .await?;
     
-        let is_valid = verify(mot_de_passe, &user.mot_de_passe).map_err(|_| SqlxError::RowNotFound)?;
-    
-        if is_valid {
-            let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET doit être défini");
-            
-            // Définir l'expiration à 48 heures
-            let expiration = Utc::now()
-                .checked_add_signed(chrono::Duration::days(2)) // 48 heures
-                .expect("Erreur de génération de la durée")
-                .timestamp() as usize;
-    
-            let claims = Claims {
-                sub: user.email.clone(),
-                exp: expiration,
-            };
-    
-            let token = encode(
-                &Header::default(),
-                &claims,
-                &EncodingKey::from_secret(jwt_secret.as_ref()),
-            )
-            .map_err(|_| SqlxError::RowNotFound)?;
-    
-            Ok((user, token))
-        } else {
-            Err(SqlxError::RowNotFound)
-        }
+        Ok(users)
     }
-    
-    
 
-    /// Récupérer tous les utilisateurs
-    pub async fn get_all(pool: &MySqlPool) -> Result<Vec<Self>, SqlxError> {
-        let users = sqlx::query_as!(
-            User,
+    /// Récupérer un utilisateur par son ID
+    pub async fn get_by_id(pool: &MySqlPool, user_id: i32) -> Result<Self, SqlxError> {
+        let user = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe
+            SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                   created_at, updated_at, created_by
             FROM users
-            "#
+            WHERE id = ?
+            "#,
         )
-        .fetch_all(pool)
+        .bind(user_id)
+        .fetch_one(pool)
         .await?;
     
-        Ok(users)
+        Ok(user)
     }
 
-    /// Récupérer un utilisateur par son ID
-    pub async fn get_by_id(pool: &MySqlPool, user_id: i32) -> Result<Self, SqlxError> {
-        let user = sqlx::query_as!(
-            User,
+    /// Récupérer un utilisateur par son email
+    pub async fn get_by_email(pool: &MySqlPool, email: &str) -> Result<Self, SqlxError> {
+        let user = sqlx::query_as::<_, User>(
             r#"
-            SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe
+            SELECT id, type_user_id, nom, prenom, email, numero_telephone, mot_de_passe,
+                   created_at, updated_at, created_by
             FROM users
-            WHERE id = ?
+            WHERE email = ?
             "#,
-            user_id
         )
+        .bind(email)
         .fetch_one(pool)
         .await?;
-    
+
         Ok(user)
     }
 
+    /// Mettre à jour les champs fournis d'un utilisateur
+    pub async fn update(pool: &MySqlPool, user_id: i32, changes: UserChanges) -> Result<(), SqlxError> {
+        let mut query = String::from("UPDATE users SET ");
+        let mut params = Vec::new();
+
+        if let Some(nom) = changes.nom {
+            query.push_str("nom = ?, ");
+            params.push(nom);
+        }
+        if let Some(prenom) = changes.prenom {
+            query.push_str("prenom = ?, ");
+            params.push(prenom);
+        }
+        if let Some(email) = changes.email {
+            query.push_str("email = ?, ");
+            params.push(email);
+        }
+        if let Some(numero_telephone) = changes.numero_telephone {
+            query.push_str("numero_telephone = ?, ");
+            params.push(numero_telephone);
+        }
+        if let Some(mot_de_passe) = changes.mot_de_passe {
+            query.push_str("mot_de_passe = ?, ");
+            params.push(mot_de_passe);
+        }
+
+        query.push_str("updated_at = ?");
+        params.push(timestamps::now().format("%Y-%m-%d %H:%M:%S").to_string());
+
+        query.push_str(" WHERE id = ?");
+        params.push(user_id.to_string());
+
+        let mut query_builder = sqlx::query(&query);
+        for param in params {
+            query_builder = query_builder.bind(param);
+        }
+
+        query_builder.execute(pool).await?;
+
+        Ok(())
+    }
+
+    /// Supprimer un utilisateur par son ID
+    pub async fn delete(pool: &MySqlPool, user_id: i32) -> Result<(), SqlxError> {
+        sqlx::query("DELETE FROM users WHERE id = ?").bind(user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,697 @@
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Serialize, Serializer};
+use sha2::Sha256;
+use sqlx::mysql::{MySqlPool, MySqlRow};
+use sqlx::{FromRow, Row};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::events::FarmEvent;
+use crate::rate_limit;
+use crate::repository::{
+    DomaineRepository, ElementRepository, ExploitationRepository, ProductionRepository, Repositories,
+    WebhookRepository,
+};
+use crate::sync::Operation;
+use crate::timestamps;
+use crate::tombstone::Entity;
+
+pub const SIGNATURE_HEADER: &str = "X-Aquafarm-Signature";
+pub const EVENT_HEADER: &str = "X-Aquafarm-Event";
+pub const DELIVERY_HEADER: &str = "X-Aquafarm-Delivery";
+
+// Livraisons traitées à chaque passage du worker
+const BATCH_SIZE: i64 = 50;
+// Délai maximal d'une livraison, vérification de l'adresse comprise
+const REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+// Réservation des livraisons d'un passage : assez longue pour les envoyer toutes l'une après l'autre,
+// plus une marge pour l'enregistrement des résultats
+const LEASE_SECONDS: i64 = BATCH_SIZE * REQUEST_TIMEOUT.as_secs() as i64 + 60;
+// Relecture de la file même sans nouvel événement, pour les nouvelles tentatives
+const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+// Délai avant la deuxième tentative, doublé ensuite jusqu'au plafond
+const FIRST_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 3600;
+
+// Abonnement d'une URL aux événements d'un domaine
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct Webhook {
+    pub id: i32,
+    pub domaine_id: i32,
+    pub url: String,
+    // Stockés séparés par des virgules, par ex. "production.create,element.delete"
+    #[serde(serialize_with = "comma_list")]
+    #[schema(value_type = Vec<String>, example = json!(["production.create", "element.delete"]))]
+    pub events: String,
+    // Clé de signature, renvoyée seulement à la création
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<i32>,
+}
+
+fn comma_list<S: Serializer>(events: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(events.split(',').filter(|e| !e.is_empty()))
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event_type: &str) -> bool {
+        self.events.split(',').any(|e| e == event_type)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "delivery_status", rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    // En attente de sa prochaine tentative
+    Pending,
+    Delivered,
+    // Abandonnée après le nombre maximal de tentatives ; peut être relancée à la main
+    Failed,
+}
+
+// Livraison d'un événement à un abonnement, avec le résultat de sa dernière tentative
+#[derive(Debug, Clone, Serialize, FromRow, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event_type: String,
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+// Résultat d'une tentative, enregistré sur la livraison
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+// Nom d'un événement : "<entité>.<opération>", par ex. "production.create"
+pub fn event_type(entity: Entity, operation: Operation) -> String {
+    let entity = match entity {
+        Entity::Domaine => "domaine",
+        Entity::Exploitation => "exploitation",
+        Entity::Element => "element",
+        Entity::Production => "production",
+    };
+    let operation = match operation {
+        Operation::Create => "create",
+        Operation::Update => "update",
+        Operation::Delete => "delete",
+    };
+
+    format!("{}.{}", entity, operation)
+}
+
+// Vérifier les événements demandés et les mettre sous la forme stockée
+pub fn parse_events(events: &[String]) -> Result<String, String> {
+    let known: Vec<String> = [Entity::Domaine, Entity::Exploitation, Entity::Element, Entity::Production]
+        .into_iter()
+        .flat_map(|entity| {
+            [Operation::Create, Operation::Update, Operation::Delete]
+                .into_iter()
+                .map(move |operation| event_type(entity, operation))
+        })
+        .collect();
+
+    if events.is_empty() {
+        return Err("Au moins un événement est requis".to_string());
+    }
+    if let Some(unknown) = events.iter().find(|e| !known.contains(e)) {
+        return Err(format!("Événement inconnu : {}", unknown));
+    }
+
+    Ok(events.join(","))
+}
+
+// Clé de signature aléatoire de 64 caractères hexadécimaux
+pub fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+// Signature envoyée dans l'en-tête X-Aquafarm-Signature : "t=<horodatage>,v1=<HMAC-SHA256 hexadécimal>"
+// Le HMAC porte sur "<horodatage>.<corps>", pour que le destinataire puisse refuser les rejeux anciens
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepte toute taille de clé");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+
+    format!("t={},v1={:x}", timestamp, mac.finalize().into_bytes())
+}
+
+// Adresse joignable depuis Internet : ni boucle locale, ni réseau privé, ni lien local, ni non spécifiée
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified() || ip.is_broadcast())
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => !(ip.is_loopback() || ip.is_unique_local() || ip.is_unicast_link_local() || ip.is_unspecified()),
+        },
+    }
+}
+
+// Les adresses internes ne reçoivent pas de webhooks, sauf avec WEBHOOK_ALLOW_PRIVATE=true ou si elles
+// figurent dans WEBHOOK_ALLOWED_NETWORKS (adresses ou plages CIDR séparées par des virgules)
+fn is_allowed(ip: IpAddr) -> bool {
+    if is_public(ip) {
+        return true;
+    }
+    if std::env::var("WEBHOOK_ALLOW_PRIVATE").is_ok_and(|v| v == "true" || v == "1") {
+        return true;
+    }
+
+    std::env::var("WEBHOOK_ALLOWED_NETWORKS")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|network| rate_limit::parse_network(network.trim()))
+        .any(|network| network.contains(&ip))
+}
+
+// Adresses de l'hôte ; refusé si l'une d'elles est interdite
+async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|_| format!("Hôte introuvable : {}", host))?
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(format!("Hôte introuvable : {}", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_allowed(addr.ip())) {
+        return Err(format!("Adresse interdite pour un webhook : {}", addr.ip()));
+    }
+
+    Ok(addrs)
+}
+
+// Vérifier l'URL d'un webhook : http ou https, vers un hôte dont aucune adresse n'est interdite
+// Faite à l'enregistrement et avant chaque livraison, le nom pouvant changer d'adresse entre-temps
+pub async fn check_url(url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(url)
+        .ok()
+        .filter(|url| matches!(url.scheme(), "http" | "https"))
+        .ok_or_else(|| "URL invalide : http ou https attendu".to_string())?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "URL invalide : http ou https attendu".to_string())?;
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    // Une adresse IPv6 est écrite entre crochets dans l'URL
+    resolve(host.trim_start_matches('[').trim_end_matches(']'), port).await.map(|_| ())
+}
+
+// Résolveur du client de livraison : la connexion n'utilise que des adresses vérifiées au moment même,
+// pour qu'un nom ne puisse pas pointer vers une adresse interne entre la vérification et l'envoi
+struct CheckedResolver;
+
+impl reqwest::dns::Resolve for CheckedResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs = resolve(&host, 0).await?;
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+// Client HTTP des livraisons ; les redirections ne sont pas suivies, elles pourraient mener
+// vers une adresse interne
+fn client() -> reqwest::Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent("aquafarm-webhooks")
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(CheckedResolver))
+        .build()
+}
+
+// Nombre de tentatives avant abandon, réglé par WEBHOOK_MAX_ATTEMPTS (8 par défaut)
+fn max_attempts() -> i32 {
+    std::env::var("WEBHOOK_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8)
+}
+
+// Attente après la tentative numéro `attempts` : 30 s, 1 min, 2 min… plafonnée à 1 h
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::seconds((FIRST_RETRY_SECONDS << exponent).min(MAX_RETRY_SECONDS))
+}
+
+// Réveil du worker de livraison, partagé par tout le processus
+fn wake_signal() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+// Demander au worker de relire la file sans attendre son prochain passage
+pub fn wake() {
+    wake_signal().notify_one();
+}
+
+// Lancer le worker de livraison ; les livraisons sont mises en file par les dépôts au moment des écritures
+pub fn start(repos: Repositories) {
+    tokio::spawn(async move {
+        let client = match client() {
+            Ok(client) => client,
+            Err(e) => {
+                tracing::error!(error = ?e, "Client HTTP des webhooks impossible à créer");
+                return;
+            },
+        };
+
+        loop {
+            if let Err(e) = deliver_due(&repos, &client).await {
+                tracing::error!(error = ?e, "Erreur lors de la lecture de la file des webhooks");
+            }
+
+            tokio::select! {
+                _ = wake_signal().notified() => {},
+                _ = tokio::time::sleep(POLL_INTERVAL) => {},
+            }
+        }
+    });
+}
+
+// Accès nécessaires à la mise en file : les abonnements, et les lignes recopiées dans les corps
+// Ils pointent sur le backend lui-même, sans passer par le dépôt qui publie
+#[derive(Clone)]
+pub struct Outbox {
+    pub webhooks: Arc<dyn WebhookRepository>,
+    pub domaines: Arc<dyn DomaineRepository>,
+    pub exploitations: Arc<dyn ExploitationRepository>,
+    pub elements: Arc<dyn ElementRepository>,
+    pub productions: Arc<dyn ProductionRepository>,
+}
+
+// Créer une livraison par abonnement du domaine intéressé par l'événement
+pub async fn enqueue(outbox: &Outbox, event: &FarmEvent) -> Result<usize, sqlx::Error> {
+    let event_type = event_type(event.entity, event.operation);
+    let webhooks: Vec<Webhook> = outbox
+        .webhooks
+        .get_webhooks_by_domaine_id(event.domaine_id)
+        .await?
+        .into_iter()
+        .filter(|webhook| webhook.subscribes_to(&event_type))
+        .collect();
+
+    if webhooks.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = payload(outbox, event, &event_type).await?;
+    for webhook in &webhooks {
+        outbox.webhooks.create_delivery(webhook.id, &event_type, payload.clone()).await?;
+    }
+
+    wake();
+    Ok(webhooks.len())
+}
+
+// Corps envoyé : l'événement et, sauf pour une suppression, la ligne telle qu'enregistrée
+async fn payload(outbox: &Outbox, event: &FarmEvent, event_type: &str) -> Result<String, sqlx::Error> {
+    let data = match event.operation {
+        Operation::Delete => Ok(serde_json::Value::Null),
+        _ => match event.entity {
+            Entity::Domaine => outbox.domaines.get_by_id(event.id).await.map(serde_json::to_value),
+            Entity::Exploitation => outbox.exploitations.get_by_id(event.id).await.map(serde_json::to_value),
+            Entity::Element => outbox.elements.get_by_id(event.id).await.map(serde_json::to_value),
+            Entity::Production => outbox.productions.get_by_id(event.id).await.map(serde_json::to_value),
+        }
+        .map(|value| value.unwrap_or_default()),
+    };
+
+    // Ligne supprimée entre-temps : l'événement est envoyé sans elle
+    let data = match data {
+        Err(sqlx::Error::RowNotFound) => serde_json::Value::Null,
+        data => data?,
+    };
+
+    let body = serde_json::json!({
+        "event": event_type,
+        "occurred_at": timestamps::now(),
+        "domaine_id": event.domaine_id,
+        "entity": event.entity,
+        "operation": event.operation,
+        "id": event.id,
+        "version": event.version,
+        "data": data,
+    });
+
+    Ok(body.to_string())
+}
+
+// Envoyer les livraisons dues ; renvoie le nombre de tentatives effectuées
+pub async fn deliver_due(repos: &Repositories, client: &reqwest::Client) -> Result<usize, sqlx::Error> {
+    let now = timestamps::now();
+    let deliveries = repos
+        .webhooks
+        .claim_due_deliveries(now, now + Duration::seconds(LEASE_SECONDS), BATCH_SIZE)
+        .await?;
+
+    for delivery in &deliveries {
+        let webhook = match repos.webhooks.get_webhook(delivery.webhook_id).await {
+            Ok(webhook) => webhook,
+            // Abonnement supprimé depuis la mise en file
+            Err(sqlx::Error::RowNotFound) => continue,
+            Err(e) => return Err(e),
+        };
+
+        let attempt = attempt(client, &webhook, delivery).await;
+        if attempt.status != DeliveryStatus::Delivered {
+            tracing::warn!(
+                webhook_id = webhook.id,
+                delivery_id = delivery.id,
+                attempts = attempt.attempts,
+                status_code = attempt.status_code,
+                error = attempt.error.as_deref(),
+                "Livraison de webhook échouée"
+            );
+        }
+        repos.webhooks.record_attempt(delivery.id, attempt).await?;
+    }
+
+    Ok(deliveries.len())
+}
+
+// Envoyer une livraison et décider de la suite : livrée, nouvelle tentative plus tard ou abandon
+async fn attempt(client: &reqwest::Client, webhook: &Webhook, delivery: &WebhookDelivery) -> Attempt {
+    let now = timestamps::now();
+    let signature = sign(&webhook.secret, now.timestamp(), delivery.payload.as_bytes());
+
+    let send = async {
+        check_url(&webhook.url).await?;
+        client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event_type)
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .header(SIGNATURE_HEADER, signature)
+            .body(delivery.payload.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+    };
+    let result = match tokio::time::timeout(REQUEST_TIMEOUT, send).await {
+        Ok(result) => result,
+        Err(_) => Err("Délai dépassé".to_string()),
+    };
+
+    let attempts = delivery.attempts + 1;
+    let (status_code, error) = match result {
+        Ok(response) if response.status().is_success() => {
+            return Attempt {
+                status: DeliveryStatus::Delivered,
+                attempts,
+                status_code: Some(i32::from(response.status().as_u16())),
+                error: None,
+                next_attempt_at: now,
+                delivered_at: Some(now),
+            };
+        },
+        Ok(response) => (
+            Some(i32::from(response.status().as_u16())),
+            format!("Réponse {}", response.status()),
+        ),
+        Err(error) => (None, error),
+    };
+
+    let status = if attempts >= max_attempts() {
+        DeliveryStatus::Failed
+    } else {
+        DeliveryStatus::Pending
+    };
+
+    Attempt {
+        status,
+        attempts,
+        status_code,
+        error: Some(error),
+        next_attempt_at: now + retry_delay(attempts),
+        delivered_at: None,
+    }
+}
+
+// Créer un abonnement dans MySQL
+pub async fn create(
+    pool: &MySqlPool,
+    domaine_id: i32,
+    url: String,
+    events: String,
+    secret: String,
+    created_by: Option<i32>,
+) -> Result<Webhook, sqlx::Error> {
+    let now = timestamps::now();
+
+    let insert_result = sqlx::query(
+        r#"
+        INSERT INTO webhooks (domaine_id, url, events, secret, created_at, created_by)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(domaine_id)
+    .bind(&url)
+    .bind(&events)
+    .bind(&secret)
+    .bind(now)
+    .bind(created_by)
+    .execute(pool)
+    .await?;
+
+    Ok(Webhook {
+        id: insert_result.last_insert_id() as i32,
+        domaine_id,
+        url,
+        events,
+        secret,
+        created_at: now,
+        created_by,
+    })
+}
+
+pub async fn get_by_domaine_id(pool: &MySqlPool, domaine_id: i32) -> Result<Vec<Webhook>, sqlx::Error> {
+    sqlx::query_as::<_, Webhook>(
+        r#"
+        SELECT id, domaine_id, url, events, secret, created_at, created_by
+        FROM webhooks
+        WHERE domaine_id = ?
+        ORDER BY id
+        "#,
+    )
+    .bind(domaine_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_by_id(pool: &MySqlPool, id: i32) -> Result<Webhook, sqlx::Error> {
+    sqlx::query_as::<_, Webhook>(
+        r#"
+        SELECT id, domaine_id, url, events, secret, created_at, created_by
+        FROM webhooks
+        WHERE id = ?
+        "#,
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await
+}
+
+// Supprimer un abonnement et, par cascade, ses livraisons
+pub async fn delete(pool: &MySqlPool, id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM webhooks WHERE id = ?").bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn create_delivery(
+    pool: &MySqlPool,
+    webhook_id: i32,
+    event_type: &str,
+    payload: String,
+) -> Result<WebhookDelivery, sqlx::Error> {
+    let now = timestamps::now();
+
+    let insert_result = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+        VALUES (?, ?, ?, ?, 0, ?, ?)
+        "#,
+    )
+    .bind(webhook_id)
+    .bind(event_type)
+    .bind(&payload)
+    .bind(DeliveryStatus::Pending)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await?;
+
+    Ok(WebhookDelivery {
+        id: insert_result.last_insert_id() as i32,
+        webhook_id,
+        event_type: event_type.to_string(),
+        payload,
+        status: DeliveryStatus::Pending,
+        attempts: 0,
+        next_attempt_at: now,
+        last_status_code: None,
+        last_error: None,
+        created_at: now,
+        delivered_at: None,
+    })
+}
+
+// Livraison lue dans MySQL ; le statut, rangé dans un VARCHAR, est lu sans vérification de type
+fn delivery_from_row(row: MySqlRow) -> Result<WebhookDelivery, sqlx::Error> {
+    Ok(WebhookDelivery {
+        id: row.try_get("id")?,
+        webhook_id: row.try_get("webhook_id")?,
+        event_type: row.try_get("event_type")?,
+        payload: row.try_get("payload")?,
+        status: row.try_get_unchecked("status")?,
+        attempts: row.try_get("attempts")?,
+        next_attempt_at: row.try_get("next_attempt_at")?,
+        last_status_code: row.try_get("last_status_code")?,
+        last_error: row.try_get("last_error")?,
+        created_at: row.try_get("created_at")?,
+        delivered_at: row.try_get("delivered_at")?,
+    })
+}
+
+// Journal des livraisons d'un abonnement, les plus récentes d'abord
+pub async fn get_deliveries(pool: &MySqlPool, webhook_id: i32) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    sqlx::query(
+        r#"
+        SELECT id, webhook_id, event_type, payload, status, attempts,
+               next_attempt_at, last_status_code, last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE webhook_id = ?
+        ORDER BY id DESC
+        "#,
+    )
+    .bind(webhook_id)
+    .try_map(delivery_from_row)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_delivery(pool: &MySqlPool, id: i32) -> Result<WebhookDelivery, sqlx::Error> {
+    sqlx::query(
+        r#"
+        SELECT id, webhook_id, event_type, payload, status, attempts,
+               next_attempt_at, last_status_code, last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE id = ?
+        "#,
+    )
+    .bind(id)
+    .try_map(delivery_from_row)
+    .fetch_one(pool)
+    .await
+}
+
+// Réserver les livraisons dues en repoussant leur prochaine tentative à `lease_until`
+// Une seule mise à jour les marque du jeton de ce passage, puis elles sont relues : deux workers
+// ne peuvent pas réserver la même livraison
+pub async fn claim_due(
+    pool: &MySqlPool,
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+    let lease_token = Uuid::new_v4().to_string();
+
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET next_attempt_at = ?, lease_token = ?
+        WHERE status = ? AND next_attempt_at <= ?
+        ORDER BY next_attempt_at, id
+        LIMIT ?
+        "#,
+    )
+    .bind(lease_until)
+    .bind(&lease_token)
+    .bind(DeliveryStatus::Pending)
+    .bind(now)
+    .bind(limit)
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        SELECT id, webhook_id, event_type, payload, status, attempts,
+               next_attempt_at, last_status_code, last_error, created_at, delivered_at
+        FROM webhook_deliveries
+        WHERE lease_token = ?
+        ORDER BY id
+        "#,
+    )
+    .bind(&lease_token)
+    .try_map(delivery_from_row)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn record_attempt(pool: &MySqlPool, id: i32, attempt: Attempt) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = ?, last_status_code = ?, last_error = ?, next_attempt_at = ?,
+            delivered_at = COALESCE(?, delivered_at)
+        WHERE id = ?
+        "#,
+    )
+    .bind(attempt.status)
+    .bind(attempt.attempts)
+    .bind(attempt.status_code)
+    .bind(attempt.error)
+    .bind(attempt.next_attempt_at)
+    .bind(attempt.delivered_at)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Remettre une livraison en file pour un envoi immédiat, compteur de tentatives remis à zéro
+pub async fn redeliver(pool: &MySqlPool, id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = 0, next_attempt_at = ?
+        WHERE id = ?
+        "#,
+    )
+    .bind(DeliveryStatus::Pending)
+    .bind(timestamps::now())
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
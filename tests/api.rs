@@ -0,0 +1,6 @@
+// Dépôts en mémoire : la suite tourne sans base de données
+async fn repositories() -> aquafarm_backend::repository::Repositories {
+    aquafarm_backend::repository::Repositories::in_memory()
+}
+
+include!("suite/api.rs");
@@ -0,0 +1,20 @@
+#![cfg(feature = "sqlite")]
+// La suite de tests/api.rs rejouée sur une base SQLite : cargo test --features sqlite
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+// Une base en mémoire vit autant que sa connexion : une seule, jamais fermée ni recyclée
+async fn repositories() -> aquafarm_backend::repository::Repositories {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    aquafarm_backend::schema::migrate_sqlite(&pool).await.unwrap();
+
+    aquafarm_backend::repository::Repositories::sqlite(pool)
+}
+
+include!("suite/api.rs");
@@ -0,0 +1,1678 @@
+// Suite d'intégration, jouée sur le dépôt en mémoire par tests/api.rs et sur SQLite par tests/sqlite.rs
+// Chacun fournit `repositories()`, qui renvoie des dépôts vides
+use actix_web::http::header::{self, HeaderMap, HeaderValue};
+use actix_web::{test, web, App};
+use serde_json::{json, Value};
+
+use aquafarm_backend::idempotency::Reservation;
+use aquafarm_backend::rate_limit::{self, Budget, RateLimiter};
+use aquafarm_backend::repository::Repositories;
+use aquafarm_backend::seed::{self, SeedOptions};
+use aquafarm_backend::sync::ClientIdReservation;
+use aquafarm_backend::tombstone::Entity;
+use aquafarm_backend::user::{self, NewUser};
+use aquafarm_backend::i18n::{self, Lang};
+use aquafarm_backend::{idempotency, metrics, routes, telemetry, timestamps};
+
+macro_rules! app {
+    ($repos:expr) => {
+        app!($repos, RateLimiter::new(None, None))
+    };
+    ($repos:expr, $rate_limiter:expr) => {
+        test::init_service(
+            App::new()
+                .app_data(web::Data::new($repos.clone()))
+                .app_data(web::Data::new($rate_limiter))
+                .wrap(actix_web::middleware::from_fn(idempotency::idempotency_middleware))
+                .wrap(actix_web::middleware::from_fn(rate_limit::rate_limit_middleware))
+                .wrap(actix_web::middleware::from_fn(telemetry::request_id_middleware))
+                .wrap(actix_web::middleware::from_fn(metrics::metrics_middleware))
+                .wrap(actix_web::middleware::from_fn(i18n::i18n_middleware))
+                .configure(routes::configure),
+        )
+        .await
+    };
+}
+
+const EMAIL: &str = "marie@aquafarm.test";
+const MOT_DE_PASSE: &str = "secret";
+
+// Créer un utilisateur dans le dépôt et renvoyer son en-tête Authorization
+async fn sign_in(repos: &Repositories) -> String {
+    std::env::set_var("JWT_SECRET", "test");
+
+    let type_user = repos
+        .references
+        .create_type_user("Pisciculteur".to_string(), None)
+        .await
+        .unwrap();
+    repos
+        .users
+        .create(
+            NewUser {
+                type_user_id: type_user.id,
+                nom: "Curie".to_string(),
+                prenom: "Marie".to_string(),
+                email: EMAIL.to_string(),
+                numero_telephone: "0600000000".to_string(),
+                mot_de_passe: MOT_DE_PASSE.to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let (_, token) = user::authenticate(repos.users.as_ref(), EMAIL.to_string(), MOT_DE_PASSE.to_string())
+        .await
+        .unwrap();
+
+    format!("Bearer {}", token)
+}
+
+#[actix_web::test]
+async fn login_checks_password() {
+    let repos = repositories().await;
+    sign_in(&repos).await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "email": EMAIL, "mot_de_passe": "mauvais" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 401);
+
+    let req = test::TestRequest::post()
+        .uri("/login")
+        .set_json(json!({ "email": EMAIL, "mot_de_passe": MOT_DE_PASSE }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["user"]["email"], EMAIL);
+    assert!(body["token"].is_string());
+}
+
+#[actix_web::test]
+async fn domaine_update_requires_current_etag() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::post()
+        .uri("/domaines/user/add")
+        .insert_header((header::AUTHORIZATION, auth.clone()))
+        .set_json(json!({ "user_id": 0, "nom_domaine": "Étang nord" }))
+        .to_request();
+    let domaine: Value = test::call_and_read_body_json(&app, req).await;
+    let uri = format!("/domaines/{}", domaine["id"]);
+
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"1\"");
+
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .set_json(json!({ "nom_domaine": "Étang sud" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 428);
+
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .insert_header((header::IF_MATCH, "\"1\""))
+        .set_json(json!({ "nom_domaine": "Étang sud" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"2\"");
+
+    let req = test::TestRequest::put()
+        .uri(&uri)
+        .insert_header((header::IF_MATCH, "\"1\""))
+        .set_json(json!({ "nom_domaine": "Étang est" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 412);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"2\"");
+
+    // Une liste passe dès qu'un de ses ETags est la version courante, « * » accepte toute version
+    let put = |if_match: &'static str, nom: &str| {
+        test::TestRequest::put()
+            .uri(&uri)
+            .insert_header((header::IF_MATCH, if_match))
+            .set_json(json!({ "nom_domaine": nom }))
+            .to_request()
+    };
+    let res = test::call_service(&app, put("\"1\", \"3\"", "Étang est")).await;
+    assert_eq!(res.status(), 412);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"2\"");
+    let res = test::call_service(&app, put("\"1\", W/\"2\"", "Étang est")).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"3\"");
+    let res = test::call_service(&app, put("*", "Étang ouest")).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"4\"");
+    let res = test::call_service(&app, put("\"4\", abc", "Étang ouest")).await;
+    assert_eq!(res.status(), 412);
+    let req = test::TestRequest::put()
+        .uri("/domaines/999")
+        .insert_header((header::IF_MATCH, "*"))
+        .set_json(json!({ "nom_domaine": "Étang ouest" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+}
+
+#[actix_web::test]
+async fn conditional_gets_use_last_modified_and_updated_since() {
+    let repos = repositories().await;
+    sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let domaine = repos.domaines.create(user.id, "Étang nord".to_string(), None).await.unwrap();
+    let uri = format!("/api/v1/domaines/{}", domaine.id);
+
+    let res = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(res.status(), 200);
+    let last_modified = res.headers().get(header::LAST_MODIFIED).unwrap().to_str().unwrap().to_string();
+    assert_eq!(last_modified, timestamps::last_modified(domaine.updated_at));
+
+    let since = |value: String| {
+        test::TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::IF_MODIFIED_SINCE, value))
+            .to_request()
+    };
+    let res = test::call_service(&app, since(last_modified)).await;
+    assert_eq!(res.status(), 304);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"1\"");
+    let earlier = timestamps::last_modified(domaine.updated_at - chrono::Duration::hours(1));
+    let res = test::call_service(&app, since(earlier)).await;
+    assert_eq!(res.status(), 200);
+
+    // Seules les lignes modifiées strictement après la date sont listées
+    let list = |updated_since: chrono::DateTime<chrono::Utc>| {
+        let updated_since = updated_since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/domaines?updated_since={}", updated_since))
+            .to_request()
+    };
+    let domaines: Vec<Value> =
+        test::call_and_read_body_json(&app, list(domaine.updated_at - chrono::Duration::seconds(1))).await;
+    assert_eq!(domaines.len(), 1);
+    assert_eq!(domaines[0]["id"], domaine.id);
+    let domaines: Vec<Value> = test::call_and_read_body_json(&app, list(domaine.updated_at)).await;
+    assert!(domaines.is_empty());
+}
+
+#[actix_web::test]
+async fn sync_maps_client_ids_and_reports_tombstones_and_conflicts() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+
+    let push = |mutations: Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/sync")
+            .insert_header((header::AUTHORIZATION, auth.clone()))
+            .set_json(json!({ "mutations": mutations }))
+            .to_request()
+    };
+    let pull = |since: Option<&str>| {
+        let uri = match since {
+            Some(token) => format!("/api/v1/sync?since={}", token),
+            None => "/api/v1/sync".to_string(),
+        };
+        test::TestRequest::get()
+            .uri(&uri)
+            .insert_header((header::AUTHORIZATION, auth.clone()))
+            .to_request()
+    };
+
+    // L'exploitation désigne son domaine par l'ID client créé dans le même envoi
+    let creations = json!([
+        { "client_id": "d1", "entity": "domaine", "op": "create", "data": { "nom_domaine": "Étang nord" } },
+        {
+            "client_id": "e1",
+            "entity": "exploitation",
+            "op": "create",
+            "data": { "domaine_id": "d1", "type_exploitation_id": bassin.id, "nom_exploitation": "Bassin 1" }
+        }
+    ]);
+    let res: Value = test::call_and_read_body_json(&app, push(creations.clone())).await;
+    let results = res["results"].as_array().unwrap();
+    assert!(results.iter().all(|r| r["status"] == "applied" && r["version"] == 1));
+    let domaine_id = results[0]["id"].as_i64().unwrap();
+    let exploitation_id = results[1]["id"].as_i64().unwrap();
+
+    // Un envoi rejoué ne crée rien de plus
+    let res: Value = test::call_and_read_body_json(&app, push(creations)).await;
+    assert_eq!(res["results"][0]["id"], domaine_id);
+    assert_eq!(res["results"][1]["id"], exploitation_id);
+
+    let changes: Value = test::call_and_read_body_json(&app, pull(None)).await;
+    assert_eq!(changes["domaines"].as_array().unwrap().len(), 1);
+    assert_eq!(changes["exploitations"][0]["domaine_id"], domaine_id);
+    assert_eq!(changes["deleted"], json!([]));
+    let token = changes["sync_token"].as_str().unwrap().to_string();
+
+    let res: Value = test::call_and_read_body_json(
+        &app,
+        push(json!([{ "client_id": "e1", "entity": "exploitation", "op": "delete", "id": "e1", "version": 1 }])),
+    )
+    .await;
+    assert_eq!(res["results"][0]["status"], "applied");
+
+    // La suppression revient comme une trace, plus comme une ligne
+    let changes: Value = test::call_and_read_body_json(&app, pull(Some(&token))).await;
+    assert_eq!(changes["exploitations"], json!([]));
+    assert_eq!(changes["deleted"].as_array().unwrap().len(), 1);
+    assert_eq!(changes["deleted"][0]["entity"], "exploitation");
+    assert_eq!(changes["deleted"][0]["entity_id"], exploitation_id);
+    assert_eq!(changes["deleted"][0]["domaine_id"], domaine_id);
+
+    // Une modification faite hors ligne sur une version dépassée est un conflit, pas une erreur du lot
+    let rename = |version: i32, nom: &str| {
+        json!({
+            "client_id": "d1",
+            "entity": "domaine",
+            "op": "update",
+            "id": domaine_id,
+            "version": version,
+            "data": { "nom_domaine": nom }
+        })
+    };
+    let res: Value = test::call_and_read_body_json(
+        &app,
+        push(json!([
+            rename(1, "Étang sud"),
+            rename(1, "Étang est"),
+            { "client_id": "e1", "entity": "exploitation", "op": "update", "id": exploitation_id, "version": 2 }
+        ])),
+    )
+    .await;
+    let results = res["results"].as_array().unwrap();
+    assert_eq!(results[0]["status"], "applied");
+    assert_eq!(results[0]["version"], 2);
+    assert_eq!(results[1]["status"], "conflict");
+    assert_eq!(results[1]["version"], 2);
+    assert_eq!(results[1]["message"], "La ressource a été modifiée entre-temps");
+    assert_eq!(results[2]["status"], "not_found");
+
+    let domaine = repos.domaines.get_by_id(domaine_id as i32).await.unwrap();
+    assert_eq!(domaine.nom_domaine, "Étang sud");
+}
+
+#[actix_web::test]
+async fn sync_create_reserves_client_id_before_creating() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+
+    let push = |data: Value| {
+        test::TestRequest::post()
+            .uri("/api/v1/sync")
+            .insert_header((header::AUTHORIZATION, auth.clone()))
+            .set_json(json!({
+                "mutations": [{ "client_id": "d1", "entity": "domaine", "op": "create", "data": data }]
+            }))
+            .to_request()
+    };
+
+    // Une création refusée libère l'identifiant : le nouvel essai crée la ligne
+    let res: Value = test::call_and_read_body_json(&app, push(json!({}))).await;
+    assert_eq!(res["results"][0]["status"], "invalid");
+    let res: Value = test::call_and_read_body_json(&app, push(json!({ "nom_domaine": "Étang nord" }))).await;
+    assert_eq!(res["results"][0]["status"], "applied");
+    let domaine_id = res["results"][0]["id"].as_i64().unwrap() as i32;
+
+    let now = timestamps::now();
+    let reserve = |client_id: &'static str, stale_before| {
+        let repos = repos.clone();
+        async move {
+            repos
+                .sync
+                .reserve_client_id(user.id, client_id, Entity::Domaine, stale_before)
+                .await
+                .unwrap()
+        }
+    };
+    assert!(matches!(
+        reserve("d1", now).await,
+        ClientIdReservation::Recorded(id) if id == domaine_id
+    ));
+
+    // Tant qu'une première requête crée la ligne, un envoi parallèle du même client_id est un conflit
+    let stale_before = now - chrono::Duration::seconds(60);
+    assert!(matches!(reserve("d2", stale_before).await, ClientIdReservation::Acquired));
+    assert!(matches!(reserve("d2", stale_before).await, ClientIdReservation::Pending));
+    let req = test::TestRequest::post()
+        .uri("/api/v1/sync")
+        .insert_header((header::AUTHORIZATION, auth.clone()))
+        .set_json(json!({
+            "mutations": [{ "client_id": "d2", "entity": "domaine", "op": "create", "data": { "nom_domaine": "Étang sud" } }]
+        }))
+        .to_request();
+    let res: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(res["results"][0]["status"], "conflict");
+    assert_eq!(repos.domaines.get_all_by_user_id(user.id, None).await.unwrap().len(), 1);
+
+    // Une réservation abandonnée est reprise passé le délai
+    let later = timestamps::now() + chrono::Duration::seconds(1);
+    assert!(matches!(reserve("d2", later).await, ClientIdReservation::Acquired));
+}
+
+#[actix_web::test]
+async fn batch_is_rolled_back_on_error() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::post()
+        .uri("/batch")
+        .insert_header((header::AUTHORIZATION, auth.clone()))
+        .set_json(json!({
+            "operations": [
+                { "op": "create_domaine", "nom_domaine": "Étang nord" },
+                { "op": "create_exploitation", "domaine_id": "$5", "type_exploitation_id": 1, "nom_exploitation": "Bassin" }
+            ]
+        }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 400);
+    let error: Value = test::read_body_json(res).await;
+    assert_eq!(error["index"], 1);
+
+    let req = test::TestRequest::get()
+        .uri("/domaines/user")
+        .insert_header((header::AUTHORIZATION, auth))
+        .to_request();
+    let domaines: Vec<Value> = test::call_and_read_body_json(&app, req).await;
+    assert!(domaines.is_empty());
+}
+
+#[actix_web::test]
+async fn idempotency_key_replays_first_response() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let create = || {
+        test::TestRequest::post()
+            .uri("/domaines/user/add")
+            .insert_header((header::AUTHORIZATION, auth.clone()))
+            .insert_header(("Idempotency-Key", "creation-etang"))
+            .set_json(json!({ "user_id": 0, "nom_domaine": "Étang nord" }))
+            .to_request()
+    };
+
+    let res = test::call_service(&app, create()).await;
+    assert_eq!(res.status(), 200);
+    assert!(res.headers().get("Idempotent-Replayed").is_none());
+    let first = test::read_body(res).await;
+
+    let res = test::call_service(&app, create()).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get("Idempotent-Replayed").unwrap(), "true");
+    assert_eq!(test::read_body(res).await, first);
+
+    let req = test::TestRequest::get()
+        .uri("/domaines/user")
+        .insert_header((header::AUTHORIZATION, auth.clone()))
+        .to_request();
+    let domaines: Vec<Value> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(domaines.len(), 1);
+}
+
+#[actix_web::test]
+async fn idempotency_never_stores_anonymous_or_token_responses() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let login = |authorization: Option<String>| {
+        let mut req = test::TestRequest::post()
+            .uri("/login")
+            .insert_header(("Idempotency-Key", "connexion"))
+            .set_json(json!({ "email": EMAIL, "mot_de_passe": MOT_DE_PASSE }));
+        if let Some(authorization) = authorization {
+            req = req.insert_header((header::AUTHORIZATION, authorization));
+        }
+        req.to_request()
+    };
+
+    // Sans utilisateur, la clé est ignorée : aucune réponse n'est partagée entre clients
+    for _ in 0..2 {
+        let res = test::call_service(&app, login(None)).await;
+        assert_eq!(res.status(), 200);
+        assert!(res.headers().get("Idempotent-Replayed").is_none());
+    }
+
+    // Connecté, la réponse porte un token marqué no-store et n'est pas conservée
+    for _ in 0..2 {
+        let res = test::call_service(&app, login(Some(auth.clone()))).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers().get(header::CACHE_CONTROL).unwrap(), "no-store");
+        assert!(res.headers().get("Idempotent-Replayed").is_none());
+    }
+}
+
+#[actix_web::test]
+async fn idempotency_reclaims_stale_reservations_and_purges_expired_keys() {
+    let repos = repositories().await;
+    let now = timestamps::now();
+    let stale_before = now - chrono::Duration::seconds(60);
+    let expired_before = now - chrono::Duration::days(1);
+    let reserve = |stale_before, expired_before| {
+        let repos = repos.clone();
+        async move {
+            repos
+                .idempotency
+                .reserve("1", "cle", "empreinte", stale_before, expired_before)
+                .await
+                .unwrap()
+        }
+    };
+
+    assert!(matches!(reserve(stale_before, expired_before).await, Reservation::Acquired));
+    assert!(matches!(
+        reserve(stale_before, expired_before).await,
+        Reservation::Existing(record) if record.status_code.is_none()
+    ));
+
+    // La première requête s'est interrompue : passé le délai de verrou, la clé est reprise
+    let later = now + chrono::Duration::seconds(1);
+    assert!(matches!(reserve(later, expired_before).await, Reservation::Acquired));
+
+    // Une réponse enregistrée n'est pas reprise avant son expiration
+    repos.idempotency.store("1", "cle", 201, None, b"{}").await.unwrap();
+    assert!(matches!(
+        reserve(later, expired_before).await,
+        Reservation::Existing(record) if record.status_code == Some(201)
+    ));
+
+    assert_eq!(repos.idempotency.purge(expired_before).await.unwrap(), 0);
+    assert_eq!(repos.idempotency.purge(later).await.unwrap(), 1);
+    assert!(matches!(reserve(stale_before, expired_before).await, Reservation::Acquired));
+}
+
+#[actix_web::test]
+async fn request_id_is_echoed_or_generated() {
+    let repos = repositories().await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::get()
+        .uri("/domaines/user")
+        .insert_header(("X-Request-Id", "trace-42"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.headers().get("X-Request-Id").unwrap(), "trace-42");
+
+    let req = test::TestRequest::get()
+        .uri("/domaines/user")
+        .insert_header(("X-Request-Id", "pas valide !"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    let generated = res.headers().get("X-Request-Id").unwrap().to_str().unwrap();
+    assert_ne!(generated, "pas valide !");
+    assert_eq!(generated.len(), 36);
+}
+
+#[actix_web::test]
+async fn metrics_count_requests_by_route() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::post()
+        .uri("/domaines/user/add")
+        .insert_header((header::AUTHORIZATION, auth.clone()))
+        .set_json(json!({ "user_id": 0, "nom_domaine": "Étang sud" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+    let req = test::TestRequest::get().uri("/metrics").to_request();
+    let body = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+
+    assert!(body.contains(r#"route="/domaines/user/add",status="200""#));
+    assert!(body.contains("aquafarm_http_request_duration_seconds_bucket"));
+    assert!(body.contains("aquafarm_domaines 1"));
+}
+
+#[actix_web::test]
+async fn health_endpoints_report_ready() {
+    let repos = repositories().await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::get().uri("/health/live").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+    let req = test::TestRequest::get().uri("/health/ready").to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["statut"], "ok");
+    assert_eq!(body["migrations"], "ok");
+}
+
+#[actix_web::test]
+async fn openapi_document_lists_routes_and_auth() {
+    let repos = repositories().await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::get().uri("/openapi.json").to_request();
+    let doc: Value = test::call_and_read_body_json(&app, req).await;
+
+    assert!(doc["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(doc["paths"]["/api/v1/domaines/{id}"]["put"].is_object());
+    assert!(doc["components"]["schemas"]["Production"].is_object());
+    assert_eq!(doc["components"]["securitySchemes"]["bearer"]["scheme"], "bearer");
+
+    // Les routes historiques de création restent documentées jusqu'à leur retrait
+    assert!(doc["paths"]["/elements"]["post"].is_object());
+    assert!(doc["paths"]["/productions/element/{element_id}"]["get"].is_object());
+    assert!(doc["components"]["schemas"]["CreateElement"].is_object());
+}
+
+#[actix_web::test]
+async fn v1_nests_collections_and_legacy_routes_are_deprecated() {
+    let repos = repositories().await;
+    let auth = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/users/me/domaines")
+        .insert_header((header::AUTHORIZATION, auth.clone()))
+        .set_json(json!({ "user_id": 0, "nom_domaine": "Étang ouest" }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert!(res.headers().get("Deprecation").is_none());
+    let domaine: Value = test::read_body_json(res).await;
+
+    let type_exploitation = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/v1/domaines/{}/exploitations", domaine["id"]))
+        .set_json(json!({ "type_exploitation_id": type_exploitation.id, "nom_exploitation": "Bassin A" }))
+        .to_request();
+    let exploitation: Value = test::call_and_read_body_json(&app, req).await;
+
+    // Une exploitation demandée sous un autre domaine est introuvable
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/domaines/999/exploitations/{}/elements", exploitation["id"]))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    let req = test::TestRequest::get()
+        .uri(&format!("/exploitations/domaine/{}", domaine["id"]))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get("Deprecation").unwrap(), "true");
+    assert!(res.headers().get("Sunset").is_some());
+}
+
+#[actix_web::test]
+async fn domaine_tree_nests_hierarchy_with_latest_production() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+    let element = repos
+        .elements
+        .create(exploitation.id, "Tilapia".to_string(), 500, None)
+        .await
+        .unwrap();
+    for (jour, quantite) in [(2, 12), (3, 15), (1, 9)] {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 5, jour).unwrap();
+        repos
+            .productions
+            .create(element.id, quantite, "kg".to_string(), date, None)
+            .await
+            .unwrap();
+    }
+
+    let tree_of = |id: i32| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/domaines/{}/tree", id))
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request()
+    };
+    let tree: Value = test::call_and_read_body_json(&app, tree_of(domaine.id)).await;
+
+    assert_eq!(tree["nom_domaine"], "Ferme du lac");
+    let exploitation = &tree["exploitations"][0];
+    assert_eq!(exploitation["type_exploitation"]["nom_type_exploitation"], "Bassin");
+    let derniere = &exploitation["elements"][0]["derniere_production"];
+    assert_eq!(derniere["date_de_production"], "2024-05-03");
+    assert_eq!(derniere["quantite_produite"], 15);
+    assert_eq!(derniere["nombre_productions"], 3);
+
+    let req = tree_of(999);
+    assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+    // Sans token, ou sur le domaine d'un autre utilisateur, l'arbre reste fermé
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/domaines/{}/tree", domaine.id))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 401);
+    let voisin = repos
+        .users
+        .create(
+            NewUser {
+                type_user_id: user.type_user_id,
+                nom: "Pasteur".to_string(),
+                prenom: "Louis".to_string(),
+                email: "louis@aquafarm.test".to_string(),
+                numero_telephone: "0600000001".to_string(),
+                mot_de_passe: "autre".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    let autre = repos.domaines.create(voisin.id, "Élevage voisin".to_string(), None).await.unwrap();
+    assert_eq!(test::call_service(&app, tree_of(autre.id)).await.status(), 403);
+}
+
+#[actix_web::test]
+async fn graphql_resolves_owned_hierarchy_and_requires_token() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/graphql")
+        .set_json(json!({ "query": "{ domaines { id } }" }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["errors"][0]["extensions"]["code"], "UNAUTHORIZED");
+
+    let mutation = r#"mutation { d: createDomaine(nomDomaine: "Ferme du lac") { id } }"#;
+    let req = test::TestRequest::post()
+        .uri("/api/v1/graphql")
+        .insert_header((header::AUTHORIZATION, authorization.clone()))
+        .set_json(json!({ "query": mutation }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let domaine_id = body["data"]["d"]["id"].as_i64().unwrap() as i32;
+
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine_id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+    repos
+        .elements
+        .create(exploitation.id, "Tilapia".to_string(), 500, None)
+        .await
+        .unwrap();
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/graphql")
+        .insert_header((header::AUTHORIZATION, authorization))
+        .set_json(json!({
+            "query": "{ domaines { nomDomaine exploitations { typeExploitation { nomTypeExploitation } elements { nomElement productions { id } } } } }"
+        }))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    let domaine = &body["data"]["domaines"][0];
+    assert_eq!(domaine["nomDomaine"], "Ferme du lac");
+    let exploitation = &domaine["exploitations"][0];
+    assert_eq!(exploitation["typeExploitation"]["nomTypeExploitation"], "Bassin");
+    assert_eq!(exploitation["elements"][0]["nomElement"], "Tilapia");
+    assert_eq!(exploitation["elements"][0]["productions"], json!([]));
+}
+
+#[actix_web::test]
+async fn domaine_events_stream_writes_to_owner() {
+    use actix_web::body::MessageBody;
+    use std::pin::Pin;
+
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let uri = format!("/api/v1/domaines/{}/events", domaine.id);
+
+    let req = test::TestRequest::get().uri(&uri).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 401);
+
+    let req = test::TestRequest::get()
+        .uri(&uri)
+        .insert_header((header::AUTHORIZATION, authorization))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "text/event-stream");
+    let mut body = res.into_body();
+
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+
+    // Les commentaires de maintien de connexion sont ignorés
+    let mut received = String::new();
+    while !received.contains("event: change") {
+        let chunk = std::future::poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+            .await
+            .unwrap()
+            .unwrap();
+        received.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    assert!(received.contains(r#""entity":"exploitation""#));
+    assert!(received.contains(r#""operation":"create""#));
+    assert!(received.contains(&format!(r#""id":{}"#, exploitation.id)));
+}
+
+#[actix_web::test]
+async fn webhook_deliveries_are_claimed_once_until_the_lease_ends() {
+    let repos = repositories().await;
+    sign_in(&repos).await;
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let webhook = repos
+        .webhooks
+        .create_webhook(
+            domaine.id,
+            "https://example.com/hook".to_string(),
+            "production.create".to_string(),
+            "secret".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    for _ in 0..3 {
+        repos.webhooks.create_delivery(webhook.id, "production.create", "{}".to_string()).await.unwrap();
+    }
+
+    let now = timestamps::now() + chrono::Duration::seconds(1);
+    let lease_until = now + chrono::Duration::seconds(600);
+    let first = repos.webhooks.claim_due_deliveries(now, lease_until, 2).await.unwrap();
+    assert_eq!(first.len(), 2);
+    let second = repos.webhooks.claim_due_deliveries(now, lease_until, 2).await.unwrap();
+    assert_eq!(second.len(), 1);
+    assert!(first.iter().all(|d| d.id != second[0].id));
+    assert!(repos.webhooks.claim_due_deliveries(now, lease_until, 2).await.unwrap().is_empty());
+
+    // Une réservation échue sans résultat enregistré est reprise
+    let later = lease_until + chrono::Duration::seconds(1);
+    let reclaimed = repos.webhooks.claim_due_deliveries(later, later, 10).await.unwrap();
+    assert_eq!(reclaimed.len(), 3);
+}
+
+#[actix_web::test]
+async fn webhooks_deliver_signed_events_and_redeliver() {
+    use actix_web::{HttpRequest, HttpResponse, HttpServer};
+    use aquafarm_backend::webhook;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+
+    // Destinataire local, seule adresse interne autorisée : transmet l'en-tête de signature et le corps
+    // reçus ; /redirect renvoie vers /hook
+    std::env::set_var("WEBHOOK_ALLOWED_NETWORKS", "127.0.0.1/32");
+    let (sender, mut received) = mpsc::unbounded_channel::<(String, String)>();
+    let receiver = HttpServer::new(move || {
+        let sender = sender.clone();
+        App::new()
+            .route(
+                "/hook",
+                web::post().to(move |req: HttpRequest, body: String| {
+                    let signature = req.headers().get(webhook::SIGNATURE_HEADER).unwrap().to_str().unwrap().to_string();
+                    sender.send((signature, body)).unwrap();
+                    async { HttpResponse::Ok().finish() }
+                }),
+            )
+            .route(
+                "/redirect",
+                web::post().to(|| async {
+                    HttpResponse::TemporaryRedirect().insert_header((header::LOCATION, "/hook")).finish()
+                }),
+            )
+    })
+    .workers(1)
+    .bind(("127.0.0.1", 0))
+    .unwrap();
+    let url = format!("http://{}/hook", receiver.addrs()[0]);
+    actix_web::rt::spawn(receiver.run());
+
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+    let element = repos
+        .elements
+        .create(exploitation.id, "Tilapia".to_string(), 500, None)
+        .await
+        .unwrap();
+
+    let req = test::TestRequest::post()
+        .uri(&format!("/api/v1/domaines/{}/webhooks", domaine.id))
+        .insert_header((header::AUTHORIZATION, authorization.clone()))
+        .set_json(json!({ "url": url, "events": ["production.inconnue"] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+    // Les adresses internes non autorisées sont refusées, qu'elles soient écrites ou résolues
+    let subscribe = |url: &str| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/domaines/{}/webhooks", domaine.id))
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .set_json(json!({ "url": url, "events": ["production.create"] }))
+            .to_request()
+    };
+    for interne in ["http://169.254.169.254/latest/meta-data", "http://10.0.0.1/hook", "http://[::1]:8080/hook"] {
+        let res = test::call_service(&app, subscribe(interne)).await;
+        assert_eq!(res.status(), 400);
+        let message = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(message.starts_with("Adresse interdite pour un webhook"));
+    }
+    let redirect_url = url.replace("/hook", "/redirect");
+    let res = test::call_service(&app, subscribe(&redirect_url)).await;
+    assert_eq!(res.status(), 201);
+    let redirected: Value = test::read_body_json(res).await;
+
+    let res = test::call_service(&app, subscribe(&url)).await;
+    assert_eq!(res.status(), 201);
+    let created: Value = test::read_body_json(res).await;
+    let secret = created["secret"].as_str().unwrap().to_string();
+    assert_eq!(created["events"], json!(["production.create"]));
+
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 5, 2).unwrap();
+    let production = repos
+        .productions
+        .create(element.id, 12, "kg".to_string(), date, None)
+        .await
+        .unwrap();
+
+    // La livraison est enregistrée avec l'écriture, avant même le démarrage du worker
+    let webhook_id = created["id"].as_i64().unwrap() as i32;
+    assert_eq!(repos.webhooks.get_deliveries(webhook_id).await.unwrap().len(), 1);
+    webhook::start(repos.clone());
+
+    let (signature, body) = tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let timestamp: i64 = signature
+        .strip_prefix("t=")
+        .and_then(|rest| rest.split(',').next())
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(signature, webhook::sign(&secret, timestamp, body.as_bytes()));
+    let payload: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(payload["event"], "production.create");
+    assert_eq!(payload["data"]["id"], production.id);
+
+    // Le résultat de la tentative est enregistré juste après la réponse du destinataire
+    let deliveries_uri = format!("/api/v1/webhooks/{}/deliveries", created["id"]);
+    let mut deliveries = Value::Null;
+    for _ in 0..50 {
+        let req = test::TestRequest::get()
+            .uri(&deliveries_uri)
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request();
+        deliveries = test::call_and_read_body_json(&app, req).await;
+        if deliveries[0]["status"] == "delivered" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(deliveries.as_array().unwrap().len(), 1);
+    assert_eq!(deliveries[0]["status"], "delivered");
+    assert_eq!(deliveries[0]["attempts"], 1);
+
+    // La redirection n'est pas suivie : la livraison reste en échec avec le code reçu
+    let redirected_uri = format!("/api/v1/webhooks/{}/deliveries", redirected["id"]);
+    let mut redirected_deliveries = Value::Null;
+    for _ in 0..50 {
+        let req = test::TestRequest::get()
+            .uri(&redirected_uri)
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request();
+        redirected_deliveries = test::call_and_read_body_json(&app, req).await;
+        if redirected_deliveries[0]["attempts"] == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(redirected_deliveries[0]["status"], "pending");
+    assert_eq!(redirected_deliveries[0]["last_status_code"], 307);
+    assert!(received.try_recv().is_err());
+
+    let req = test::TestRequest::post()
+        .uri(&format!("{}/{}/redeliver", deliveries_uri, deliveries[0]["id"]))
+        .insert_header((header::AUTHORIZATION, authorization))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 202);
+    let (_, body_again) = tokio::time::timeout(Duration::from_secs(10), received.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(body_again, body);
+}
+
+#[actix_web::test]
+async fn rate_limits_reads_and_writes_separately_per_client() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let limiter = RateLimiter::new(Some(Budget::per_minute(3)), Some(Budget::per_minute(1)))
+        .with_api_keys(["capteur-1".to_string()]);
+    let app = app!(repos, limiter);
+
+    let get = |peer: &str| {
+        test::TestRequest::get()
+            .uri("/api/v1/types_user")
+            .peer_addr(peer.parse().unwrap())
+            .to_request()
+    };
+
+    for remaining in ["2", "1", "0"] {
+        let res = test::call_service(&app, get("10.0.0.1:4000")).await;
+        assert_eq!(res.status(), 200);
+        assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "3");
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), remaining);
+        assert_eq!(res.headers().get("ratelimit-policy").unwrap(), "3;w=60");
+    }
+    let res = test::call_service(&app, get("10.0.0.1:4001")).await;
+    assert_eq!(res.status(), 429);
+    assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "20");
+
+    // Autre adresse, autre seau
+    assert_eq!(test::call_service(&app, get("10.0.0.2:4000")).await.status(), 200);
+
+    // Les écritures ont leur propre budget, compté par utilisateur quelle que soit l'adresse
+    let post = |peer: &str| {
+        test::TestRequest::post()
+            .uri("/api/v1/types_user")
+            .peer_addr(peer.parse().unwrap())
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .set_json(json!({ "nom_type_user": "Technicien" }))
+            .to_request()
+    };
+    let res = test::call_service(&app, post("10.0.0.1:4000")).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "1");
+    assert_eq!(test::call_service(&app, post("10.0.0.3:4000")).await.status(), 429);
+
+    // Une clé d'API déclarée a son propre seau ; une clé inconnue reste comptée avec son adresse
+    let with_key = |key: &str| {
+        test::TestRequest::get()
+            .uri("/api/v1/types_user")
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .insert_header((rate_limit::API_KEY_HEADER, key.to_string()))
+            .to_request()
+    };
+    assert_eq!(test::call_service(&app, with_key("inconnue")).await.status(), 429);
+    let res = test::call_service(&app, with_key("capteur-1")).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "2");
+
+    // La supervision n'est jamais limitée
+    for _ in 0..5 {
+        let req = test::TestRequest::get().uri("/health/live").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+}
+
+#[actix_web::test]
+async fn rate_limit_reads_client_behind_trusted_proxies_only() {
+    let repos = repositories().await;
+    let limiter = RateLimiter::new(Some(Budget::per_minute(1)), None)
+        .with_trusted_proxies(["10.0.0.0/8".parse().unwrap()]);
+    let app = app!(repos, limiter);
+
+    let get = |peer: &str, forwarded_for: Option<&str>| {
+        let mut req = test::TestRequest::get()
+            .uri("/api/v1/types_user")
+            .peer_addr(peer.parse().unwrap());
+        if let Some(forwarded_for) = forwarded_for {
+            req = req.insert_header(("X-Forwarded-For", forwarded_for.to_string()));
+        }
+        req.to_request()
+    };
+
+    // Derrière le proxy, chaque client a son seau : le saut le plus à droite hors des proxys
+    assert_eq!(test::call_service(&app, get("10.0.0.1:4000", Some("203.0.113.7"))).await.status(), 200);
+    assert_eq!(test::call_service(&app, get("10.0.0.1:4000", Some("203.0.113.8"))).await.status(), 200);
+
+    // Une adresse ajoutée à gauche par le client ne lui ouvre pas un nouveau seau
+    let spoofed = get("10.0.0.1:4000", Some("198.51.100.1, 203.0.113.7, 10.0.0.2"));
+    assert_eq!(test::call_service(&app, spoofed).await.status(), 429);
+
+    // Un client connecté directement ne choisit pas son adresse
+    assert_eq!(test::call_service(&app, get("192.0.2.1:4000", Some("203.0.113.9"))).await.status(), 200);
+    assert_eq!(test::call_service(&app, get("192.0.2.1:4000", Some("203.0.113.10"))).await.status(), 429);
+}
+
+#[actix_web::test]
+async fn search_ignores_accents_ranks_and_hides_other_users_domaines() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let voisin = repos
+        .users
+        .create(
+            NewUser {
+                type_user_id: user.type_user_id,
+                nom: "Pasteur".to_string(),
+                prenom: "Louis".to_string(),
+                email: "louis@aquafarm.test".to_string(),
+                numero_telephone: "0600000001".to_string(),
+                mot_de_passe: "autre".to_string(),
+            },
+            None,
+        )
+        .await
+        .unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin d'élevage".to_string(), None)
+        .await
+        .unwrap();
+    let autre = repos.domaines.create(voisin.id, "Élevage voisin".to_string(), None).await.unwrap();
+    repos
+        .exploitations
+        .create(bassin.id, autre.id, "Bassin d'élevage".to_string(), None)
+        .await
+        .unwrap();
+
+    let search = |q: &str| {
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/search?q={}", q))
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request()
+    };
+
+    let hits: Value = test::call_and_read_body_json(&app, search("ELEVAGE")).await;
+    assert_eq!(
+        hits,
+        json!([{
+            "kind": "exploitation",
+            "id": exploitation.id,
+            "nom": "Bassin d'élevage",
+            "domaine_id": domaine.id,
+            "exploitation_id": null,
+            "score": 3,
+        }])
+    );
+
+    // Le type dont le nom est exactement la recherche passe devant
+    let hits: Value = test::call_and_read_body_json(&app, search("bass")).await;
+    assert_eq!(hits.as_array().unwrap().len(), 2);
+    let hits: Value = test::call_and_read_body_json(&app, search("bassin")).await;
+    assert_eq!(hits[0]["kind"], "type_exploitation");
+    assert_eq!(hits[1]["kind"], "exploitation");
+    let hits: Value = test::call_and_read_body_json(&app, search("bassin&limit=1")).await;
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    assert_eq!(hits[0]["kind"], "type_exploitation");
+
+    let req = test::TestRequest::get().uri("/api/v1/search?q=bassin").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 401);
+}
+
+#[actix_web::test]
+async fn trash_restores_rows_after_their_parent_and_purges_them() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+    let element = repos
+        .elements
+        .create(exploitation.id, "Tilapias".to_string(), 200, None)
+        .await
+        .unwrap();
+    let production = repos
+        .productions
+        .create(
+            element.id,
+            40,
+            "kg".to_string(),
+            chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let delete = |uri: String, version: i32| {
+        test::TestRequest::delete()
+            .uri(&uri)
+            .insert_header((header::IF_MATCH, format!("\"{}\"", version)))
+            .to_request()
+    };
+    let restore = |entity: &str, id: i32| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/users/me/trash/{}/{}/restore", entity, id))
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request()
+    };
+    let trash = || {
+        test::TestRequest::get()
+            .uri("/api/v1/users/me/trash")
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request()
+    };
+
+    // La production à la corbeille ne retient plus son élément
+    let production_uri = format!("/api/v1/productions/{}", production.id);
+    let res = test::call_service(&app, delete(production_uri.clone(), 1)).await;
+    assert_eq!(res.status(), 200);
+    let res = test::call_service(&app, test::TestRequest::get().uri(&production_uri).to_request()).await;
+    assert_eq!(res.status(), 404);
+    let res = test::call_service(&app, delete(format!("/api/v1/elements/{}", element.id), 1)).await;
+    assert_eq!(res.status(), 200);
+
+    let items: Value = test::call_and_read_body_json(&app, trash()).await;
+    let items = items.as_array().unwrap();
+    let mut entities: Vec<&str> = items.iter().map(|i| i["entity"].as_str().unwrap()).collect();
+    entities.sort_unstable();
+    assert_eq!(entities, ["element", "production"]);
+    assert!(items.iter().all(|i| i["nom"] == "Tilapias" && i["domaine_id"] == domaine.id));
+
+    // Une ligne à la corbeille n'appartient plus à personne, sauf pour sa restauration
+    let owner_of = repos.domaines.owner_of(Entity::Production, production.id).await.unwrap();
+    assert_eq!(owner_of, None);
+    let owner = repos.trash.owner_including_trash(Entity::Production, production.id).await.unwrap();
+    assert_eq!(owner, Some(user.id));
+
+    // La production attend son élément
+    let res = test::call_service(&app, restore("production", production.id)).await;
+    assert_eq!(res.status(), 409);
+    let res = test::call_service(&app, restore("element", element.id)).await;
+    assert_eq!(res.status(), 200);
+    let res = test::call_service(&app, restore("production", production.id)).await;
+    assert_eq!(res.status(), 200);
+    let res = test::call_service(&app, restore("production", production.id)).await;
+    assert_eq!(res.status(), 404);
+
+    // Une nouvelle version à chaque passage par la corbeille
+    let res = test::call_service(&app, test::TestRequest::get().uri(&production_uri).to_request()).await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"3\"");
+
+    let res = test::call_service(&app, delete(production_uri.clone(), 3)).await;
+    assert_eq!(res.status(), 200);
+    let purged = repos
+        .trash
+        .purge(timestamps::now() + chrono::Duration::days(1))
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let items: Value = test::call_and_read_body_json(&app, trash()).await;
+    assert_eq!(items, json!([]));
+    // La trace de suppression survit à la purge, pour les clients hors ligne
+    let changes = repos.sync.changes_since(user.id, chrono::DateTime::<chrono::Utc>::MIN_UTC).await.unwrap();
+    assert!(changes.deleted.iter().any(|t| t.entity == Entity::Production && t.entity_id == production.id));
+    let res = test::call_service(&app, restore("production", production.id)).await;
+    assert_eq!(res.status(), 404);
+}
+
+#[actix_web::test]
+async fn deleting_a_parent_reports_dependents_or_cascades() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+    let tilapias = repos
+        .elements
+        .create(exploitation.id, "Tilapias".to_string(), 200, None)
+        .await
+        .unwrap();
+    let carpes = repos
+        .elements
+        .create(exploitation.id, "Carpes".to_string(), 80, None)
+        .await
+        .unwrap();
+    let production = repos
+        .productions
+        .create(
+            tilapias.id,
+            40,
+            "kg".to_string(),
+            chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let delete = |uri: String, version: i32| {
+        test::TestRequest::delete()
+            .uri(&uri)
+            .insert_header((header::IF_MATCH, format!("\"{}\"", version)))
+            .to_request()
+    };
+    let domaine_uri = format!("/api/v1/domaines/{}", domaine.id);
+    let exploitation_uri = format!("/api/v1/exploitations/{}", exploitation.id);
+
+    let res = test::call_service(&app, delete(domaine_uri.clone(), 1)).await;
+    assert_eq!(res.status(), 409);
+    let body: Value = test::read_body_json(res).await;
+    assert_eq!(
+        body,
+        json!({
+            "message": "Suppression impossible : des lignes en dépendent. Supprimez-les d'abord ou ajoutez ?cascade=true",
+            "dependents": { "exploitations": 1, "elements": 2, "productions": 1 },
+        })
+    );
+    let req = test::TestRequest::delete()
+        .uri(&domaine_uri)
+        .insert_header((header::IF_MATCH, "\"1\""))
+        .insert_header((header::ACCEPT_LANGUAGE, "en"))
+        .to_request();
+    let body: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(
+        body["message"],
+        "Cannot delete: other rows depend on this one. Delete them first or add ?cascade=true"
+    );
+
+    // Le même refus annule un lot, et les descendants ne comptent que s'ils sont en place
+    let req = test::TestRequest::post()
+        .uri("/api/v1/batch")
+        .insert_header((header::AUTHORIZATION, authorization.clone()))
+        .set_json(json!({
+            "operations": [
+                { "op": "delete_production", "id": production.id, "version": 1 },
+                { "op": "delete_element", "id": tilapias.id, "version": 1 },
+                { "op": "delete_exploitation", "id": exploitation.id, "version": 1 }
+            ]
+        }))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 409);
+    let error: Value = test::read_body_json(res).await;
+    assert_eq!(error["index"], 2);
+    assert_eq!(error["message"], "Suppression impossible : des lignes en dépendent");
+    let req = test::TestRequest::get()
+        .uri(&format!("/api/v1/productions/{}", production.id))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), 200);
+
+    // Version périmée : rien n'est supprimé
+    let res = test::call_service(&app, delete(format!("{}?cascade=true", exploitation_uri), 2)).await;
+    assert_eq!(res.status(), 412);
+    let res = test::call_service(&app, test::TestRequest::get().uri(&exploitation_uri).to_request()).await;
+    assert_eq!(res.status(), 200);
+
+    let res = test::call_service(&app, delete(format!("{}?cascade=true", exploitation_uri), 1)).await;
+    assert_eq!(res.status(), 200);
+    let deleted: Value = test::read_body_json(res).await;
+    assert_eq!(
+        deleted,
+        json!({
+            "domaines": [],
+            "exploitations": [exploitation.id],
+            "elements": [tilapias.id, carpes.id],
+            "productions": [production.id],
+        })
+    );
+    let res = test::call_service(
+        &app,
+        test::TestRequest::get().uri(&format!("/api/v1/elements/{}", carpes.id)).to_request(),
+    )
+    .await;
+    assert_eq!(res.status(), 404);
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/users/me/trash")
+        .insert_header((header::AUTHORIZATION, authorization))
+        .to_request();
+    let items: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(items.as_array().unwrap().len(), 4);
+
+    // Le domaine, désormais vide, se supprime sans cascade
+    let res = test::call_service(&app, delete(domaine_uri, 1)).await;
+    assert_eq!(res.status(), 200);
+}
+
+#[actix_web::test]
+async fn restoring_a_cascaded_parent_brings_back_its_subtree() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+    let domaine = repos.domaines.create(user.id, "Ferme du lac".to_string(), None).await.unwrap();
+    let exploitation = repos
+        .exploitations
+        .create(bassin.id, domaine.id, "Bassin nord".to_string(), None)
+        .await
+        .unwrap();
+    let element = repos
+        .elements
+        .create(exploitation.id, "Tilapias".to_string(), 200, None)
+        .await
+        .unwrap();
+    let production = repos
+        .productions
+        .create(
+            element.id,
+            40,
+            "kg".to_string(),
+            chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+    // Supprimée juste avant la cascade, dans la même seconde : elle n'en fait pas partie
+    let ancienne = repos
+        .productions
+        .create(
+            element.id,
+            12,
+            "kg".to_string(),
+            chrono::NaiveDate::from_ymd_opt(2024, 4, 1).unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert!(repos.productions.delete(ancienne.id, 1).await.unwrap());
+
+    let restore = |entity: &str, id: i32| {
+        test::TestRequest::post()
+            .uri(&format!("/api/v1/users/me/trash/{}/{}/restore", entity, id))
+            .insert_header((header::AUTHORIZATION, authorization.clone()))
+            .to_request()
+    };
+
+    let req = test::TestRequest::delete()
+        .uri(&format!("/api/v1/domaines/{}?cascade=true", domaine.id))
+        .insert_header((header::IF_MATCH, "\"1\""))
+        .to_request();
+    let deleted: Value = test::call_and_read_body_json(&app, req).await;
+
+    // Un enfant attend toujours son parent
+    let res = test::call_service(&app, restore("element", element.id)).await;
+    assert_eq!(res.status(), 409);
+    let body = test::read_body(res).await;
+    assert_eq!(body, "Le parent est à la corbeille : restaurez-le d'abord");
+
+    // Restaurer le domaine ramène tout ce que la cascade avait supprimé
+    let res = test::call_service(&app, restore("domaine", domaine.id)).await;
+    assert_eq!(res.status(), 200);
+    let restored: Value = test::read_body_json(res).await;
+    assert_eq!(restored, deleted);
+    assert_eq!(restored["productions"], json!([production.id]));
+
+    let res = test::call_service(
+        &app,
+        test::TestRequest::get()
+            .uri(&format!("/api/v1/productions/{}", production.id))
+            .to_request(),
+    )
+    .await;
+    assert_eq!(res.status(), 200);
+    assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"3\"");
+
+    let req = test::TestRequest::get()
+        .uri("/api/v1/users/me/trash")
+        .insert_header((header::AUTHORIZATION, authorization.clone()))
+        .to_request();
+    let items: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(items.as_array().unwrap().len(), 1);
+    assert_eq!(items[0]["entity"], "production");
+    assert_eq!(items[0]["id"], ancienne.id);
+
+    // Les traces de suppression disparaissent avec la restauration
+    let changes = repos.sync.changes_since(user.id, chrono::DateTime::<chrono::Utc>::MIN_UTC).await.unwrap();
+    assert_eq!(changes.deleted.len(), 1);
+}
+
+#[actix_web::test]
+async fn messages_and_type_names_follow_accept_language() {
+    let repos = repositories().await;
+    let authorization = sign_in(&repos).await;
+    let app = app!(repos);
+
+    let login = |accept_language: Option<&str>| {
+        let req = test::TestRequest::post()
+            .uri("/api/v1/auth/login")
+            .set_json(json!({ "email": EMAIL, "mot_de_passe": "mauvais" }));
+        match accept_language {
+            Some(value) => req.insert_header((header::ACCEPT_LANGUAGE, value.to_string())),
+            None => req,
+        }
+        .to_request()
+    };
+
+    // Sans préférence, les messages restent en français
+    let res = test::call_service(&app, login(None)).await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.headers().get(header::CONTENT_LANGUAGE).unwrap(), "fr");
+    assert_eq!(test::read_body(res).await, "Email ou mot de passe incorrect");
+
+    // L'allemand n'est pas disponible : l'anglais, cité ensuite, est retenu
+    let res = test::call_service(&app, login(Some("de-DE, en-GB;q=0.8, fr;q=0.5"))).await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(res.headers().get(header::CONTENT_LANGUAGE).unwrap(), "en");
+    assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept-Language");
+    assert_eq!(test::read_body(res).await, "Incorrect email or password");
+
+    let bassin = repos
+        .references
+        .create_type_exploitation("Bassin".to_string(), None)
+        .await
+        .unwrap();
+
+    let translate = |uri: String, nom: &str| {
+        test::TestRequest::put()
+            .uri(&uri)
+            .set_json(json!({ "nom": nom }))
+            .to_request()
+    };
+    let uri = format!("/api/v1/types_exploitation/{}/translations/en", bassin.id);
+    let res = test::call_service(&app, translate(uri, "Pond")).await;
+    assert_eq!(res.status(), 200);
+    let uri = "/api/v1/types_exploitation/999/translations/en".to_string();
+    let res = test::call_service(&app, translate(uri, "Tank")).await;
+    assert_eq!(res.status(), 404);
+    let uri = format!("/api/v1/types_exploitation/{}/translations/fr", bassin.id);
+    let res = test::call_service(&app, translate(uri, "Étang")).await;
+    assert_eq!(res.status(), 400);
+
+    let list = |accept_language: &str| {
+        test::TestRequest::get()
+            .uri("/api/v1/types_exploitation")
+            .insert_header((header::ACCEPT_LANGUAGE, accept_language.to_string()))
+            .to_request()
+    };
+    let types: Value = test::call_and_read_body_json(&app, list("en")).await;
+    assert_eq!(types[0]["nom_type_exploitation"], "Pond");
+    let types: Value = test::call_and_read_body_json(&app, list("fr-FR")).await;
+    assert_eq!(types[0]["nom_type_exploitation"], "Bassin");
+
+    // Les refus d'authentification et les messages des corps JSON suivent aussi la langue
+    let req = test::TestRequest::get()
+        .uri("/api/v1/users/me")
+        .insert_header((header::ACCEPT_LANGUAGE, "en"))
+        .to_request();
+    let res = test::call_service(&app, req).await;
+    assert_eq!(res.status(), 401);
+    assert_eq!(test::read_body(res).await, "Missing token");
+
+    let req = test::TestRequest::post()
+        .uri("/api/v1/sync")
+        .insert_header((header::AUTHORIZATION, authorization))
+        .insert_header((header::ACCEPT_LANGUAGE, "en"))
+        .set_json(json!({
+            "mutations": [{ "client_id": "d1", "entity": "domaine", "op": "create", "data": {} }]
+        }))
+        .to_request();
+    let res: Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(res["results"][0]["status"], "invalid");
+    assert_eq!(res["results"][0]["message"], "Missing required field: nom_domaine");
+}
+
+#[actix_web::test]
+async fn accept_language_is_negotiated_by_weight_with_french_fallback() {
+    let negotiate = |accept_language: Option<&str>| {
+        let mut headers = HeaderMap::new();
+        if let Some(value) = accept_language {
+            headers.insert(header::ACCEPT_LANGUAGE, HeaderValue::from_str(value).unwrap());
+        }
+        Lang::negotiate(&headers)
+    };
+
+    // Sans en-tête, ou sans langue disponible, le français
+    assert_eq!(negotiate(None), Lang::Fr);
+    assert_eq!(negotiate(Some("de, es;q=0.9")), Lang::Fr);
+    assert_eq!(negotiate(Some("n'importe quoi")), Lang::Fr);
+
+    // Le poids le plus fort l'emporte, quel que soit l'ordre ; à poids égal, la première citée
+    assert_eq!(negotiate(Some("fr;q=0.3, en-US;q=0.7")), Lang::En);
+    assert_eq!(negotiate(Some("en;q=0.5, fr;q=0.5")), Lang::En);
+    assert_eq!(negotiate(Some("EN-gb")), Lang::En);
+
+    // q=0 exclut une langue ; le joker désigne la langue par défaut
+    assert_eq!(negotiate(Some("en;q=0, fr;q=0.1")), Lang::Fr);
+    assert_eq!(negotiate(Some("en;q=0")), Lang::Fr);
+    assert_eq!(negotiate(Some("*;q=0.9, en;q=0.8")), Lang::Fr);
+    assert_eq!(negotiate(Some("de, *;q=0.1, en;q=0.5")), Lang::En);
+}
+
+// Données générées, sans les horodatages de création qui dépendent de l'horloge
+async fn seeded(options: &SeedOptions) -> (seed::SeedReport, Vec<String>) {
+    let repos = repositories().await;
+    sign_in(&repos).await;
+    let user = repos.users.get_by_email(EMAIL).await.unwrap();
+    let report = seed::demo(&repos, user.id, options).await.unwrap();
+
+    let mut lignes = Vec::new();
+    for domaine in repos.domaines.get_all_by_user_id(user.id, None).await.unwrap() {
+        lignes.push(format!("domaine {}", domaine.nom_domaine));
+        for exploitation in repos.exploitations.get_by_domaine_id(domaine.id, None).await.unwrap() {
+            lignes.push(format!("exploitation {} {}", exploitation.type_exploitation_id, exploitation.nom_exploitation));
+            for q in repos.releves.get_qualite_eau(exploitation.id).await.unwrap() {
+                lignes.push(format!("qualité {} {} {} {}", q.date_releve, q.temperature, q.oxygene_dissous, q.ph));
+            }
+            for element in repos.elements.get_by_exploitation_id(exploitation.id, None).await.unwrap() {
+                lignes.push(format!("élément {} {}", element.nom_element, element.quantite));
+                for p in repos.productions.get_by_element_id(element.id, None).await.unwrap() {
+                    lignes.push(format!("production {} {}", p.date_de_production, p.quantite_produite));
+                }
+                for a in repos.releves.get_alimentations(element.id).await.unwrap() {
+                    lignes.push(format!("alimentation {} {}", a.date_alimentation, a.quantite_kg));
+                }
+            }
+        }
+    }
+
+    (report, lignes)
+}
+
+#[actix_web::test]
+async fn seeding_twice_with_the_same_seed_generates_the_same_farms() {
+    let options = SeedOptions { graine: 42, domaines: 1, annees: 1, ..SeedOptions::default() };
+
+    let (report, lignes) = seeded(&options).await;
+    let (encore, memes_lignes) = seeded(&options).await;
+    assert_eq!(report, encore);
+    assert_eq!(lignes, memes_lignes);
+
+    // Un relevé d'eau par exploitation et une ration par production, chaque jour
+    let jours = 365;
+    assert_eq!(report.domaines, 1);
+    assert_eq!(report.qualite_eau, report.exploitations * jours);
+    assert_eq!(report.productions, report.elements * jours);
+    assert_eq!(report.alimentations, report.productions);
+
+    // L'eau est plus chaude et moins oxygénée en juillet qu'en janvier
+    let moyenne = |mois: &str, rang: usize| {
+        let valeurs: Vec<f64> = lignes
+            .iter()
+            .filter(|l| l.starts_with(&format!("qualité 2023-{}", mois)))
+            .map(|l| l.split(' ').nth(rang).unwrap().parse().unwrap())
+            .collect();
+        valeurs.iter().sum::<f64>() / valeurs.len() as f64
+    };
+    assert!(moyenne("07", 2) > moyenne("01", 2) + 10.0);
+    assert!(moyenne("07", 3) < moyenne("01", 3));
+
+    // Une autre graine donne d'autres fermes
+    let (_, autres_lignes) = seeded(&SeedOptions { graine: 7, ..options }).await;
+    assert_ne!(lignes, autres_lignes);
+}